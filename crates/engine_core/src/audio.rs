@@ -0,0 +1,1005 @@
+// Audio: WAV/OGG decoding and a pure-Rust mixer that renders into memory.
+// The host either hands the mixer to the audio device (feature "audio-output") or
+// steps it with the fixed update, so headless runs and replays mix the same samples.
+use crate::state::{EngineState, Layer};
+use anyhow::Result;
+use lewton::inside_ogg::OggStreamReader;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::Arc;
+
+/// Mixer output rate in frames per second (stereo)
+pub const OUTPUT_RATE: u32 = 48_000;
+/// Frames decoded per step when streaming
+const CHUNK_FRAMES: usize = 4096;
+/// Chunks a music stream decodes ahead of playback
+const PREFETCH_CHUNKS: usize = 8;
+
+/// Mixing group with its own volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    Sfx,
+    Music,
+    Ui,
+}
+
+impl Bus {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sfx" => Some(Self::Sfx),
+            "music" => Some(Self::Music),
+            "ui" => Some(Self::Ui),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Decoded PCM: stereo frames at the file's own sample rate
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SoundData {
+    pub sample_rate: u32,
+    pub frames: Vec<[f32; 2]>,
+}
+
+impl SoundData {
+    /// Decode a whole `.wav` or `.ogg` file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::decode_all(open_decoder(path)?.as_mut())
+            .map_err(|e| anyhow::Error::msg(format!("sound '{}': {}", path.display(), e)))
+    }
+
+    pub fn from_wav_bytes(bytes: Vec<u8>) -> Result<Self> {
+        Self::decode_all(&mut WavDecoder::new(std::io::Cursor::new(bytes))?)
+    }
+
+    fn decode_all(decoder: &mut dyn Decoder) -> Result<Self> {
+        let mut frames = VecDeque::new();
+        while decoder.decode(&mut frames)? {}
+        Ok(Self {
+            sample_rate: decoder.sample_rate(),
+            frames: frames.into(),
+        })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frames.len() as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
+// Incremental decoder shared by sounds and music streams
+trait Decoder: Send {
+    fn sample_rate(&self) -> u32;
+    /// Append the next chunk of frames; false once the stream has ended
+    fn decode(&mut self, out: &mut VecDeque<[f32; 2]>) -> Result<bool>;
+    fn rewind(&mut self) -> Result<()>;
+}
+
+// Mono is duplicated, extra channels beyond the first two are dropped
+fn push_interleaved(out: &mut VecDeque<[f32; 2]>, samples: &[f32], channels: usize) {
+    for frame in samples.chunks_exact(channels.max(1)) {
+        let l = frame[0];
+        out.push_back([l, if channels > 1 { frame[1] } else { l }]);
+    }
+}
+
+struct WavDecoder<R: Read + Seek + Send> {
+    reader: hound::WavReader<R>,
+    channels: usize,
+    scratch: Vec<f32>,
+}
+
+impl<R: Read + Seek + Send> WavDecoder<R> {
+    fn new(reader: R) -> Result<Self> {
+        let reader = hound::WavReader::new(reader)?;
+        let channels = reader.spec().channels as usize;
+        Ok(Self {
+            reader,
+            channels,
+            scratch: Vec::new(),
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> Decoder for WavDecoder<R> {
+    fn sample_rate(&self) -> u32 {
+        self.reader.spec().sample_rate
+    }
+
+    fn decode(&mut self, out: &mut VecDeque<[f32; 2]>) -> Result<bool> {
+        let spec = self.reader.spec();
+        let want = CHUNK_FRAMES * self.channels;
+        self.scratch.clear();
+        match spec.sample_format {
+            hound::SampleFormat::Float => {
+                for s in self.reader.samples::<f32>().take(want) {
+                    self.scratch.push(s?);
+                }
+            }
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                for s in self.reader.samples::<i32>().take(want) {
+                    self.scratch.push(s? as f32 * scale);
+                }
+            }
+        }
+        push_interleaved(out, &self.scratch, self.channels);
+        Ok(self.scratch.len() == want)
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.reader.seek(0)?;
+        Ok(())
+    }
+}
+
+struct OggDecoder<R: Read + Seek + Send> {
+    reader: OggStreamReader<R>,
+}
+
+impl<R: Read + Seek + Send> Decoder for OggDecoder<R> {
+    fn sample_rate(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    fn decode(&mut self, out: &mut VecDeque<[f32; 2]>) -> Result<bool> {
+        let channels = self.reader.ident_hdr.audio_channels as usize;
+        match self.reader.read_dec_packet_itl()? {
+            Some(pcm) => {
+                let samples: Vec<f32> = pcm.iter().map(|&s| s as f32 / 32768.0).collect();
+                push_interleaved(out, &samples, channels);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.reader.seek_absgp_pg(0)?;
+        Ok(())
+    }
+}
+
+fn open_decoder(path: &Path) -> Result<Box<dyn Decoder>> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let open = || {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| anyhow::Error::msg(format!("sound '{}': {}", path.display(), e)))
+    };
+    let fail = |e: &dyn std::fmt::Display| {
+        anyhow::Error::msg(format!("sound '{}': {}", path.display(), e))
+    };
+    match ext.as_deref() {
+        Some("wav") => Ok(Box::new(WavDecoder::new(open()?).map_err(|e| fail(&e))?)),
+        Some("ogg") | Some("oga") => {
+            let reader = OggStreamReader::new(open()?).map_err(|e| fail(&e))?;
+            Ok(Box::new(OggDecoder { reader }))
+        }
+        _ => Err(anyhow::Error::msg(format!(
+            "sound '{}': unsupported format (use .wav or .ogg)",
+            path.display()
+        ))),
+    }
+}
+
+/// Music decoded chunk by chunk on its own thread while it plays
+pub struct MusicStream {
+    sample_rate: u32,
+    ready: Receiver<Vec<[f32; 2]>>,
+    // Played-out chunks go back to the decoder thread for reuse
+    spent: SyncSender<Vec<[f32; 2]>>,
+    chunk: Vec<[f32; 2]>,
+    pos: usize,
+}
+
+// Result of pulling the next source frame from a stream
+enum Pull {
+    Frame([f32; 2]),
+    /// The decoder thread has not caught up yet
+    Pending,
+    Ended,
+}
+
+impl MusicStream {
+    /// Open a `.wav` or `.ogg` file, decode its first chunk and keep decoding
+    /// ahead on a background thread, rewinding at the end when looping
+    pub fn open(path: impl AsRef<Path>, looping: bool) -> Result<Self> {
+        Self::new(open_decoder(path.as_ref())?, looping)
+    }
+
+    fn new(mut decoder: Box<dyn Decoder>, looping: bool) -> Result<Self> {
+        let sample_rate = decoder.sample_rate();
+        let (ready_tx, ready) = mpsc::sync_channel(PREFETCH_CHUNKS);
+        let (spent, spent_rx) = mpsc::sync_channel(PREFETCH_CHUNKS + 2);
+        let mut buffer = VecDeque::new();
+        let mut more = decoder.decode(&mut buffer)?;
+        let _ = ready_tx.send(buffer.drain(..).collect());
+        std::thread::Builder::new()
+            .name("music-decoder".to_string())
+            .spawn(move || {
+                // Decode errors end the track; a loop that decodes nothing stops too
+                let mut decoded = true;
+                loop {
+                    if !more {
+                        if !looping || !decoded || decoder.rewind().is_err() {
+                            return;
+                        }
+                        decoded = false;
+                    }
+                    more = decoder.decode(&mut buffer).unwrap_or_else(|e| {
+                        tracing::warn!("music decode failed: {}", e);
+                        false
+                    });
+                    if buffer.is_empty() {
+                        continue;
+                    }
+                    decoded = true;
+                    let mut chunk: Vec<[f32; 2]> = spent_rx.try_recv().unwrap_or_default();
+                    chunk.clear();
+                    chunk.extend(buffer.drain(..));
+                    // Blocks while the prefetch is full; fails once the stream is dropped
+                    if ready_tx.send(chunk).is_err() {
+                        return;
+                    }
+                }
+            })?;
+        Ok(Self {
+            sample_rate,
+            ready,
+            spent,
+            chunk: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // Next source frame; only waits for the decoder thread when `wait` is set
+    fn next_frame(&mut self, wait: bool) -> Pull {
+        while self.pos >= self.chunk.len() {
+            let next = if wait {
+                self.ready.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                self.ready.try_recv()
+            };
+            match next {
+                Ok(chunk) => {
+                    let spent = std::mem::replace(&mut self.chunk, chunk);
+                    let _ = self.spent.try_send(spent);
+                    self.pos = 0;
+                }
+                Err(TryRecvError::Empty) => return Pull::Pending,
+                Err(TryRecvError::Disconnected) => return Pull::Ended,
+            }
+        }
+        self.pos += 1;
+        Pull::Frame(self.chunk[self.pos - 1])
+    }
+}
+
+/// What a positional sound follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emitter {
+    /// Fixed world position
+    Point([f32; 2]),
+    /// An entity's transform; the last known position is kept once it is gone
+    Entity(u32),
+}
+
+/// Distance falloff in world units: full volume up to `min_distance`, silent
+/// from `max_distance`. `None` derives the distance from the view width.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Attenuation {
+    pub min_distance: Option<f32>,
+    pub max_distance: Option<f32>,
+}
+
+/// Where positional sounds are heard from: the centre of the view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Listener {
+    pub x: f32,
+    pub y: f32,
+    /// Half the view width; a sound this far to the side is panned fully
+    pub half_width: f32,
+}
+
+impl Listener {
+    /// Listener for sounds on `layer`, following the same parallax, scroll and
+    /// screen-space rules the renderer applies to the layer's sprites
+    pub fn for_layer(camera: (f32, f32), view: (f32, f32), layer: Option<&Layer>) -> Self {
+        let offset = layer.map_or(camera, |l| l.view_offset(camera));
+        Self {
+            x: offset.0 + view.0 * 0.5,
+            y: offset.1 + view.1 * 0.5,
+            half_width: view.0 * 0.5,
+        }
+    }
+}
+
+/// Gain and pan of a sound at `pos` heard by `listener`. Gain falls off linearly
+/// between the attenuation distances (defaults: half a view, then two views);
+/// pan follows the horizontal offset and saturates at the view edge.
+pub fn spatial_gain_pan(listener: &Listener, pos: [f32; 2], att: Attenuation) -> (f32, f32) {
+    let (dx, dy) = (pos[0] - listener.x, pos[1] - listener.y);
+    let dist = (dx * dx + dy * dy).sqrt();
+    let min = att.min_distance.unwrap_or(listener.half_width).max(0.0);
+    let max = att
+        .max_distance
+        .unwrap_or(listener.half_width * 4.0)
+        .max(min);
+    let gain = if dist <= min {
+        1.0
+    } else if dist >= max {
+        0.0
+    } else {
+        1.0 - (dist - min) / (max - min)
+    };
+    let pan = if listener.half_width > 0.0 {
+        (dx / listener.half_width).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+    (gain, pan)
+}
+
+/// Per-play settings for a sound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayParams {
+    pub volume: f32,
+    /// Playback speed; 2.0 is an octave up
+    pub pitch: f32,
+    /// -1 left .. 1 right
+    pub pan: f32,
+    pub looping: bool,
+    pub bus: Bus,
+    /// Positional sounds are attenuated and panned relative to the camera
+    pub emitter: Option<Emitter>,
+    pub attenuation: Attenuation,
+    /// Layer whose volume, mute and parallax apply to the sound
+    pub layer: Option<u32>,
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pitch: 1.0,
+            pan: 0.0,
+            looping: false,
+            bus: Bus::Sfx,
+            emitter: None,
+            attenuation: Attenuation::default(),
+            layer: None,
+        }
+    }
+}
+
+/// Requests queued by scripts and applied to the mixer by the host
+pub enum AudioCommand {
+    LoadSound {
+        id: u32,
+        data: Arc<SoundData>,
+    },
+    Play {
+        voice: u64,
+        sound: u32,
+        params: PlayParams,
+    },
+    Stop {
+        voice: u64,
+    },
+    /// Change a playing voice; `None` fields keep their value
+    Update {
+        voice: u64,
+        volume: Option<f32>,
+        pitch: Option<f32>,
+        pan: Option<f32>,
+        emitter: Option<Emitter>,
+    },
+    /// Crossfade from the current track over `fade` seconds
+    PlayMusic {
+        stream: MusicStream,
+        volume: f32,
+        fade: f32,
+    },
+    StopMusic {
+        fade: f32,
+    },
+    SetBusVolume {
+        bus: Bus,
+        volume: f32,
+    },
+}
+
+struct Voice {
+    id: u64,
+    sound: Arc<SoundData>,
+    /// Position in source frames
+    pos: f64,
+    params: PlayParams,
+    /// Gain and pan from the last `spatialize`
+    spatial: (f32, f32),
+    /// Last known emitter position
+    at: Option<[f32; 2]>,
+}
+
+struct MusicTrack {
+    stream: MusicStream,
+    volume: f32,
+    /// Fade gain and its change per output frame
+    gain: f32,
+    fade_step: f32,
+    // Linear resampling state between two source frames; source frames are
+    // owed while `frac` is 1 or more
+    prev: [f32; 2],
+    next: [f32; 2],
+    frac: f64,
+}
+
+/// Mixes sounds and music into interleaved stereo f32
+pub struct Mixer {
+    sample_rate: u32,
+    sounds: HashMap<u32, Arc<SoundData>>,
+    voices: Vec<Voice>,
+    bus_volume: [f32; 3],
+    music: Option<MusicTrack>,
+    fading: Vec<MusicTrack>,
+    // Fractional frames owed by `advance`
+    pending: f64,
+    scratch: Vec<f32>,
+    realtime: bool,
+}
+
+// Balance keeps centred sounds at full volume in both channels
+fn pan_gains(pan: f32) -> [f32; 2] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
+}
+
+fn fade_step(fade: f32, rate: u32) -> f32 {
+    if fade > 0.0 {
+        1.0 / (fade * rate as f32)
+    } else {
+        f32::INFINITY
+    }
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            sounds: HashMap::new(),
+            voices: Vec::new(),
+            bus_volume: [1.0; 3],
+            music: None,
+            fading: Vec::new(),
+            pending: 0.0,
+            scratch: Vec::new(),
+            realtime: false,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Never wait on music decoding in `render`; a stream that falls behind
+    /// leaves a gap instead. Set when an audio device callback drives the mixer.
+    pub fn set_realtime(&mut self, realtime: bool) {
+        self.realtime = realtime;
+    }
+
+    /// Sounds currently playing (music excluded)
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn music_playing(&self) -> bool {
+        self.music.is_some()
+    }
+
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        self.bus_volume[bus.index()]
+    }
+
+    pub fn apply(&mut self, cmd: AudioCommand) {
+        match cmd {
+            AudioCommand::LoadSound { id, data } => {
+                self.sounds.insert(id, data);
+            }
+            AudioCommand::Play {
+                voice,
+                sound,
+                params,
+            } => {
+                if let Some(sound) = self.sounds.get(&sound) {
+                    let at = match params.emitter {
+                        Some(Emitter::Point(p)) => Some(p),
+                        _ => None,
+                    };
+                    self.voices.push(Voice {
+                        id: voice,
+                        sound: sound.clone(),
+                        pos: 0.0,
+                        params,
+                        // Positional voices stay silent until first spatialized
+                        spatial: (if params.emitter.is_some() { 0.0 } else { 1.0 }, 0.0),
+                        at,
+                    });
+                }
+            }
+            AudioCommand::Stop { voice } => self.voices.retain(|v| v.id != voice),
+            AudioCommand::Update {
+                voice,
+                volume,
+                pitch,
+                pan,
+                emitter,
+            } => {
+                if let Some(v) = self.voices.iter_mut().find(|v| v.id == voice) {
+                    let p = &mut v.params;
+                    p.volume = volume.unwrap_or(p.volume);
+                    p.pitch = pitch.unwrap_or(p.pitch);
+                    p.pan = pan.unwrap_or(p.pan);
+                    if let Some(e) = emitter {
+                        p.emitter = Some(e);
+                        if let Emitter::Point(pos) = e {
+                            v.at = Some(pos);
+                        }
+                    }
+                }
+            }
+            AudioCommand::PlayMusic {
+                stream,
+                volume,
+                fade,
+            } => {
+                self.stop_music(fade);
+                let step = fade_step(fade, self.sample_rate);
+                self.music = Some(MusicTrack {
+                    stream,
+                    volume,
+                    gain: if step.is_finite() { 0.0 } else { 1.0 },
+                    fade_step: if step.is_finite() { step } else { 0.0 },
+                    prev: [0.0; 2],
+                    next: [0.0; 2],
+                    // Both resampling frames are pulled on the first render
+                    frac: 2.0,
+                });
+            }
+            AudioCommand::StopMusic { fade } => self.stop_music(fade),
+            AudioCommand::SetBusVolume { bus, volume } => {
+                self.bus_volume[bus.index()] = volume.max(0.0);
+            }
+        }
+    }
+
+    fn stop_music(&mut self, fade: f32) {
+        if let Some(mut old) = self.music.take() {
+            let step = fade_step(fade, self.sample_rate);
+            if step.is_finite() {
+                old.fade_step = -step;
+                self.fading.push(old);
+            }
+        }
+    }
+
+    /// Mix into `out` (interleaved stereo), replacing its contents
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        let rate = self.sample_rate as f64;
+        let buses = self.bus_volume;
+        self.voices.retain_mut(|v| {
+            let frames = &v.sound.frames;
+            let len = frames.len();
+            if len == 0 {
+                return false;
+            }
+            let step = v.sound.sample_rate as f64 / rate * v.params.pitch.max(0.0) as f64;
+            let (spatial_gain, spatial_pan) = v.spatial;
+            let [gl, gr] = pan_gains(v.params.pan + spatial_pan);
+            let gain = v.params.volume * spatial_gain * buses[v.params.bus.index()];
+            for frame in out.chunks_exact_mut(2) {
+                if v.pos >= len as f64 {
+                    if !v.params.looping {
+                        return false;
+                    }
+                    v.pos %= len as f64;
+                }
+                let i = v.pos as usize;
+                let t = (v.pos - i as f64) as f32;
+                let a = frames[i];
+                let b = match (i + 1 < len, v.params.looping) {
+                    (true, _) => frames[i + 1],
+                    (false, true) => frames[0],
+                    (false, false) => a,
+                };
+                frame[0] += (a[0] + (b[0] - a[0]) * t) * gain * gl;
+                frame[1] += (a[1] + (b[1] - a[1]) * t) * gain * gr;
+                v.pos += step;
+            }
+            v.pos < len as f64 || v.params.looping
+        });
+
+        let music_bus = buses[Bus::Music.index()];
+        let wait = !self.realtime;
+        let mut mix_track = |track: &mut MusicTrack| -> bool {
+            let step = track.stream.sample_rate() as f64 / rate;
+            'frames: for frame in out.chunks_exact_mut(2) {
+                while track.frac >= 1.0 {
+                    match track.stream.next_frame(wait) {
+                        Pull::Frame(f) => {
+                            track.prev = track.next;
+                            track.next = f;
+                            track.frac -= 1.0;
+                        }
+                        Pull::Pending => continue 'frames,
+                        Pull::Ended => return false,
+                    }
+                }
+                track.gain = (track.gain + track.fade_step).clamp(0.0, 1.0);
+                if track.fade_step < 0.0 && track.gain <= 0.0 {
+                    return false;
+                }
+                let t = track.frac as f32;
+                let (prev, next) = (track.prev, track.next);
+                let g = track.volume * track.gain * music_bus;
+                frame[0] += (prev[0] + (next[0] - prev[0]) * t) * g;
+                frame[1] += (prev[1] + (next[1] - prev[1]) * t) * g;
+                track.frac += step;
+            }
+            true
+        };
+        if let Some(track) = self.music.as_mut() {
+            if !mix_track(track) {
+                self.music = None;
+            }
+        }
+        self.fading.retain_mut(|t| mix_track(t));
+
+        for s in out.iter_mut() {
+            *s = s.clamp(-1.0, 1.0);
+        }
+    }
+
+    /// Recompute positional gain/pan and layer volume for every voice from the
+    /// camera, view size, entity transforms and layers. Hosts call this once per
+    /// update, after applying script commands.
+    pub fn spatialize(&mut self, state: &EngineState) {
+        let (vw, vh) = state.get_virtual_resolution().size();
+        let view = (vw as f32, vh as f32);
+        let camera = state.camera_xy();
+        let transforms = state.get_transforms();
+        for v in &mut self.voices {
+            let layer = v.params.layer.and_then(|id| state.layers().get(id));
+            let layer_gain = layer.map_or(1.0, |l| if l.muted { 0.0 } else { l.volume });
+            if let Some(Emitter::Entity(id)) = v.params.emitter {
+                if let Some(t) = transforms.chunks_exact(6).find(|t| t[0] as u32 == id) {
+                    v.at = Some([t[1], t[2]]);
+                }
+            }
+            v.spatial = match (v.params.emitter, v.at) {
+                (Some(_), Some(at)) => {
+                    let listener = Listener::for_layer(camera, view, layer);
+                    let (gain, pan) = spatial_gain_pan(&listener, at, v.params.attenuation);
+                    (gain * layer_gain, pan)
+                }
+                // Entity never seen in the transforms
+                (Some(_), None) => (0.0, 0.0),
+                (None, _) => (layer_gain, 0.0),
+            };
+        }
+    }
+
+    /// Mix and discard `seconds` of audio, for hosts without an output device
+    pub fn advance(&mut self, seconds: f64) {
+        self.pending += seconds * self.sample_rate as f64;
+        let frames = self.pending.floor();
+        self.pending -= frames;
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize(frames as usize * 2, 0.0);
+        self.render(&mut scratch);
+        self.scratch = scratch;
+    }
+}
+
+/// Default output device backend
+#[cfg(feature = "audio-output")]
+pub mod output {
+    use super::Mixer;
+    use anyhow::Result;
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::sync::{Arc, Mutex};
+
+    /// Keeps the device stream alive; audio stops when dropped
+    pub struct AudioOutput {
+        _stream: cpal::Stream,
+    }
+
+    /// Play `mixer` through the default output device
+    pub fn start(mixer: Arc<Mutex<Mixer>>) -> Result<AudioOutput> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| anyhow::Error::msg("no audio output device"))?;
+        let rate = {
+            let mut m = mixer
+                .lock()
+                .map_err(|_| anyhow::Error::msg("audio mixer poisoned"))?;
+            m.set_realtime(true);
+            m.sample_rate()
+        };
+        let config = cpal::StreamConfig {
+            channels: 2,
+            sample_rate: cpal::SampleRate(rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+        let stream = device.build_output_stream(
+            &config,
+            move |data: &mut [f32], _| match mixer.lock() {
+                Ok(mut m) => m.render(data),
+                Err(_) => data.fill(0.0),
+            },
+            |e| tracing::warn!("audio output error: {}", e),
+            None,
+        )?;
+        stream.play()?;
+        Ok(AudioOutput { _stream: stream })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Layers, UI_LAYER};
+
+    fn wav(samples: &[i16], channels: u16, rate: u32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut w = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for s in samples {
+            w.write_sample(*s).unwrap();
+        }
+        w.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn test_mixer_renders_voices_and_buses() {
+        // Mono half-scale DC at the output rate, 100 frames
+        let dc = SoundData::from_wav_bytes(wav(&[16384; 100], 1, OUTPUT_RATE)).unwrap();
+        assert_eq!((dc.frames.len(), dc.frames[0]), (100, [0.5, 0.5]));
+
+        let mut m = Mixer::new(OUTPUT_RATE);
+        m.apply(AudioCommand::LoadSound {
+            id: 1,
+            data: Arc::new(dc),
+        });
+        let play = |voice, params| AudioCommand::Play {
+            voice,
+            sound: 1,
+            params,
+        };
+        m.apply(play(
+            7,
+            PlayParams {
+                pan: 1.0,
+                ..Default::default()
+            },
+        ));
+        m.apply(play(
+            8,
+            PlayParams {
+                pitch: 2.0,
+                bus: Bus::Ui,
+                ..Default::default()
+            },
+        ));
+        m.apply(AudioCommand::SetBusVolume {
+            bus: Bus::Ui,
+            volume: 0.5,
+        });
+        let mut out = vec![0.0; 120 * 2];
+        m.render(&mut out);
+        // Panned hard right plus the half-volume ui voice in both channels
+        assert_eq!(&out[..2], &[0.25, 0.75]);
+        // Double pitch ends after 50 frames, the other voice after 100
+        assert_eq!(&out[60 * 2..60 * 2 + 2], &[0.0, 0.5]);
+        assert_eq!(&out[110 * 2..], &[0.0; 20]);
+        assert_eq!(m.voice_count(), 0);
+    }
+
+    // 8 mono frames of `level` at 4 Hz
+    fn music(level: i16, looping: bool) -> MusicStream {
+        let dec = WavDecoder::new(std::io::Cursor::new(wav(&[level; 8], 1, 4))).unwrap();
+        MusicStream::new(Box::new(dec), looping).unwrap()
+    }
+
+    #[test]
+    fn test_music_is_upsampled_and_fades_in() {
+        // Music at half the output rate is upsampled and fades in over 4 frames
+        let mut m = Mixer::new(8);
+        m.apply(AudioCommand::PlayMusic {
+            stream: music(16384, false),
+            volume: 1.0,
+            fade: 0.5,
+        });
+        let mut out = vec![0.0; 24 * 2];
+        m.render(&mut out);
+        let left: Vec<f32> = out.iter().step_by(2).copied().collect();
+        assert_eq!(&left[..5], &[0.125, 0.25, 0.375, 0.5, 0.5]);
+        // 8 source frames cover 14 output frames before the last one has no successor
+        assert_eq!(left[13], 0.5);
+        assert_eq!(left[14], 0.0);
+        assert!(!m.music_playing());
+    }
+
+    #[test]
+    fn test_looping_music_plays_on_until_faded_out() {
+        let mut m = Mixer::new(8);
+        m.apply(AudioCommand::PlayMusic {
+            stream: music(-16384, true),
+            volume: 1.0,
+            fade: 0.0,
+        });
+        m.advance(10.0);
+        assert!(m.music_playing());
+        m.apply(AudioCommand::StopMusic { fade: 0.25 });
+        let mut out = vec![0.0; 4 * 2];
+        m.render(&mut out);
+        assert_eq!(&out[..4], &[-0.25, -0.25, 0.0, 0.0]);
+        assert!(!m.music_playing());
+    }
+
+    #[test]
+    fn test_realtime_render_leaves_a_gap_instead_of_waiting() {
+        // A stream whose decoder thread has nothing ready yet
+        let (ready_tx, ready) = mpsc::sync_channel(1);
+        let (spent, _spent_rx) = mpsc::sync_channel(1);
+        let stream = MusicStream {
+            sample_rate: 8,
+            ready,
+            spent,
+            chunk: Vec::new(),
+            pos: 0,
+        };
+        let mut m = Mixer::new(8);
+        m.set_realtime(true);
+        m.apply(AudioCommand::PlayMusic {
+            stream,
+            volume: 1.0,
+            fade: 0.0,
+        });
+        let mut out = vec![1.0; 2 * 2];
+        m.render(&mut out);
+        assert_eq!(out, vec![0.0; 4]);
+        assert!(m.music_playing());
+
+        ready_tx.send(vec![[0.5, 0.5]; 4]).unwrap();
+        m.render(&mut out);
+        assert_eq!(out, vec![0.5; 4]);
+        drop(ready_tx);
+        m.render(&mut out);
+        assert!(!m.music_playing());
+    }
+
+    #[test]
+    fn test_spatial_gain_and_pan() {
+        let listener = Listener {
+            x: 0.0,
+            y: 0.0,
+            half_width: 100.0,
+        };
+        let att = Attenuation::default();
+        // Inside half a view: full volume, panned by horizontal offset
+        assert_eq!(spatial_gain_pan(&listener, [50.0, 0.0], att), (1.0, 0.5));
+        // Linear falloff out to two views; far sounds saturate the pan
+        assert_eq!(spatial_gain_pan(&listener, [0.0, 250.0], att), (0.5, 0.0));
+        assert_eq!(spatial_gain_pan(&listener, [-500.0, 0.0], att), (0.0, -1.0));
+        let near = Attenuation {
+            min_distance: Some(0.0),
+            max_distance: Some(10.0),
+        };
+        assert_eq!(spatial_gain_pan(&listener, [5.0, 0.0], near).0, 0.5);
+    }
+
+    #[test]
+    fn test_listener_follows_layer_view() {
+        // Listener follows layer parallax/scroll and ignores the camera in screen space
+        let mut layers = Layers::with_defaults();
+        let bg = layers.define_or_update("bg".to_string(), -1);
+        if let Some(l) = layers.by_name_mut("bg") {
+            (l.parallax_x, l.scroll_y) = (0.5, 10.0);
+        }
+        let at = |layer| {
+            let l = Listener::for_layer((100.0, 40.0), (200.0, 100.0), layer);
+            (l.x, l.y, l.half_width)
+        };
+        assert_eq!(at(None), (200.0, 90.0, 100.0));
+        assert_eq!(at(layers.get(bg)), (150.0, 100.0, 100.0));
+        assert_eq!(
+            at(layers.id_of(UI_LAYER).and_then(|id| layers.get(id))),
+            (100.0, 50.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_spatialize_applies_emitters_and_layer_volume() {
+        // An entity emitter one view-half to the right of the 1920x1080 view centre
+        let mut state = EngineState::new();
+        state
+            .set_transforms_from_f32_slice(&[5.0, 1920.0, 540.0, 0.0, 1.0, 1.0])
+            .unwrap();
+        let fx = state.layers_mut().define_or_update("fx".to_string(), 0);
+        let mut m = Mixer::new(8);
+        m.apply(AudioCommand::LoadSound {
+            id: 1,
+            data: Arc::new(SoundData {
+                sample_rate: 8,
+                frames: vec![[0.5, 0.5]; 8],
+            }),
+        });
+        let params = PlayParams {
+            emitter: Some(Emitter::Entity(5)),
+            looping: true,
+            ..Default::default()
+        };
+        m.apply(AudioCommand::Play {
+            voice: 1,
+            sound: 1,
+            params,
+        });
+        let mut out = [0.0; 2];
+        m.render(&mut out);
+        assert_eq!(out, [0.0, 0.0]);
+        m.spatialize(&state);
+        m.render(&mut out);
+        assert_eq!(out, [0.0, 0.5]);
+
+        // Layer volume and mute apply to sounds played on the layer
+        m.apply(AudioCommand::Play {
+            voice: 2,
+            sound: 1,
+            params: PlayParams {
+                layer: Some(fx),
+                ..Default::default()
+            },
+        });
+        if let Some(l) = state.layers_mut().by_name_mut("fx") {
+            l.volume = 0.5;
+        }
+        m.apply(AudioCommand::Update {
+            voice: 1,
+            volume: None,
+            pitch: None,
+            pan: None,
+            emitter: Some(Emitter::Point([960.0, 540.0])),
+        });
+        m.spatialize(&state);
+        m.render(&mut out);
+        assert_eq!(out, [0.75, 0.75]);
+        if let Some(l) = state.layers_mut().by_name_mut("fx") {
+            l.muted = true;
+        }
+        m.spatialize(&state);
+        m.render(&mut out);
+        assert_eq!(out, [0.5, 0.5]);
+    }
+}
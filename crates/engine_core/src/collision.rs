@@ -0,0 +1,592 @@
+use glam::Vec2;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Default spatial hash cell size in world units
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Axis-aligned box given by its half extents. `auto_size` boxes take their
+    /// size from the transform's w/h on every sync.
+    Aabb {
+        half: Vec2,
+        auto_size: bool,
+    },
+    Circle {
+        radius: f32,
+    },
+}
+
+/// Collision shape plus filtering. Two colliders interact only if each one's
+/// mask includes the other's layer bits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collider {
+    pub shape: Shape,
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl Collider {
+    pub fn aabb(w: f32, h: f32) -> Self {
+        Self {
+            shape: Shape::Aabb {
+                half: Vec2::new(w, h) * 0.5,
+                auto_size: false,
+            },
+            layer: 1,
+            mask: u32::MAX,
+        }
+    }
+
+    pub fn circle(radius: f32) -> Self {
+        Self {
+            shape: Shape::Circle { radius },
+            layer: 1,
+            mask: u32::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactPhase {
+    Begin,
+    Stay,
+    End,
+}
+
+impl ContactPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContactPhase::Begin => "begin",
+            ContactPhase::Stay => "stay",
+            ContactPhase::End => "end",
+        }
+    }
+}
+
+/// Contact between two entity ids (`a < b`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub a: u32,
+    pub b: u32,
+    pub phase: ContactPhase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub id: u32,
+    pub point: Vec2,
+    pub normal: Vec2,
+    pub distance: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Body {
+    collider: Collider,
+    pos: Vec2,
+}
+
+impl Body {
+    fn bounds(&self) -> (Vec2, Vec2) {
+        let half = match self.collider.shape {
+            Shape::Aabb { half, .. } => half,
+            Shape::Circle { radius } => Vec2::splat(radius),
+        };
+        (self.pos - half, self.pos + half)
+    }
+}
+
+/// Colliders keyed by entity id, broadphased through a uniform spatial hash.
+///
+/// Positions come from the v2 transform rows (`id, x, y, rot, w, h`, x/y being
+/// the center); rotation is ignored. The hash is rebuilt lazily after changes.
+#[derive(Debug, Clone)]
+pub struct CollisionWorld {
+    cell_size: f32,
+    bodies: BTreeMap<u32, Body>,
+    grid: HashMap<(i32, i32), Vec<u32>>,
+    // Occupied cell range (min, max), used to end ray walks early
+    grid_bounds: Option<((i32, i32), (i32, i32))>,
+    grid_dirty: bool,
+    contacts: BTreeSet<(u32, u32)>,
+    events: Vec<CollisionEvent>,
+}
+
+impl CollisionWorld {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(1.0),
+            bodies: BTreeMap::new(),
+            grid: HashMap::new(),
+            grid_bounds: None,
+            grid_dirty: false,
+            contacts: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Add or replace the collider for `id`, keeping its last known position.
+    pub fn set_collider(&mut self, id: u32, collider: Collider) {
+        let pos = self.bodies.get(&id).map(|b| b.pos).unwrap_or(Vec2::ZERO);
+        self.bodies.insert(id, Body { collider, pos });
+        self.grid_dirty = true;
+    }
+
+    /// Returns false if `id` had no collider.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let removed = self.bodies.remove(&id).is_some();
+        self.grid_dirty |= removed;
+        removed
+    }
+
+    pub fn has_collider(&self, id: u32) -> bool {
+        self.bodies.contains_key(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    pub fn set_position(&mut self, id: u32, pos: Vec2) {
+        if let Some(b) = self.bodies.get_mut(&id) {
+            b.pos = pos;
+            self.grid_dirty = true;
+        }
+    }
+
+    pub fn position(&self, id: u32) -> Option<Vec2> {
+        self.bodies.get(&id).map(|b| b.pos)
+    }
+
+    /// Pull positions (and auto sizes) for known colliders from v2 transform rows.
+    pub fn sync_transforms(&mut self, rows: &[f32]) {
+        if self.bodies.is_empty() {
+            return;
+        }
+        for row in rows.chunks_exact(6) {
+            let Some(b) = self.bodies.get_mut(&(row[0] as u32)) else {
+                continue;
+            };
+            b.pos = Vec2::new(row[1], row[2]);
+            if let Shape::Aabb {
+                half,
+                auto_size: true,
+            } = &mut b.collider.shape
+            {
+                *half = Vec2::new(row[4], row[5]) * 0.5;
+            }
+            self.grid_dirty = true;
+        }
+    }
+
+    /// Recompute contacts. Events are `Begin` for new overlaps, `Stay` for
+    /// continuing ones and `End` for pairs that separated or lost a collider.
+    pub fn step(&mut self) -> &[CollisionEvent] {
+        self.rebuild_grid();
+        let mut now = BTreeSet::new();
+        for ids in self.grid.values() {
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    let pair = if a < b { (*a, *b) } else { (*b, *a) };
+                    if !now.contains(&pair) && self.pair_overlaps(pair.0, pair.1) {
+                        now.insert(pair);
+                    }
+                }
+            }
+        }
+        self.events.clear();
+        for &(a, b) in self.contacts.union(&now) {
+            let phase = match (self.contacts.contains(&(a, b)), now.contains(&(a, b))) {
+                (false, true) => ContactPhase::Begin,
+                (true, true) => ContactPhase::Stay,
+                _ => ContactPhase::End,
+            };
+            self.events.push(CollisionEvent { a, b, phase });
+        }
+        self.contacts = now;
+        &self.events
+    }
+
+    /// Events from the last `step`, ordered by (a, b).
+    pub fn events(&self) -> &[CollisionEvent] {
+        &self.events
+    }
+
+    /// Colliders overlapping the rectangle `min..max` whose layer is in `mask`.
+    pub fn query_rect(&mut self, min: Vec2, max: Vec2, mask: u32) -> Vec<u32> {
+        self.rebuild_grid();
+        let mut out = BTreeSet::new();
+        let center = (min + max) * 0.5;
+        let half = (max - min).abs() * 0.5;
+        let probe = Body {
+            collider: Collider {
+                shape: Shape::Aabb {
+                    half,
+                    auto_size: false,
+                },
+                layer: u32::MAX,
+                mask,
+            },
+            pos: center,
+        };
+        for cell in self.cells(min.min(max), min.max(max)) {
+            for id in self.grid.get(&cell).into_iter().flatten() {
+                let b = &self.bodies[id];
+                if b.collider.layer & mask != 0 && overlaps(&probe, b) {
+                    out.insert(*id);
+                }
+            }
+        }
+        out.into_iter().collect()
+    }
+
+    /// Colliders containing `p` whose layer is in `mask`.
+    pub fn query_point(&mut self, p: Vec2, mask: u32) -> Vec<u32> {
+        self.rebuild_grid();
+        let mut out: Vec<u32> = self
+            .grid
+            .get(&self.cell_of(p))
+            .into_iter()
+            .flatten()
+            .filter(|id| {
+                let b = &self.bodies[id];
+                b.collider.layer & mask != 0 && contains(b, p)
+            })
+            .copied()
+            .collect();
+        out.sort_unstable();
+        out
+    }
+
+    /// First collider hit by the ray from `origin` along `dir`, within `max_dist`.
+    pub fn raycast(&mut self, origin: Vec2, dir: Vec2, max_dist: f32, mask: u32) -> Option<RayHit> {
+        let dir = dir.try_normalize()?;
+        self.rebuild_grid();
+
+        // Clip the ray to the occupied cells, so far-off origins and infinite
+        // rays only walk the cells that can hold bodies
+        let (lo, hi) = self.grid_bounds?;
+        let box_min = Vec2::new(lo.0 as f32, lo.1 as f32) * self.cell_size;
+        let box_max = Vec2::new(hi.0 as f32 + 1.0, hi.1 as f32 + 1.0) * self.cell_size;
+        let (t_enter, t_exit) = slab(origin, dir, box_min, box_max)?;
+        let (t_enter, t_exit) = (t_enter.max(0.0), t_exit.min(max_dist));
+        if t_enter > t_exit {
+            return None;
+        }
+
+        // Walk the grid cells along the ray (Amanatides & Woo)
+        let start = self.cell_of(origin + dir * t_enter);
+        let mut cell = (start.0.clamp(lo.0, hi.0), start.1.clamp(lo.1, hi.1));
+        let step = (dir.x.signum() as i32, dir.y.signum() as i32);
+        let boundary = |c: i32, s: i32| (c + if s > 0 { 1 } else { 0 }) as f32 * self.cell_size;
+        let t_max_axis = |o: f32, d: f32, c: i32, s: i32| {
+            if d == 0.0 {
+                f32::INFINITY
+            } else {
+                (boundary(c, s) - o) / d
+            }
+        };
+        let mut t_max = Vec2::new(
+            t_max_axis(origin.x, dir.x, cell.0, step.0),
+            t_max_axis(origin.y, dir.y, cell.1, step.1),
+        );
+        let t_delta = Vec2::new(
+            if dir.x == 0.0 {
+                f32::INFINITY
+            } else {
+                self.cell_size / dir.x.abs()
+            },
+            if dir.y == 0.0 {
+                f32::INFINITY
+            } else {
+                self.cell_size / dir.y.abs()
+            },
+        );
+
+        let inside = |c: (i32, i32)| (lo.0..=hi.0).contains(&c.0) && (lo.1..=hi.1).contains(&c.1);
+        let mut best: Option<RayHit> = None;
+        let mut t_cell = t_enter;
+        while t_cell <= t_exit && inside(cell) {
+            for id in self.grid.get(&cell).into_iter().flatten() {
+                let b = &self.bodies[id];
+                if b.collider.layer & mask == 0 {
+                    continue;
+                }
+                if let Some((t, normal)) = ray_hit(b, origin, dir) {
+                    let closer =
+                        best.is_none_or(|h| t < h.distance || (t == h.distance && *id < h.id));
+                    if t <= max_dist && closer {
+                        best = Some(RayHit {
+                            id: *id,
+                            point: origin + dir * t,
+                            normal,
+                            distance: t,
+                        });
+                    }
+                }
+            }
+            // Bodies span several cells, so a hit is final once it lies before the next cell
+            let t_next = t_max.x.min(t_max.y);
+            if best.is_some_and(|h| h.distance <= t_next) {
+                break;
+            }
+            if t_max.x < t_max.y {
+                cell.0 += step.0;
+                t_max.x += t_delta.x;
+            } else {
+                cell.1 += step.1;
+                t_max.y += t_delta.y;
+            }
+            t_cell = t_next;
+        }
+        best
+    }
+
+    fn pair_overlaps(&self, a: u32, b: u32) -> bool {
+        let (ba, bb) = (&self.bodies[&a], &self.bodies[&b]);
+        ba.collider.mask & bb.collider.layer != 0
+            && bb.collider.mask & ba.collider.layer != 0
+            && overlaps(ba, bb)
+    }
+
+    fn cell_of(&self, p: Vec2) -> (i32, i32) {
+        (
+            (p.x / self.cell_size).floor() as i32,
+            (p.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    // Cells covering min..max, clipped to the occupied range
+    fn cells(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (i32, i32)> {
+        let ((lx, ly), (hx, hy)) = self.grid_bounds.unwrap_or(((0, 0), (-1, -1)));
+        let (x0, y0) = self.cell_of(min);
+        let (x1, y1) = self.cell_of(max);
+        let (y0, y1) = (y0.max(ly), y1.min(hy));
+        (x0.max(lx)..=x1.min(hx)).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    fn rebuild_grid(&mut self) {
+        if !self.grid_dirty {
+            return;
+        }
+        for ids in self.grid.values_mut() {
+            ids.clear();
+        }
+        for (id, b) in self.bodies.iter() {
+            let (min, max) = b.bounds();
+            let (x0, y0) = self.cell_of(min);
+            let (x1, y1) = self.cell_of(max);
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    self.grid.entry((x, y)).or_default().push(*id);
+                }
+            }
+        }
+        self.grid.retain(|_, ids| !ids.is_empty());
+        self.grid_bounds = self.grid.keys().fold(None, |acc, &(x, y)| {
+            Some(match acc {
+                None => ((x, y), (x, y)),
+                Some((lo, hi)) => ((lo.0.min(x), lo.1.min(y)), (hi.0.max(x), hi.1.max(y))),
+            })
+        });
+        self.grid_dirty = false;
+    }
+}
+
+impl Default for CollisionWorld {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+fn overlaps(a: &Body, b: &Body) -> bool {
+    match (a.collider.shape, b.collider.shape) {
+        (Shape::Aabb { half: ha, .. }, Shape::Aabb { half: hb, .. }) => {
+            let d = (a.pos - b.pos).abs();
+            d.x <= ha.x + hb.x && d.y <= ha.y + hb.y
+        }
+        (Shape::Circle { radius: ra }, Shape::Circle { radius: rb }) => {
+            a.pos.distance_squared(b.pos) <= (ra + rb) * (ra + rb)
+        }
+        (Shape::Aabb { half, .. }, Shape::Circle { radius }) => {
+            circle_box(b.pos, radius, a.pos, half)
+        }
+        (Shape::Circle { radius }, Shape::Aabb { half, .. }) => {
+            circle_box(a.pos, radius, b.pos, half)
+        }
+    }
+}
+
+fn circle_box(center: Vec2, radius: f32, box_pos: Vec2, half: Vec2) -> bool {
+    let closest = center.clamp(box_pos - half, box_pos + half);
+    center.distance_squared(closest) <= radius * radius
+}
+
+fn contains(b: &Body, p: Vec2) -> bool {
+    match b.collider.shape {
+        Shape::Aabb { half, .. } => {
+            let d = (p - b.pos).abs();
+            d.x <= half.x && d.y <= half.y
+        }
+        Shape::Circle { radius } => p.distance_squared(b.pos) <= radius * radius,
+    }
+}
+
+// Entry and exit distances of the ray through the box min..max, if it crosses it ahead
+fn slab(origin: Vec2, dir: Vec2, min: Vec2, max: Vec2) -> Option<(f32, f32)> {
+    let inv = dir.recip();
+    let t1 = (min - origin) * inv;
+    let t2 = (max - origin) * inv;
+    let (tmin, tmax) = (t1.min(t2), t1.max(t2));
+    let t_enter = tmin.x.max(tmin.y);
+    let t_exit = tmax.x.min(tmax.y);
+    if t_enter > t_exit || t_exit < 0.0 {
+        return None;
+    }
+    Some((t_enter, t_exit))
+}
+
+// Entry distance and surface normal of a ray starting outside the body
+fn ray_hit(b: &Body, origin: Vec2, dir: Vec2) -> Option<(f32, Vec2)> {
+    match b.collider.shape {
+        Shape::Aabb { half, .. } => {
+            let inv = dir.recip();
+            let t1 = (b.pos - half - origin) * inv;
+            let t2 = (b.pos + half - origin) * inv;
+            let (tmin, tmax) = (t1.min(t2), t1.max(t2));
+            let t_enter = tmin.x.max(tmin.y);
+            let t_exit = tmax.x.min(tmax.y);
+            if t_enter > t_exit || t_exit < 0.0 {
+                return None;
+            }
+            if t_enter < 0.0 {
+                // Origin inside the box
+                return Some((0.0, -dir));
+            }
+            let normal = if tmin.x > tmin.y {
+                Vec2::new(-dir.x.signum(), 0.0)
+            } else {
+                Vec2::new(0.0, -dir.y.signum())
+            };
+            Some((t_enter, normal))
+        }
+        Shape::Circle { radius } => {
+            let m = origin - b.pos;
+            let c = m.length_squared() - radius * radius;
+            if c <= 0.0 {
+                return Some((0.0, -dir));
+            }
+            let bq = m.dot(dir);
+            let disc = bq * bq - c;
+            if bq > 0.0 || disc < 0.0 {
+                return None;
+            }
+            let t = -bq - disc.sqrt();
+            Some((t, (origin + dir * t - b.pos) / radius))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Box 1 and circle 2 overlap; wide box 3 overlaps both on its own layer
+    fn world() -> CollisionWorld {
+        let mut world = CollisionWorld::new(16.0);
+        world.set_collider(1, Collider::aabb(10.0, 10.0));
+        world.set_collider(2, Collider::circle(4.0));
+        world.set_collider(
+            3,
+            Collider {
+                layer: 2,
+                mask: 2,
+                ..Collider::aabb(100.0, 2.0)
+            },
+        );
+        // id, x, y, rot, w, h
+        world.sync_transforms(&[1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 8.0, 0.0, 0.0, 1.0, 1.0]);
+        world.set_position(3, Vec2::new(0.0, 0.0));
+        world
+    }
+
+    #[test]
+    fn test_contact_phases_respect_masks() {
+        let mut world = world();
+        let phases = |w: &mut CollisionWorld| -> Vec<(u32, u32, ContactPhase)> {
+            w.step().iter().map(|e| (e.a, e.b, e.phase)).collect()
+        };
+        // 3 overlaps both but its mask excludes layer 1
+        assert_eq!(phases(&mut world), vec![(1, 2, ContactPhase::Begin)]);
+        assert_eq!(phases(&mut world), vec![(1, 2, ContactPhase::Stay)]);
+        world.set_position(2, Vec2::new(40.0, 0.0));
+        assert_eq!(phases(&mut world), vec![(1, 2, ContactPhase::End)]);
+        assert!(world.step().is_empty());
+    }
+
+    #[test]
+    fn test_point_and_rect_queries() {
+        let mut world = world();
+        world.set_position(2, Vec2::new(40.0, 0.0));
+        assert_eq!(world.query_point(Vec2::new(3.0, 0.5), u32::MAX), vec![1, 3]);
+        assert_eq!(world.query_point(Vec2::new(3.0, 0.5), 1), vec![1]);
+        assert_eq!(
+            world.query_rect(Vec2::new(30.0, -5.0), Vec2::new(50.0, 5.0), u32::MAX),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_raycast_hits_nearest_within_distance() {
+        let mut world = world();
+        world.set_position(2, Vec2::new(40.0, 0.0));
+        let hit = world
+            .raycast(Vec2::new(-100.0, 20.0), Vec2::new(1.0, 0.0), 500.0, 1)
+            .is_none();
+        assert!(hit);
+        let hit = world
+            .raycast(Vec2::new(100.0, 0.0), Vec2::new(-1.0, 0.0), 500.0, 1)
+            .unwrap();
+        assert_eq!(hit.id, 2);
+        assert!((hit.distance - 56.0).abs() < 1e-4);
+        assert_eq!(hit.normal, Vec2::new(1.0, 0.0));
+        assert!(world
+            .raycast(Vec2::new(100.0, 0.0), Vec2::new(-1.0, 0.0), 50.0, 1)
+            .is_none());
+    }
+
+    #[test]
+    fn test_raycast_from_far_origin_only_walks_the_grid() {
+        let mut world = CollisionWorld::new(16.0);
+        world.set_collider(1, Collider::aabb(10.0, 10.0));
+        world.set_position(1, Vec2::new(0.0, 0.0));
+        // Origins whose cells saturate i32 still hit, without walking ~2^31 cells
+        let hit = world
+            .raycast(Vec2::new(1e12, 0.0), Vec2::new(-1.0, 0.0), f32::INFINITY, 1)
+            .unwrap();
+        assert_eq!(hit.id, 1);
+        assert_eq!(hit.normal, Vec2::new(1.0, 0.0));
+        let hit = world.raycast(
+            Vec2::new(-f32::MAX, 2.0),
+            Vec2::new(1.0, 0.0),
+            f32::INFINITY,
+            1,
+        );
+        assert_eq!(hit.map(|h| h.id), Some(1));
+        assert!(world
+            .raycast(
+                Vec2::new(1e12, 50.0),
+                Vec2::new(-1.0, 0.0),
+                f32::INFINITY,
+                1
+            )
+            .is_none());
+        assert!(world
+            .raycast(Vec2::new(1e12, 0.0), Vec2::new(1.0, 0.0), f32::INFINITY, 1)
+            .is_none());
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Entity ids pack a slot index (low bits) and a generation (high bits) into a u32
+/// that stays below 2^24, so ids survive the f32 transform buffers unchanged.
+pub const ENTITY_INDEX_BITS: u32 = 16;
+pub const ENTITY_GENERATION_BITS: u32 = 8;
+const INDEX_MASK: u32 = (1 << ENTITY_INDEX_BITS) - 1;
+const GENERATION_MASK: u32 = (1 << ENTITY_GENERATION_BITS) - 1;
+
+/// Maximum number of simultaneously alive entities (index 0 is never handed out)
+pub const MAX_ENTITIES: u32 = INDEX_MASK;
+
+// Freed slots wait until this many are queued before one is reused, so a slot comes
+// back at most once per this many destroys and its generation wraps only after
+// 256x as many; a bullet handle stays dead long after the bullet is gone
+const MIN_FREE_BEFORE_REUSE: usize = 1024;
+
+pub fn entity_index(id: u32) -> u32 {
+    id & INDEX_MASK
+}
+
+pub fn entity_generation(id: u32) -> u32 {
+    (id >> ENTITY_INDEX_BITS) & GENERATION_MASK
+}
+
+fn pack(index: u32, generation: u32) -> u32 {
+    ((generation & GENERATION_MASK) << ENTITY_INDEX_BITS) | index
+}
+
+/// Generational entity allocator with a FIFO free queue.
+///
+/// Destroying an entity bumps its slot's generation, so handles to the old
+/// entity stop being alive even after the slot is reused.
+#[derive(Debug, Clone)]
+pub struct EntityAllocator {
+    // Indexed by slot; slot 0 is a placeholder so the first id is 1
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: VecDeque<u32>,
+    live_count: u32,
+}
+
+impl EntityAllocator {
+    pub fn new() -> Self {
+        Self {
+            generations: vec![0],
+            alive: vec![false],
+            free: VecDeque::new(),
+            live_count: 0,
+        }
+    }
+
+    pub fn create(&mut self) -> Result<u32> {
+        let fresh = self.generations.len() as u32;
+        let index = if self.free.len() >= MIN_FREE_BEFORE_REUSE || fresh > MAX_ENTITIES {
+            // Oldest freed slot first; near the id limit any freed slot will do
+            match self.free.pop_front() {
+                Some(i) => i,
+                None => {
+                    return Err(anyhow::Error::msg(format!(
+                        "Entity limit reached ({} alive)",
+                        MAX_ENTITIES
+                    )))
+                }
+            }
+        } else {
+            self.generations.push(0);
+            self.alive.push(false);
+            fresh
+        };
+        self.alive[index as usize] = true;
+        self.live_count += 1;
+        Ok(pack(index, self.generations[index as usize]))
+    }
+
+    /// Returns false if `id` was already dead (double destroy or stale handle).
+    pub fn destroy(&mut self, id: u32) -> bool {
+        if !self.is_alive(id) {
+            return false;
+        }
+        let index = entity_index(id) as usize;
+        self.alive[index] = false;
+        self.generations[index] = (self.generations[index] + 1) & GENERATION_MASK;
+        self.free.push_back(index as u32);
+        self.live_count -= 1;
+        true
+    }
+
+    pub fn is_alive(&self, id: u32) -> bool {
+        let index = entity_index(id) as usize;
+        index < self.alive.len()
+            && self.alive[index]
+            && self.generations[index] == entity_generation(id)
+    }
+
+    pub fn live_count(&self) -> u32 {
+        self.live_count
+    }
+}
+
+impl Default for EntityAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reused_slot_invalidates_stale_handle() {
+        let mut alloc = EntityAllocator::new();
+        let a = alloc.create().unwrap();
+        let b = alloc.create().unwrap();
+        assert_eq!((a, b), (1, 2));
+        assert!(alloc.destroy(a));
+        assert!(!alloc.destroy(a));
+        let c = alloc.create().unwrap();
+        assert_ne!(c, a);
+        assert!(!alloc.is_alive(a));
+        assert!(alloc.is_alive(c) && alloc.is_alive(b));
+        assert_eq!(alloc.live_count(), 2);
+        assert!(c < (1 << 24), "ids must stay exact in f32");
+
+        // One bullet per frame for half an hour: slots are reused, stale handles never revive
+        let stale = alloc.create().unwrap();
+        alloc.destroy(stale);
+        let mut reused = false;
+        for _ in 0..100_000 {
+            let bullet = alloc.create().unwrap();
+            reused |= entity_index(bullet) == entity_index(stale);
+            assert!(!alloc.is_alive(stale));
+            alloc.destroy(bullet);
+        }
+        assert!(reused);
+        assert!((alloc.generations.len() as u32) < MAX_ENTITIES);
+    }
+
+    #[test]
+    fn test_limit_reuses_freed_slots() {
+        let mut alloc = EntityAllocator::new();
+        let ids: Vec<u32> = (0..MAX_ENTITIES).map(|_| alloc.create().unwrap()).collect();
+        assert!(alloc.create().is_err());
+        alloc.destroy(ids[7]);
+        let id = alloc.create().unwrap();
+        assert_eq!(entity_index(id), entity_index(ids[7]));
+        assert!(!alloc.is_alive(ids[7]) && alloc.is_alive(id));
+    }
+}
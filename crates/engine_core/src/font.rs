@@ -0,0 +1,565 @@
+use anyhow::Result;
+use glam::Vec2;
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One glyph of a BMFont page, in page pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Glyph {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub xoffset: f32,
+    pub yoffset: f32,
+    pub xadvance: f32,
+    pub page: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Wrapped lines stretch their spaces to fill `max_width`; last lines stay left
+    Justify,
+}
+
+impl Align {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "left" => Some(Align::Left),
+            "center" => Some(Align::Center),
+            "right" => Some(Align::Right),
+            "justify" => Some(Align::Justify),
+            _ => None,
+        }
+    }
+}
+
+/// Glyph placed by `BitmapFont::layout`, relative to the anchor with y pointing down
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+    /// Index of the source char in the laid out text
+    pub index: usize,
+    pub page: u32,
+    pub top_left: Vec2,
+    pub size: Vec2,
+    /// (u0, v0, u1, v1) with v0 at the top of the glyph
+    pub uv: [f32; 4],
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayoutOptions {
+    pub align: Align,
+    /// Enables word wrapping
+    pub max_width: Option<f32>,
+    /// Extra pixels between lines, on top of the font's line height
+    pub line_spacing: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LineMetrics {
+    /// Index of the line's first char in the laid out text
+    pub first: usize,
+    /// Chars on the line, excluding the break
+    pub count: usize,
+    pub width: f32,
+    /// Top of the line, down from the anchor
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub quads: Vec<GlyphQuad>,
+    pub width: f32,
+    pub height: f32,
+    pub lines: Vec<LineMetrics>,
+}
+
+/// Inline markup tag covering chars `start..end` of the plain text
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupSpan {
+    pub tag: String,
+    pub value: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split `[tag]`, `[tag=value]` and `[/tag]` markup from text. Only tags named in
+/// `known` are recognised; anything else in brackets stays literal, and `[[` is a
+/// literal `[`. Unclosed tags run to the end of the text.
+pub fn parse_markup(src: &str, known: &[&str]) -> Result<(String, Vec<MarkupSpan>)> {
+    let mut plain = String::with_capacity(src.len());
+    let mut len = 0usize;
+    let mut open: Vec<MarkupSpan> = Vec::new();
+    let mut spans = Vec::new();
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(after) = rest.strip_prefix("[[") {
+                plain.push('[');
+                len += 1;
+                rest = after;
+                continue;
+            }
+            if let Some(close) = rest.find(']') {
+                let inner = &rest[1..close];
+                if let Some(name) = inner.strip_prefix('/') {
+                    if known.contains(&name) {
+                        let at = open.iter().rposition(|s| s.tag == name).ok_or_else(|| {
+                            anyhow::Error::msg(format!("[/{}] without matching [{}]", name, name))
+                        })?;
+                        let mut span = open.remove(at);
+                        span.end = len;
+                        spans.push(span);
+                        rest = &rest[close + 1..];
+                        continue;
+                    }
+                } else {
+                    let (name, value) = match inner.split_once('=') {
+                        Some((n, v)) => (n, Some(v.to_string())),
+                        None => (inner, None),
+                    };
+                    if known.contains(&name) {
+                        open.push(MarkupSpan {
+                            tag: name.to_string(),
+                            value,
+                            start: len,
+                            end: len,
+                        });
+                        rest = &rest[close + 1..];
+                        continue;
+                    }
+                }
+            }
+        }
+        plain.push(c);
+        len += 1;
+        rest = &rest[c.len_utf8()..];
+    }
+    for mut span in open {
+        span.end = len;
+        spans.push(span);
+    }
+    spans.sort_by_key(|s| s.start);
+    Ok((plain, spans))
+}
+
+/// AngelCode BMFont (text or JSON descriptor)
+#[derive(Debug, Clone, Default)]
+pub struct BitmapFont {
+    pub face: String,
+    pub line_height: f32,
+    pub base: f32,
+    pub scale_w: f32,
+    pub scale_h: f32,
+    /// Page images resolved against the descriptor's directory
+    pub pages: Vec<PathBuf>,
+    /// Engine texture id per page; assigned by whoever queues the image loads
+    pub page_textures: Vec<u32>,
+    glyphs: HashMap<u32, Glyph>,
+    kerning: HashMap<(u32, u32), f32>,
+}
+
+impl BitmapFont {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::Error::msg(format!("font '{}': {}", path.display(), e)))?;
+        if bytes.starts_with(b"BMF") {
+            return Err(anyhow::Error::msg(format!(
+                "font '{}': binary BMFont files are not supported (export as text or JSON)",
+                path.display()
+            )));
+        }
+        let src = String::from_utf8_lossy(&bytes);
+        Self::parse(&src, path.parent().unwrap_or(Path::new("")))
+            .map_err(|e| anyhow::Error::msg(format!("font '{}': {}", path.display(), e)))
+    }
+
+    /// Parse a text or JSON descriptor; page files resolve against `base_dir`.
+    pub fn parse(src: &str, base_dir: &Path) -> Result<Self> {
+        let mut font = if src.trim_start().starts_with('{') {
+            Self::parse_json(src, base_dir)?
+        } else {
+            Self::parse_text(src, base_dir)?
+        };
+        if font.pages.is_empty() {
+            return Err(anyhow::Error::msg("no pages"));
+        }
+        if font.line_height <= 0.0 {
+            return Err(anyhow::Error::msg("missing common lineHeight"));
+        }
+        font.page_textures = vec![0; font.pages.len()];
+        Ok(font)
+    }
+
+    fn parse_text(src: &str, base_dir: &Path) -> Result<Self> {
+        let mut font = BitmapFont::default();
+        let mut pages: Vec<(u32, PathBuf)> = Vec::new();
+        for line in src.lines() {
+            let (tag, attrs) = match line.trim().split_once(char::is_whitespace) {
+                Some((tag, rest)) => (tag, text_attrs(rest)),
+                None => continue,
+            };
+            let num = |key: &str| {
+                attrs
+                    .get(key)
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .unwrap_or(0.0)
+            };
+            match tag {
+                "info" => font.face = attrs.get("face").cloned().unwrap_or_default(),
+                "common" => {
+                    font.line_height = num("lineHeight");
+                    font.base = num("base");
+                    font.scale_w = num("scaleW");
+                    font.scale_h = num("scaleH");
+                }
+                "page" => {
+                    let file = attrs
+                        .get("file")
+                        .ok_or_else(|| anyhow::Error::msg("page without file"))?;
+                    pages.push((num("id") as u32, base_dir.join(file)));
+                }
+                "char" => {
+                    font.glyphs.insert(
+                        num("id") as u32,
+                        Glyph {
+                            x: num("x"),
+                            y: num("y"),
+                            w: num("width"),
+                            h: num("height"),
+                            xoffset: num("xoffset"),
+                            yoffset: num("yoffset"),
+                            xadvance: num("xadvance"),
+                            page: num("page") as u32,
+                        },
+                    );
+                }
+                "kerning" => {
+                    font.kerning
+                        .insert((num("first") as u32, num("second") as u32), num("amount"));
+                }
+                _ => {}
+            }
+        }
+        pages.sort_by_key(|(id, _)| *id);
+        font.pages = pages.into_iter().map(|(_, p)| p).collect();
+        Ok(font)
+    }
+
+    fn parse_json(src: &str, base_dir: &Path) -> Result<Self> {
+        let doc: Json = serde_json::from_str(src).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+        let num = |v: &Json, key: &str| v[key].as_f64().unwrap_or(0.0) as f32;
+        let mut font = BitmapFont {
+            face: doc["info"]["face"].as_str().unwrap_or_default().to_string(),
+            line_height: num(&doc["common"], "lineHeight"),
+            base: num(&doc["common"], "base"),
+            scale_w: num(&doc["common"], "scaleW"),
+            scale_h: num(&doc["common"], "scaleH"),
+            ..Default::default()
+        };
+        for page in doc["pages"].as_array().into_iter().flatten() {
+            let file = page
+                .as_str()
+                .ok_or_else(|| anyhow::Error::msg("pages must be file names"))?;
+            font.pages.push(base_dir.join(file));
+        }
+        for c in doc["chars"].as_array().into_iter().flatten() {
+            font.glyphs.insert(
+                num(c, "id") as u32,
+                Glyph {
+                    x: num(c, "x"),
+                    y: num(c, "y"),
+                    w: num(c, "width"),
+                    h: num(c, "height"),
+                    xoffset: num(c, "xoffset"),
+                    yoffset: num(c, "yoffset"),
+                    xadvance: num(c, "xadvance"),
+                    page: num(c, "page") as u32,
+                },
+            );
+        }
+        for k in doc["kernings"].as_array().into_iter().flatten() {
+            font.kerning.insert(
+                (num(k, "first") as u32, num(k, "second") as u32),
+                num(k, "amount"),
+            );
+        }
+        Ok(font)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&(c as u32))
+    }
+
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.kerning
+            .get(&(first as u32, second as u32))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    // Missing glyphs fall back to '?' so unsupported text stays visible
+    fn glyph_or_fallback(&self, c: char) -> Option<&Glyph> {
+        self.glyph(c).or_else(|| self.glyph('?'))
+    }
+
+    fn advance(&self, prev: Option<char>, c: char) -> f32 {
+        let kern = prev.map(|p| self.kerning(p, c)).unwrap_or(0.0);
+        kern + self.glyph_or_fallback(c).map(|g| g.xadvance).unwrap_or(0.0)
+    }
+
+    /// Width of a single line, ignoring trailing spaces.
+    pub fn measure_line(&self, chars: &[char]) -> f32 {
+        let trimmed = chars.len() - chars.iter().rev().take_while(|c| **c == ' ').count();
+        let mut prev = None;
+        let mut width = 0.0;
+        for &c in &chars[..trimmed] {
+            width += self.advance(prev, c);
+            prev = Some(c);
+        }
+        width
+    }
+
+    // Greedy word wrap; words longer than the limit are split between chars.
+    // Returns (first char index, chars, broken by wrapping) per line.
+    fn wrap(&self, text: &str, max_width: Option<f32>) -> Vec<(usize, Vec<char>, bool)> {
+        let mut lines = Vec::new();
+        let mut base = 0usize;
+        for para in text.split('\n') {
+            let chars: Vec<char> = para.chars().filter(|c| *c != '\r').collect();
+            let para_len = para.chars().count();
+            let Some(max) = max_width else {
+                lines.push((base, chars, false));
+                base += para_len + 1;
+                continue;
+            };
+            let mut start = 0;
+            loop {
+                let mut end = start;
+                let mut width = 0.0;
+                let mut last_space = None;
+                let mut prev = None;
+                while end < chars.len() {
+                    let c = chars[end];
+                    let adv = self.advance(prev, c);
+                    if c != ' ' && width + adv > max && end > start {
+                        break;
+                    }
+                    if c == ' ' {
+                        last_space = Some(end);
+                    }
+                    width += adv;
+                    prev = Some(c);
+                    end += 1;
+                }
+                if end < chars.len() {
+                    if let Some(sp) = last_space.filter(|sp| *sp > start) {
+                        end = sp;
+                    }
+                }
+                let line = chars[start..end].to_vec();
+                start = end;
+                while start < chars.len() && chars[start] == ' ' {
+                    start += 1;
+                }
+                lines.push((base + end - line.len(), line, start < chars.len()));
+                if start >= chars.len() {
+                    break;
+                }
+            }
+            base += para_len + 1;
+        }
+        lines
+    }
+
+    /// Lay out UTF-8 text in font pixels. Each line is aligned against the anchor:
+    /// left edge, center or right edge; justified lines start at the left edge.
+    pub fn layout(&self, text: &str, opts: &LayoutOptions) -> TextLayout {
+        let mut out = TextLayout::default();
+        let (sw, sh) = (self.scale_w.max(1.0), self.scale_h.max(1.0));
+        let pitch = self.line_height + opts.line_spacing;
+        for (row, (first, chars, wrapped)) in
+            self.wrap(text, opts.max_width).into_iter().enumerate()
+        {
+            let line_w = self.measure_line(&chars);
+            let mut x = match opts.align {
+                Align::Left | Align::Justify => 0.0,
+                Align::Center => -line_w * 0.5,
+                Align::Right => -line_w,
+            };
+            // Extra advance per interior space when justifying a wrapped line
+            let spaces = chars.iter().filter(|c| **c == ' ').count();
+            let stretch = match (opts.align, opts.max_width) {
+                (Align::Justify, Some(max)) if wrapped && spaces > 0 => {
+                    (max - line_w).max(0.0) / spaces as f32
+                }
+                _ => 0.0,
+            };
+            let y = row as f32 * pitch;
+            let mut prev = None;
+            for (i, &c) in chars.iter().enumerate() {
+                let Some(g) = self.glyph_or_fallback(c) else {
+                    continue;
+                };
+                x += prev.map(|p| self.kerning(p, c)).unwrap_or(0.0);
+                if g.w > 0.0 && g.h > 0.0 {
+                    out.quads.push(GlyphQuad {
+                        index: first + i,
+                        page: g.page,
+                        top_left: Vec2::new(x + g.xoffset, y + g.yoffset),
+                        size: Vec2::new(g.w, g.h),
+                        uv: [g.x / sw, g.y / sh, (g.x + g.w) / sw, (g.y + g.h) / sh],
+                    });
+                }
+                x += g.xadvance;
+                if c == ' ' {
+                    x += stretch;
+                }
+                prev = Some(c);
+            }
+            let width = if stretch > 0.0 {
+                line_w + stretch * spaces as f32
+            } else {
+                line_w
+            };
+            out.width = out.width.max(width);
+            out.lines.push(LineMetrics {
+                first,
+                count: chars.len(),
+                width,
+                y,
+            });
+        }
+        let n = out.lines.len() as f32;
+        out.height = (n * pitch - opts.line_spacing).max(0.0);
+        out
+    }
+}
+
+// key=value pairs of a BMFont text line; values may be quoted and contain spaces
+fn text_attrs(rest: &str) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+    let mut rest = rest.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().to_string();
+        let after = &rest[eq + 1..];
+        let (value, tail) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match after.find(char::is_whitespace) {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            }
+        };
+        out.insert(key, value.to_string());
+        rest = tail.trim_start();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FNT: &str = r#"info face="Pixel Sans" size=8 bold=0
+common lineHeight=10 base=8 scaleW=64 scaleH=32 pages=1
+page id=0 file="pixel_0.png"
+chars count=4
+char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=3 page=0 chnl=15
+char id=65 x=0 y=0 width=5 height=8 xoffset=0 yoffset=1 xadvance=6 page=0 chnl=15
+char id=86 x=8 y=0 width=5 height=8 xoffset=0 yoffset=1 xadvance=6 page=0 chnl=15
+char id=233 x=16 y=0 width=4 height=9 xoffset=1 yoffset=0 xadvance=5 page=0 chnl=15
+kernings count=1
+kerning first=65 second=86 amount=-1
+"#;
+
+    #[test]
+    fn test_bmfont_header_and_pages() {
+        let font = BitmapFont::parse(FNT, Path::new("fonts")).unwrap();
+        assert_eq!(font.face, "Pixel Sans");
+        assert_eq!(font.pages, vec![PathBuf::from("fonts/pixel_0.png")]);
+    }
+
+    #[test]
+    fn test_layout_kerns_and_reads_utf8() {
+        let font = BitmapFont::parse(FNT, Path::new("fonts")).unwrap();
+        // "AV" kerns by -1; 'é' is a multi-byte char with its own glyph
+        let l = font.layout("AVé", &LayoutOptions::default());
+        let xs: Vec<f32> = l.quads.iter().map(|q| q.top_left.x).collect();
+        assert_eq!(xs, vec![0.0, 5.0, 12.0]);
+        assert_eq!(l.width, 16.0);
+        assert_eq!(l.quads[1].uv, [8.0 / 64.0, 0.0, 13.0 / 64.0, 0.25]);
+    }
+
+    #[test]
+    fn test_layout_centres_lines() {
+        let font = BitmapFont::parse(FNT, Path::new("fonts")).unwrap();
+        let c = font.layout(
+            "AA",
+            &LayoutOptions {
+                align: Align::Center,
+                ..Default::default()
+            },
+        );
+        assert_eq!(c.quads[0].top_left.x, -6.0);
+    }
+
+    #[test]
+    fn test_layout_wraps_and_justifies() {
+        let font = BitmapFont::parse(FNT, Path::new("fonts")).unwrap();
+        // Wraps at the space; the second line starts one line height plus spacing down
+        let opts = LayoutOptions {
+            max_width: Some(14.0),
+            line_spacing: 2.0,
+            ..Default::default()
+        };
+        let w = font.layout("AA AA", &opts);
+        assert_eq!((w.lines.len(), w.height), (2, 22.0));
+        assert_eq!(w.quads[2].top_left, Vec2::new(0.0, 13.0));
+        assert_eq!((w.lines[1].first, w.lines[1].count), (3, 2));
+        assert_eq!(w.quads[2].index, 3);
+
+        // Justify stretches the space of the wrapped first line only
+        let j = font.layout(
+            "A A A",
+            &LayoutOptions {
+                align: Align::Justify,
+                max_width: Some(18.0),
+                ..opts
+            },
+        );
+        let xs: Vec<f32> = j.quads.iter().map(|q| q.top_left.x).collect();
+        assert_eq!(xs, vec![0.0, 12.0, 0.0]);
+        assert_eq!(j.lines[0].width, 18.0);
+    }
+
+    #[test]
+    fn test_markup_spans() {
+        let (plain, spans) = parse_markup(
+            "Hi [color=#F00]red [wave]w[/wave][/color] [b] [[x]",
+            &["color", "wave"],
+        )
+        .unwrap();
+        assert_eq!(plain, "Hi red w [b] [x]");
+        assert_eq!(
+            spans[0],
+            MarkupSpan {
+                tag: "color".into(),
+                value: Some("#F00".into()),
+                start: 3,
+                end: 8
+            }
+        );
+        assert_eq!((spans[1].start, spans[1].end), (7, 8));
+        assert!(parse_markup("[/wave]", &["wave"]).is_err());
+    }
+}
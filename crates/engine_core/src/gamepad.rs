@@ -0,0 +1,401 @@
+// Gamepads: button/axis names, per-pad state with deadzones and stable slot
+// assignment. The gilrs backend (feature "gamepad") and headless tests both drive
+// pads through the same `InputState` methods, so recordings replay identically.
+use crate::input::InputState;
+
+/// Pads tracked at once; indices stay fixed while a pad is unplugged
+pub const MAX_PADS: usize = 4;
+/// Radial deadzone for sticks, as a fraction of full deflection
+pub const STICK_DEADZONE: f32 = 0.15;
+pub const TRIGGER_DEADZONE: f32 = 0.05;
+
+/// Buttons by position (Xbox A = South, PlayStation Cross = South)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 15] = [
+        Self::South,
+        Self::East,
+        Self::West,
+        Self::North,
+        Self::LeftBumper,
+        Self::RightBumper,
+        Self::Select,
+        Self::Start,
+        Self::Mode,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::South => "South",
+            Self::East => "East",
+            Self::West => "West",
+            Self::North => "North",
+            Self::LeftBumper => "LeftBumper",
+            Self::RightBumper => "RightBumper",
+            Self::Select => "Select",
+            Self::Start => "Start",
+            Self::Mode => "Mode",
+            Self::LeftStick => "LeftStick",
+            Self::RightStick => "RightStick",
+            Self::DPadUp => "DPadUp",
+            Self::DPadDown => "DPadDown",
+            Self::DPadLeft => "DPadLeft",
+            Self::DPadRight => "DPadRight",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.name() == name)
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Analog inputs. Sticks are -1..1 with y up; triggers are 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 6] = [
+        Self::LeftX,
+        Self::LeftY,
+        Self::RightX,
+        Self::RightY,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LeftX => "LeftX",
+            Self::LeftY => "LeftY",
+            Self::RightX => "RightX",
+            Self::RightY => "RightY",
+            Self::LeftTrigger => "LeftTrigger",
+            Self::RightTrigger => "RightTrigger",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Raw state of one pad; deadzones are applied when axes are read
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PadState {
+    pub connected: bool,
+    /// One bit per `PadButton`, in declaration order
+    pub buttons: u32,
+    pub axes: [f32; 6],
+}
+
+impl PadState {
+    pub fn down(&self, button: PadButton) -> bool {
+        self.buttons & button.bit() != 0
+    }
+
+    pub fn set_button(&mut self, button: PadButton, down: bool) {
+        if down {
+            self.buttons |= button.bit();
+        } else {
+            self.buttons &= !button.bit();
+        }
+    }
+
+    /// Axis value with the stick (radial) or trigger deadzone applied
+    pub fn axis(&self, axis: PadAxis) -> f32 {
+        let a = &self.axes;
+        match axis {
+            PadAxis::LeftX => stick_deadzone([a[0], a[1]], STICK_DEADZONE)[0],
+            PadAxis::LeftY => stick_deadzone([a[0], a[1]], STICK_DEADZONE)[1],
+            PadAxis::RightX => stick_deadzone([a[2], a[3]], STICK_DEADZONE)[0],
+            PadAxis::RightY => stick_deadzone([a[2], a[3]], STICK_DEADZONE)[1],
+            PadAxis::LeftTrigger => trigger_deadzone(a[4], TRIGGER_DEADZONE),
+            PadAxis::RightTrigger => trigger_deadzone(a[5], TRIGGER_DEADZONE),
+        }
+    }
+}
+
+/// Zero sticks inside `deadzone` and rescale the rest so output still spans 0..1
+/// in every direction
+pub fn stick_deadzone(v: [f32; 2], deadzone: f32) -> [f32; 2] {
+    let len = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    if len <= deadzone || len == 0.0 {
+        return [0.0, 0.0];
+    }
+    let scaled = ((len - deadzone) / (1.0 - deadzone)).min(1.0);
+    [v[0] / len * scaled, v[1] / len * scaled]
+}
+
+fn trigger_deadzone(v: f32, deadzone: f32) -> f32 {
+    if v <= deadzone {
+        0.0
+    } else {
+        ((v - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// Maps backend device ids to stable pad indices. A reconnecting device gets its
+/// old index back; new devices take a fresh index before reusing an unplugged one.
+#[derive(Debug, Default)]
+pub struct PadSlots {
+    // (device id, connected) per index
+    slots: Vec<(usize, bool)>,
+}
+
+impl PadSlots {
+    pub fn connect(&mut self, device: usize) -> Option<usize> {
+        let index = match self.slots.iter().position(|s| s.0 == device) {
+            Some(i) => i,
+            None if self.slots.len() < MAX_PADS => {
+                self.slots.push((device, true));
+                self.slots.len() - 1
+            }
+            None => self.slots.iter().position(|s| !s.1)?,
+        };
+        self.slots[index] = (device, true);
+        Some(index)
+    }
+
+    pub fn disconnect(&mut self, device: usize) -> Option<usize> {
+        let index = self.index_of(device)?;
+        self.slots[index].1 = false;
+        Some(index)
+    }
+
+    /// Index of a connected device
+    pub fn index_of(&self, device: usize) -> Option<usize> {
+        self.slots.iter().position(|&(d, c)| d == device && c)
+    }
+}
+
+/// Physical pads through gilrs
+#[cfg(feature = "gamepad")]
+pub mod backend {
+    use super::{PadAxis, PadButton, PadSlots};
+    use crate::input::InputState;
+    use anyhow::Result;
+    use gilrs::{Axis, Button, EventType, Gilrs};
+
+    pub struct GamepadBackend {
+        gilrs: Gilrs,
+        slots: PadSlots,
+        started: bool,
+    }
+
+    fn button(b: Button) -> Option<PadButton> {
+        Some(match b {
+            Button::South => PadButton::South,
+            Button::East => PadButton::East,
+            Button::West => PadButton::West,
+            Button::North => PadButton::North,
+            Button::LeftTrigger => PadButton::LeftBumper,
+            Button::RightTrigger => PadButton::RightBumper,
+            Button::Select => PadButton::Select,
+            Button::Start => PadButton::Start,
+            Button::Mode => PadButton::Mode,
+            Button::LeftThumb => PadButton::LeftStick,
+            Button::RightThumb => PadButton::RightStick,
+            Button::DPadUp => PadButton::DPadUp,
+            Button::DPadDown => PadButton::DPadDown,
+            Button::DPadLeft => PadButton::DPadLeft,
+            Button::DPadRight => PadButton::DPadRight,
+            _ => return None,
+        })
+    }
+
+    fn axis(a: Axis) -> Option<PadAxis> {
+        Some(match a {
+            Axis::LeftStickX => PadAxis::LeftX,
+            Axis::LeftStickY => PadAxis::LeftY,
+            Axis::RightStickX => PadAxis::RightX,
+            Axis::RightStickY => PadAxis::RightY,
+            Axis::LeftZ => PadAxis::LeftTrigger,
+            Axis::RightZ => PadAxis::RightTrigger,
+            _ => return None,
+        })
+    }
+
+    impl GamepadBackend {
+        pub fn new() -> Result<Self> {
+            let gilrs = Gilrs::new().map_err(|e| anyhow::Error::msg(e.to_string()))?;
+            Ok(Self {
+                gilrs,
+                slots: PadSlots::default(),
+                started: false,
+            })
+        }
+
+        /// Apply pending device events to `input`
+        pub fn poll(&mut self, input: &mut InputState) {
+            // Pads plugged in before startup do not send Connected events
+            if !self.started {
+                self.started = true;
+                for (id, _) in self.gilrs.gamepads() {
+                    if let Some(i) = self.slots.connect(id.into()) {
+                        input.connect_pad(i);
+                    }
+                }
+            }
+            while let Some(ev) = self.gilrs.next_event() {
+                let device: usize = ev.id.into();
+                match ev.event {
+                    EventType::Connected => {
+                        if let Some(i) = self.slots.connect(device) {
+                            input.connect_pad(i);
+                        }
+                    }
+                    EventType::Disconnected => {
+                        if let Some(i) = self.slots.disconnect(device) {
+                            input.disconnect_pad(i);
+                        }
+                    }
+                    EventType::ButtonPressed(b, _) | EventType::ButtonReleased(b, _) => {
+                        let down = matches!(ev.event, EventType::ButtonPressed(..));
+                        if let (Some(i), Some(b)) = (self.slots.index_of(device), button(b)) {
+                            input.set_pad_button(i, b, down);
+                        }
+                    }
+                    // Analog triggers report as buttons on most drivers
+                    EventType::ButtonChanged(b, v, _) => {
+                        let a = match b {
+                            Button::LeftTrigger2 => PadAxis::LeftTrigger,
+                            Button::RightTrigger2 => PadAxis::RightTrigger,
+                            _ => continue,
+                        };
+                        if let Some(i) = self.slots.index_of(device) {
+                            input.set_pad_axis(i, a, v);
+                        }
+                    }
+                    EventType::AxisChanged(a, v, _) => {
+                        if let (Some(i), Some(a)) = (self.slots.index_of(device), axis(a)) {
+                            input.set_pad_axis(i, a, v);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Scripted pad for tests and headless runs; writes through `InputState`
+pub struct VirtualGamepad<'a> {
+    input: &'a mut InputState,
+    index: usize,
+}
+
+impl<'a> VirtualGamepad<'a> {
+    /// Plug in pad `index` (0-based)
+    pub fn connect(input: &'a mut InputState, index: usize) -> Self {
+        input.connect_pad(index);
+        Self { input, index }
+    }
+
+    pub fn press(&mut self, button: PadButton) -> &mut Self {
+        self.input.set_pad_button(self.index, button, true);
+        self
+    }
+
+    pub fn release(&mut self, button: PadButton) -> &mut Self {
+        self.input.set_pad_button(self.index, button, false);
+        self
+    }
+
+    pub fn axis(&mut self, axis: PadAxis, value: f32) -> &mut Self {
+        self.input.set_pad_axis(self.index, axis, value);
+        self
+    }
+
+    pub fn disconnect(self) {
+        self.input.disconnect_pad(self.index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stick_and_trigger_deadzones() {
+        assert_eq!(stick_deadzone([0.1, 0.1], 0.15), [0.0, 0.0]);
+        // Full deflection stays full; the deadzone edge maps to zero
+        assert_eq!(stick_deadzone([0.0, -1.0], 0.15), [0.0, -1.0]);
+        let [x, _] = stick_deadzone([0.575, 0.0], 0.15);
+        assert!((x - 0.5).abs() < 1e-6);
+        assert_eq!(trigger_deadzone(0.04, 0.05), 0.0);
+    }
+
+    #[test]
+    fn test_reconnecting_pad_keeps_its_slot() {
+        let mut slots = PadSlots::default();
+        assert_eq!(slots.connect(7), Some(0));
+        assert_eq!(slots.connect(3), Some(1));
+        assert_eq!(slots.disconnect(7), Some(0));
+        assert_eq!(slots.index_of(7), None);
+        // New devices get fresh slots; the unplugged one comes back to index 0
+        assert_eq!(slots.connect(9), Some(2));
+        assert_eq!(slots.connect(7), Some(0));
+        assert_eq!(slots.connect(10), Some(3));
+        assert_eq!(slots.connect(11), None);
+        slots.disconnect(3);
+        assert_eq!(slots.connect(11), Some(1));
+    }
+
+    #[test]
+    fn test_virtual_gamepad_drives_input_state() {
+        let mut input = InputState::new();
+        VirtualGamepad::connect(&mut input, 1)
+            .press(PadButton::South)
+            .press(PadButton::DPadLeft)
+            .axis(PadAxis::LeftX, -0.3)
+            .axis(PadAxis::RightTrigger, 1.0);
+        let pad = input.pads[1];
+        assert!(pad.down(PadButton::South) && !pad.down(PadButton::East));
+        assert_eq!(pad.axis(PadAxis::RightTrigger), 1.0);
+        assert_eq!(pad.axis(PadAxis::LeftY), 0.0);
+
+        VirtualGamepad::connect(&mut input, 1).disconnect();
+        assert_eq!(input.pads[1], PadState::default());
+    }
+
+    #[test]
+    fn test_button_and_axis_names() {
+        assert_eq!(PadButton::parse("DPadLeft"), Some(PadButton::DPadLeft));
+        assert_eq!(PadAxis::parse("Up"), None);
+    }
+}
@@ -1,80 +1,319 @@
 use crate::metrics::MetricsCollector;
+use std::collections::VecDeque;
 
-// Minimal 4x6 bitmap font for a limited ASCII subset (A-Z, 0-9, space, colon, period, slash, dash, underscore, pipe)
-// Each glyph is 4x6 pixels, stored as 6 rows of 4 bits (LSB on the right).
-fn glyph_bits(c: char) -> [u8; 6] {
-    use std::collections::HashMap;
-    fn map() -> HashMap<char, [u8; 6]> {
-        let mut m = HashMap::new();
-        // Digits 0-9
-        m.insert('0', [0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110]);
-        m.insert('1', [0b0010, 0b0110, 0b0010, 0b0010, 0b0010, 0b0111]);
-        m.insert('2', [0b0110, 0b1001, 0b0001, 0b0010, 0b0100, 0b1111]);
-        m.insert('3', [0b1110, 0b0001, 0b0110, 0b0001, 0b0001, 0b1110]);
-        m.insert('4', [0b0001, 0b0011, 0b0101, 0b1001, 0b1111, 0b0001]);
-        m.insert('5', [0b1111, 0b1000, 0b1110, 0b0001, 0b1001, 0b0110]);
-        m.insert('6', [0b0110, 0b1000, 0b1110, 0b1001, 0b1001, 0b0110]);
-        m.insert('7', [0b1111, 0b0001, 0b0010, 0b0010, 0b0100, 0b0100]);
-        m.insert('8', [0b0110, 0b1001, 0b0110, 0b1001, 0b1001, 0b0110]);
-        m.insert('9', [0b0110, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110]);
-        // A-Z
-        m.insert('A', [0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001]);
-        m.insert('B', [0b1110, 0b1001, 0b1110, 0b1001, 0b1001, 0b1110]);
-        m.insert('C', [0b0110, 0b1001, 0b1000, 0b1000, 0b1001, 0b0110]);
-        m.insert('D', [0b1110, 0b1001, 0b1001, 0b1001, 0b1001, 0b1110]);
-        m.insert('E', [0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111]);
-        m.insert('F', [0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1000]);
-        m.insert('G', [0b0110, 0b1001, 0b1000, 0b1011, 0b1001, 0b0110]);
-        m.insert('H', [0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b1001]);
-        m.insert('I', [0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111]);
-        m.insert('J', [0b0001, 0b0001, 0b0001, 0b1001, 0b1001, 0b0110]);
-        m.insert('K', [0b1001, 0b1010, 0b1100, 0b1010, 0b1010, 0b1001]);
-        m.insert('L', [0b1000, 0b1000, 0b1000, 0b1000, 0b1000, 0b1111]);
-        m.insert('M', [0b1001, 0b1111, 0b1111, 0b1001, 0b1001, 0b1001]);
-        m.insert('N', [0b1001, 0b1101, 0b1101, 0b1011, 0b1011, 0b1001]);
-        m.insert('O', [0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110]);
-        m.insert('P', [0b1110, 0b1001, 0b1110, 0b1000, 0b1000, 0b1000]);
-        m.insert('Q', [0b0110, 0b1001, 0b1001, 0b1011, 0b1010, 0b0111]);
-        m.insert('R', [0b1110, 0b1001, 0b1110, 0b1010, 0b1001, 0b1001]);
-        m.insert('S', [0b0111, 0b1000, 0b0110, 0b0001, 0b0001, 0b1110]);
-        m.insert('T', [0b1111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0010]);
-        m.insert('U', [0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110]);
-        m.insert('V', [0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0110]);
-        m.insert('W', [0b1001, 0b1001, 0b1001, 0b1111, 0b1111, 0b1001]);
-        m.insert('X', [0b1001, 0b1001, 0b0110, 0b0110, 0b1001, 0b1001]);
-        m.insert('Y', [0b1001, 0b1001, 0b0110, 0b0010, 0b0010, 0b0010]);
-        m.insert('Z', [0b1111, 0b0001, 0b0010, 0b0100, 0b1000, 0b1111]);
-        // Punct/space
-        m.insert(' ', [0, 0, 0, 0, 0, 0]);
-        m.insert(':', [0, 0b0010, 0, 0, 0b0010, 0]);
-        m.insert('.', [0, 0, 0, 0, 0b0011, 0b0011]);
-        m.insert('/', [0b0001, 0b0010, 0b0010, 0b0100, 0b0100, 0b1000]);
-        m.insert('-', [0, 0, 0b1111, 0, 0, 0]);
-        m.insert('_', [0, 0, 0, 0, 0, 0b1111]);
-        m.insert('|', [0b0010, 0b0010, 0b0010, 0b0010, 0b0010, 0b0010]);
-        m
-    }
-    thread_local! {
-        static GLYPHS: std::cell::RefCell<Option<std::collections::HashMap<char, [u8;6]>>> = const { std::cell::RefCell::new(None) };
+// Built-in debug font: printable ASCII and Latin-1 in a 4x10 cell. Each glyph is 10
+// rows of 4 bits (LSB on the right): rows 0-1 hold accents on capitals, rows 2-7 the
+// body (baseline under row 7) and rows 8-9 descenders.
+pub const GLYPH_W: u32 = 4;
+pub const GLYPH_H: u32 = 10;
+const CELL_W: u32 = GLYPH_W + 1;
+const CELL_H: u32 = GLYPH_H + 1;
+
+/// Script lines the host keeps for the HUD; `HudConfig::lines` is capped to this
+pub const HISTORY_LINES: usize = 64;
+
+// Frames between metrics header refreshes (~4 Hz at 60 FPS), so a jittering FPS
+// counter doesn't force a rebuild every frame
+const HEADER_REFRESH_FRAMES: u32 = 15;
+
+// U+0020..=U+007E
+#[rustfmt::skip]
+static ASCII: [[u8; 10]; 95] = [
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // ' '
+    [0b0000, 0b0000, 0b0010, 0b0010, 0b0010, 0b0010, 0b0000, 0b0010, 0b0000, 0b0000], // '!'
+    [0b0000, 0b0000, 0b0101, 0b0101, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '"'
+    [0b0000, 0b0000, 0b0101, 0b1111, 0b0101, 0b0101, 0b1111, 0b0101, 0b0000, 0b0000], // '#'
+    [0b0000, 0b0000, 0b0111, 0b1010, 0b0110, 0b0011, 0b1110, 0b0010, 0b0000, 0b0000], // '$'
+    [0b0000, 0b0000, 0b1001, 0b0001, 0b0010, 0b0100, 0b1000, 0b1001, 0b0000, 0b0000], // '%'
+    [0b0000, 0b0000, 0b0100, 0b1010, 0b0100, 0b1011, 0b1001, 0b0111, 0b0000, 0b0000], // '&'
+    [0b0000, 0b0000, 0b0010, 0b0010, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '\''
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0100, 0b0100, 0b0100, 0b0010, 0b0000, 0b0000], // '('
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0010, 0b0010, 0b0010, 0b0100, 0b0000, 0b0000], // ')'
+    [0b0000, 0b0000, 0b0000, 0b1001, 0b0110, 0b1111, 0b0110, 0b1001, 0b0000, 0b0000], // '*'
+    [0b0000, 0b0000, 0b0000, 0b0010, 0b0010, 0b0111, 0b0010, 0b0010, 0b0000, 0b0000], // '+'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0010, 0b0100, 0b0000], // ','
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1111, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '-'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0011, 0b0011, 0b0000, 0b0000], // '.'
+    [0b0000, 0b0000, 0b0001, 0b0010, 0b0010, 0b0100, 0b0100, 0b1000, 0b0000, 0b0000], // '/'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // '0'
+    [0b0000, 0b0000, 0b0010, 0b0110, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // '1'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0001, 0b0010, 0b0100, 0b1111, 0b0000, 0b0000], // '2'
+    [0b0000, 0b0000, 0b1110, 0b0001, 0b0110, 0b0001, 0b0001, 0b1110, 0b0000, 0b0000], // '3'
+    [0b0000, 0b0000, 0b0001, 0b0011, 0b0101, 0b1001, 0b1111, 0b0001, 0b0000, 0b0000], // '4'
+    [0b0000, 0b0000, 0b1111, 0b1000, 0b1110, 0b0001, 0b1001, 0b0110, 0b0000, 0b0000], // '5'
+    [0b0000, 0b0000, 0b0110, 0b1000, 0b1110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // '6'
+    [0b0000, 0b0000, 0b1111, 0b0001, 0b0010, 0b0010, 0b0100, 0b0100, 0b0000, 0b0000], // '7'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // '8'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110, 0b0000, 0b0000], // '9'
+    [0b0000, 0b0000, 0b0000, 0b0010, 0b0000, 0b0000, 0b0010, 0b0000, 0b0000, 0b0000], // ':'
+    [0b0000, 0b0000, 0b0000, 0b0010, 0b0000, 0b0000, 0b0000, 0b0010, 0b0100, 0b0000], // ';'
+    [0b0000, 0b0000, 0b0001, 0b0010, 0b0100, 0b0010, 0b0001, 0b0000, 0b0000, 0b0000], // '<'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1111, 0b0000, 0b1111, 0b0000, 0b0000, 0b0000], // '='
+    [0b0000, 0b0000, 0b1000, 0b0100, 0b0010, 0b0100, 0b1000, 0b0000, 0b0000, 0b0000], // '>'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0010, 0b0010, 0b0000, 0b0010, 0b0000, 0b0000], // '?'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1011, 0b1011, 0b1000, 0b0110, 0b0000, 0b0000], // '@'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'A'
+    [0b0000, 0b0000, 0b1110, 0b1001, 0b1110, 0b1001, 0b1001, 0b1110, 0b0000, 0b0000], // 'B'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1000, 0b1000, 0b1001, 0b0110, 0b0000, 0b0000], // 'C'
+    [0b0000, 0b0000, 0b1110, 0b1001, 0b1001, 0b1001, 0b1001, 0b1110, 0b0000, 0b0000], // 'D'
+    [0b0000, 0b0000, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'E'
+    [0b0000, 0b0000, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1000, 0b0000, 0b0000], // 'F'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1000, 0b1011, 0b1001, 0b0110, 0b0000, 0b0000], // 'G'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b1001, 0b0000, 0b0000], // 'H'
+    [0b0000, 0b0000, 0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'I'
+    [0b0000, 0b0000, 0b0001, 0b0001, 0b0001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'J'
+    [0b0000, 0b0000, 0b1001, 0b1010, 0b1100, 0b1010, 0b1010, 0b1001, 0b0000, 0b0000], // 'K'
+    [0b0000, 0b0000, 0b1000, 0b1000, 0b1000, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'L'
+    [0b0000, 0b0000, 0b1001, 0b1111, 0b1111, 0b1001, 0b1001, 0b1001, 0b0000, 0b0000], // 'M'
+    [0b0000, 0b0000, 0b1001, 0b1101, 0b1101, 0b1011, 0b1011, 0b1001, 0b0000, 0b0000], // 'N'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'O'
+    [0b0000, 0b0000, 0b1110, 0b1001, 0b1110, 0b1000, 0b1000, 0b1000, 0b0000, 0b0000], // 'P'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b1011, 0b1010, 0b0111, 0b0000, 0b0000], // 'Q'
+    [0b0000, 0b0000, 0b1110, 0b1001, 0b1110, 0b1010, 0b1001, 0b1001, 0b0000, 0b0000], // 'R'
+    [0b0000, 0b0000, 0b0111, 0b1000, 0b0110, 0b0001, 0b0001, 0b1110, 0b0000, 0b0000], // 'S'
+    [0b0000, 0b0000, 0b1111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0010, 0b0000, 0b0000], // 'T'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'U'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0110, 0b0000, 0b0000], // 'V'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b1111, 0b1111, 0b1001, 0b0000, 0b0000], // 'W'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b0110, 0b0110, 0b1001, 0b1001, 0b0000, 0b0000], // 'X'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b0110, 0b0010, 0b0010, 0b0010, 0b0000, 0b0000], // 'Y'
+    [0b0000, 0b0000, 0b1111, 0b0001, 0b0010, 0b0100, 0b1000, 0b1111, 0b0000, 0b0000], // 'Z'
+    [0b0000, 0b0000, 0b0110, 0b0100, 0b0100, 0b0100, 0b0100, 0b0110, 0b0000, 0b0000], // '['
+    [0b0000, 0b0000, 0b1000, 0b0100, 0b0100, 0b0010, 0b0010, 0b0001, 0b0000, 0b0000], // '\\'
+    [0b0000, 0b0000, 0b0110, 0b0010, 0b0010, 0b0010, 0b0010, 0b0110, 0b0000, 0b0000], // ']'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '^'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b1111, 0b0000, 0b0000], // '_'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '`'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'a'
+    [0b0000, 0b0000, 0b1000, 0b1000, 0b1110, 0b1001, 0b1001, 0b1110, 0b0000, 0b0000], // 'b'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1000, 0b1000, 0b0111, 0b0000, 0b0000], // 'c'
+    [0b0000, 0b0000, 0b0001, 0b0001, 0b0111, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'd'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0110, 0b1111, 0b1000, 0b0110, 0b0000, 0b0000], // 'e'
+    [0b0000, 0b0000, 0b0011, 0b0100, 0b1110, 0b0100, 0b0100, 0b0100, 0b0000, 0b0000], // 'f'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110], // 'g'
+    [0b0000, 0b0000, 0b1000, 0b1000, 0b1110, 0b1001, 0b1001, 0b1001, 0b0000, 0b0000], // 'h'
+    [0b0000, 0b0000, 0b0010, 0b0000, 0b0110, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'i'
+    [0b0000, 0b0000, 0b0010, 0b0000, 0b0110, 0b0010, 0b0010, 0b0010, 0b0010, 0b1100], // 'j'
+    [0b0000, 0b0000, 0b1000, 0b1000, 0b1010, 0b1100, 0b1010, 0b1001, 0b0000, 0b0000], // 'k'
+    [0b0000, 0b0000, 0b0110, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'l'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1010, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'm'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1110, 0b1001, 0b1001, 0b1001, 0b0000, 0b0000], // 'n'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'o'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1110, 0b1001, 0b1001, 0b1110, 0b1000, 0b1000], // 'p'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1001, 0b1001, 0b0111, 0b0001, 0b0001], // 'q'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1011, 0b1100, 0b1000, 0b1000, 0b0000, 0b0000], // 'r'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1100, 0b0011, 0b1110, 0b0000, 0b0000], // 's'
+    [0b0000, 0b0000, 0b0000, 0b0100, 0b1110, 0b0100, 0b0100, 0b0011, 0b0000, 0b0000], // 't'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'u'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b1001, 0b0110, 0b0110, 0b0000, 0b0000], // 'v'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b1001, 0b1111, 0b1010, 0b0000, 0b0000], // 'w'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b0110, 0b0110, 0b1001, 0b0000, 0b0000], // 'x'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110], // 'y'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1111, 0b0010, 0b0100, 0b1111, 0b0000, 0b0000], // 'z'
+    [0b0000, 0b0000, 0b0011, 0b0100, 0b1000, 0b0100, 0b0100, 0b0011, 0b0000, 0b0000], // '{'
+    [0b0000, 0b0000, 0b0010, 0b0010, 0b0010, 0b0010, 0b0010, 0b0010, 0b0000, 0b0000], // '|'
+    [0b0000, 0b0000, 0b1100, 0b0010, 0b0001, 0b0010, 0b0010, 0b1100, 0b0000, 0b0000], // '}'
+    [0b0000, 0b0000, 0b0000, 0b0101, 0b1010, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '~'
+];
+
+// U+00A0..=U+00FF
+#[rustfmt::skip]
+static LATIN1: [[u8; 10]; 96] = [
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // NBSP
+    [0b0000, 0b0000, 0b0010, 0b0000, 0b0010, 0b0010, 0b0010, 0b0010, 0b0000, 0b0000], // '¡'
+    [0b0000, 0b0000, 0b0010, 0b0111, 0b1010, 0b1010, 0b0111, 0b0010, 0b0000, 0b0000], // '¢'
+    [0b0000, 0b0000, 0b0110, 0b0100, 0b1110, 0b0100, 0b0100, 0b1111, 0b0000, 0b0000], // '£'
+    [0b0000, 0b0000, 0b1001, 0b0110, 0b1001, 0b1001, 0b0110, 0b1001, 0b0000, 0b0000], // '¤'
+    [0b0000, 0b0000, 0b1001, 0b1001, 0b0110, 0b1111, 0b0010, 0b0010, 0b0000, 0b0000], // '¥'
+    [0b0000, 0b0000, 0b0010, 0b0010, 0b0000, 0b0000, 0b0010, 0b0010, 0b0000, 0b0000], // '¦'
+    [0b0000, 0b0000, 0b0111, 0b1000, 0b0110, 0b1001, 0b0110, 0b0001, 0b1110, 0b0000], // '§'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '¨'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1101, 0b1101, 0b1001, 0b0110, 0b0000, 0b0000], // '©'
+    [0b0000, 0b0000, 0b0111, 0b1001, 0b0111, 0b0000, 0b1111, 0b0000, 0b0000, 0b0000], // 'ª'
+    [0b0000, 0b0000, 0b0000, 0b0101, 0b1010, 0b0101, 0b0000, 0b0000, 0b0000, 0b0000], // '«'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1111, 0b0001, 0b0000, 0b0000, 0b0000, 0b0000], // '¬'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0110, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // SHY
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1011, 0b1011, 0b1001, 0b0110, 0b0000, 0b0000], // '®'
+    [0b0000, 0b0000, 0b1111, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '¯'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '°'
+    [0b0000, 0b0000, 0b0010, 0b0111, 0b0010, 0b0000, 0b0111, 0b0000, 0b0000, 0b0000], // '±'
+    [0b0000, 0b0000, 0b1100, 0b0010, 0b0100, 0b1110, 0b0000, 0b0000, 0b0000, 0b0000], // '²'
+    [0b0000, 0b0000, 0b1110, 0b0010, 0b0110, 0b0010, 0b1110, 0b0000, 0b0000, 0b0000], // '³'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '´'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1001, 0b1001, 0b1001, 0b1110, 0b1000, 0b1000], // 'µ'
+    [0b0000, 0b0000, 0b0111, 0b1101, 0b1101, 0b0101, 0b0101, 0b0101, 0b0000, 0b0000], // '¶'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0010, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000], // '·'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0000, 0b0010, 0b0100], // '¸'
+    [0b0000, 0b0000, 0b0100, 0b1100, 0b0100, 0b1110, 0b0000, 0b0000, 0b0000, 0b0000], // '¹'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b0000, 0b1111, 0b0000, 0b0000, 0b0000], // 'º'
+    [0b0000, 0b0000, 0b0000, 0b1010, 0b0101, 0b1010, 0b0000, 0b0000, 0b0000, 0b0000], // '»'
+    [0b0000, 0b0000, 0b1000, 0b1001, 0b0010, 0b0101, 0b1011, 0b0001, 0b0000, 0b0000], // '¼'
+    [0b0000, 0b0000, 0b1000, 0b1010, 0b0100, 0b1011, 0b0001, 0b0011, 0b0000, 0b0000], // '½'
+    [0b0000, 0b0000, 0b1100, 0b1001, 0b1110, 0b0101, 0b1011, 0b0001, 0b0000, 0b0000], // '¾'
+    [0b0000, 0b0000, 0b0010, 0b0000, 0b0010, 0b0100, 0b1001, 0b0110, 0b0000, 0b0000], // '¿'
+    [0b0100, 0b0010, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'À'
+    [0b0010, 0b0100, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'Á'
+    [0b0110, 0b1001, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'Â'
+    [0b0101, 0b1010, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'Ã'
+    [0b1001, 0b0000, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'Ä'
+    [0b0110, 0b0110, 0b0110, 0b1001, 0b1001, 0b1111, 0b1001, 0b1001, 0b0000, 0b0000], // 'Å'
+    [0b0000, 0b0000, 0b0111, 0b1010, 0b1111, 0b1010, 0b1010, 0b1011, 0b0000, 0b0000], // 'Æ'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1000, 0b1000, 0b1001, 0b0110, 0b0010, 0b0100], // 'Ç'
+    [0b0100, 0b0010, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'È'
+    [0b0010, 0b0100, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'É'
+    [0b0110, 0b1001, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'Ê'
+    [0b1001, 0b0000, 0b1111, 0b1000, 0b1110, 0b1000, 0b1000, 0b1111, 0b0000, 0b0000], // 'Ë'
+    [0b0100, 0b0010, 0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'Ì'
+    [0b0010, 0b0100, 0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'Í'
+    [0b0110, 0b1001, 0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'Î'
+    [0b1001, 0b0000, 0b0111, 0b0010, 0b0010, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'Ï'
+    [0b0000, 0b0000, 0b0110, 0b0101, 0b1111, 0b0101, 0b0101, 0b0110, 0b0000, 0b0000], // 'Ð'
+    [0b0101, 0b1010, 0b1001, 0b1101, 0b1101, 0b1011, 0b1011, 0b1001, 0b0000, 0b0000], // 'Ñ'
+    [0b0100, 0b0010, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ò'
+    [0b0010, 0b0100, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ó'
+    [0b0110, 0b1001, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ô'
+    [0b0101, 0b1010, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Õ'
+    [0b1001, 0b0000, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ö'
+    [0b0000, 0b0000, 0b0000, 0b1001, 0b0110, 0b0110, 0b1001, 0b0000, 0b0000, 0b0000], // '×'
+    [0b0000, 0b0000, 0b0111, 0b1011, 0b1011, 0b1101, 0b1101, 0b1110, 0b0000, 0b0000], // 'Ø'
+    [0b0100, 0b0010, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ù'
+    [0b0010, 0b0100, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ú'
+    [0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Û'
+    [0b1001, 0b0000, 0b1001, 0b1001, 0b1001, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'Ü'
+    [0b0010, 0b0100, 0b1001, 0b1001, 0b0110, 0b0010, 0b0010, 0b0010, 0b0000, 0b0000], // 'Ý'
+    [0b0000, 0b0000, 0b1000, 0b1110, 0b1001, 0b1110, 0b1000, 0b1000, 0b0000, 0b0000], // 'Þ'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1010, 0b1001, 0b1001, 0b1010, 0b0000, 0b0000], // 'ß'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'à'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'á'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'â'
+    [0b0000, 0b0000, 0b0101, 0b1010, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'ã'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'ä'
+    [0b0000, 0b0000, 0b0110, 0b0110, 0b0110, 0b0001, 0b1011, 0b0111, 0b0000, 0b0000], // 'å'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b1101, 0b0111, 0b1010, 0b0101, 0b0000, 0b0000], // 'æ'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1000, 0b1000, 0b0111, 0b0010, 0b0100], // 'ç'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0110, 0b1111, 0b1000, 0b0110, 0b0000, 0b0000], // 'è'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0110, 0b1111, 0b1000, 0b0110, 0b0000, 0b0000], // 'é'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b1111, 0b1000, 0b0110, 0b0000, 0b0000], // 'ê'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b0110, 0b1111, 0b1000, 0b0110, 0b0000, 0b0000], // 'ë'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0110, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'ì'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0110, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'í'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'î'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b0110, 0b0010, 0b0010, 0b0111, 0b0000, 0b0000], // 'ï'
+    [0b0000, 0b0000, 0b0110, 0b0010, 0b0111, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'ð'
+    [0b0000, 0b0000, 0b0101, 0b1010, 0b1110, 0b1001, 0b1001, 0b1001, 0b0000, 0b0000], // 'ñ'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'ò'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'ó'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'ô'
+    [0b0000, 0b0000, 0b0101, 0b1010, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'õ'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b0110, 0b1001, 0b1001, 0b0110, 0b0000, 0b0000], // 'ö'
+    [0b0000, 0b0000, 0b0000, 0b0010, 0b0000, 0b1111, 0b0000, 0b0010, 0b0000, 0b0000], // '÷'
+    [0b0000, 0b0000, 0b0000, 0b0000, 0b0111, 0b1011, 0b1101, 0b1110, 0b0000, 0b0000], // 'ø'
+    [0b0000, 0b0000, 0b0100, 0b0010, 0b1001, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'ù'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b1001, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'ú'
+    [0b0000, 0b0000, 0b0110, 0b1001, 0b1001, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'û'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b1001, 0b1001, 0b1001, 0b0111, 0b0000, 0b0000], // 'ü'
+    [0b0000, 0b0000, 0b0010, 0b0100, 0b1001, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110], // 'ý'
+    [0b0000, 0b0000, 0b1000, 0b1000, 0b1110, 0b1001, 0b1001, 0b1110, 0b1000, 0b1000], // 'þ'
+    [0b0000, 0b0000, 0b1001, 0b0000, 0b1001, 0b1001, 0b1001, 0b0111, 0b0001, 0b0110], // 'ÿ'
+];
+
+// Drawn for chars outside both tables so they don't silently vanish
+const MISSING: [u8; 10] = [
+    0b0000, 0b0000, 0b1111, 0b1001, 0b1001, 0b1001, 0b1001, 0b1111, 0b0000, 0b0000,
+];
+
+pub fn glyph_bits(c: char) -> [u8; 10] {
+    match c as u32 {
+        cp @ 0x20..=0x7e => ASCII[(cp - 0x20) as usize],
+        cp @ 0xa0..=0xff => LATIN1[(cp - 0xa0) as usize],
+        // Control chars (tabs, newlines, C1) draw as blanks
+        0x00..=0x1f | 0x7f..=0x9f => [0; 10],
+        _ => MISSING,
     }
-    GLYPHS.with(|cell| {
-        if cell.borrow().is_none() {
-            *cell.borrow_mut() = Some(map());
+}
+
+/// Screen corner the HUD panel is anchored to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HudCorner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl HudCorner {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "top_left" => Some(HudCorner::TopLeft),
+            "top_right" => Some(HudCorner::TopRight),
+            "bottom_left" => Some(HudCorner::BottomLeft),
+            "bottom_right" => Some(HudCorner::BottomRight),
+            _ => None,
         }
-        let map = cell.borrow();
-        if let Some(map) = map.as_ref() {
-            *map.get(&c).unwrap_or(&[0; 6])
-        } else {
-            [0; 6]
+    }
+}
+
+/// Layout and colors of the debug overlay
+#[derive(Debug, Clone, PartialEq)]
+pub struct HudConfig {
+    pub corner: HudCorner,
+    /// Newest script lines shown under the header
+    pub lines: usize,
+    /// Panel width in chars; longer lines are cut
+    pub columns: usize,
+    pub text_color: [u8; 4],
+    pub background: [u8; 4],
+    /// Metrics header (FPS, frame p99, sprites, ...) as the first line
+    pub show_metrics: bool,
+}
+
+impl Default for HudConfig {
+    fn default() -> Self {
+        Self {
+            corner: HudCorner::TopLeft,
+            lines: 9,
+            columns: 64,
+            text_color: [255, 255, 255, 255],
+            background: [0, 0, 0, 160],
+            show_metrics: true,
         }
-    })
+    }
+}
+
+/// Append a script line, dropping the oldest past `HISTORY_LINES`
+pub fn push_line(queue: &mut VecDeque<String>, line: String) {
+    while queue.len() >= HISTORY_LINES {
+        queue.pop_front();
+    }
+    queue.push_back(line);
+}
+
+pub fn metrics_header(metrics: &MetricsCollector) -> String {
+    let stats = metrics.get_performance_stats();
+    let current = metrics.current_metrics();
+    let fps = if current.cpu_frame_ms > 0.0 {
+        1000.0 / current.cpu_frame_ms
+    } else {
+        0.0
+    };
+    let p99 = stats.get("cpu_frame_p99_ms").copied().unwrap_or(0.0);
+    format!(
+        "FPS:{:.1} P99:{:.1} SPR:{} FFI:{} LUA:{:.1}MB GC:{:.2}",
+        fps,
+        p99,
+        current.sprites_submitted,
+        current.ffi_calls,
+        current.lua_mem_mb,
+        current.lua_gc_time_ms
+    )
 }
 
 fn draw_char(rgba: &mut [u8], tex_w: u32, x: u32, y: u32, c: char, color: [u8; 4]) {
     let bits = glyph_bits(c);
     for (row, mask) in bits.iter().enumerate() {
-        for col in 0..4u32 {
-            let on = (mask >> (3 - col)) & 1 == 1;
+        for col in 0..GLYPH_W {
+            let on = (mask >> (GLYPH_W - 1 - col)) & 1 == 1;
             if on {
                 let px = x + col;
                 let py = y + row as u32;
@@ -87,55 +326,144 @@ fn draw_char(rgba: &mut [u8], tex_w: u32, x: u32, y: u32, c: char, color: [u8; 4
     }
 }
 
-pub fn rasterize_hud(lines: &[String], metrics: &MetricsCollector) -> (Vec<u8>, u32, u32) {
-    // Compose first line from metrics
-    let stats = metrics.get_performance_stats();
-    let fps = if metrics.current_metrics().cpu_frame_ms > 0.0 {
-        1000.0 / metrics.current_metrics().cpu_frame_ms
-    } else {
-        0.0
-    };
-    let p99 = stats.get("cpu_frame_p99_ms").copied().unwrap_or(0.0);
-    let sprites = metrics.current_metrics().sprites_submitted;
-    let ffi = metrics.current_metrics().ffi_calls;
-    let header = format!("FPS:{:.1} P99:{:.1} SPR:{} FFI:{}", fps, p99, sprites, ffi);
-
-    // Prepare text buffer (max 10 lines, 64 chars each)
-    let mut all_lines: Vec<String> = Vec::new();
-    all_lines.push(header);
-    for s in lines.iter().rev().take(9) {
-        all_lines.push(s.clone());
-    }
-    let max_chars = 64usize;
-    let char_w = 5u32; // 4px glyph + 1px spacing
-    let char_h = 7u32; // 6px glyph + 1px spacing
-    let width = (max_chars as u32) * char_w;
-    let height = (all_lines.len() as u32) * char_h;
-    let mut rgba = vec![0u8; (width * height * 4) as usize];
-
-    // Background translucent black
-    for y in 0..height {
-        for x in 0..width {
-            let idx = ((y * width + x) * 4) as usize;
-            rgba[idx] = 0;
-            rgba[idx + 1] = 0;
-            rgba[idx + 2] = 0;
-            rgba[idx + 3] = 160;
+/// Rasterize lines into an RGBA panel `config.columns` chars wide
+pub fn rasterize(lines: &[String], config: &HudConfig) -> (Vec<u8>, u32, u32) {
+    let columns = config.columns.max(1);
+    let width = columns as u32 * CELL_W + 1;
+    let height = lines.len() as u32 * CELL_H + 1;
+    let mut rgba = config.background.repeat((width * height) as usize);
+    for (li, line) in lines.iter().enumerate() {
+        for (ci, ch) in line.chars().take(columns).enumerate() {
+            let cx = ci as u32 * CELL_W + 1;
+            let cy = li as u32 * CELL_H + 1;
+            draw_char(&mut rgba, width, cx, cy, ch, config.text_color);
         }
     }
+    (rgba, width, height)
+}
+
+pub enum HudUpdate {
+    Unchanged,
+    /// Nothing to show: no header and no script lines
+    Hidden,
+    Image {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+}
 
-    // Draw lines (uppercase, unsupported chars → space)
-    for (li, line) in all_lines.iter().enumerate() {
-        let mut s = line.to_uppercase();
-        if s.len() > max_chars {
-            s.truncate(max_chars);
+/// Tracks what the overlay last showed so it is only re-rasterized when the text
+/// or config changes
+#[derive(Default)]
+pub struct HudOverlay {
+    header: String,
+    header_age: u32,
+    shown: Option<(HudConfig, Vec<String>)>,
+}
+
+impl HudOverlay {
+    pub fn update(
+        &mut self,
+        config: &HudConfig,
+        script_lines: &VecDeque<String>,
+        metrics: &MetricsCollector,
+    ) -> HudUpdate {
+        let mut lines = Vec::with_capacity(config.lines + 1);
+        if config.show_metrics {
+            if self.header.is_empty() || self.header_age >= HEADER_REFRESH_FRAMES {
+                self.header = metrics_header(metrics);
+                self.header_age = 0;
+            }
+            self.header_age += 1;
+            lines.push(self.header.clone());
         }
-        for (ci, ch) in s.chars().enumerate() {
-            let cx = (ci as u32) * char_w + 1;
-            let cy = (li as u32) * char_h + 1;
-            draw_char(&mut rgba, width, cx, cy, ch, [255, 255, 255, 255]);
+        let count = config.lines.min(HISTORY_LINES);
+        let skip = script_lines.len().saturating_sub(count);
+        lines.extend(script_lines.iter().skip(skip).cloned());
+
+        if let Some((shown_config, shown_lines)) = &self.shown {
+            if shown_config == config && *shown_lines == lines {
+                return HudUpdate::Unchanged;
+            }
         }
+        let update = if lines.is_empty() {
+            HudUpdate::Hidden
+        } else {
+            let (rgba, width, height) = rasterize(&lines, config);
+            HudUpdate::Image {
+                rgba,
+                width,
+                height,
+            }
+        };
+        self.shown = Some((config.clone(), lines));
+        update
     }
+}
 
-    (rgba, width, height)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_font_covers_latin1() {
+        // Every printable ASCII and Latin-1 char has ink, except the blanks
+        let printable = (0x20u32..0x7f)
+            .chain(0xa0..=0xff)
+            .filter_map(char::from_u32);
+        for c in printable.filter(|c| !matches!(c, ' ' | '\u{a0}')) {
+            assert!(glyph_bits(c).iter().any(|r| *r != 0), "{:?} is blank", c);
+        }
+        assert_ne!(glyph_bits('a'), glyph_bits('A'));
+        assert_ne!(glyph_bits('é'), glyph_bits('e'));
+        assert_eq!(glyph_bits('\t'), [0; 10]);
+        assert_eq!(glyph_bits('€'), MISSING);
+    }
+
+    #[test]
+    fn test_overlay_rebuilds_only_when_lines_or_config_change() {
+        let metrics = MetricsCollector::new();
+        let mut overlay = HudOverlay::default();
+        let mut config = HudConfig {
+            show_metrics: false,
+            lines: 2,
+            columns: 8,
+            ..Default::default()
+        };
+        let mut q = VecDeque::new();
+        assert!(matches!(
+            overlay.update(&config, &q, &metrics),
+            HudUpdate::Hidden
+        ));
+        for l in ["a=1", "b=(2)", "c=3%"] {
+            push_line(&mut q, l.to_string());
+        }
+        let HudUpdate::Image {
+            rgba,
+            width,
+            height,
+        } = overlay.update(&config, &q, &metrics)
+        else {
+            panic!("expected a rebuild");
+        };
+        // Only the newest two lines: 8 columns of 5px (+1 border), 2 rows of 11px (+1)
+        assert_eq!((width, height), (41, 23));
+        assert_eq!(&rgba[..4], &[0, 0, 0, 160]);
+        assert!(matches!(
+            overlay.update(&config, &q, &metrics),
+            HudUpdate::Unchanged
+        ));
+
+        config.corner = HudCorner::BottomRight;
+        assert!(matches!(
+            overlay.update(&config, &q, &metrics),
+            HudUpdate::Image { .. }
+        ));
+        push_line(&mut q, "d".to_string());
+        assert!(matches!(
+            overlay.update(&config, &q, &metrics),
+            HudUpdate::Image { .. }
+        ));
+    }
 }
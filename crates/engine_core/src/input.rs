@@ -1,3 +1,4 @@
+use crate::gamepad::{PadAxis, PadButton, PadState, MAX_PADS};
 use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
@@ -6,6 +7,15 @@ pub struct InputState {
     pub mouse_buttons: HashSet<String>,
     pub mouse_x: f64,
     pub mouse_y: f64,
+    /// Wheel movement in lines since the last fixed update (y > 0 scrolls up)
+    pub wheel: [f32; 2],
+    /// Gamepads by stable index
+    pub pads: [PadState; MAX_PADS],
+    /// Characters committed since the last fixed update (typed or from the IME)
+    pub text: String,
+    /// IME composition in progress and its caret as a byte range into it
+    pub preedit: String,
+    pub preedit_cursor: Option<(usize, usize)>,
 }
 
 impl Default for InputState {
@@ -21,10 +31,36 @@ impl InputState {
             mouse_buttons: HashSet::new(),
             mouse_x: 0.0,
             mouse_y: 0.0,
+            wheel: [0.0, 0.0],
+            pads: [PadState::default(); MAX_PADS],
+            text: String::new(),
+            preedit: String::new(),
+            preedit_cursor: None,
         }
     }
 
-    pub fn clear(&mut self) {}
+    /// Reset per-update deltas once a fixed update has seen them
+    pub fn clear(&mut self) {
+        self.wheel = [0.0, 0.0];
+        self.text.clear();
+    }
+
+    /// Append committed text; control characters (Enter, Backspace, Tab..) are left
+    /// to the key state
+    pub fn push_text(&mut self, text: &str) {
+        self.text.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        self.preedit.clear();
+        self.preedit.push_str(text);
+        self.preedit_cursor = cursor;
+    }
+
+    pub fn add_wheel(&mut self, dx: f32, dy: f32) {
+        self.wheel[0] += dx;
+        self.wheel[1] += dy;
+    }
 
     pub fn set_mouse_pos(&mut self, x: f64, y: f64) {
         self.mouse_x = x;
@@ -46,4 +82,29 @@ impl InputState {
             self.mouse_buttons.remove(&name);
         }
     }
+
+    pub fn connect_pad(&mut self, index: usize) {
+        if let Some(pad) = self.pads.get_mut(index) {
+            pad.connected = true;
+        }
+    }
+
+    /// Unplugging clears the pad so no button stays held
+    pub fn disconnect_pad(&mut self, index: usize) {
+        if let Some(pad) = self.pads.get_mut(index) {
+            *pad = PadState::default();
+        }
+    }
+
+    pub fn set_pad_button(&mut self, index: usize, button: PadButton, down: bool) {
+        if let Some(pad) = self.pads.get_mut(index).filter(|p| p.connected) {
+            pad.set_button(button, down);
+        }
+    }
+
+    pub fn set_pad_axis(&mut self, index: usize, axis: PadAxis, value: f32) {
+        if let Some(pad) = self.pads.get_mut(index).filter(|p| p.connected) {
+            pad.axes[axis as usize] = value.clamp(-1.0, 1.0);
+        }
+    }
 }
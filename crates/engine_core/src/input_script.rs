@@ -0,0 +1,149 @@
+use crate::input::InputState;
+use crate::stable_keys;
+use anyhow::Result;
+
+/// Scripted input for headless runs. One line per frame range lists what is held
+/// during those fixed updates; frames nobody mentions have nothing held.
+///
+/// ```text
+/// # frames  held inputs
+/// 0-59      KeyD
+/// 60        KeyD Space
+/// 90-120    MouseLeft mouse=320,200
+/// ```
+///
+/// Keys use the `engine.keys` names, buttons the `engine.get_input` names and
+/// `mouse=x,y` moves the pointer (window pixels), where it stays until moved again.
+#[derive(Debug, Clone, Default)]
+pub struct InputScript {
+    spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    first: u64,
+    last: u64,
+    keys: Vec<u32>,
+    buttons: Vec<String>,
+    mouse: Option<(f64, f64)>,
+}
+
+const BUTTONS: [&str; 5] = [
+    "MouseLeft",
+    "MouseRight",
+    "MouseMiddle",
+    "MouseBack",
+    "MouseForward",
+];
+
+impl InputScript {
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut spans = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(range) = words.next() else {
+                continue;
+            };
+            let err = |msg: String| anyhow::Error::msg(format!("line {}: {}", i + 1, msg));
+            let frame = |s: &str| {
+                s.parse::<u64>()
+                    .map_err(|_| err(format!("bad frame '{}'", s)))
+            };
+            let (first, last) = match range.split_once('-') {
+                Some((a, b)) => (frame(a)?, frame(b)?),
+                None => (frame(range)?, frame(range)?),
+            };
+            if last < first {
+                return Err(err(format!("frame range {} ends before it starts", range)));
+            }
+            let mut span = Span {
+                first,
+                last,
+                keys: Vec::new(),
+                buttons: Vec::new(),
+                mouse: None,
+            };
+            for word in words {
+                if let Some(pos) = word.strip_prefix("mouse=") {
+                    let xy = pos
+                        .split_once(',')
+                        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+                    span.mouse = Some(xy.ok_or_else(|| err(format!("bad position '{}'", pos)))?);
+                } else if BUTTONS.contains(&word) {
+                    span.buttons.push(word.to_string());
+                } else if let Some(code) = stable_keys::from_name(word) {
+                    span.keys.push(code);
+                } else {
+                    return Err(err(format!("unknown input '{}'", word)));
+                }
+            }
+            spans.push(span);
+        }
+        Ok(Self { spans })
+    }
+
+    /// Frames the script covers (one past its last mentioned frame)
+    pub fn len(&self) -> u64 {
+        self.spans.iter().map(|s| s.last + 1).max().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Replace the held keys and buttons with this frame's
+    pub fn apply(&self, frame: u64, input: &mut InputState) {
+        input.keys.clear();
+        input.mouse_buttons.clear();
+        for span in self
+            .spans
+            .iter()
+            .filter(|s| s.first <= frame && frame <= s.last)
+        {
+            input.keys.extend(span.keys.iter().copied());
+            input.mouse_buttons.extend(span.buttons.iter().cloned());
+            if let Some((x, y)) = span.mouse {
+                input.mouse_x = x;
+                input.mouse_y = y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_overlap_and_mouse_sticks() {
+        let script = InputScript::parse(
+            "# walk right, then jump\n\
+             0-3 KeyD\n\
+             \n\
+             2   Space MouseLeft mouse=10,20  # press\n",
+        )
+        .unwrap();
+        assert_eq!(script.len(), 4);
+
+        let mut input = InputState::new();
+        script.apply(2, &mut input);
+        assert!(input.keys.contains(&stable_keys::KEY_D));
+        assert!(input.keys.contains(&stable_keys::SPACE));
+        assert!(input.mouse_buttons.contains("MouseLeft"));
+        assert_eq!((input.mouse_x, input.mouse_y), (10.0, 20.0));
+
+        script.apply(3, &mut input);
+        assert_eq!(input.keys.len(), 1);
+        assert!(input.mouse_buttons.is_empty());
+        assert_eq!((input.mouse_x, input.mouse_y), (10.0, 20.0));
+
+        script.apply(4, &mut input);
+        assert!(input.keys.is_empty());
+
+        let e = InputScript::parse("0 KeyD\n5-1 Space").unwrap_err();
+        assert!(e.to_string().contains("line 2"), "{}", e);
+        assert!(InputScript::parse("0 Jump").is_err());
+        assert!(InputScript::parse("0 mouse=1").is_err());
+    }
+}
@@ -1,12 +1,20 @@
 #![deny(warnings)]
 
+pub mod audio;
+pub mod collision;
+pub mod entity;
+pub mod font;
+pub mod gamepad;
 pub mod hud;
 pub mod input;
+pub mod input_script;
 pub mod metrics;
 pub mod present_pass_math;
 pub mod renderer;
 pub mod resources;
 pub mod stable_keys;
 pub mod state;
+pub mod tilemap;
 pub mod time;
+pub mod ui;
 pub mod window;
@@ -56,6 +56,21 @@ pub struct LetterboxResult {
     pub height: f32,
 }
 
+/// Letterboxed canvas rect for a window and virtual mode, plus whether the canvas is
+/// sampled with linear filtering. The present pass and the UI pass both use this.
+pub fn present_rect(
+    window_width: f32,
+    window_height: f32,
+    virtual_mode: VirtualResolution,
+) -> (LetterboxResult, bool) {
+    let (vw, vh) = virtual_mode.size();
+    let (vw, vh) = (vw as f32, vh as f32);
+    let base = calculate_base_scale(window_width, window_height, vw, vh);
+    let scaling = calculate_final_scaling(base, virtual_mode);
+    let rect = calculate_letterbox_rect(window_width, window_height, vw, vh, scaling.scale);
+    (rect, scaling.use_linear_filtering)
+}
+
 /// Calculates letterboxing position and size for the scaled virtual canvas within the window
 pub fn calculate_letterbox_rect(
     window_width: f32,
@@ -167,6 +182,24 @@ mod tests {
         assert_eq!(result.height, 360.0);
     }
 
+    #[test]
+    fn test_present_rect_matches_ui_native_size() {
+        // Retro in 1000x600: base 3.125 floors to 3 -> 960x540 centered
+        let (rect, linear) = present_rect(1000.0, 600.0, VirtualResolution::Retro320x180);
+        assert_eq!(
+            (rect.x, rect.y, rect.width, rect.height),
+            (20.0, 30.0, 960.0, 540.0)
+        );
+        assert!(!linear);
+
+        let mut state = crate::state::EngineState::new();
+        state.set_virtual_resolution(VirtualResolution::Retro320x180);
+        state.set_window_size(1000, 600);
+        assert_eq!(state.ui_size(), (320.0, 180.0));
+        state.set_ui_space(crate::state::UiSpace::Native);
+        assert_eq!(state.ui_size(), (960.0, 540.0));
+    }
+
     #[test]
     fn test_pixel_to_ndc() {
         // Left edge of 800px window
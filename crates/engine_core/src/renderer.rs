@@ -1,6 +1,11 @@
+use crate::hud::HudCorner;
+use crate::present_pass_math::present_rect;
+use crate::state::UiSpace;
+use crate::tilemap::Tilemap;
 use anyhow::Result;
 use glam::{Mat4, Vec2, Vec4};
 use image::GenericImageView;
+use std::collections::HashMap;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use winit::window::Window;
@@ -157,6 +162,10 @@ pub struct SpriteRenderer {
     // Separate uniform buffer for presentation pass to avoid conflicts
     present_uniform_buffer: wgpu::Buffer,
     present_uniform_bind_group: wgpu::BindGroup,
+    // UI pass projection: UI units onto the letterboxed canvas rect
+    ui_uniform_buffer: wgpu::Buffer,
+    ui_uniform_bind_group: wgpu::BindGroup,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
     texture_bind_group_layout: wgpu::BindGroupLayout,
 
     // Sprite batch data
@@ -168,10 +177,17 @@ pub struct SpriteRenderer {
     texture_bind_groups: Vec<Option<wgpu::BindGroup>>,
     white_texture: Texture,
 
-    // Batches for per-texture draws
-    batches: Vec<DrawBatch>,
+    // Sprite batches and tile layers in draw order
+    draw_items: Vec<DrawItem>,
+    // Sprite batches of UI layers, drawn after the canvas is presented
+    ui_items: Vec<DrawItem>,
+    ui_space: UiSpace,
     last_draw_calls: u32,
 
+    // Cached tilemap chunk meshes and per-tile-layer view matrices
+    tile_chunks: HashMap<TileChunkKey, TileChunkMesh>,
+    tile_layer_uniforms: HashMap<(u32, usize), (wgpu::Buffer, wgpu::BindGroup)>,
+
     // Transforms for entity management
     transforms: std::collections::HashMap<u32, Transform>,
 
@@ -180,6 +196,7 @@ pub struct SpriteRenderer {
     hud_bind_group: Option<wgpu::BindGroup>,
     hud_size: (u32, u32),
     hud_scale: f32,
+    hud_corner: HudCorner,
 
     // Clear/background color
     clear_color: wgpu::Color,
@@ -384,6 +401,20 @@ impl SpriteRenderer {
             }],
             label: Some("present_uniform_bind_group"),
         });
+        let ui_uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ui_uniform_buffer"),
+            size: std::mem::size_of::<[[f32; 4]; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let ui_uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: ui_uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("ui_uniform_bind_group"),
+        });
 
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -483,19 +514,27 @@ impl SpriteRenderer {
             uniform_bind_group,
             present_uniform_buffer,
             present_uniform_bind_group,
+            ui_uniform_buffer,
+            ui_uniform_bind_group,
+            uniform_bind_group_layout,
             texture_bind_group_layout,
             sprite_vertices: Vec::with_capacity(max_sprites as usize * 4),
             sprite_indices: Vec::with_capacity(max_sprites as usize * 6),
             textures,
             texture_bind_groups,
             white_texture,
-            batches: Vec::with_capacity(64),
+            draw_items: Vec::with_capacity(64),
+            ui_items: Vec::new(),
+            ui_space: UiSpace::Virtual,
             last_draw_calls: 0,
+            tile_chunks: HashMap::new(),
+            tile_layer_uniforms: HashMap::new(),
             transforms: std::collections::HashMap::new(),
             hud_texture: None,
             hud_bind_group: None,
             hud_size: (0, 0),
             hud_scale: 2.0,
+            hud_corner: HudCorner::TopLeft,
             clear_color: wgpu::Color::BLACK,
             virtual_mode: crate::state::VirtualResolution::Hd1920x1080,
             scene_texture: None,
@@ -552,7 +591,7 @@ impl SpriteRenderer {
     }
 
     pub fn set_transforms_v2(&mut self, transforms: &[f32]) -> Result<()> {
-        if transforms.len() % 6 != 0 {
+        if !transforms.len().is_multiple_of(6) {
             return Err(anyhow::anyhow!(
                 "Transform array must have stride of 6 (id, x, y, rot, w, h)"
             ));
@@ -579,8 +618,10 @@ impl SpriteRenderer {
         Ok(())
     }
 
-    fn add_sprite_to_batch(&mut self, sprite: SpriteInstance, sprite_idx: usize) -> Result<()> {
+    fn add_sprite_to_batch(&mut self, sprite: SpriteInstance) -> Result<()> {
         let half_size = sprite.size * 0.5;
+        // Scene and UI sprites share the vertex buffer
+        let base_idx = self.sprite_vertices.len() as u16;
 
         // Calculate sprite corners with rotation
         let cos_rot = sprite.rotation.cos();
@@ -617,7 +658,6 @@ impl SpriteRenderer {
         }
 
         // Add indices (two triangles per quad)
-        let base_idx = (sprite_idx * 4) as u16;
         self.sprite_indices.extend_from_slice(&[
             base_idx,
             base_idx + 1,
@@ -693,40 +733,8 @@ impl SpriteRenderer {
                 timestamp_writes: None,
             });
 
-            if !self.sprite_vertices.is_empty() {
-                pass.set_pipeline(&self.render_pipeline);
-                pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-                pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-                pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-
-                // This bind group is only needed if there are sprites with no texture
-                let white_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &self.texture_bind_group_layout,
-                    entries: &[
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: wgpu::BindingResource::TextureView(&self.white_texture.view),
-                        },
-                        wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: wgpu::BindingResource::Sampler(&self.white_texture.sampler),
-                        },
-                    ],
-                    label: Some("white_bg"),
-                });
-
-                for batch in &self.batches {
-                    if let Some(bg) = self.get_bind_group(batch.texture_id) {
-                        pass.set_bind_group(1, bg, &[]);
-                    } else {
-                        pass.set_bind_group(1, &white_bind_group, &[]);
-                    }
-                    pass.draw_indexed(
-                        batch.start_index..(batch.start_index + batch.index_count),
-                        0,
-                        0..1,
-                    );
-                }
+            if !self.draw_items.is_empty() {
+                self.draw_scene(&mut pass);
             }
             drop(pass);
         }
@@ -758,29 +766,11 @@ impl SpriteRenderer {
         present_pass.set_bind_group(0, &self.present_uniform_bind_group, &[]);
 
         // Draw the virtual canvas (letterboxed)
+        let (window_w, window_h) = (self.config.width as f32, self.config.height as f32);
+        let (letterbox, use_linear_scaling) = present_rect(window_w, window_h, self.virtual_mode);
         if let Some(scene) = &self.scene_texture {
-            let (window_w, window_h) = (self.config.width as f32, self.config.height as f32);
-            let (virtual_w, virtual_h) = (self.virtual_size.0 as f32, self.virtual_size.1 as f32);
-
-            let scale = (window_w / virtual_w).min(window_h / virtual_h);
-
-            let (final_scale, use_linear_scaling) = match self.virtual_mode {
-                crate::state::VirtualResolution::Retro320x180 => (scale.floor().max(1.0), false),
-                crate::state::VirtualResolution::Hd1920x1080 => {
-                    let ideal_scale = (scale * 100.0).round() / 100.0;
-                    let nearest_int_scale = scale.round();
-                    if (ideal_scale - nearest_int_scale).abs() < 0.05 {
-                        (nearest_int_scale, false) // Use integer scaling
-                    } else {
-                        (scale, true) // Use linear scaling
-                    }
-                }
-            };
-
-            let scaled_w = virtual_w * final_scale;
-            let scaled_h = virtual_h * final_scale;
-            let offset_x = (window_w - scaled_w) / 2.0;
-            let offset_y = (window_h - scaled_h) / 2.0;
+            let (scaled_w, scaled_h) = (letterbox.width, letterbox.height);
+            let (offset_x, offset_y) = (letterbox.x, letterbox.y);
 
             let ndc_left = (offset_x / window_w) * 2.0 - 1.0;
             let ndc_right = ((offset_x + scaled_w) / window_w) * 2.0 - 1.0;
@@ -852,16 +842,50 @@ impl SpriteRenderer {
             present_pass.draw_indexed(0..6, 0, 0..1);
         }
 
+        // UI layers over the letterboxed canvas, at window resolution
+        if !self.ui_items.is_empty() {
+            let (uw, uh) = self.ui_units();
+            let (sx, sy) = (uw / letterbox.width, uh / letterbox.height);
+            // Window edges in UI units; the letterbox's bottom-left is the UI origin
+            let bottom = letterbox.y + letterbox.height;
+            let projection = Mat4::orthographic_lh(
+                -letterbox.x * sx,
+                (window_w - letterbox.x) * sx,
+                (bottom - window_h) * sy,
+                bottom * sy,
+                -1000.0,
+                1000.0,
+            );
+            self.queue.write_buffer(
+                &self.ui_uniform_buffer,
+                0,
+                bytemuck::cast_slice(&projection.to_cols_array()),
+            );
+            self.draw_items_with(
+                &mut present_pass,
+                &self.ui_items,
+                &self.ui_uniform_bind_group,
+            );
+            present_pass.set_bind_group(0, &self.present_uniform_bind_group, &[]);
+        }
+
         // Draw the HUD on top
         if let Some(hud_bg) = self.hud_bind_group.as_ref() {
             let (hud_w, hud_h) = (self.hud_size.0 as f32, self.hud_size.1 as f32);
             let (win_w, win_h) = (self.config.width as f32, self.config.height as f32);
 
-            // Position HUD at top-left. Convert pixel coordinates to NDC.
-            let x = 10.0; // 10px from left
-            let y = 10.0; // 10px from top
+            // Position HUD 10px in from its corner. Convert pixel coordinates to NDC.
+            let margin = 10.0;
             let w = hud_w * self.hud_scale;
             let h = hud_h * self.hud_scale;
+            let x = match self.hud_corner {
+                HudCorner::TopLeft | HudCorner::BottomLeft => margin,
+                HudCorner::TopRight | HudCorner::BottomRight => win_w - margin - w,
+            };
+            let y = match self.hud_corner {
+                HudCorner::TopLeft | HudCorner::TopRight => margin,
+                HudCorner::BottomLeft | HudCorner::BottomRight => win_h - margin - h,
+            };
 
             let ndc_left = (x / win_w) * 2.0 - 1.0;
             let ndc_right = ((x + w) / win_w) * 2.0 - 1.0;
@@ -988,39 +1012,8 @@ impl SpriteRenderer {
                 timestamp_writes: None,
             });
 
-            if !self.sprite_vertices.is_empty() {
-                pass.set_pipeline(&self.render_pipeline);
-                pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-                pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-                pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-
-                let white_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &self.texture_bind_group_layout,
-                    entries: &[
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: wgpu::BindingResource::TextureView(&self.white_texture.view),
-                        },
-                        wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: wgpu::BindingResource::Sampler(&self.white_texture.sampler),
-                        },
-                    ],
-                    label: Some("white_bg"),
-                });
-
-                for batch in &self.batches {
-                    if let Some(bg) = self.get_bind_group(batch.texture_id) {
-                        pass.set_bind_group(1, bg, &[]);
-                    } else {
-                        pass.set_bind_group(1, &white_bind_group, &[]);
-                    }
-                    pass.draw_indexed(
-                        batch.start_index..(batch.start_index + batch.index_count),
-                        0,
-                        0..1,
-                    );
-                }
+            if !self.draw_items.is_empty() {
+                self.draw_scene(&mut pass);
             }
             drop(pass);
 
@@ -1072,6 +1065,98 @@ impl SpriteRenderer {
         }
     }
 
+    // Shared by the windowed and headless virtual passes
+    fn draw_scene(&self, pass: &mut wgpu::RenderPass<'_>) {
+        self.draw_items_with(pass, &self.draw_items, &self.uniform_bind_group);
+    }
+
+    // UI coordinate space size, mirroring EngineState::ui_size
+    fn ui_units(&self) -> (f32, f32) {
+        match self.ui_space {
+            UiSpace::Virtual => (self.virtual_size.0 as f32, self.virtual_size.1 as f32),
+            UiSpace::Native => {
+                let (window_w, window_h) = (self.config.width as f32, self.config.height as f32);
+                let (rect, _) = present_rect(window_w, window_h, self.virtual_mode);
+                (rect.width, rect.height)
+            }
+        }
+    }
+
+    // Sprite batches bind `sprite_uniforms`; tile layers bring their own view matrix
+    fn draw_items_with(
+        &self,
+        pass: &mut wgpu::RenderPass<'_>,
+        items: &[DrawItem],
+        sprite_uniforms: &wgpu::BindGroup,
+    ) {
+        pass.set_pipeline(&self.render_pipeline);
+
+        // This bind group is only needed if there are sprites with no texture
+        let white_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.white_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.white_texture.sampler),
+                },
+            ],
+            label: Some("white_bg"),
+        });
+        let texture_bg = |tex_id: u32| self.get_bind_group(tex_id).unwrap_or(&white_bind_group);
+
+        // Tile draws swap buffers and bind group 0, so sprite batches rebind lazily
+        let mut sprite_state_bound = false;
+        for item in items {
+            match item {
+                DrawItem::Sprites(batch) => {
+                    if !sprite_state_bound {
+                        pass.set_bind_group(0, sprite_uniforms, &[]);
+                        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                        pass.set_index_buffer(
+                            self.index_buffer.slice(..),
+                            wgpu::IndexFormat::Uint16,
+                        );
+                        sprite_state_bound = true;
+                    }
+                    pass.set_bind_group(1, texture_bg(batch.texture_id), &[]);
+                    pass.draw_indexed(
+                        batch.start_index..(batch.start_index + batch.index_count),
+                        0,
+                        0..1,
+                    );
+                }
+                DrawItem::Tiles(tiles) => {
+                    let Some((_, layer_bg)) =
+                        self.tile_layer_uniforms.get(&(tiles.map, tiles.layer))
+                    else {
+                        continue;
+                    };
+                    pass.set_bind_group(0, layer_bg, &[]);
+                    sprite_state_bound = false;
+                    for &(cx, cy) in &tiles.chunks {
+                        let key = (tiles.map, tiles.layer, cx, cy);
+                        let Some(mesh) = self.tile_chunks.get(&key) else {
+                            continue;
+                        };
+                        let Some((vb, ib)) = &mesh.buffers else {
+                            continue;
+                        };
+                        pass.set_vertex_buffer(0, vb.slice(..));
+                        pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint16);
+                        for &(tex_id, start, count) in &mesh.ranges {
+                            pass.set_bind_group(1, texture_bg(tex_id), &[]);
+                            pass.draw_indexed(start..start + count, 0, 0..1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     pub fn update_from_engine_state(
         &mut self,
         engine_state: &crate::state::EngineState,
@@ -1085,126 +1170,417 @@ impl SpriteRenderer {
             a: cc[3] as f64,
         };
         self.virtual_mode = engine_state.get_virtual_resolution();
+        self.ui_space = engine_state.ui_space();
         // Ensure scene texture exists for current virtual mode
         self.ensure_scene_texture(engine_state)?;
         // Clear previous frame data
         self.sprite_vertices.clear();
         self.sprite_indices.clear();
 
-        // Update transforms
+        // Update transforms; destroyed entities lose theirs even if still in the buffer
         self.set_transforms_v2(engine_state.get_transforms())?;
+        for id in engine_state.destroyed_entities() {
+            self.transforms.remove(id);
+        }
 
         // Sort sprites by (layer order, z), then group consecutive sprites with same texture
-        self.batches.clear();
+        self.draw_items.clear();
+        let tile_draws = self.prepare_tile_layers(engine_state)?;
+        let mut next_tile = 0usize;
         let sprites = engine_state.get_sprites();
 
-        // Filter sprites that have transforms and sort by z-coordinate
-        let mut sorted_sprites: Vec<&crate::state::SpriteData> = sprites
+        // Sprites with transforms plus entity-less quads, sorted by (layer order, z).
+        // The sort is stable, so quads draw over sprites at the same z.
+        let mut queued: Vec<(u32, f32, bool, SpriteInstance)> = sprites
             .iter()
-            .filter(|sd| self.transforms.contains_key(&sd.entity_id))
+            .filter_map(|sd| {
+                let t = self.transforms.get(&sd.entity_id)?;
+                Some((
+                    sd.layer_id,
+                    sd.z,
+                    false,
+                    SpriteInstance {
+                        entity_id: sd.entity_id,
+                        texture_id: sd.texture_id,
+                        position: t.position,
+                        rotation: t.rotation,
+                        size: t.size, // Use direct pixel size
+                        uv_rect: Vec4::from_array(sd.uv),
+                        color: Vec4::from_array(sd.color),
+                    },
+                ))
+            })
             .collect();
+        queued.extend(engine_state.get_quads().iter().map(|q| {
+            (
+                q.layer_id,
+                q.z,
+                true,
+                SpriteInstance {
+                    entity_id: u32::MAX,
+                    texture_id: q.texture_id,
+                    position: Vec2::from_array(q.center),
+                    rotation: 0.0,
+                    size: Vec2::from_array(q.size),
+                    uv_rect: Vec4::from_array(q.uv),
+                    color: Vec4::from_array(q.color),
+                },
+            )
+        }));
         let layers = engine_state.layers();
-        sorted_sprites.sort_by(|a, b| {
-            let ao = layers.order_of(a.layer_id);
-            let bo = layers.order_of(b.layer_id);
+        let is_ui = |layer_id: u32| layers.get(layer_id).is_some_and(|l| l.ui);
+        let (ui_queued, mut queued): (Vec<_>, Vec<_>) =
+            queued.into_iter().partition(|q| is_ui(q.0));
+        queued.sort_by(|a, b| {
+            let ao = layers.order_of(a.0);
+            let bo = layers.order_of(b.0);
             match ao.cmp(&bo) {
-                std::cmp::Ordering::Equal => a
-                    .z
-                    .partial_cmp(&b.z)
-                    .unwrap_or(std::cmp::Ordering::Equal),
+                std::cmp::Ordering::Equal => {
+                    a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal)
+                }
                 other => other,
             }
         });
 
-        let mut sprite_idx = 0usize;
         let mut current_batch_texture = None;
         let mut current_batch_start = 0u32;
 
         let (cam_x, cam_y) = engine_state.camera_xy();
-        for sd in sorted_sprites.into_iter() {
+        for (layer_id, _, is_quad, mut sprite_instance) in queued {
             // Ensure texture and bind group cache
-            if let Some(bytes) = engine_state.get_texture(sd.texture_id) {
-                self.ensure_texture_cached(sd.texture_id, bytes)?;
+            if let Some(bytes) = engine_state.get_texture(sprite_instance.texture_id) {
+                self.ensure_texture_cached(sprite_instance.texture_id, bytes)?;
             }
 
-            if let Some(transform) = self.transforms.get(&sd.entity_id) {
-                // Check if we need to start a new batch (different texture or first sprite)
-                if current_batch_texture != Some(sd.texture_id) {
-                    // Finish previous batch if it exists
-                    if let Some(tex_id) = current_batch_texture {
-                        let end = self.sprite_indices.len() as u32;
-                        if end > current_batch_start {
-                            self.batches.push(DrawBatch {
-                                texture_id: tex_id,
-                                start_index: current_batch_start,
-                                index_count: end - current_batch_start,
-                            });
-                        }
-                    }
-                    // Start new batch
-                    current_batch_texture = Some(sd.texture_id);
-                    current_batch_start = self.sprite_indices.len() as u32;
-                }
+            // Tile layers draw beneath sprites of the same or a higher layer order
+            let order = layers.order_of(layer_id);
+            while next_tile < tile_draws.len() && tile_draws[next_tile].0 <= order {
+                self.finish_batch(current_batch_texture.take(), current_batch_start);
+                self.draw_items
+                    .push(DrawItem::Tiles(tile_draws[next_tile].1.clone()));
+                next_tile += 1;
+            }
 
-                // Compute per-layer camera offset and optional retro snapping
-                let (px, py) = {
-                    let l = layers.get(sd.layer_id);
-                    // Defaults if layer missing
-                    let (parx, pary, ss, sx, sy) = if let Some(layer) = l {
-                        (layer.parallax_x, layer.parallax_y, layer.screen_space, layer.scroll_x, layer.scroll_y)
-                    } else {
-                        (1.0, 1.0, false, 0.0, 0.0)
-                    };
-                    let (ex, ey) = if ss {
-                        (0.0, 0.0)
+            // Check if we need to start a new batch (different texture or first sprite)
+            if current_batch_texture != Some(sprite_instance.texture_id) {
+                // Finish previous batch if it exists
+                self.finish_batch(current_batch_texture, current_batch_start);
+                // Start new batch
+                current_batch_texture = Some(sprite_instance.texture_id);
+                current_batch_start = self.sprite_indices.len() as u32;
+            }
+
+            // Compute per-layer camera offset and optional retro snapping
+            let (px, py) = {
+                let l = layers.get(layer_id);
+                // Defaults if layer missing
+                let (parx, pary, ss, sx, sy) = if let Some(layer) = l {
+                    (
+                        layer.parallax_x,
+                        layer.parallax_y,
+                        layer.screen_space,
+                        layer.scroll_x,
+                        layer.scroll_y,
+                    )
+                } else {
+                    (1.0, 1.0, false, 0.0, 0.0)
+                };
+                let (ex, ey) = if ss {
+                    (0.0, 0.0)
+                } else {
+                    (cam_x * parx + sx, cam_y * pary + sy)
+                };
+                let mut px = sprite_instance.position.x - ex;
+                let mut py = sprite_instance.position.y - ey;
+                if matches!(
+                    self.virtual_mode,
+                    crate::state::VirtualResolution::Retro320x180
+                ) {
+                    // Round to integer pixels on the virtual canvas to avoid shimmer;
+                    // quads snap their corner so odd-sized glyphs stay crisp
+                    let half = if is_quad {
+                        sprite_instance.size * 0.5
                     } else {
-                        (cam_x * parx + sx, cam_y * pary + sy)
+                        Vec2::ZERO
                     };
-                    let mut px = transform.position.x - ex;
-                    let mut py = transform.position.y - ey;
-                    if matches!(self.virtual_mode, crate::state::VirtualResolution::Retro320x180) {
-                        // Round to integer pixels on the virtual canvas to avoid shimmer
-                        px = px.round();
-                        py = py.round();
-                    }
-                    (px, py)
+                    px = (px - half.x).round() + half.x;
+                    py = (py - half.y).round() + half.y;
+                }
+                (px, py)
+            };
+
+            sprite_instance.position = Vec2::new(px, py);
+            self.add_sprite_to_batch(sprite_instance)?;
+        }
+
+        // Finish final batch if it exists
+        self.finish_batch(current_batch_texture, current_batch_start);
+        for (_, tiles) in tile_draws.into_iter().skip(next_tile) {
+            self.draw_items.push(DrawItem::Tiles(tiles));
+        }
+        self.prepare_ui(engine_state, ui_queued)?;
+        self.last_draw_calls = self
+            .draw_items
+            .iter()
+            .chain(&self.ui_items)
+            .map(|item| match item {
+                DrawItem::Sprites(_) => 1,
+                DrawItem::Tiles(t) => t
+                    .chunks
+                    .iter()
+                    .filter_map(|&(cx, cy)| self.tile_chunks.get(&(t.map, t.layer, cx, cy)))
+                    .map(|mesh| mesh.ranges.len() as u32)
+                    .sum(),
+            })
+            .sum();
+
+        Ok(())
+    }
+
+    // Batch UI-layer sprites after the scene's. UI ignores the camera; positions snap
+    // to whole window pixels in native space and to virtual pixels in retro mode.
+    fn prepare_ui(
+        &mut self,
+        engine_state: &crate::state::EngineState,
+        mut queued: Vec<(u32, f32, bool, SpriteInstance)>,
+    ) -> Result<()> {
+        let layers = engine_state.layers();
+        queued.sort_by(|a, b| {
+            (layers.order_of(a.0), a.1)
+                .partial_cmp(&(layers.order_of(b.0), b.1))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let scene_items = self.draw_items.len();
+        let snap = match self.ui_space {
+            UiSpace::Native => true,
+            UiSpace::Virtual => {
+                matches!(
+                    self.virtual_mode,
+                    crate::state::VirtualResolution::Retro320x180
+                )
+            }
+        };
+        let mut current_batch_texture = None;
+        let mut current_batch_start = 0u32;
+        for (_, _, is_quad, mut sprite) in queued {
+            if let Some(bytes) = engine_state.get_texture(sprite.texture_id) {
+                self.ensure_texture_cached(sprite.texture_id, bytes)?;
+            }
+            if current_batch_texture != Some(sprite.texture_id) {
+                self.finish_batch(current_batch_texture, current_batch_start);
+                current_batch_texture = Some(sprite.texture_id);
+                current_batch_start = self.sprite_indices.len() as u32;
+            }
+            if snap {
+                let half = if is_quad {
+                    sprite.size * 0.5
+                } else {
+                    Vec2::ZERO
                 };
+                sprite.position = (sprite.position - half).round() + half;
+            }
+            self.add_sprite_to_batch(sprite)?;
+        }
+        self.finish_batch(current_batch_texture, current_batch_start);
+        self.ui_items = self.draw_items.split_off(scene_items);
+        Ok(())
+    }
+
+    fn finish_batch(&mut self, texture_id: Option<u32>, start_index: u32) {
+        let Some(texture_id) = texture_id else {
+            return;
+        };
+        let end = self.sprite_indices.len() as u32;
+        if end > start_index {
+            self.draw_items.push(DrawItem::Sprites(DrawBatch {
+                texture_id,
+                start_index,
+                index_count: end - start_index,
+            }));
+        }
+    }
 
-                let sprite_instance = SpriteInstance {
-                    entity_id: sd.entity_id,
-                    texture_id: sd.texture_id,
-                    position: glam::Vec2::new(px, py),
-                    rotation: transform.rotation,
-                    size: transform.size, // Use direct pixel size
-                    uv_rect: Vec4::new(sd.uv[0], sd.uv[1], sd.uv[2], sd.uv[3]),
-                    color: Vec4::new(sd.color[0], sd.color[1], sd.color[2], sd.color[3]),
+    // Update per-layer view matrices and rebuild dirty chunks in view. Returns the
+    // visible tile layers with their engine layer order, in draw order.
+    fn prepare_tile_layers(
+        &mut self,
+        engine_state: &crate::state::EngineState,
+    ) -> Result<Vec<(i32, TileDraw)>> {
+        let layers = engine_state.layers();
+        let (cam_x, cam_y) = engine_state.camera_xy();
+        let (vw, vh) = (self.virtual_size.0 as f32, self.virtual_size.1 as f32);
+        let projection = Mat4::orthographic_lh(0.0, vw, 0.0, vh, -1000.0, 1000.0);
+        let mut draws = Vec::new();
+
+        for (map_id, map) in engine_state.tilemaps() {
+            for ts in &map.tilesets {
+                if let Some(bytes) = engine_state.get_texture(ts.texture_id) {
+                    self.ensure_texture_cached(ts.texture_id, bytes)?;
+                }
+            }
+
+            for (li, tl) in map.layers.iter().enumerate() {
+                let layer = layers.id_of(&tl.engine_layer).and_then(|id| layers.get(id));
+                if !tl.visible || layer.is_some_and(|l| !l.visible) {
+                    continue;
+                }
+                let (eye, order) = match layer {
+                    Some(l) if l.screen_space => (Vec2::ZERO, l.order),
+                    Some(l) => (
+                        Vec2::new(
+                            cam_x * l.parallax_x + l.scroll_x,
+                            cam_y * l.parallax_y + l.scroll_y,
+                        ),
+                        l.order,
+                    ),
+                    None => (Vec2::new(cam_x, cam_y), 0),
                 };
-                self.add_sprite_to_batch(sprite_instance, sprite_idx)?;
-                sprite_idx += 1;
+                let mut shift = tl.offset - eye;
+                if matches!(
+                    self.virtual_mode,
+                    crate::state::VirtualResolution::Retro320x180
+                ) {
+                    shift = shift.round();
+                }
+                let matrix = projection * Mat4::from_translation(shift.extend(0.0));
+                let key = (map_id, li);
+                if !self.tile_layer_uniforms.contains_key(&key) {
+                    let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("tile_layer_uniform_buffer"),
+                        size: 64,
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                        mapped_at_creation: false,
+                    });
+                    let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &self.uniform_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffer.as_entire_binding(),
+                        }],
+                        label: Some("tile_layer_bind_group"),
+                    });
+                    self.tile_layer_uniforms.insert(key, (buffer, bind_group));
+                }
+                let (buffer, _) = &self.tile_layer_uniforms[&key];
+                self.queue
+                    .write_buffer(buffer, 0, bytemuck::cast_slice(&matrix.to_cols_array()));
+
+                // Cull whole chunks against the view rectangle in map space
+                let view_min = -shift;
+                let view_max = view_min + Vec2::new(vw, vh);
+                let (cw, ch) = tl.chunk_counts();
+                let mut chunks = Vec::new();
+                for cy in 0..ch {
+                    for cx in 0..cw {
+                        let (min, max) = map.chunk_bounds(li, cx, cy);
+                        if max.x <= view_min.x
+                            || min.x >= view_max.x
+                            || max.y <= view_min.y
+                            || min.y >= view_max.y
+                        {
+                            continue;
+                        }
+                        self.ensure_tile_chunk(map_id, map, li, cx, cy);
+                        chunks.push((cx, cy));
+                    }
+                }
+                if !chunks.is_empty() {
+                    draws.push((
+                        order,
+                        TileDraw {
+                            map: map_id,
+                            layer: li,
+                            chunks,
+                        },
+                    ));
+                }
             }
         }
 
-        // Finish final batch if it exists
-        if let Some(tex_id) = current_batch_texture {
-            let end = self.sprite_indices.len() as u32;
-            if end > current_batch_start {
-                self.batches.push(DrawBatch {
-                    texture_id: tex_id,
-                    start_index: current_batch_start,
-                    index_count: end - current_batch_start,
+        // Forget meshes and uniforms of unloaded maps or layers
+        let live = |map_id: u32, layer: usize| {
+            engine_state
+                .tilemap(map_id)
+                .is_some_and(|m| layer < m.layers.len())
+        };
+        self.tile_chunks.retain(|k, _| live(k.0, k.1));
+        self.tile_layer_uniforms.retain(|k, _| live(k.0, k.1));
+
+        draws.sort_by_key(|(order, _)| *order);
+        Ok(draws)
+    }
+
+    // Rebuild a chunk mesh only when its revision changed since it was cached
+    fn ensure_tile_chunk(&mut self, map_id: u32, map: &Tilemap, layer: usize, cx: u32, cy: u32) {
+        let key = (map_id, layer, cx, cy);
+        let revision = map.layers[layer].chunk_revision(cx, cy);
+        if self
+            .tile_chunks
+            .get(&key)
+            .is_some_and(|m| m.revision == revision)
+        {
+            return;
+        }
+
+        let mut quads = Vec::new();
+        map.chunk_quads(layer, cx, cy, &mut quads);
+        quads.sort_by_key(|q| q.texture_id);
+        let mut vertices = Vec::with_capacity(quads.len() * 4);
+        let mut indices: Vec<u16> = Vec::with_capacity(quads.len() * 6);
+        let mut ranges: Vec<(u32, u32, u32)> = Vec::new();
+        for q in &quads {
+            let half = q.size * 0.5;
+            let corners = [
+                Vec2::new(-half.x, -half.y),
+                Vec2::new(half.x, -half.y),
+                Vec2::new(half.x, half.y),
+                Vec2::new(-half.x, half.y),
+            ];
+            let base = vertices.len() as u16;
+            for (corner, uv) in corners.iter().zip(q.uvs) {
+                let p = q.center + *corner;
+                vertices.push(SpriteVertex {
+                    position: [p.x, p.y, 0.0],
+                    tex_coords: uv,
+                    color: [1.0, 1.0, 1.0, q.alpha],
                 });
             }
+            match ranges.last_mut() {
+                Some(r) if r.0 == q.texture_id => r.2 += 6,
+                _ => ranges.push((q.texture_id, indices.len() as u32, 6)),
+            }
+            indices.extend_from_slice(&[base, base + 1, base + 2, base + 2, base + 3, base]);
         }
-        self.last_draw_calls = self.batches.len() as u32;
 
-        Ok(())
+        let buffers = (!quads.is_empty()).then(|| {
+            let vb = self
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("tile_chunk_vertex_buffer"),
+                    contents: bytemuck::cast_slice(&vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+            let ib = self
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("tile_chunk_index_buffer"),
+                    contents: bytemuck::cast_slice(&indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                });
+            (vb, ib)
+        });
+        self.tile_chunks.insert(
+            key,
+            TileChunkMesh {
+                revision,
+                buffers,
+                ranges,
+            },
+        );
     }
 
     fn ensure_scene_texture(&mut self, engine_state: &crate::state::EngineState) -> Result<()> {
-        let (vw, vh) = match engine_state.get_virtual_resolution() {
-            crate::state::VirtualResolution::Retro320x180 => (320u32, 180u32),
-            crate::state::VirtualResolution::Hd1920x1080 => (1920u32, 1080u32),
-        };
+        let (vw, vh) = engine_state.get_virtual_resolution().size();
 
         let recreate = match &self.scene_texture {
             Some(existing) => {
@@ -1314,6 +1690,42 @@ impl SpriteRenderer {
     }
 
     pub fn set_hud_rgba(&mut self, rgba: &[u8], w: u32, h: u32) -> Result<()> {
+        // The panel texture shares the surface format; swizzle for BGRA surfaces
+        let swizzled;
+        let rgba = match self.config.format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                let mut bgra = rgba.to_vec();
+                for px in bgra.chunks_exact_mut(4) {
+                    px.swap(0, 2);
+                }
+                swizzled = bgra;
+                &swizzled[..]
+            }
+            _ => rgba,
+        };
+        // Same-sized rebuilds only re-upload the pixels
+        if let (Some(tex), true) = (&self.hud_texture, self.hud_size == (w, h)) {
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &tex.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                rgba,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(w * 4),
+                    rows_per_image: Some(h),
+                },
+                wgpu::Extent3d {
+                    width: w,
+                    height: h,
+                    depth_or_array_layers: 1,
+                },
+            );
+            return Ok(());
+        }
         // Create texture and bind group for HUD panel
         let texture = self.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("hud_texture"),
@@ -1379,6 +1791,17 @@ impl SpriteRenderer {
     pub fn set_hud_scale(&mut self, scale: f32) {
         self.hud_scale = scale.max(1.0);
     }
+
+    pub fn set_hud_corner(&mut self, corner: HudCorner) {
+        self.hud_corner = corner;
+    }
+
+    /// Stop drawing the HUD panel until the next `set_hud_rgba`
+    pub fn clear_hud(&mut self) {
+        self.hud_texture = None;
+        self.hud_bind_group = None;
+        self.hud_size = (0, 0);
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -1387,3 +1810,27 @@ struct DrawBatch {
     start_index: u32,
     index_count: u32,
 }
+
+// Visible chunks of one tilemap layer
+#[derive(Debug, Clone)]
+struct TileDraw {
+    map: u32,
+    layer: usize,
+    chunks: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+enum DrawItem {
+    Sprites(DrawBatch),
+    Tiles(TileDraw),
+}
+
+// (map id, layer index, chunk x, chunk y)
+type TileChunkKey = (u32, usize, u32, u32);
+
+struct TileChunkMesh {
+    revision: u64,
+    buffers: Option<(wgpu::Buffer, wgpu::Buffer)>,
+    // (texture id, first index, index count)
+    ranges: Vec<(u32, u32, u32)>,
+}
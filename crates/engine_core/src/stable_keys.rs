@@ -73,6 +73,10 @@ pub const CONTEXT_MENU: u32 = 0x0302;
 pub const ENTER: u32 = 0x0303;
 pub const SPACE: u32 = 0x0304;
 pub const TAB: u32 = 0x0305;
+pub const ESCAPE: u32 = 0x0306;
+pub const PRINT_SCREEN: u32 = 0x0307;
+pub const SCROLL_LOCK: u32 = 0x0308;
+pub const PAUSE: u32 = 0x0309;
 
 // Arrow keys (0x0400 - 0x0403)
 pub const ARROW_DOWN: u32 = 0x0400;
@@ -85,8 +89,10 @@ pub const END: u32 = 0x0500;
 pub const HOME: u32 = 0x0501;
 pub const PAGE_DOWN: u32 = 0x0502;
 pub const PAGE_UP: u32 = 0x0503;
+pub const DELETE: u32 = 0x0504;
+pub const INSERT: u32 = 0x0505;
 
-// Function keys (0x0600 - 0x060B)
+// Function keys (0x0600 - 0x0617)
 pub const F1: u32 = 0x0600;
 pub const F2: u32 = 0x0601;
 pub const F3: u32 = 0x0602;
@@ -99,6 +105,18 @@ pub const F9: u32 = 0x0608;
 pub const F10: u32 = 0x0609;
 pub const F11: u32 = 0x060A;
 pub const F12: u32 = 0x060B;
+pub const F13: u32 = 0x060C;
+pub const F14: u32 = 0x060D;
+pub const F15: u32 = 0x060E;
+pub const F16: u32 = 0x060F;
+pub const F17: u32 = 0x0610;
+pub const F18: u32 = 0x0611;
+pub const F19: u32 = 0x0612;
+pub const F20: u32 = 0x0613;
+pub const F21: u32 = 0x0614;
+pub const F22: u32 = 0x0615;
+pub const F23: u32 = 0x0616;
+pub const F24: u32 = 0x0617;
 
 // Numpad (0x0700 - 0x071F)
 pub const NUMPAD_0: u32 = 0x0700;
@@ -117,12 +135,22 @@ pub const NUMPAD_DIVIDE: u32 = 0x070C;
 pub const NUMPAD_MULTIPLY: u32 = 0x070D;
 pub const NUMPAD_SUBTRACT: u32 = 0x070E;
 pub const NUMPAD_ENTER: u32 = 0x070F;
+pub const NUM_LOCK: u32 = 0x0710;
 
 // International (0x0800 - 0x080F)
 pub const INTL_BACKSLASH: u32 = 0x0800;
 pub const INTL_RO: u32 = 0x0801;
 pub const INTL_YEN: u32 = 0x0802;
 
+// Media (0x0900 - 0x090F)
+pub const MEDIA_PLAY_PAUSE: u32 = 0x0900;
+pub const MEDIA_STOP: u32 = 0x0901;
+pub const MEDIA_TRACK_NEXT: u32 = 0x0902;
+pub const MEDIA_TRACK_PREVIOUS: u32 = 0x0903;
+pub const AUDIO_VOLUME_DOWN: u32 = 0x0904;
+pub const AUDIO_VOLUME_UP: u32 = 0x0905;
+pub const AUDIO_VOLUME_MUTE: u32 = 0x0906;
+
 /// Convert winit KeyCode to stable key code.
 /// Returns None for unsupported keys.
 pub fn winit_to_stable(key: winit::keyboard::KeyCode) -> Option<u32> {
@@ -199,6 +227,10 @@ pub fn winit_to_stable(key: winit::keyboard::KeyCode) -> Option<u32> {
         KeyCode::Enter => Some(ENTER),
         KeyCode::Space => Some(SPACE),
         KeyCode::Tab => Some(TAB),
+        KeyCode::Escape => Some(ESCAPE),
+        KeyCode::PrintScreen => Some(PRINT_SCREEN),
+        KeyCode::ScrollLock => Some(SCROLL_LOCK),
+        KeyCode::Pause => Some(PAUSE),
 
         // Arrows
         KeyCode::ArrowDown => Some(ARROW_DOWN),
@@ -211,6 +243,8 @@ pub fn winit_to_stable(key: winit::keyboard::KeyCode) -> Option<u32> {
         KeyCode::Home => Some(HOME),
         KeyCode::PageDown => Some(PAGE_DOWN),
         KeyCode::PageUp => Some(PAGE_UP),
+        KeyCode::Delete => Some(DELETE),
+        KeyCode::Insert => Some(INSERT),
 
         // Function keys
         KeyCode::F1 => Some(F1),
@@ -225,6 +259,18 @@ pub fn winit_to_stable(key: winit::keyboard::KeyCode) -> Option<u32> {
         KeyCode::F10 => Some(F10),
         KeyCode::F11 => Some(F11),
         KeyCode::F12 => Some(F12),
+        KeyCode::F13 => Some(F13),
+        KeyCode::F14 => Some(F14),
+        KeyCode::F15 => Some(F15),
+        KeyCode::F16 => Some(F16),
+        KeyCode::F17 => Some(F17),
+        KeyCode::F18 => Some(F18),
+        KeyCode::F19 => Some(F19),
+        KeyCode::F20 => Some(F20),
+        KeyCode::F21 => Some(F21),
+        KeyCode::F22 => Some(F22),
+        KeyCode::F23 => Some(F23),
+        KeyCode::F24 => Some(F24),
 
         // Numpad
         KeyCode::Numpad0 => Some(NUMPAD_0),
@@ -243,12 +289,22 @@ pub fn winit_to_stable(key: winit::keyboard::KeyCode) -> Option<u32> {
         KeyCode::NumpadMultiply => Some(NUMPAD_MULTIPLY),
         KeyCode::NumpadSubtract => Some(NUMPAD_SUBTRACT),
         KeyCode::NumpadEnter => Some(NUMPAD_ENTER),
+        KeyCode::NumLock => Some(NUM_LOCK),
 
         // International
         KeyCode::IntlBackslash => Some(INTL_BACKSLASH),
         KeyCode::IntlRo => Some(INTL_RO),
         KeyCode::IntlYen => Some(INTL_YEN),
 
+        // Media
+        KeyCode::MediaPlayPause => Some(MEDIA_PLAY_PAUSE),
+        KeyCode::MediaStop => Some(MEDIA_STOP),
+        KeyCode::MediaTrackNext => Some(MEDIA_TRACK_NEXT),
+        KeyCode::MediaTrackPrevious => Some(MEDIA_TRACK_PREVIOUS),
+        KeyCode::AudioVolumeDown => Some(AUDIO_VOLUME_DOWN),
+        KeyCode::AudioVolumeUp => Some(AUDIO_VOLUME_UP),
+        KeyCode::AudioVolumeMute => Some(AUDIO_VOLUME_MUTE),
+
         // Unsupported keys
         _ => None,
     }
@@ -330,6 +386,10 @@ pub fn stable_to_winit(stable_key: u32) -> Option<winit::keyboard::KeyCode> {
         ENTER => Some(KeyCode::Enter),
         SPACE => Some(KeyCode::Space),
         TAB => Some(KeyCode::Tab),
+        ESCAPE => Some(KeyCode::Escape),
+        PRINT_SCREEN => Some(KeyCode::PrintScreen),
+        SCROLL_LOCK => Some(KeyCode::ScrollLock),
+        PAUSE => Some(KeyCode::Pause),
 
         // Arrows
         ARROW_DOWN => Some(KeyCode::ArrowDown),
@@ -342,6 +402,8 @@ pub fn stable_to_winit(stable_key: u32) -> Option<winit::keyboard::KeyCode> {
         HOME => Some(KeyCode::Home),
         PAGE_DOWN => Some(KeyCode::PageDown),
         PAGE_UP => Some(KeyCode::PageUp),
+        DELETE => Some(KeyCode::Delete),
+        INSERT => Some(KeyCode::Insert),
 
         // Function keys
         F1 => Some(KeyCode::F1),
@@ -356,6 +418,18 @@ pub fn stable_to_winit(stable_key: u32) -> Option<winit::keyboard::KeyCode> {
         F10 => Some(KeyCode::F10),
         F11 => Some(KeyCode::F11),
         F12 => Some(KeyCode::F12),
+        F13 => Some(KeyCode::F13),
+        F14 => Some(KeyCode::F14),
+        F15 => Some(KeyCode::F15),
+        F16 => Some(KeyCode::F16),
+        F17 => Some(KeyCode::F17),
+        F18 => Some(KeyCode::F18),
+        F19 => Some(KeyCode::F19),
+        F20 => Some(KeyCode::F20),
+        F21 => Some(KeyCode::F21),
+        F22 => Some(KeyCode::F22),
+        F23 => Some(KeyCode::F23),
+        F24 => Some(KeyCode::F24),
 
         // Numpad
         NUMPAD_0 => Some(KeyCode::Numpad0),
@@ -374,13 +448,214 @@ pub fn stable_to_winit(stable_key: u32) -> Option<winit::keyboard::KeyCode> {
         NUMPAD_MULTIPLY => Some(KeyCode::NumpadMultiply),
         NUMPAD_SUBTRACT => Some(KeyCode::NumpadSubtract),
         NUMPAD_ENTER => Some(KeyCode::NumpadEnter),
+        NUM_LOCK => Some(KeyCode::NumLock),
 
         // International
         INTL_BACKSLASH => Some(KeyCode::IntlBackslash),
         INTL_RO => Some(KeyCode::IntlRo),
         INTL_YEN => Some(KeyCode::IntlYen),
 
+        // Media
+        MEDIA_PLAY_PAUSE => Some(KeyCode::MediaPlayPause),
+        MEDIA_STOP => Some(KeyCode::MediaStop),
+        MEDIA_TRACK_NEXT => Some(KeyCode::MediaTrackNext),
+        MEDIA_TRACK_PREVIOUS => Some(KeyCode::MediaTrackPrevious),
+        AUDIO_VOLUME_DOWN => Some(KeyCode::AudioVolumeDown),
+        AUDIO_VOLUME_UP => Some(KeyCode::AudioVolumeUp),
+        AUDIO_VOLUME_MUTE => Some(KeyCode::AudioVolumeMute),
+
         // Invalid
         _ => None,
     }
 }
+
+/// `engine.keys` names and their codes. Scripts and input scripts use these names,
+/// so like the codes they must never change.
+pub const KEY_NAMES: &[(&str, u32)] = &[
+    // Letters
+    ("KeyA", KEY_A),
+    ("KeyB", KEY_B),
+    ("KeyC", KEY_C),
+    ("KeyD", KEY_D),
+    ("KeyE", KEY_E),
+    ("KeyF", KEY_F),
+    ("KeyG", KEY_G),
+    ("KeyH", KEY_H),
+    ("KeyI", KEY_I),
+    ("KeyJ", KEY_J),
+    ("KeyK", KEY_K),
+    ("KeyL", KEY_L),
+    ("KeyM", KEY_M),
+    ("KeyN", KEY_N),
+    ("KeyO", KEY_O),
+    ("KeyP", KEY_P),
+    ("KeyQ", KEY_Q),
+    ("KeyR", KEY_R),
+    ("KeyS", KEY_S),
+    ("KeyT", KEY_T),
+    ("KeyU", KEY_U),
+    ("KeyV", KEY_V),
+    ("KeyW", KEY_W),
+    ("KeyX", KEY_X),
+    ("KeyY", KEY_Y),
+    ("KeyZ", KEY_Z),
+    // Digits
+    ("Digit0", DIGIT_0),
+    ("Digit1", DIGIT_1),
+    ("Digit2", DIGIT_2),
+    ("Digit3", DIGIT_3),
+    ("Digit4", DIGIT_4),
+    ("Digit5", DIGIT_5),
+    ("Digit6", DIGIT_6),
+    ("Digit7", DIGIT_7),
+    ("Digit8", DIGIT_8),
+    ("Digit9", DIGIT_9),
+    // Symbols
+    ("Backquote", BACKQUOTE),
+    ("Backslash", BACKSLASH),
+    ("BracketLeft", BRACKET_LEFT),
+    ("BracketRight", BRACKET_RIGHT),
+    ("Comma", COMMA),
+    ("Equal", EQUAL),
+    ("Minus", MINUS),
+    ("Period", PERIOD),
+    ("Quote", QUOTE),
+    ("Semicolon", SEMICOLON),
+    ("Slash", SLASH),
+    // Modifiers
+    ("AltLeft", ALT_LEFT),
+    ("AltRight", ALT_RIGHT),
+    ("ControlLeft", CONTROL_LEFT),
+    ("ControlRight", CONTROL_RIGHT),
+    ("ShiftLeft", SHIFT_LEFT),
+    ("ShiftRight", SHIFT_RIGHT),
+    ("SuperLeft", SUPER_LEFT),
+    ("SuperRight", SUPER_RIGHT),
+    // Special keys
+    ("Backspace", BACKSPACE),
+    ("CapsLock", CAPS_LOCK),
+    ("ContextMenu", CONTEXT_MENU),
+    ("Enter", ENTER),
+    ("Space", SPACE),
+    ("Tab", TAB),
+    ("Escape", ESCAPE),
+    ("PrintScreen", PRINT_SCREEN),
+    ("ScrollLock", SCROLL_LOCK),
+    ("Pause", PAUSE),
+    // Arrow keys
+    ("ArrowDown", ARROW_DOWN),
+    ("ArrowLeft", ARROW_LEFT),
+    ("ArrowRight", ARROW_RIGHT),
+    ("ArrowUp", ARROW_UP),
+    // Navigation
+    ("End", END),
+    ("Home", HOME),
+    ("PageDown", PAGE_DOWN),
+    ("PageUp", PAGE_UP),
+    ("Delete", DELETE),
+    ("Insert", INSERT),
+    // Function keys
+    ("F1", F1),
+    ("F2", F2),
+    ("F3", F3),
+    ("F4", F4),
+    ("F5", F5),
+    ("F6", F6),
+    ("F7", F7),
+    ("F8", F8),
+    ("F9", F9),
+    ("F10", F10),
+    ("F11", F11),
+    ("F12", F12),
+    ("F13", F13),
+    ("F14", F14),
+    ("F15", F15),
+    ("F16", F16),
+    ("F17", F17),
+    ("F18", F18),
+    ("F19", F19),
+    ("F20", F20),
+    ("F21", F21),
+    ("F22", F22),
+    ("F23", F23),
+    ("F24", F24),
+    // Numpad
+    ("Numpad0", NUMPAD_0),
+    ("Numpad1", NUMPAD_1),
+    ("Numpad2", NUMPAD_2),
+    ("Numpad3", NUMPAD_3),
+    ("Numpad4", NUMPAD_4),
+    ("Numpad5", NUMPAD_5),
+    ("Numpad6", NUMPAD_6),
+    ("Numpad7", NUMPAD_7),
+    ("Numpad8", NUMPAD_8),
+    ("Numpad9", NUMPAD_9),
+    ("NumpadAdd", NUMPAD_ADD),
+    ("NumpadDecimal", NUMPAD_DECIMAL),
+    ("NumpadDivide", NUMPAD_DIVIDE),
+    ("NumpadMultiply", NUMPAD_MULTIPLY),
+    ("NumpadSubtract", NUMPAD_SUBTRACT),
+    ("NumpadEnter", NUMPAD_ENTER),
+    ("NumLock", NUM_LOCK),
+    // International
+    ("IntlBackslash", INTL_BACKSLASH),
+    ("IntlRo", INTL_RO),
+    ("IntlYen", INTL_YEN),
+    // Media
+    ("MediaPlayPause", MEDIA_PLAY_PAUSE),
+    ("MediaStop", MEDIA_STOP),
+    ("MediaTrackNext", MEDIA_TRACK_NEXT),
+    ("MediaTrackPrevious", MEDIA_TRACK_PREVIOUS),
+    ("AudioVolumeDown", AUDIO_VOLUME_DOWN),
+    ("AudioVolumeUp", AUDIO_VOLUME_UP),
+    ("AudioVolumeMute", AUDIO_VOLUME_MUTE),
+];
+
+/// Stable key code for an `engine.keys` name ("KeyA", "Space", "ArrowLeft"..)
+pub fn from_name(name: &str) -> Option<u32> {
+    KEY_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, code)| *code)
+}
+
+/// Character a stable key types on a US layout, if any. Used for simple text entry
+/// from key snapshots; modifiers other than shift are the caller's concern.
+pub fn to_char(stable_key: u32, shift: bool) -> Option<char> {
+    let c = match stable_key {
+        KEY_A..=KEY_Z => {
+            let c = (b'a' + (stable_key - KEY_A) as u8) as char;
+            return Some(if shift { c.to_ascii_uppercase() } else { c });
+        }
+        DIGIT_0..=DIGIT_9 if shift => b")!@#$%^&*("[(stable_key - DIGIT_0) as usize] as char,
+        DIGIT_0..=DIGIT_9 => (b'0' + (stable_key - DIGIT_0) as u8) as char,
+        NUMPAD_0..=NUMPAD_9 => (b'0' + (stable_key - NUMPAD_0) as u8) as char,
+        SPACE => ' ',
+        BACKQUOTE => pick(shift, '`', '~'),
+        BACKSLASH => pick(shift, '\\', '|'),
+        BRACKET_LEFT => pick(shift, '[', '{'),
+        BRACKET_RIGHT => pick(shift, ']', '}'),
+        COMMA => pick(shift, ',', '<'),
+        EQUAL => pick(shift, '=', '+'),
+        MINUS => pick(shift, '-', '_'),
+        PERIOD => pick(shift, '.', '>'),
+        QUOTE => pick(shift, '\'', '"'),
+        SEMICOLON => pick(shift, ';', ':'),
+        SLASH => pick(shift, '/', '?'),
+        NUMPAD_ADD => '+',
+        NUMPAD_DECIMAL => '.',
+        NUMPAD_DIVIDE => '/',
+        NUMPAD_MULTIPLY => '*',
+        NUMPAD_SUBTRACT => '-',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn pick(shift: bool, plain: char, shifted: char) -> char {
+    if shift {
+        shifted
+    } else {
+        plain
+    }
+}
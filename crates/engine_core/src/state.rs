@@ -1,5 +1,6 @@
+use crate::tilemap::{Tilemap, TilemapCommand};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Sprite data for v2 flat array format (engine-native representation)
 #[derive(Debug, Clone)]
@@ -12,6 +13,18 @@ pub struct SpriteData {
     pub layer_id: u32,   // layer handle for ordering (0 = default "main")
 }
 
+/// Textured quad without an entity (e.g. text glyphs); replaced every update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadData {
+    pub texture_id: u32,
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+    pub uv: [f32; 4],    // u0, v0, u1, v1
+    pub color: [f32; 4], // r, g, b, a
+    pub z: f32,
+    pub layer_id: u32,
+}
+
 /// Central engine state that owns all game resources
 /// This is the single source of truth for all game data
 #[derive(Debug)]
@@ -22,13 +35,15 @@ pub struct EngineState {
     // Sprite data (engine-native representation)
     sprites_front: Vec<SpriteData>,
     sprites_back: Vec<SpriteData>,
+    quads: Vec<QuadData>,
 
     // Texture storage
     textures: HashMap<u32, Vec<u8>>,     // texture_id -> raw bytes
     texture_names: HashMap<u32, String>, // texture_id -> name for debugging
 
-    // Entity management
-    next_entity_id: u32,
+    // Entity management (ids come from the script API's allocator)
+    // Entities destroyed this frame; the renderer drops their transforms
+    destroyed_entities: Vec<u32>,
     next_texture_id: u32,
 
     // Time management
@@ -36,6 +51,9 @@ pub struct EngineState {
 
     // Performance tracking
     ffi_calls_this_frame: u32,
+    reloads_this_frame: u32,
+    script_timeouts: u32,
+    lua_gc: Option<(std::time::Duration, f64)>,
 
     // Window info
     window_width: u32,
@@ -46,6 +64,8 @@ pub struct EngineState {
 
     // Virtual/internal render resolution mode
     virtual_mode: VirtualResolution,
+    // Coordinate space of UI layers
+    ui_space: UiSpace,
 
     // Simple camera (position only for v0)
     camera_x: f32,
@@ -53,6 +73,13 @@ pub struct EngineState {
 
     // Layers registry (simple: name -> id, id -> Layer)
     layers: Layers,
+
+    // Cursor changes requested by scripts, applied by the window
+    cursor_request: CursorRequest,
+    text_input_request: Option<TextInputRequest>,
+
+    // Loaded tilemaps by script-assigned id
+    tilemaps: BTreeMap<u32, Tilemap>,
 }
 
 impl EngineState {
@@ -61,28 +88,39 @@ impl EngineState {
             transform_buffer: Vec::with_capacity(10000 * 6), // Pre-allocate for max entities
             sprites_front: Vec::with_capacity(10000),        // Pre-allocate for max entities
             sprites_back: Vec::with_capacity(10000),
+            quads: Vec::new(),
             textures: HashMap::new(),
             texture_names: HashMap::new(),
-            next_entity_id: 1,
+            destroyed_entities: Vec::new(),
             next_texture_id: 1,
             fixed_time: 0.0,
             ffi_calls_this_frame: 0,
+            reloads_this_frame: 0,
+            script_timeouts: 0,
+            lua_gc: None,
             window_width: 1920,
             window_height: 1080,
             clear_color: [0.0, 0.0, 0.0, 1.0],
             virtual_mode: VirtualResolution::Hd1920x1080,
+            ui_space: UiSpace::Virtual,
             camera_x: 0.0,
             camera_y: 0.0,
             layers: Layers::with_defaults(),
+            cursor_request: CursorRequest::default(),
+            text_input_request: None,
+            tilemaps: BTreeMap::new(),
         }
     }
 
     // Entity Management
-    pub fn create_entity(&mut self) -> u32 {
-        let id = self.next_entity_id;
-        self.next_entity_id += 1;
-        tracing::debug!("Created entity {}", id);
-        id
+    /// Drop render-side state for an entity destroyed through the script API.
+    pub fn release_entity(&mut self, id: u32) {
+        self.destroyed_entities.push(id);
+        self.sprites_front.retain(|s| s.entity_id != id);
+    }
+
+    pub fn destroyed_entities(&self) -> &[u32] {
+        &self.destroyed_entities
     }
 
     // Texture Management
@@ -116,7 +154,7 @@ impl EngineState {
 
     // Transform Management (v2 flat array format)
     pub fn set_transforms(&mut self, transforms: Vec<f64>) -> Result<()> {
-        if transforms.len() % 6 != 0 {
+        if !transforms.len().is_multiple_of(6) {
             return Err(anyhow::anyhow!(
                 "ARG_ERROR: set_transforms stride mismatch (got={}, want=6)",
                 transforms.len() % 6
@@ -137,7 +175,7 @@ impl EngineState {
     }
 
     pub fn set_transforms_from_slice(&mut self, transforms: &[f64]) -> Result<()> {
-        if transforms.len() % 6 != 0 {
+        if !transforms.len().is_multiple_of(6) {
             return Err(anyhow::anyhow!(
                 "ARG_ERROR: set_transforms stride mismatch (got={}, want=6)",
                 transforms.len() % 6
@@ -154,7 +192,7 @@ impl EngineState {
     }
 
     pub fn set_transforms_from_f32_slice(&mut self, transforms: &[f32]) -> Result<()> {
-        if transforms.len() % 6 != 0 {
+        if !transforms.len().is_multiple_of(6) {
             return Err(anyhow::anyhow!(
                 "ARG_ERROR: set_transforms stride mismatch (got={}, want=6)",
                 transforms.len() % 6
@@ -248,6 +286,15 @@ impl EngineState {
         }
     }
 
+    /// Replace the entity-less quads drawn alongside sprites.
+    pub fn set_quads(&mut self, quads: Vec<QuadData>) {
+        self.quads = quads;
+    }
+
+    pub fn get_quads(&self) -> &[QuadData] {
+        &self.quads
+    }
+
     pub fn get_sprites(&self) -> &[SpriteData] {
         &self.sprites_front
     }
@@ -269,8 +316,38 @@ impl EngineState {
         self.ffi_calls_this_frame
     }
 
+    /// Host reports a script hot reload; drained into metrics by the window.
+    pub fn note_reload(&mut self) {
+        self.reloads_this_frame += 1;
+    }
+
+    pub fn take_reloads(&mut self) -> u32 {
+        std::mem::take(&mut self.reloads_this_frame)
+    }
+
+    /// Host reports a script call aborted by its budget; the window's watchdog
+    /// counts it as a spike even if the call returned quickly.
+    pub fn note_script_timeout(&mut self) {
+        self.script_timeouts += 1;
+    }
+
+    pub fn take_script_timeouts(&mut self) -> u32 {
+        std::mem::take(&mut self.script_timeouts)
+    }
+
+    /// Host reports a Lua GC step (time spent, heap MB); steps within a frame add up.
+    pub fn note_lua_gc(&mut self, duration: std::time::Duration, memory_mb: f64) {
+        let total = self.lua_gc.map(|(d, _)| d).unwrap_or_default() + duration;
+        self.lua_gc = Some((total, memory_mb));
+    }
+
+    pub fn take_lua_gc(&mut self) -> Option<(std::time::Duration, f64)> {
+        self.lua_gc.take()
+    }
+
     pub fn reset_frame_counters(&mut self) {
         self.ffi_calls_this_frame = 0;
+        self.destroyed_entities.clear();
     }
 
     // Validation against plan requirements
@@ -316,20 +393,148 @@ impl EngineState {
         self.virtual_mode
     }
 
+    // UI pass coordinate space
+    pub fn set_ui_space(&mut self, space: UiSpace) {
+        self.ui_space = space;
+    }
+
+    pub fn ui_space(&self) -> UiSpace {
+        self.ui_space
+    }
+
+    /// Size of the UI coordinate space: the virtual resolution, or the letterboxed
+    /// canvas in window pixels for `UiSpace::Native`
+    pub fn ui_size(&self) -> (f32, f32) {
+        let [w, h] = self.ui_viewport().units;
+        (w, h)
+    }
+
+    /// Where the mouse lands: the letterboxed virtual canvas, camera and layers
+    pub fn canvas_view(&self) -> CanvasView {
+        let (w, h) = (self.window_width as f32, self.window_height as f32);
+        let (rect, _) = crate::present_pass_math::present_rect(w, h, self.virtual_mode);
+        let (vw, vh) = self.virtual_mode.size();
+        CanvasView {
+            canvas: UiViewport {
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+                units: [vw as f32, vh as f32],
+            },
+            camera: (self.camera_x, self.camera_y),
+            layers: self.layers.clone(),
+        }
+    }
+
+    // Cursor: merged script requests, taken by the window once per frame
+    pub fn request_cursor(&mut self, req: CursorRequest) {
+        let cur = &mut self.cursor_request;
+        cur.visible = req.visible.or(cur.visible);
+        cur.confine = req.confine.or(cur.confine);
+        cur.position = req.position.or(cur.position);
+    }
+
+    pub fn take_cursor_request(&mut self) -> Option<CursorRequest> {
+        let req = std::mem::take(&mut self.cursor_request);
+        (req != CursorRequest::default()).then_some(req)
+    }
+
+    // Text input: the last start/stop of the frame wins
+    pub fn request_text_input(&mut self, req: TextInputRequest) {
+        self.text_input_request = Some(req);
+    }
+
+    pub fn take_text_input_request(&mut self) -> Option<TextInputRequest> {
+        self.text_input_request.take()
+    }
+
+    /// Letterbox rect of the UI pass in the window and the UI units mapped onto it
+    pub fn ui_viewport(&self) -> UiViewport {
+        let (w, h) = (self.window_width as f32, self.window_height as f32);
+        let (rect, _) = crate::present_pass_math::present_rect(w, h, self.virtual_mode);
+        let units = match self.ui_space {
+            UiSpace::Virtual => {
+                let (vw, vh) = self.virtual_mode.size();
+                [vw as f32, vh as f32]
+            }
+            UiSpace::Native => [rect.width, rect.height],
+        };
+        UiViewport {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+            units,
+        }
+    }
+
     // Determinism: compute a stable hash of the transform buffer
     pub fn compute_transform_hash(&self) -> u64 {
-        // FNV-1a 64-bit over f64 bit patterns
-        let mut hash: u64 = 0xcbf29ce484222325;
-        let prime: u64 = 0x100000001b3;
+        let mut h = StateHasher::new();
         for v in &self.transform_buffer {
-            let bits = v.to_bits();
-            let b = bits.to_le_bytes();
-            for byte in b {
-                hash ^= byte as u64;
-                hash = hash.wrapping_mul(prime);
+            h.write_f32(*v);
+        }
+        h.finish()
+    }
+
+    /// Hash per subsystem, in a fixed order; replays compare these to name what diverged
+    pub fn subsystem_hashes(&self) -> Vec<(&'static str, u64)> {
+        let mut sprites = StateHasher::new();
+        for s in &self.sprites_front {
+            sprites.write_u32(s.entity_id);
+            sprites.write_u32(s.texture_id);
+            sprites.write_f32s(&s.uv);
+            sprites.write_f32s(&s.color);
+            sprites.write_f32(s.z);
+            sprites.write_u32(s.layer_id);
+        }
+        let mut quads = StateHasher::new();
+        for q in &self.quads {
+            quads.write_u32(q.texture_id);
+            quads.write_f32s(&q.center);
+            quads.write_f32s(&q.size);
+            quads.write_f32s(&q.uv);
+            quads.write_f32s(&q.color);
+            quads.write_f32(q.z);
+            quads.write_u32(q.layer_id);
+        }
+        let mut camera = StateHasher::new();
+        camera.write_f32s(&[self.camera_x, self.camera_y]);
+        let mut layers = StateHasher::new();
+        for l in &self.layers.vec {
+            layers.write_str(&l.name);
+            layers.write_u32(l.order as u32);
+            layers.write_f32s(&[l.parallax_x, l.parallax_y, l.shake_factor]);
+            layers.write_f32s(&[l.scroll_x, l.scroll_y, l.volume]);
+            layers.write(&[
+                l.screen_space as u8,
+                l.visible as u8,
+                l.ui as u8,
+                l.muted as u8,
+            ]);
+        }
+        // Tile edits are gameplay state; the map's uid and cached chunks are not
+        let mut tilemaps = StateHasher::new();
+        for (id, map) in &self.tilemaps {
+            tilemaps.write_u32(*id);
+            for l in &map.layers {
+                tilemaps.write(&[l.visible as u8]);
+                tilemaps.write_f32(l.opacity);
+                tilemaps.write_u32(l.data.len() as u32);
+                for gid in &l.data {
+                    tilemaps.write_u32(*gid);
+                }
             }
         }
-        hash
+        vec![
+            ("transforms", self.compute_transform_hash()),
+            ("sprites", sprites.finish()),
+            ("quads", quads.finish()),
+            ("camera", camera.finish()),
+            ("layers", layers.finish()),
+            ("tilemaps", tilemaps.finish()),
+        ]
     }
 
     // Zero-copy swap of the transform buffer with a script-owned buffer, taking only `elems` items
@@ -354,7 +559,170 @@ pub enum VirtualResolution {
     Hd1920x1080,
 }
 
+impl VirtualResolution {
+    pub fn size(self) -> (u32, u32) {
+        match self {
+            VirtualResolution::Retro320x180 => (320, 180),
+            VirtualResolution::Hd1920x1080 => (1920, 1080),
+        }
+    }
+}
+
+/// Units of UI layers. Both put (0, 0) at the bottom-left of the letterboxed canvas
+/// and are drawn after it is scaled, so UI never goes through the low-res canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiSpace {
+    /// Virtual canvas pixels (320x180 in retro mode)
+    #[default]
+    Virtual,
+    /// Window pixels, for crisp UI over a scaled-up retro canvas
+    Native,
+}
+
+/// Placement of the UI pass: the letterbox rect in window pixels (top-left origin)
+/// and the size of the UI coordinate space stretched over it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiViewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub units: [f32; 2],
+}
+
+impl Default for UiViewport {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: 1920.0,
+            height: 1080.0,
+            units: [1920.0, 1080.0],
+        }
+    }
+}
+
+impl UiViewport {
+    /// Map a window-pixel position (e.g. the mouse) to y-up UI units
+    pub fn window_to_ui(&self, wx: f32, wy: f32) -> [f32; 2] {
+        let u = (wx - self.x) / self.width.max(1.0);
+        let v = (wy - self.y) / self.height.max(1.0);
+        [u * self.units[0], (1.0 - v) * self.units[1]]
+    }
+}
+
+/// FNV-1a 64, the hash behind determinism checks and replay digests
+#[derive(Debug, Clone, Copy)]
+pub struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.write(&v.to_le_bytes());
+    }
+
+    /// Length-prefixed, so consecutive strings cannot shift bytes between them
+    pub fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    pub fn write_f32(&mut self, v: f32) {
+        self.write(&v.to_bits().to_le_bytes());
+    }
+
+    pub fn write_f32s(&mut self, vs: &[f32]) {
+        for v in vs {
+            self.write_f32(*v);
+        }
+    }
+
+    pub fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+/// Cursor changes requested by scripts; `None` leaves a setting alone
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CursorRequest {
+    pub visible: Option<bool>,
+    /// Keep the cursor inside the window
+    pub confine: Option<bool>,
+    /// Warp to a virtual canvas position (y-up)
+    pub position: Option<[f32; 2]>,
+}
+
+/// IME control requested by scripts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextInputRequest {
+    /// Enable IME composition; the rect `[x, y, w, h]` (virtual canvas, y-up) is
+    /// the text field the candidate window should avoid
+    Start(Option<[f32; 4]>),
+    Stop,
+}
+
+/// Mapping from window pixels to the virtual canvas and the world
+#[derive(Debug, Clone, Default)]
+pub struct CanvasView {
+    /// Letterboxed canvas in the window, with the virtual size as its units
+    pub canvas: UiViewport,
+    pub camera: (f32, f32),
+    pub layers: Layers,
+}
+
+impl CanvasView {
+    /// Window pixels (top-left origin) to y-up virtual pixels; `None` when the
+    /// point falls in the letterbox bars
+    pub fn window_to_virtual(&self, wx: f32, wy: f32) -> Option<[f32; 2]> {
+        let c = &self.canvas;
+        let inside = wx >= c.x && wx < c.x + c.width && wy >= c.y && wy < c.y + c.height;
+        inside.then(|| c.window_to_ui(wx, wy))
+    }
+
+    /// Inverse of `window_to_virtual`
+    pub fn virtual_to_window(&self, v: [f32; 2]) -> [f32; 2] {
+        let c = &self.canvas;
+        [
+            c.x + v[0] / c.units[0].max(1.0) * c.width,
+            c.y + (1.0 - v[1] / c.units[1].max(1.0)) * c.height,
+        ]
+    }
+
+    /// Virtual position to world position as drawn on `layer` (plain camera offset
+    /// when `None`); `None` for unknown layers
+    pub fn virtual_to_world(&self, v: [f32; 2], layer: Option<&str>) -> Option<[f32; 2]> {
+        let (ox, oy) = match layer {
+            Some(name) => {
+                let id = self.layers.id_of(name)?;
+                self.layers.get(id)?.view_offset(self.camera)
+            }
+            None => self.camera,
+        };
+        Some([v[0] + ox, v[1] + oy])
+    }
+}
+
 // ---- Layers (minimal v0) ----
+/// Built-in UI-pass layer. The `@` keeps it apart from layers scripts already name
+/// themselves (a script's own "ui" layer stays an ordinary scene layer).
+pub const UI_LAYER: &str = "@ui";
+
 #[derive(Debug, Clone)]
 pub struct Layer {
     pub name: String,
@@ -366,9 +734,29 @@ pub struct Layer {
     pub shake_factor: f32,
     pub scroll_x: f32,
     pub scroll_y: f32,
+    /// Drawn in the UI pass over the presented canvas instead of the scene
+    pub ui: bool,
+    /// Gain for sounds played on this layer
+    pub volume: f32,
+    pub muted: bool,
 }
 
-#[derive(Debug, Default)]
+impl Layer {
+    /// World offset of the layer's view: the camera scaled by parallax plus scroll,
+    /// or nothing for screen-space and UI layers
+    pub fn view_offset(&self, camera: (f32, f32)) -> (f32, f32) {
+        if self.screen_space || self.ui {
+            (0.0, 0.0)
+        } else {
+            (
+                camera.0 * self.parallax_x + self.scroll_x,
+                camera.1 * self.parallax_y + self.scroll_y,
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Layers {
     by_name: HashMap<String, u32>,
     vec: Vec<Layer>,
@@ -378,6 +766,8 @@ impl Layers {
     pub fn with_defaults() -> Self {
         let mut l = Layers { by_name: HashMap::new(), vec: Vec::new() };
         l.define_or_update("main".to_string(), 0);
+        let ui = l.define_or_update(UI_LAYER.to_string(), 1000);
+        l.vec[ui as usize].ui = true;
         l
     }
     pub fn define_or_update(&mut self, name: String, order: i32) -> u32 {
@@ -398,6 +788,9 @@ impl Layers {
             shake_factor: 1.0,
             scroll_x: 0.0,
             scroll_y: 0.0,
+            ui: false,
+            volume: 1.0,
+            muted: false,
         });
         self.by_name.insert(name, id);
         id
@@ -409,7 +802,12 @@ impl Layers {
     pub fn order_of(&self, id: u32) -> i32 {
         self.vec.get(id as usize).map(|l| l.order).unwrap_or(0)
     }
-    pub fn get(&self, id: u32) -> Option<&Layer> { self.vec.get(id as usize) }
+    pub fn get(&self, id: u32) -> Option<&Layer> {
+        self.vec.get(id as usize)
+    }
+    pub fn id_of(&self, name: &str) -> Option<u32> {
+        self.by_name.get(name).copied()
+    }
     pub fn by_name_mut(&mut self, name: &str) -> Option<&mut Layer> {
         let id = *self.by_name.get(name)? as usize;
         self.vec.get_mut(id)
@@ -434,3 +832,138 @@ impl EngineState {
     pub fn layers_mut(&mut self) -> &mut Layers { &mut self.layers }
     pub fn layers(&self) -> &Layers { &self.layers }
 }
+
+impl EngineState {
+    // Tilemaps
+    pub fn apply_tilemap_command(&mut self, cmd: TilemapCommand) {
+        match cmd {
+            TilemapCommand::Load(id, map) => {
+                self.tilemaps.insert(id, *map);
+            }
+            TilemapCommand::SetTile {
+                map,
+                layer,
+                x,
+                y,
+                gid,
+            } => {
+                if let Some(m) = self.tilemaps.get_mut(&map) {
+                    if let Err(e) = m.set_tile(layer, x, y, gid) {
+                        tracing::warn!("tilemap {}: {}", map, e);
+                    }
+                }
+            }
+            TilemapCommand::Unload(id) => {
+                self.tilemaps.remove(&id);
+            }
+        }
+    }
+    pub fn tilemap(&self, id: u32) -> Option<&Tilemap> {
+        self.tilemaps.get(&id)
+    }
+    pub fn tilemaps(&self) -> impl Iterator<Item = (u32, &Tilemap)> {
+        self.tilemaps.iter().map(|(id, m)| (*id, m))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_reserved_ui_layer_defaults_to_the_ui_pass() {
+        let mut layers = Layers::with_defaults();
+        let ui = layers.id_of(UI_LAYER).unwrap();
+        assert!(layers.get(ui).unwrap().ui);
+        // A script's own "ui" layer stays in the scene and follows the camera
+        let own = layers.resolve_or_create("ui");
+        assert_ne!(own, ui);
+        assert!(!layers.get(own).unwrap().ui);
+        assert_eq!(layers.get(own).unwrap().view_offset((5.0, 2.0)), (5.0, 2.0));
+    }
+
+    #[test]
+    fn test_changing_one_subsystem_changes_only_its_hash() {
+        const MAP: &str = r#"{ "width": 2, "height": 1, "tilewidth": 8, "tileheight": 8,
+            "tilesets": [{ "firstgid": 1, "name": "t", "image": "t.png", "tilewidth": 8,
+                           "tileheight": 8, "columns": 2, "tilecount": 2,
+                           "imagewidth": 16, "imageheight": 8 }],
+            "layers": [{ "type": "tilelayer", "name": "ground", "width": 2, "height": 1,
+                         "data": [1, 0] }] }"#;
+        let base = || {
+            let mut state = EngineState::new();
+            let map = Tilemap::from_tiled_json(MAP, std::path::Path::new(".")).unwrap();
+            state.apply_tilemap_command(TilemapCommand::Load(1, Box::new(map)));
+            state
+        };
+        let before = base().subsystem_hashes();
+        let changed = |edit: &dyn Fn(&mut EngineState)| -> Vec<&'static str> {
+            let mut state = base();
+            edit(&mut state);
+            let after = state.subsystem_hashes();
+            before
+                .iter()
+                .zip(&after)
+                .filter(|(b, a)| b.1 != a.1)
+                .map(|(b, _)| b.0)
+                .collect()
+        };
+        let transforms = |s: &mut EngineState| {
+            s.set_transforms_from_f32_slice(&[1.0, 2.0, 3.0, 0.0, 1.0, 1.0])
+                .unwrap();
+        };
+        let sprites = |s: &mut EngineState| {
+            s.set_sprites_from_slice(&[SpriteData {
+                entity_id: 1,
+                texture_id: 0,
+                uv: [0.0, 0.0, 1.0, 1.0],
+                color: [1.0; 4],
+                z: 0.0,
+                layer_id: 0,
+            }])
+            .unwrap();
+        };
+        let quads = |s: &mut EngineState| {
+            s.set_quads(vec![QuadData {
+                texture_id: 0,
+                center: [0.0; 2],
+                size: [1.0; 2],
+                uv: [0.0, 0.0, 1.0, 1.0],
+                color: [1.0; 4],
+                z: 0.0,
+                layer_id: 0,
+            }]);
+        };
+        let camera = |s: &mut EngineState| s.set_camera_xy(4.0, 0.0);
+        let layers = |s: &mut EngineState| {
+            s.layers_mut().define_or_update("bg".to_string(), -1);
+        };
+        let tilemaps = |s: &mut EngineState| {
+            s.apply_tilemap_command(TilemapCommand::SetTile {
+                map: 1,
+                layer: 0,
+                x: 1,
+                y: 0,
+                gid: 2,
+            });
+        };
+        assert_eq!(changed(&transforms), ["transforms"]);
+        assert_eq!(changed(&sprites), ["sprites"]);
+        assert_eq!(changed(&quads), ["quads"]);
+        assert_eq!(changed(&camera), ["camera"]);
+        assert_eq!(changed(&layers), ["layers"]);
+        assert_eq!(changed(&tilemaps), ["tilemaps"]);
+    }
+
+    #[test]
+    fn test_strings_are_hashed_with_their_length() {
+        let hash = |parts: &[&str]| {
+            let mut h = StateHasher::new();
+            for p in parts {
+                h.write_str(p);
+            }
+            h.finish()
+        };
+        assert_ne!(hash(&["ab", "c"]), hash(&["a", "bc"]));
+    }
+}
@@ -0,0 +1,638 @@
+use anyhow::Result;
+use glam::Vec2;
+use serde_json::Value as Json;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Chunk edge length in tiles; each chunk is meshed and cached separately
+pub const CHUNK_TILES: u32 = 16;
+
+// Tiled stores flip flags in the top bits of each gid
+pub const FLIP_H: u32 = 0x8000_0000;
+pub const FLIP_V: u32 = 0x4000_0000;
+pub const FLIP_D: u32 = 0x2000_0000;
+pub const GID_MASK: u32 = 0x1FFF_FFFF;
+
+// Unique across maps so a reloaded map never matches a stale cached chunk
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+fn next_revision() -> u64 {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    pub name: String,
+    pub first_gid: u32,
+    /// Image path resolved against the map's directory
+    pub image: PathBuf,
+    /// Engine texture id; assigned by whoever queues the image load
+    pub texture_id: u32,
+    pub tile_w: u32,
+    pub tile_h: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub image_w: u32,
+    pub image_h: u32,
+    pub margin: u32,
+    pub spacing: u32,
+}
+
+impl Tileset {
+    fn contains(&self, gid: u32) -> bool {
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    // (u0, v0, u1, v1) of a tile index within this tileset
+    fn uv(&self, local: u32) -> [f32; 4] {
+        let col = local % self.columns.max(1);
+        let row = local / self.columns.max(1);
+        let x = (self.margin + col * (self.tile_w + self.spacing)) as f32;
+        let y = (self.margin + row * (self.tile_h + self.spacing)) as f32;
+        let (iw, ih) = (self.image_w.max(1) as f32, self.image_h.max(1) as f32);
+        [
+            x / iw,
+            y / ih,
+            (x + self.tile_w as f32) / iw,
+            (y + self.tile_h as f32) / ih,
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    pub name: String,
+    /// Engine layer used for ordering, parallax, scroll and visibility. Taken from
+    /// a `layer` custom property in Tiled, otherwise the Tiled layer name.
+    pub engine_layer: String,
+    pub width: u32,
+    pub height: u32,
+    /// Raw Tiled gids (flip flags included), row-major; 0 is empty
+    pub data: Vec<u32>,
+    pub visible: bool,
+    pub opacity: f32,
+    /// Tiled layer offset converted to engine space (y up)
+    pub offset: Vec2,
+    chunk_revisions: Vec<u64>,
+}
+
+impl TileLayer {
+    pub fn chunk_counts(&self) -> (u32, u32) {
+        (
+            self.width.div_ceil(CHUNK_TILES),
+            self.height.div_ceil(CHUNK_TILES),
+        )
+    }
+
+    /// Changes whenever a tile inside the chunk changes.
+    pub fn chunk_revision(&self, cx: u32, cy: u32) -> u64 {
+        let (cw, _) = self.chunk_counts();
+        self.chunk_revisions
+            .get((cy * cw + cx) as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Tiled object layer; objects keep Tiled's JSON shape, except that `y` (and polygon
+/// point offsets) are flipped into engine space, `properties` is flattened to a
+/// name -> value map and `type` falls back to `class`.
+#[derive(Debug, Clone)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub objects: Vec<Json>,
+}
+
+/// One textured quad produced when meshing a chunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileQuad {
+    pub texture_id: u32,
+    pub center: Vec2,
+    pub size: Vec2,
+    /// Texture coordinates for the bottom-left, bottom-right, top-right and
+    /// top-left corners (engine y up), with flips already applied
+    pub uvs: [[f32; 2]; 4],
+    pub alpha: f32,
+}
+
+/// Orthogonal tilemap imported from Tiled JSON (`.tmj`/`.json`). In engine space the
+/// map covers `[0, width * tile_w] x [0, height * tile_h]` with Tiled's row 0 on top.
+#[derive(Debug, Clone)]
+pub struct Tilemap {
+    /// Identifies this loaded instance (a reload gets a new uid)
+    pub uid: u64,
+    pub width: u32,
+    pub height: u32,
+    pub tile_w: u32,
+    pub tile_h: u32,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<TileLayer>,
+    pub object_layers: Vec<ObjectLayer>,
+}
+
+/// Tilemap changes forwarded from scripts to the engine state
+#[derive(Debug, Clone)]
+pub enum TilemapCommand {
+    Load(u32, Box<Tilemap>),
+    SetTile {
+        map: u32,
+        layer: usize,
+        x: u32,
+        y: u32,
+        gid: u32,
+    },
+    Unload(u32),
+}
+
+impl Tilemap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::Error::msg(format!("tilemap '{}': {}", path.display(), e)))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Self::from_tiled_json(&json, dir)
+            .map_err(|e| anyhow::Error::msg(format!("tilemap '{}': {}", path.display(), e)))
+    }
+
+    /// Parse a Tiled map. External tilesets and images resolve against `base_dir`.
+    pub fn from_tiled_json(json: &str, base_dir: &Path) -> Result<Self> {
+        let doc: Json =
+            serde_json::from_str(json).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+        let orientation = doc["orientation"].as_str().unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            return Err(anyhow::Error::msg(format!(
+                "unsupported orientation '{}' (only orthogonal maps)",
+                orientation
+            )));
+        }
+        if doc["infinite"].as_bool() == Some(true) {
+            return Err(anyhow::Error::msg(
+                "infinite maps are not supported (disable 'Infinite' in Tiled)",
+            ));
+        }
+
+        let mut map = Tilemap {
+            uid: next_revision(),
+            width: uint(&doc, "width")?,
+            height: uint(&doc, "height")?,
+            tile_w: uint(&doc, "tilewidth")?,
+            tile_h: uint(&doc, "tileheight")?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            object_layers: Vec::new(),
+        };
+        for ts in doc["tilesets"].as_array().into_iter().flatten() {
+            map.tilesets.push(parse_tileset(ts, base_dir)?);
+        }
+        map.tilesets.sort_by_key(|t| t.first_gid);
+        map.width
+            .checked_mul(map.tile_w)
+            .zip(map.height.checked_mul(map.tile_h))
+            .ok_or_else(|| {
+                anyhow::Error::msg(format!(
+                    "map size {}x{} tiles of {}x{} is too large",
+                    map.width, map.height, map.tile_w, map.tile_h
+                ))
+            })?;
+        map.add_layers(&doc["layers"], Vec2::ZERO, true)?;
+        Ok(map)
+    }
+
+    // Flattens group layers, accumulating their offsets and visibility
+    fn add_layers(&mut self, layers: &Json, offset: Vec2, visible: bool) -> Result<()> {
+        let map_h = (self.height * self.tile_h) as f64;
+        for layer in layers.as_array().into_iter().flatten() {
+            let name = layer["name"].as_str().unwrap_or_default().to_string();
+            let offset = offset
+                + Vec2::new(
+                    layer["offsetx"].as_f64().unwrap_or(0.0) as f32,
+                    -layer["offsety"].as_f64().unwrap_or(0.0) as f32,
+                );
+            let visible = visible && layer["visible"].as_bool().unwrap_or(true);
+            match layer["type"].as_str().unwrap_or_default() {
+                "tilelayer" => {
+                    let (w, h) = (uint(layer, "width")?, uint(layer, "height")?);
+                    let data: Vec<u32> = match &layer["data"] {
+                        Json::Array(a) => a
+                            .iter()
+                            .map(|v| {
+                                v.as_u64().and_then(|g| u32::try_from(g).ok()).ok_or_else(|| {
+                                    anyhow::Error::msg(format!("layer '{}': bad tile gid {}", name, v))
+                                })
+                            })
+                            .collect::<Result<_>>()?,
+                        _ => {
+                            return Err(anyhow::Error::msg(format!(
+                                "layer '{}': only CSV/array tile data is supported (set layer format to CSV)",
+                                name
+                            )))
+                        }
+                    };
+                    let count = w.checked_mul(h).ok_or_else(|| {
+                        anyhow::Error::msg(format!("layer '{}': {}x{} is too large", name, w, h))
+                    })?;
+                    if data.len() != count as usize {
+                        return Err(anyhow::Error::msg(format!(
+                            "layer '{}': expected {} tiles, found {}",
+                            name,
+                            count,
+                            data.len()
+                        )));
+                    }
+                    let props = properties(&layer["properties"]);
+                    let engine_layer = props["layer"].as_str().unwrap_or(&name).to_string();
+                    let mut tl = TileLayer {
+                        name,
+                        engine_layer,
+                        width: w,
+                        height: h,
+                        data,
+                        visible,
+                        opacity: layer["opacity"].as_f64().unwrap_or(1.0) as f32,
+                        offset,
+                        chunk_revisions: Vec::new(),
+                    };
+                    let (cw, ch) = tl.chunk_counts();
+                    tl.chunk_revisions = (0..cw * ch).map(|_| next_revision()).collect();
+                    self.layers.push(tl);
+                }
+                "objectgroup" => {
+                    let objects = layer["objects"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|o| normalize_object(o, map_h))
+                        .collect();
+                    self.object_layers.push(ObjectLayer { name, objects });
+                }
+                "group" => self.add_layers(&layer["layers"], offset, visible)?,
+                // Image layers are left to scripts
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|l| l.name == name)
+    }
+
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|l| l.name == name)
+    }
+
+    /// Raw gid at tile (x, y), flip flags included.
+    pub fn tile(&self, layer: usize, x: u32, y: u32) -> Option<u32> {
+        let l = self.layers.get(layer)?;
+        if x >= l.width || y >= l.height {
+            return None;
+        }
+        l.data.get((y * l.width + x) as usize).copied()
+    }
+
+    /// Store a raw gid and invalidate only the chunk that contains the tile.
+    /// Returns false if the tile already had this value.
+    pub fn set_tile(&mut self, layer: usize, x: u32, y: u32, gid: u32) -> Result<bool> {
+        let l = self.layers.get_mut(layer).ok_or_else(|| {
+            anyhow::Error::msg(format!("tilemap has no tile layer {}", layer + 1))
+        })?;
+        if x >= l.width || y >= l.height {
+            return Err(anyhow::Error::msg(format!(
+                "tile ({}, {}) is outside layer '{}' ({}x{})",
+                x, y, l.name, l.width, l.height
+            )));
+        }
+        let slot = &mut l.data[(y * l.width + x) as usize];
+        if *slot == gid {
+            return Ok(false);
+        }
+        *slot = gid;
+        let (cw, _) = l.chunk_counts();
+        let chunk = ((y / CHUNK_TILES) * cw + x / CHUNK_TILES) as usize;
+        l.chunk_revisions[chunk] = next_revision();
+        Ok(true)
+    }
+
+    pub fn tileset_for(&self, gid: u32) -> Option<&Tileset> {
+        let gid = gid & GID_MASK;
+        self.tilesets.iter().rev().find(|t| t.contains(gid))
+    }
+
+    /// Largest tileset tile, for culling tiles that overhang their cell.
+    pub fn max_tile_size(&self) -> Vec2 {
+        self.tilesets.iter().fold(
+            Vec2::new(self.tile_w as f32, self.tile_h as f32),
+            |acc, t| acc.max(Vec2::new(t.tile_w as f32, t.tile_h as f32)),
+        )
+    }
+
+    /// Map-space bounds of a chunk, widened for tiles that overhang their cell.
+    pub fn chunk_bounds(&self, layer: usize, cx: u32, cy: u32) -> (Vec2, Vec2) {
+        let h = self
+            .layers
+            .get(layer)
+            .map(|l| l.height)
+            .unwrap_or(self.height);
+        let (tw, th) = (self.tile_w as f32, self.tile_h as f32);
+        let rows = (cy * CHUNK_TILES).min(h)..((cy + 1) * CHUNK_TILES).min(h);
+        let min = Vec2::new((cx * CHUNK_TILES) as f32 * tw, (h - rows.end) as f32 * th);
+        let max = Vec2::new(
+            ((cx + 1) * CHUNK_TILES) as f32 * tw,
+            (h - rows.start) as f32 * th,
+        );
+        // Oversized tiles grow to the right and upwards from their cell
+        (min, max + self.max_tile_size() - Vec2::new(tw, th))
+    }
+
+    /// Append the quads of one chunk, in map space without the layer offset.
+    /// Oversized tiles are anchored to the bottom-left of their cell, as in Tiled.
+    pub fn chunk_quads(&self, layer: usize, cx: u32, cy: u32, out: &mut Vec<TileQuad>) {
+        let Some(l) = self.layers.get(layer) else {
+            return;
+        };
+        let (tw, th) = (self.tile_w as f32, self.tile_h as f32);
+        let x_end = ((cx + 1) * CHUNK_TILES).min(l.width);
+        let y_end = ((cy + 1) * CHUNK_TILES).min(l.height);
+        for y in cy * CHUNK_TILES..y_end {
+            for x in cx * CHUNK_TILES..x_end {
+                let raw = l.data[(y * l.width + x) as usize];
+                let gid = raw & GID_MASK;
+                if gid == 0 {
+                    continue;
+                }
+                let Some(ts) = self.tileset_for(gid) else {
+                    continue;
+                };
+                let size = Vec2::new(ts.tile_w as f32, ts.tile_h as f32);
+                let bottom_left = Vec2::new(x as f32 * tw, (l.height - 1 - y) as f32 * th);
+                out.push(TileQuad {
+                    texture_id: ts.texture_id,
+                    center: bottom_left + size * 0.5,
+                    size,
+                    uvs: corner_uvs(ts.uv(gid - ts.first_gid), raw),
+                    alpha: l.opacity,
+                });
+            }
+        }
+    }
+}
+
+fn corner_uvs(uv: [f32; 4], raw: u32) -> [[f32; 2]; 4] {
+    let [u0, v0, u1, v1] = uv;
+    let mut c = [[u0, v1], [u1, v1], [u1, v0], [u0, v0]];
+    // Diagonal flip first (transpose: swaps bottom-left and top-right), then horizontal/vertical
+    if raw & FLIP_D != 0 {
+        c.swap(0, 2);
+    }
+    if raw & FLIP_H != 0 {
+        c = [c[1], c[0], c[3], c[2]];
+    }
+    if raw & FLIP_V != 0 {
+        c = [c[3], c[2], c[1], c[0]];
+    }
+    c
+}
+
+fn uint(v: &Json, key: &str) -> Result<u32> {
+    v[key]
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| anyhow::Error::msg(format!("missing or invalid '{}'", key)))
+}
+
+// Tiled's [{name, type, value}] list as a {name: value} object
+fn properties(props: &Json) -> Json {
+    let mut out = serde_json::Map::new();
+    for p in props.as_array().into_iter().flatten() {
+        if let Some(name) = p["name"].as_str() {
+            out.insert(name.to_string(), p["value"].clone());
+        }
+    }
+    Json::Object(out)
+}
+
+fn normalize_object(obj: &Json, map_h: f64) -> Json {
+    let mut obj = obj.clone();
+    if let Some(o) = obj.as_object_mut() {
+        if let Some(y) = o.get("y").and_then(|y| y.as_f64()) {
+            o.insert("y".to_string(), Json::from(map_h - y));
+        }
+        for key in ["polygon", "polyline"] {
+            for p in o
+                .get_mut(key)
+                .and_then(|p| p.as_array_mut())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(py) = p["y"].as_f64() {
+                    p["y"] = Json::from(-py);
+                }
+            }
+        }
+        let props = properties(o.get("properties").unwrap_or(&Json::Null));
+        o.insert("properties".to_string(), props);
+        let class = o.get("class").cloned();
+        let empty_type = o
+            .get("type")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .is_empty();
+        if let (Some(class), true) = (class, empty_type) {
+            o.insert("type".to_string(), class);
+        }
+    }
+    obj
+}
+
+fn parse_tileset(ts: &Json, base_dir: &Path) -> Result<Tileset> {
+    let first_gid = uint(ts, "firstgid")?;
+    // External tileset: merge its contents, resolving its image against its own directory
+    if let Some(source) = ts["source"].as_str() {
+        let path = base_dir.join(source);
+        if path.extension().and_then(|e| e.to_str()) == Some("tsx") {
+            return Err(anyhow::Error::msg(format!(
+                "tileset '{}': XML tilesets are not supported (export it as JSON .tsj)",
+                source
+            )));
+        }
+        let data = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::Error::msg(format!("tileset '{}': {}", path.display(), e)))?;
+        let mut ext: Json =
+            serde_json::from_str(&data).map_err(|e| anyhow::Error::msg(e.to_string()))?;
+        ext["firstgid"] = Json::from(first_gid);
+        return parse_tileset(&ext, path.parent().unwrap_or(Path::new("")));
+    }
+
+    let name = ts["name"].as_str().unwrap_or_default().to_string();
+    let image = ts["image"].as_str().ok_or_else(|| {
+        anyhow::Error::msg(format!(
+            "tileset '{}': image collection tilesets are not supported",
+            name
+        ))
+    })?;
+    let tile_w = uint(ts, "tilewidth")?;
+    let image_w = uint(ts, "imagewidth")?;
+    let tile_h = uint(ts, "tileheight")?;
+    let tile_count = uint(ts, "tilecount")?;
+    let optional = |key: &str| match ts.get(key) {
+        None | Some(Json::Null) => Ok(0),
+        Some(_) => uint(ts, key),
+    };
+    let (margin, spacing) = (optional("margin")?, optional("spacing")?);
+    let too_large = || anyhow::Error::msg(format!("tileset '{}': tile layout is too large", name));
+    let (stride_w, stride_h) = (
+        tile_w.checked_add(spacing).ok_or_else(too_large)?,
+        tile_h.checked_add(spacing).ok_or_else(too_large)?,
+    );
+    let columns = match optional("columns")? {
+        0 => (image_w
+            .saturating_sub(margin.saturating_mul(2))
+            .saturating_add(spacing)
+            / stride_w.max(1))
+        .max(1),
+        c => c,
+    };
+    // Gids and the pixel corner of the last tile must fit in u32, so lookups and
+    // UVs never overflow later
+    let rows = tile_count.div_ceil(columns);
+    let far_edge = |count: u32, stride: u32, size: u32| {
+        count
+            .saturating_sub(1)
+            .checked_mul(stride)
+            .and_then(|p| p.checked_add(margin))
+            .and_then(|p| p.checked_add(size))
+    };
+    first_gid.checked_add(tile_count).ok_or_else(too_large)?;
+    far_edge(columns.min(tile_count), stride_w, tile_w).ok_or_else(too_large)?;
+    far_edge(rows, stride_h, tile_h).ok_or_else(too_large)?;
+    Ok(Tileset {
+        first_gid,
+        image: base_dir.join(image),
+        texture_id: 0,
+        tile_w,
+        tile_h,
+        columns,
+        tile_count,
+        image_w,
+        image_h: uint(ts, "imageheight")?,
+        margin,
+        spacing,
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"{
+      "orientation": "orthogonal", "infinite": false,
+      "width": 20, "height": 2, "tilewidth": 8, "tileheight": 8,
+      "tilesets": [{ "firstgid": 1, "name": "terrain", "image": "terrain.png",
+                     "tilewidth": 8, "tileheight": 8, "columns": 4, "tilecount": 8,
+                     "imagewidth": 32, "imageheight": 16 }],
+      "layers": [
+        { "type": "group", "name": "world", "offsetx": 4, "offsety": 2, "layers": [
+          { "type": "tilelayer", "name": "ground", "width": 20, "height": 2,
+            "properties": [{ "name": "layer", "type": "string", "value": "main" }],
+            "data": [1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0, 0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,2147483654] }
+        ]},
+        { "type": "objectgroup", "name": "spawns", "objects": [
+          { "id": 1, "name": "player", "class": "spawn", "x": 16, "y": 4, "width": 0, "height": 0,
+            "properties": [{ "name": "facing", "type": "string", "value": "left" }] }
+        ]}
+      ]
+    }"#;
+
+    #[test]
+    fn test_tiled_import_resolves_layers_and_objects() {
+        let map = Tilemap::from_tiled_json(MAP, Path::new("maps")).unwrap();
+        assert_eq!(map.tilesets[0].image, Path::new("maps/terrain.png"));
+        let ground = &map.layers[0];
+        assert_eq!(
+            (ground.engine_layer.as_str(), ground.offset),
+            ("main", Vec2::new(4.0, -2.0))
+        );
+        assert_eq!(ground.chunk_counts(), (2, 1));
+        assert_eq!(
+            map.chunk_bounds(0, 1, 0),
+            (Vec2::new(128.0, 0.0), Vec2::new(256.0, 16.0))
+        );
+        let spawn = &map.object_layer("spawns").unwrap().objects[0];
+        assert_eq!(
+            (spawn["type"].as_str(), spawn["y"].as_f64()),
+            (Some("spawn"), Some(12.0))
+        );
+        assert_eq!(spawn["properties"]["facing"], "left");
+    }
+
+    #[test]
+    fn test_chunk_quads_map_gids_to_sheet_uvs() {
+        let map = Tilemap::from_tiled_json(MAP, Path::new("maps")).unwrap();
+        let mut quads = Vec::new();
+        map.chunk_quads(0, 0, 0, &mut quads);
+        assert_eq!(quads.len(), 1);
+        // Row 0 is the top row of the map; its top-left corner samples the sheet origin
+        assert_eq!(quads[0].center, Vec2::new(4.0, 12.0));
+        assert_eq!(quads[0].uvs[3], [0.0, 0.0]);
+        quads.clear();
+        // gid 6 flipped horizontally: tile (1, 1) in the sheet, u0/u1 swapped
+        map.chunk_quads(0, 1, 0, &mut quads);
+        assert_eq!(quads[0].center, Vec2::new(156.0, 4.0));
+        assert_eq!(quads[0].uvs[0], [0.5, 1.0]);
+        assert_eq!(quads[0].uvs[1], [0.25, 1.0]);
+    }
+
+    #[test]
+    fn test_set_tile_invalidates_only_its_chunk() {
+        let mut map = Tilemap::from_tiled_json(MAP, Path::new("maps")).unwrap();
+        let ground = &map.layers[0];
+        let (rev0, rev1) = (ground.chunk_revision(0, 0), ground.chunk_revision(1, 0));
+        assert!(map.set_tile(0, 3, 1, 2).unwrap());
+        assert!(!map.set_tile(0, 3, 1, 2).unwrap());
+        assert_ne!(map.layers[0].chunk_revision(0, 0), rev0);
+        assert_eq!(map.layers[0].chunk_revision(1, 0), rev1);
+        assert!(map.set_tile(0, 20, 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_sizes_and_gids_are_load_errors() {
+        let load = |from: &str, to: &str| {
+            let json = MAP.replace(from, to);
+            assert_ne!(json, MAP, "fixture did not change");
+            Tilemap::from_tiled_json(&json, Path::new("maps"))
+                .unwrap_err()
+                .to_string()
+        };
+        // Tile layer, map and tileset sizes that overflow u32 must not panic or truncate
+        let e = load(
+            r#""name": "ground", "width": 20, "height": 2"#,
+            r#""name": "ground", "width": 65536, "height": 65536"#,
+        );
+        assert!(e.contains("too large"), "{}", e);
+        let e = load(
+            r#""width": 20, "height": 2, "tilewidth""#,
+            r#""width": 4294967296, "height": 2, "tilewidth""#,
+        );
+        assert!(e.contains("'width'"), "{}", e);
+        let e = load(
+            r#""height": 2, "tilewidth": 8,"#,
+            r#""height": 2, "tilewidth": 536870912,"#,
+        );
+        assert!(e.contains("map size"), "{}", e);
+        let e = load(r#""firstgid": 1,"#, r#""firstgid": 4294967295,"#);
+        assert!(e.contains("tileset 'terrain'"), "{}", e);
+        let e = load(
+            r#""columns": 4, "tilecount": 8"#,
+            r#""columns": 4, "tilecount": 8, "spacing": 4294967295"#,
+        );
+        assert!(e.contains("tileset 'terrain'"), "{}", e);
+        let e = load(
+            r#""columns": 4, "tilecount": 8"#,
+            r#""columns": 4, "tilecount": 8, "margin": 4294967290"#,
+        );
+        assert!(e.contains("tileset 'terrain'"), "{}", e);
+        let e = load("2147483654]", "4294967296]");
+        assert!(e.contains("bad tile gid 4294967296"), "{}", e);
+    }
+}
@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-const FIXED_TIMESTEP: f64 = 1.0 / 60.0; // 60 FPS fixed timestep
+pub const FIXED_TIMESTEP: f64 = 1.0 / 60.0; // 60 FPS fixed timestep
 const MAX_FRAME_TIME: f64 = 0.25; // Don't spiral of death beyond 4 FPS
 
 pub struct FixedTimeStep {
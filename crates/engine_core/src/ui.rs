@@ -0,0 +1,305 @@
+// Immediate-mode UI core: widget interaction, focus navigation and nine-slice geometry.
+// Widgets are identified by stable hashes and driven only by `UiInput`, so the same
+// input snapshots (e.g. a replay) always produce the same clicks and focus changes.
+
+/// Widget rect in y-up UI units; `top` is the upper edge
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(left: f32, top: f32, width: f32, height: f32) -> Self {
+        Self {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, p: [f32; 2]) -> bool {
+        p[0] >= self.left
+            && p[0] < self.left + self.width
+            && p[1] <= self.top
+            && p[1] > self.top - self.height
+    }
+
+    pub fn center(&self) -> [f32; 2] {
+        [self.left + self.width * 0.5, self.top - self.height * 0.5]
+    }
+}
+
+/// Edge-triggered navigation for one frame (keyboard, gamepad or scripted)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NavInput {
+    /// Move focus to the previous/next focusable widget
+    pub up: bool,
+    pub down: bool,
+    /// Adjust the focused widget (sliders)
+    pub left: bool,
+    pub right: bool,
+    /// Click the focused widget
+    pub activate: bool,
+}
+
+/// Everything widgets react to in one frame
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UiInput {
+    /// Pointer in UI units
+    pub pointer: Option<[f32; 2]>,
+    pub pointer_down: bool,
+    pub pointer_pressed: bool,
+    pub pointer_released: bool,
+    pub nav: NavInput,
+    /// Characters typed this frame, for the focused text field
+    pub typed: Vec<char>,
+    pub backspace: bool,
+}
+
+/// How the pointer and navigation affected one widget this frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Response {
+    pub hovered: bool,
+    /// Pressed on this widget and still held
+    pub held: bool,
+    /// Released over the widget, or activated while focused
+    pub clicked: bool,
+    pub focused: bool,
+}
+
+/// Stable widget id (FNV-1a over the scope and label)
+pub fn widget_id(scope: &str, label: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in scope.bytes().chain([0u8]).chain(label.bytes()) {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Interaction state kept between frames
+#[derive(Debug, Default)]
+pub struct UiContext {
+    input: UiInput,
+    active: Option<u64>,
+    focus: Option<u64>,
+    // Focusable widgets in submission order, this frame and the last
+    order: Vec<u64>,
+    prev_order: Vec<u64>,
+}
+
+impl UiContext {
+    /// Start a frame: forget widgets that were not submitted last frame and move
+    /// focus through them in submission order.
+    pub fn begin(&mut self, input: UiInput) {
+        self.prev_order = std::mem::take(&mut self.order);
+        if !self.input.pointer_down {
+            self.active = None;
+        }
+        if self.focus.is_some_and(|f| !self.prev_order.contains(&f)) {
+            self.focus = None;
+        }
+        let n = self.prev_order.len();
+        if n > 0 && input.nav.up != input.nav.down {
+            let at = self
+                .focus
+                .and_then(|f| self.prev_order.iter().position(|&id| id == f));
+            let next = match (at, input.nav.down) {
+                (Some(i), true) => (i + 1) % n,
+                (Some(i), false) => (i + n - 1) % n,
+                (None, true) => 0,
+                (None, false) => n - 1,
+            };
+            self.focus = Some(self.prev_order[next]);
+        }
+        self.input = input;
+    }
+
+    pub fn input(&self) -> &UiInput {
+        &self.input
+    }
+
+    /// Register a focusable widget covering `rect` and report its interaction
+    pub fn interact(&mut self, id: u64, rect: Rect) -> Response {
+        self.order.push(id);
+        let over = self.input.pointer.is_some_and(|p| rect.contains(p));
+        let hovered = over && self.active.is_none_or(|a| a == id);
+        if hovered && self.input.pointer_pressed {
+            self.active = Some(id);
+            self.focus = Some(id);
+        }
+        let mine = self.active == Some(id);
+        let focused = self.focus == Some(id);
+        Response {
+            hovered,
+            held: mine && self.input.pointer_down,
+            clicked: (mine && over && self.input.pointer_released)
+                || (focused && self.input.nav.activate),
+            focused,
+        }
+    }
+
+    pub fn focused(&self) -> Option<u64> {
+        self.focus
+    }
+}
+
+/// Split `dest` into the pieces of a nine-slice. `uv` is the source frame as
+/// [u0, v0, u1, v1] with v0 at the image top, `border` the unscaled edge in UI
+/// units and `border_uv` the same edge in uv units. Corners keep their size;
+/// edges and centre stretch. Returns (rect, uv) pairs, skipping empty pieces.
+pub fn nine_slice(
+    dest: Rect,
+    uv: [f32; 4],
+    border: f32,
+    border_uv: [f32; 2],
+) -> Vec<(Rect, [f32; 4])> {
+    let b = border.min(dest.width * 0.5).min(dest.height * 0.5).max(0.0);
+    let k = if border > 0.0 { b / border } else { 0.0 };
+    let (du, dv) = (border_uv[0] * k, border_uv[1] * k);
+    let right = dest.left + dest.width;
+    let bottom = dest.top - dest.height;
+    let xs = [dest.left, dest.left + b, right - b, right];
+    let us = [uv[0], uv[0] + du, uv[2] - du, uv[2]];
+    let ys = [dest.top, dest.top - b, bottom + b, bottom];
+    let vs = [uv[1], uv[1] + dv, uv[3] - dv, uv[3]];
+    let mut out = Vec::with_capacity(9);
+    for row in 0..3 {
+        for col in 0..3 {
+            let (w, h) = (xs[col + 1] - xs[col], ys[row] - ys[row + 1]);
+            if w <= 0.0 || h <= 0.0 {
+                continue;
+            }
+            out.push((
+                Rect::new(xs[col], ys[row], w, h),
+                [us[col], vs[row], us[col + 1], vs[row + 1]],
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(pointer: [f32; 2], down: bool, prev_down: bool) -> UiInput {
+        UiInput {
+            pointer: Some(pointer),
+            pointer_down: down,
+            pointer_pressed: down && !prev_down,
+            pointer_released: !down && prev_down,
+            ..Default::default()
+        }
+    }
+
+    // Buttons A and B stacked vertically
+    fn buttons() -> [(u64, Rect); 2] {
+        [
+            (widget_id("button", "A"), Rect::new(0.0, 20.0, 40.0, 20.0)),
+            (widget_id("button", "B"), Rect::new(0.0, 50.0, 40.0, 20.0)),
+        ]
+    }
+
+    // Press and release on `at` over two frames; the last frame's responses
+    fn click(ui: &mut UiContext, at: [f32; 2]) -> Vec<Response> {
+        ui.begin(frame(at, true, false));
+        for (id, rect) in buttons() {
+            ui.interact(id, rect);
+        }
+        ui.begin(frame(at, false, true));
+        buttons()
+            .into_iter()
+            .map(|(id, rect)| ui.interact(id, rect))
+            .collect()
+    }
+
+    #[test]
+    fn test_drag_onto_another_widget_clicks_nothing() {
+        let mut ui = UiContext::default();
+        let [(a, ra), (b, rb)] = buttons();
+        // Press on A, drag onto B and release: nothing clicks, A keeps the pointer
+        ui.begin(frame([10.0, 10.0], true, false));
+        assert!(ui.interact(a, ra).held);
+        ui.interact(b, rb);
+        ui.begin(frame([10.0, 40.0], false, true));
+        assert!(!ui.interact(a, ra).clicked);
+        assert!(!ui.interact(b, rb).clicked);
+    }
+
+    #[test]
+    fn test_press_and_release_clicks_and_focuses() {
+        let mut ui = UiContext::default();
+        let r = click(&mut ui, [10.0, 40.0]);
+        assert!(!r[0].clicked);
+        assert!(r[1].clicked && r[1].focused && r[1].hovered);
+    }
+
+    #[test]
+    fn test_navigation_wraps_and_activates_the_focus() {
+        let mut ui = UiContext::default();
+        let [(a, ra), (b, rb)] = buttons();
+        click(&mut ui, [10.0, 40.0]);
+        // Navigation wraps through last frame's order; activate clicks the focus
+        ui.begin(UiInput {
+            nav: NavInput {
+                down: true,
+                activate: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert!(ui.interact(a, ra).clicked);
+        assert!(!ui.interact(b, rb).focused);
+    }
+
+    #[test]
+    fn test_widgets_that_stop_being_submitted_lose_focus() {
+        let mut ui = UiContext::default();
+        let [(a, ra), _] = buttons();
+        click(&mut ui, [10.0, 40.0]);
+        ui.begin(UiInput::default());
+        ui.interact(a, ra);
+        ui.begin(UiInput::default());
+        assert_eq!(ui.focused(), None);
+    }
+
+    #[test]
+    fn test_nine_slice_keeps_corners() {
+        let pieces = nine_slice(
+            Rect::new(10.0, 100.0, 40.0, 20.0),
+            [0.0, 0.0, 0.5, 0.5],
+            4.0,
+            [0.125, 0.125],
+        );
+        assert_eq!(pieces.len(), 9);
+        // Top-left corner: 4x4 at the dest corner, uv from the frame's top-left
+        assert_eq!(
+            pieces[0],
+            (Rect::new(10.0, 100.0, 4.0, 4.0), [0.0, 0.0, 0.125, 0.125])
+        );
+        // Centre stretches over the rest
+        assert_eq!(
+            pieces[4],
+            (
+                Rect::new(14.0, 96.0, 32.0, 12.0),
+                [0.125, 0.125, 0.375, 0.375]
+            )
+        );
+
+        // Borders shrink to fit small rects; the middle column collapses
+        let pieces = nine_slice(
+            Rect::new(0.0, 8.0, 6.0, 8.0),
+            [0.0, 0.0, 1.0, 1.0],
+            4.0,
+            [0.25, 0.25],
+        );
+        assert_eq!(pieces.len(), 6);
+        assert_eq!(pieces[1].0, Rect::new(3.0, 8.0, 3.0, 3.0));
+        assert_eq!(pieces[1].1, [0.8125, 0.0, 1.0, 0.1875]);
+    }
+}
@@ -1,7 +1,8 @@
+use crate::hud::{HudConfig, HudOverlay, HudUpdate};
 use crate::input::InputState;
 use crate::metrics::MetricsCollector;
 use crate::renderer::SpriteRenderer;
-use crate::state::EngineState;
+use crate::state::{CursorRequest, EngineState, TextInputRequest};
 use crate::time::FixedTimeStep;
 use anyhow::Result;
 use std::sync::Arc;
@@ -17,6 +18,10 @@ type OnStartCb = Box<dyn FnMut(&mut EngineState)>;
 type OnUpdateCb = Box<dyn FnMut(f64, &mut EngineState)>;
 type OnEndFrameCb = Box<dyn FnMut(&EngineState, &MetricsCollector)>;
 type HudLinesHandle = std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<String>>>;
+type HudConfigHandle = std::sync::Arc<std::sync::Mutex<HudConfig>>;
+
+// Pixel scroll deltas (touchpads) per wheel line
+const PIXELS_PER_LINE: f32 = 40.0;
 
 pub struct EngineWindow {
     window: Option<Arc<Window>>,
@@ -32,8 +37,14 @@ pub struct EngineWindow {
     on_end_frame: Option<OnEndFrameCb>,
     // Input
     pub(crate) input: std::sync::Arc<std::sync::Mutex<InputState>>,
+    // While the IME is active, typed text arrives as Ime::Commit instead of key text
+    ime_active: bool,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<crate::gamepad::backend::GamepadBackend>,
     // HUD lines (provided by host)
     hud_lines: Option<HudLinesHandle>,
+    hud_config: Option<HudConfigHandle>,
+    hud: HudOverlay,
 }
 
 impl EngineWindow {
@@ -50,7 +61,14 @@ impl EngineWindow {
             script_on_update: None,
             on_end_frame: None,
             input: std::sync::Arc::new(std::sync::Mutex::new(InputState::new())),
+            ime_active: false,
+            #[cfg(feature = "gamepad")]
+            gamepads: crate::gamepad::backend::GamepadBackend::new()
+                .map_err(|e| tracing::warn!("Gamepads unavailable: {}", e))
+                .ok(),
             hud_lines: None,
+            hud_config: None,
+            hud: HudOverlay::default(),
         }
     }
 
@@ -98,6 +116,146 @@ impl EngineWindow {
     pub fn set_hud_lines_handle(&mut self, h: HudLinesHandle) {
         self.hud_lines = Some(h);
     }
+
+    pub fn set_hud_config_handle(&mut self, h: HudConfigHandle) {
+        self.hud_config = Some(h);
+    }
+
+    /// Drive the scripts without a window or GPU: exactly one fixed update per frame,
+    /// no rendering. `feed` sets the input for each frame and returns false to stop.
+    /// Returns the number of frames run.
+    pub fn run_headless<F>(mut self, mut feed: F) -> Result<u64>
+    where
+        F: FnMut(u64, &mut InputState) -> bool,
+    {
+        // Same window size scripts see at startup in a real window
+        self.engine_state.set_window_size(1920, 1080);
+        if let Some(cb) = &mut self.script_on_start {
+            cb(&mut self.engine_state);
+        }
+        self.script_on_start_called = true;
+
+        let dt = self.timestep.fixed_dt();
+        let mut frames = 0;
+        loop {
+            let more = match self.input.lock() {
+                Ok(mut input) => feed(frames, &mut input),
+                Err(_) => false,
+            };
+            if !more {
+                break;
+            }
+            self.metrics.begin_frame();
+            self.frame_count += 1;
+            self.fixed_update(dt);
+            self.apply_requests();
+            self.finish_frame();
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
+    fn fixed_update(&mut self, dt: f64) {
+        // Update engine time
+        self.engine_state.update_time(dt);
+
+        // Invoke script with simple watchdog
+        if let Some(cb) = &mut self.script_on_update {
+            let start = std::time::Instant::now();
+            cb(dt, &mut self.engine_state);
+            if let Ok(mut input) = self.input.lock() {
+                input.clear();
+            }
+            let elapsed = start.elapsed();
+            let timed_out = self.engine_state.take_script_timeouts() > 0;
+            if elapsed.as_micros() > 2_000 || timed_out {
+                // > 2ms, or aborted by the script budget
+                tracing::warn!("Watchdog: on_update took {:?}", elapsed);
+                self.metrics.record_watchdog_spike();
+            }
+        }
+    }
+
+    // Requests and samples the fixed updates left in engine state
+    fn apply_requests(&mut self) {
+        if let Some(req) = self.engine_state.take_cursor_request() {
+            self.apply_cursor(req);
+        }
+        if let Some(req) = self.engine_state.take_text_input_request() {
+            self.apply_text_input(req);
+        }
+        for _ in 0..self.engine_state.take_reloads() {
+            self.metrics.record_reload();
+        }
+        if let Some((gc_time, mem_mb)) = self.engine_state.take_lua_gc() {
+            self.metrics.record_lua_gc(gc_time, mem_mb);
+        }
+    }
+
+    fn finish_frame(&mut self) {
+        // Reset engine frame counters
+        self.engine_state.reset_frame_counters();
+
+        // End frame metrics collection
+        self.metrics.end_frame();
+
+        // Host callback with latest metrics snapshot
+        if let Some(cb) = &mut self.on_end_frame {
+            cb(&self.engine_state, &self.metrics);
+        }
+    }
+
+    fn apply_cursor(&self, req: CursorRequest) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if let Some(visible) = req.visible {
+            window.set_cursor_visible(visible);
+        }
+        if let Some(confine) = req.confine {
+            use winit::window::CursorGrabMode;
+            let result = if confine {
+                // Not every platform can confine; locking in place is the fallback
+                window
+                    .set_cursor_grab(CursorGrabMode::Confined)
+                    .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked))
+            } else {
+                window.set_cursor_grab(CursorGrabMode::None)
+            };
+            if let Err(e) = result {
+                tracing::warn!("Cursor confine failed: {}", e);
+            }
+        }
+        if let Some(pos) = req.position {
+            let [x, y] = self.engine_state.canvas_view().virtual_to_window(pos);
+            let at = winit::dpi::PhysicalPosition::new(x as f64, y as f64);
+            if let Err(e) = window.set_cursor_position(at) {
+                tracing::warn!("Cursor move failed: {}", e);
+            }
+        }
+    }
+
+    fn apply_text_input(&self, req: TextInputRequest) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        match req {
+            TextInputRequest::Start(rect) => {
+                window.set_ime_allowed(true);
+                if let Some([x, y, w, h]) = rect {
+                    // Virtual rects are y-up; the IME wants the top-left corner
+                    let view = self.engine_state.canvas_view();
+                    let [x0, y0] = view.virtual_to_window([x, y + h]);
+                    let [x1, y1] = view.virtual_to_window([x + w, y]);
+                    window.set_ime_cursor_area(
+                        winit::dpi::PhysicalPosition::new(x0 as f64, y0 as f64),
+                        winit::dpi::PhysicalSize::new((x1 - x0) as f64, (y1 - y0) as f64),
+                    );
+                }
+            }
+            TextInputRequest::Stop => window.set_ime_allowed(false),
+        }
+    }
 }
 
 impl Default for EngineWindow {
@@ -155,6 +313,19 @@ impl ApplicationHandler for EngineWindow {
                     input.set_mouse_pos(position.x, position.y);
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                use winit::event::MouseScrollDelta;
+                // Touchpads report pixels; count them in lines of PIXELS_PER_LINE
+                let (dx, dy) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(p) => {
+                        (p.x as f32 / PIXELS_PER_LINE, p.y as f32 / PIXELS_PER_LINE)
+                    }
+                };
+                if let Ok(mut input) = self.input.lock() {
+                    input.add_wheel(dx, dy);
+                }
+            }
             WindowEvent::MouseInput { state, button, .. } => {
                 use winit::event::{ElementState, MouseButton};
                 let name = match button {
@@ -173,13 +344,34 @@ impl ApplicationHandler for EngineWindow {
             WindowEvent::KeyboardInput { event, .. } => {
                 use winit::event::ElementState;
                 use winit::keyboard::PhysicalKey;
-                if let PhysicalKey::Code(keycode) = event.physical_key {
-                    let down = matches!(event.state, ElementState::Pressed);
-                    if let Ok(mut input) = self.input.lock() {
+                let down = matches!(event.state, ElementState::Pressed);
+                if let Ok(mut input) = self.input.lock() {
+                    if let PhysicalKey::Code(keycode) = event.physical_key {
                         if let Some(stable_key) = crate::stable_keys::winit_to_stable(keycode) {
                             input.set_key(stable_key, down);
                         }
                     }
+                    // Layout-aware characters, including shifted symbols and repeats
+                    if let Some(text) = event.text.as_ref().filter(|_| down && !self.ime_active) {
+                        input.push_text(text);
+                    }
+                }
+            }
+            WindowEvent::Ime(ime) => {
+                use winit::event::Ime;
+                if let Ok(mut input) = self.input.lock() {
+                    match ime {
+                        Ime::Enabled => self.ime_active = true,
+                        Ime::Preedit(text, cursor) => input.set_preedit(&text, cursor),
+                        Ime::Commit(text) => {
+                            input.set_preedit("", None);
+                            input.push_text(&text);
+                        }
+                        Ime::Disabled => {
+                            self.ime_active = false;
+                            input.set_preedit("", None);
+                        }
+                    }
                 }
             }
             WindowEvent::RedrawRequested => {
@@ -230,35 +422,32 @@ impl ApplicationHandler for EngineWindow {
             return;
         }
 
+        // Pad events land in the input state before this frame's updates read it
+        #[cfg(feature = "gamepad")]
+        if let Some(pads) = &mut self.gamepads {
+            if let Ok(mut input) = self.input.lock() {
+                pads.poll(&mut input);
+            }
+        }
+
         // Begin frame metrics collection
         self.metrics.begin_frame();
         self.frame_count += 1;
 
         // Run fixed timestep updates (invoke script on_update if provided)
         let mut updates_run = 0;
-        self.timestep.update(|dt| {
+        let mut timestep = std::mem::take(&mut self.timestep);
+        timestep.update(|dt| {
             updates_run += 1;
             // This runs at exactly 60 FPS for deterministic behavior
             if updates_run <= 5 {
                 // Limit debug spam
                 tracing::debug!("Fixed update: dt={:.6}s", dt);
             }
-
-            // Update engine time
-            self.engine_state.update_time(dt);
-
-            // Invoke script with simple watchdog
-            if let Some(cb) = &mut self.script_on_update {
-                let start = std::time::Instant::now();
-                cb(dt, &mut self.engine_state);
-                let elapsed = start.elapsed();
-                if elapsed.as_micros() > 2_000 {
-                    // > 2ms
-                    tracing::warn!("Watchdog: on_update took {:?}", elapsed);
-                    self.metrics.record_watchdog_spike();
-                }
-            }
+            self.fixed_update(dt);
         });
+        self.timestep = timestep;
+        self.apply_requests();
 
         // Update renderer with current engine state
         if let Some(renderer) = &mut self.renderer {
@@ -270,31 +459,36 @@ impl ApplicationHandler for EngineWindow {
             self.metrics
                 .record_draws(renderer.get_draw_call_count(), renderer.get_sprite_count());
 
-            // Update HUD overlay if we have lines
+            // Update HUD overlay if we have lines; unchanged text skips the rebuild
             if let Some(lines) = &self.hud_lines {
+                let config = self
+                    .hud_config
+                    .as_ref()
+                    .and_then(|c| c.lock().ok().map(|c| c.clone()))
+                    .unwrap_or_default();
                 if let Ok(l) = lines.lock() {
-                    let (rgba, w, h) = crate::hud::rasterize_hud(
-                        &l.iter().cloned().collect::<Vec<_>>(),
-                        &self.metrics,
-                    );
-                    let _ = renderer.set_hud_rgba(&rgba, w, h);
+                    match self.hud.update(&config, &l, &self.metrics) {
+                        HudUpdate::Unchanged => {}
+                        HudUpdate::Hidden => renderer.clear_hud(),
+                        HudUpdate::Image {
+                            rgba,
+                            width,
+                            height,
+                        } => {
+                            renderer.set_hud_corner(config.corner);
+                            if let Err(e) = renderer.set_hud_rgba(&rgba, width, height) {
+                                tracing::error!("Failed to update HUD: {}", e);
+                            }
+                        }
+                    }
                 }
             }
         }
 
-        // Reset engine frame counters
-        self.engine_state.reset_frame_counters();
-
-        // End frame metrics collection
-        self.metrics.end_frame();
-
-        // Host callback with latest metrics snapshot
-        if let Some(cb) = &mut self.on_end_frame {
-            cb(&self.engine_state, &self.metrics);
-        }
+        self.finish_frame();
 
         // Log metrics every 5 seconds (300 frames at 60 FPS)
-        if self.frame_count % 300 == 0 {
+        if self.frame_count.is_multiple_of(300) {
             let stats = self.metrics.get_performance_stats();
             let violations = self.metrics.validate_performance_budgets();
             let engine_violations = self.engine_state.validate_performance_budgets();
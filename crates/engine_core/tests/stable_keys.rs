@@ -0,0 +1,170 @@
+// Stable key codes are stored in recordings and saved bindings, so a code must never
+// change once assigned. The table below pins every code; extend it, never edit it.
+use engine_core::stable_keys::{stable_to_winit, winit_to_stable};
+use proptest::prelude::*;
+use std::collections::HashSet;
+use winit::keyboard::KeyCode;
+
+const PINNED: &[(u32, KeyCode)] = &[
+    // Letters
+    (0x0001, KeyCode::KeyA),
+    (0x0002, KeyCode::KeyB),
+    (0x0003, KeyCode::KeyC),
+    (0x0004, KeyCode::KeyD),
+    (0x0005, KeyCode::KeyE),
+    (0x0006, KeyCode::KeyF),
+    (0x0007, KeyCode::KeyG),
+    (0x0008, KeyCode::KeyH),
+    (0x0009, KeyCode::KeyI),
+    (0x000A, KeyCode::KeyJ),
+    (0x000B, KeyCode::KeyK),
+    (0x000C, KeyCode::KeyL),
+    (0x000D, KeyCode::KeyM),
+    (0x000E, KeyCode::KeyN),
+    (0x000F, KeyCode::KeyO),
+    (0x0010, KeyCode::KeyP),
+    (0x0011, KeyCode::KeyQ),
+    (0x0012, KeyCode::KeyR),
+    (0x0013, KeyCode::KeyS),
+    (0x0014, KeyCode::KeyT),
+    (0x0015, KeyCode::KeyU),
+    (0x0016, KeyCode::KeyV),
+    (0x0017, KeyCode::KeyW),
+    (0x0018, KeyCode::KeyX),
+    (0x0019, KeyCode::KeyY),
+    (0x001A, KeyCode::KeyZ),
+    // Digits
+    (0x0030, KeyCode::Digit0),
+    (0x0031, KeyCode::Digit1),
+    (0x0032, KeyCode::Digit2),
+    (0x0033, KeyCode::Digit3),
+    (0x0034, KeyCode::Digit4),
+    (0x0035, KeyCode::Digit5),
+    (0x0036, KeyCode::Digit6),
+    (0x0037, KeyCode::Digit7),
+    (0x0038, KeyCode::Digit8),
+    (0x0039, KeyCode::Digit9),
+    // Symbols
+    (0x0100, KeyCode::Backquote),
+    (0x0101, KeyCode::Backslash),
+    (0x0102, KeyCode::BracketLeft),
+    (0x0103, KeyCode::BracketRight),
+    (0x0104, KeyCode::Comma),
+    (0x0105, KeyCode::Equal),
+    (0x0106, KeyCode::Minus),
+    (0x0107, KeyCode::Period),
+    (0x0108, KeyCode::Quote),
+    (0x0109, KeyCode::Semicolon),
+    (0x010A, KeyCode::Slash),
+    // Modifiers
+    (0x0200, KeyCode::AltLeft),
+    (0x0201, KeyCode::AltRight),
+    (0x0202, KeyCode::ControlLeft),
+    (0x0203, KeyCode::ControlRight),
+    (0x0204, KeyCode::ShiftLeft),
+    (0x0205, KeyCode::ShiftRight),
+    (0x0206, KeyCode::SuperLeft),
+    (0x0207, KeyCode::SuperRight),
+    // Special
+    (0x0300, KeyCode::Backspace),
+    (0x0301, KeyCode::CapsLock),
+    (0x0302, KeyCode::ContextMenu),
+    (0x0303, KeyCode::Enter),
+    (0x0304, KeyCode::Space),
+    (0x0305, KeyCode::Tab),
+    (0x0306, KeyCode::Escape),
+    (0x0307, KeyCode::PrintScreen),
+    (0x0308, KeyCode::ScrollLock),
+    (0x0309, KeyCode::Pause),
+    // Arrows
+    (0x0400, KeyCode::ArrowDown),
+    (0x0401, KeyCode::ArrowLeft),
+    (0x0402, KeyCode::ArrowRight),
+    (0x0403, KeyCode::ArrowUp),
+    // Navigation
+    (0x0500, KeyCode::End),
+    (0x0501, KeyCode::Home),
+    (0x0502, KeyCode::PageDown),
+    (0x0503, KeyCode::PageUp),
+    (0x0504, KeyCode::Delete),
+    (0x0505, KeyCode::Insert),
+    // Function keys
+    (0x0600, KeyCode::F1),
+    (0x0601, KeyCode::F2),
+    (0x0602, KeyCode::F3),
+    (0x0603, KeyCode::F4),
+    (0x0604, KeyCode::F5),
+    (0x0605, KeyCode::F6),
+    (0x0606, KeyCode::F7),
+    (0x0607, KeyCode::F8),
+    (0x0608, KeyCode::F9),
+    (0x0609, KeyCode::F10),
+    (0x060A, KeyCode::F11),
+    (0x060B, KeyCode::F12),
+    (0x060C, KeyCode::F13),
+    (0x060D, KeyCode::F14),
+    (0x060E, KeyCode::F15),
+    (0x060F, KeyCode::F16),
+    (0x0610, KeyCode::F17),
+    (0x0611, KeyCode::F18),
+    (0x0612, KeyCode::F19),
+    (0x0613, KeyCode::F20),
+    (0x0614, KeyCode::F21),
+    (0x0615, KeyCode::F22),
+    (0x0616, KeyCode::F23),
+    (0x0617, KeyCode::F24),
+    // Numpad
+    (0x0700, KeyCode::Numpad0),
+    (0x0701, KeyCode::Numpad1),
+    (0x0702, KeyCode::Numpad2),
+    (0x0703, KeyCode::Numpad3),
+    (0x0704, KeyCode::Numpad4),
+    (0x0705, KeyCode::Numpad5),
+    (0x0706, KeyCode::Numpad6),
+    (0x0707, KeyCode::Numpad7),
+    (0x0708, KeyCode::Numpad8),
+    (0x0709, KeyCode::Numpad9),
+    (0x070A, KeyCode::NumpadAdd),
+    (0x070B, KeyCode::NumpadDecimal),
+    (0x070C, KeyCode::NumpadDivide),
+    (0x070D, KeyCode::NumpadMultiply),
+    (0x070E, KeyCode::NumpadSubtract),
+    (0x070F, KeyCode::NumpadEnter),
+    (0x0710, KeyCode::NumLock),
+    // International
+    (0x0800, KeyCode::IntlBackslash),
+    (0x0801, KeyCode::IntlRo),
+    (0x0802, KeyCode::IntlYen),
+    // Media
+    (0x0900, KeyCode::MediaPlayPause),
+    (0x0901, KeyCode::MediaStop),
+    (0x0902, KeyCode::MediaTrackNext),
+    (0x0903, KeyCode::MediaTrackPrevious),
+    (0x0904, KeyCode::AudioVolumeDown),
+    (0x0905, KeyCode::AudioVolumeUp),
+    (0x0906, KeyCode::AudioVolumeMute),
+];
+
+#[test]
+fn pinned_codes_are_unique() {
+    let codes: HashSet<u32> = PINNED.iter().map(|(c, _)| *c).collect();
+    let keys: HashSet<KeyCode> = PINNED.iter().map(|(_, k)| *k).collect();
+    assert_eq!(codes.len(), PINNED.len());
+    assert_eq!(keys.len(), PINNED.len());
+}
+
+proptest! {
+    #[test]
+    fn pinned_codes_round_trip(i in 0..PINNED.len()) {
+        let (code, key) = PINNED[i];
+        prop_assert_eq!(winit_to_stable(key), Some(code));
+        prop_assert_eq!(stable_to_winit(code), Some(key));
+    }
+
+    // Mostly the assigned ranges, where a stray code would land
+    #[test]
+    fn only_pinned_codes_map_to_keys(code in prop_oneof![0u32..0x1000, any::<u32>()]) {
+        let pinned = PINNED.iter().find(|(c, _)| *c == code).map(|(_, k)| *k);
+        prop_assert_eq!(stable_to_winit(code), pinned);
+    }
+}
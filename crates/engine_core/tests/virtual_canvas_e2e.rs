@@ -7,7 +7,8 @@ use image::ImageEncoder;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-
+/// Textures queued for the harness: (id, name, encoded bytes)
+type TextureQueue = Rc<RefCell<Vec<(u32, String, Vec<u8>)>>>;
 
 /// Wrapper around framebuffer data that handles coordinate system differences
 struct FramebufferReader<'a> {
@@ -63,7 +64,7 @@ fn pixel_matches(actual: [u8; 4], expected: [u8; 4], tolerance: u8) -> bool {
 
 /// Test harness that executes Lua scripts and captures results for verification.
 struct E2ETestHarness {
-    textures: Rc<RefCell<Vec<(u32, String, Vec<u8>)>>>,
+    textures: TextureQueue,
 }
 
 impl E2ETestHarness {
@@ -95,6 +96,10 @@ impl E2ETestHarness {
         let layers_capture = Rc::new(RefCell::new(engine_core::state::Layers::with_defaults()));
         let clear_color_capture = Rc::new(RefCell::new(None));
         let render_mode_capture = Rc::new(RefCell::new(None));
+        let destroyed_capture = Rc::new(RefCell::new(Vec::<u32>::new()));
+        let tilemap_capture = Rc::new(RefCell::new(
+            Vec::<engine_core::tilemap::TilemapCommand>::new(),
+        ));
 
         let callbacks = engine_scripting::api::EngineCallbacks {
             set_transforms_cb: {
@@ -121,6 +126,16 @@ impl E2ETestHarness {
                     *render_mode_shared.borrow_mut() = Some(mode.to_string());
                 })
             },
+            set_ui_mode_cb: Rc::new(|_space| {}),
+            ui_viewport_provider: Rc::new(|| engine_core::state::UiViewport {
+                x: 0.0,
+                y: 0.0,
+                width: 320.0,
+                height: 180.0,
+                units: [320.0, 180.0],
+            }),
+            cursor_cb: Rc::new(|_req| {}),
+            text_input_cb: Rc::new(|_req| {}),
             // Enable typed path to preserve layer_id from sugar
             set_transforms_f32_cb: None,
             submit_sprites_typed_cb: {
@@ -132,11 +147,17 @@ impl E2ETestHarness {
                     cap.borrow_mut().extend_from_slice(&v[..take]);
                 }))
             },
-            metrics_provider: Rc::new(|| (0.016, 60, 1)),
+            metrics_provider: Rc::new(|| engine_scripting::api::MetricsSnapshot {
+                cpu_frame_ms: 0.016,
+                sprites_submitted: 60,
+                ffi_calls: 1,
+                ..Default::default()
+            }),
             load_texture_cb: Rc::new(|_path, _id| {}),
             input_provider: Rc::new(Default::default),
             window_size_provider: Rc::new(|| (320, 180)),
             hud_printf_cb: Rc::new(|_msg| {}),
+            hud_config_cb: Rc::new(|_config| {}),
             // Camera/layers callbacks used by scripts
             camera_set_cb: {
                 let cc = camera_capture.clone();
@@ -156,6 +177,70 @@ impl E2ETestHarness {
                 let lc = layers_capture.clone();
                 Rc::new(move |name: String| lc.borrow_mut().resolve_or_create(&name))
             },
+            layer_set_cb: {
+                let lc = layers_capture.clone();
+                Rc::new(
+                    move |name: String,
+                          order: Option<i32>,
+                          parallax: Option<(f32, f32)>,
+                          screen_space: Option<bool>,
+                          visible: Option<bool>,
+                          shake: Option<f32>,
+                          ui: Option<bool>,
+                          volume: Option<f32>,
+                          muted: Option<bool>| {
+                        let mut layers = lc.borrow_mut();
+                        layers.resolve_or_create(&name);
+                        if let Some(l) = layers.by_name_mut(&name) {
+                            if let Some(o) = order {
+                                l.order = o;
+                            }
+                            if let Some((px, py)) = parallax {
+                                l.parallax_x = px;
+                                l.parallax_y = py;
+                            }
+                            if let Some(ss) = screen_space {
+                                l.screen_space = ss;
+                            }
+                            if let Some(v) = visible {
+                                l.visible = v;
+                            }
+                            if let Some(s) = shake {
+                                l.shake_factor = s;
+                            }
+                            if let Some(u) = ui {
+                                l.ui = u;
+                            }
+                            if let Some(v) = volume {
+                                l.volume = v;
+                            }
+                            if let Some(m) = muted {
+                                l.muted = m;
+                            }
+                        }
+                    },
+                )
+            },
+            destroy_entity_cb: {
+                let ds = destroyed_capture.clone();
+                Rc::new(move |id: u32| ds.borrow_mut().push(id))
+            },
+            tilemap_cb: {
+                let tc = tilemap_capture.clone();
+                Rc::new(move |cmd| tc.borrow_mut().push(cmd))
+            },
+            audio_cb: Rc::new(|_cmd| {}),
+            layer_scroll_cb: {
+                let lc = layers_capture.clone();
+                Rc::new(move |name: String, dx: f32, dy: f32| {
+                    let mut layers = lc.borrow_mut();
+                    layers.resolve_or_create(&name);
+                    if let Some(l) = layers.by_name_mut(&name) {
+                        l.scroll_x += dx;
+                        l.scroll_y += dy;
+                    }
+                })
+            },
         };
 
         api.setup_engine_namespace_with_sinks_and_metrics(sandbox.lua(), callbacks)?;
@@ -184,6 +269,15 @@ impl E2ETestHarness {
             engine_state.set_transforms(transforms)?;
         }
 
+        // Entities destroyed by the script lose their transforms in the renderer
+        for id in destroyed_capture.borrow().iter() {
+            engine_state.release_entity(*id);
+        }
+        for cmd in tilemap_capture.borrow_mut().drain(..) {
+            engine_state.apply_tilemap_command(cmd);
+        }
+        engine_state.set_quads(api.take_quads());
+
         // Apply camera and layers from script
         let (cx, cy) = *camera_capture.borrow();
         engine_state.set_camera_xy(cx, cy);
@@ -1,6 +1,13 @@
+use crate::input_map::{ActionState, InputMap, InputMapState};
 use anyhow::Result;
+use engine_core::collision::CollisionWorld;
+use engine_core::entity::EntityAllocator;
+use engine_core::gamepad::{PadAxis, PadButton, PadState, MAX_PADS};
+use engine_core::hud::{HudConfig, HudCorner};
 use engine_core::stable_keys;
-use engine_core::state::SpriteData;
+use engine_core::state::{
+    CanvasView, CursorRequest, SpriteData, TextInputRequest, UiSpace, UiViewport,
+};
 use mlua::{AnyUserData, FromLua, Lua, RegistryKey, UserData, UserDataMethods, Value};
 use serde::Deserialize;
 use std::cell::RefCell;
@@ -12,19 +19,39 @@ type SetTransformsCb = Rc<dyn Fn(&[f64])>;
 type SetTransformsF32Cb = Option<Rc<dyn Fn(Rc<RefCell<Vec<f32>>>, usize, usize)>>;
 type SubmitSpritesCb = Rc<dyn Fn(&[SpriteV2])>;
 type SubmitSpritesTypedCb = Option<Rc<dyn Fn(Rc<RefCell<Vec<SpriteData>>>, usize, usize)>>;
-type MetricsProviderCb = Rc<dyn Fn() -> (f64, u32, u32)>;
+type MetricsProviderCb = Rc<dyn Fn() -> MetricsSnapshot>;
 type LoadTextureCb = Rc<dyn Fn(String, u32)>;
 type InputProviderCb = Rc<dyn Fn() -> InputSnapshot>;
 type WindowSizeProviderCb = Rc<dyn Fn() -> (u32, u32)>;
 type HudPrintfCb = Rc<dyn Fn(String)>;
+type HudConfigCb = Rc<dyn Fn(HudConfig)>;
 type SetClearColorCb = Rc<dyn Fn(f32, f32, f32, f32)>;
 type SetRenderModeCb = Rc<dyn Fn(&'static str)>;
+type SetUiModeCb = Rc<dyn Fn(UiSpace)>;
+type CursorCb = Rc<dyn Fn(CursorRequest)>;
+type TextInputCb = Rc<dyn Fn(TextInputRequest)>;
+pub(crate) type UiViewportCb = Rc<dyn Fn() -> UiViewport>;
 type CameraSetCb = Rc<dyn Fn(f32, f32)>;
 type CameraGetCb = Rc<dyn Fn() -> (f32, f32)>;
 type LayerDefineCb = Rc<dyn Fn(String, i32) -> u32>;
-type LayerResolveCb = Rc<dyn Fn(String) -> u32>;
-type LayerSetCb = Rc<dyn Fn(String, Option<i32>, Option<(f32, f32)>, Option<bool>, Option<bool>, Option<f32>)>;
+pub(crate) type LayerResolveCb = Rc<dyn Fn(String) -> u32>;
+type LayerSetCb = Rc<
+    dyn Fn(
+        String,
+        Option<i32>,
+        Option<(f32, f32)>,
+        Option<bool>,
+        Option<bool>,
+        Option<f32>,
+        Option<bool>,
+        Option<f32>,
+        Option<bool>,
+    ),
+>;
 type LayerScrollCb = Rc<dyn Fn(String, f32, f32)>;
+type DestroyEntityCb = Rc<dyn Fn(u32)>;
+type TilemapCb = Rc<dyn Fn(engine_core::tilemap::TilemapCommand)>;
+type AudioCb = Rc<dyn Fn(engine_core::audio::AudioCommand)>;
 
 /// Complex tuple type for sprite texture parameters
 type SpriteTexParams = (
@@ -42,6 +69,16 @@ type SpriteTexParams = (
     Option<f32>,
 );
 
+/// Values reported to scripts by `engine.get_metrics()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsSnapshot {
+    pub cpu_frame_ms: f64,
+    pub sprites_submitted: u32,
+    pub ffi_calls: u32,
+    pub lua_gc_ms: f64,
+    pub lua_mem_mb: f64,
+}
+
 /// Callback configuration for the extended engine namespace
 pub struct EngineCallbacks {
     pub set_transforms_cb: SetTransformsCb,
@@ -53,8 +90,17 @@ pub struct EngineCallbacks {
     pub input_provider: InputProviderCb,
     pub window_size_provider: WindowSizeProviderCb,
     pub hud_printf_cb: HudPrintfCb,
+    // Full HUD config after each engine.hud_config call
+    pub hud_config_cb: HudConfigCb,
     pub set_clear_color_cb: SetClearColorCb,
     pub set_render_mode_cb: SetRenderModeCb,
+    // UI layers: coordinate space and its current size
+    pub set_ui_mode_cb: SetUiModeCb,
+    pub ui_viewport_provider: UiViewportCb,
+    // engine.cursor visibility, confinement and warps
+    pub cursor_cb: CursorCb,
+    // engine.text_input_start / text_input_stop (IME composition)
+    pub text_input_cb: TextInputCb,
     // New: camera and layers (minimal v0)
     pub camera_set_cb: CameraSetCb,
     pub camera_get_cb: CameraGetCb,
@@ -62,6 +108,12 @@ pub struct EngineCallbacks {
     pub layer_resolve_cb: LayerResolveCb,
     pub layer_set_cb: LayerSetCb,
     pub layer_scroll_cb: LayerScrollCb,
+    // Entity lifecycle: notified after engine.destroy_entity succeeds
+    pub destroy_entity_cb: DestroyEntityCb,
+    // Tilemap loads, tile edits and unloads from engine.tilemap_load handles
+    pub tilemap_cb: TilemapCb,
+    // Sound loads, plays, music and bus changes for the host mixer
+    pub audio_cb: AudioCb,
 }
 
 /// Current engine API version
@@ -79,7 +131,31 @@ impl UserData for EntityId {
         methods.add_meta_method("__tostring", |_, this, ()| {
             Ok(format!("Entity({})", this.0))
         });
+        methods.add_method("is_alive", |lua, this, ()| {
+            Ok(match lua.app_data_ref::<SharedEntities>() {
+                Some(ents) => ents.0.borrow().is_alive(this.0),
+                None => false,
+            })
+        });
+    }
+}
+
+/// Entity allocator shared with EntityId methods through Lua app data
+#[derive(Clone)]
+pub(crate) struct SharedEntities(pub(crate) Rc<RefCell<EntityAllocator>>);
+
+/// Resolve a sprite's entity handle, rejecting destroyed entities.
+fn live_sprite_entity(lua: &Lua, ud: &AnyUserData) -> mlua::Result<u32> {
+    let id = ud.borrow::<EntityId>()?.0;
+    if let Some(ents) = lua.app_data_ref::<SharedEntities>() {
+        if !ents.0.borrow().is_alive(id) {
+            return Err(mlua::Error::RuntimeError(format!(
+                "sprite submitted for dead Entity({}) (destroyed or stale handle)",
+                id
+            )));
+        }
     }
+    Ok(id)
 }
 
 impl UserData for TextureHandle {
@@ -99,6 +175,27 @@ pub struct InputSnapshot {
     pub mouse_y: f64,
     pub mouse_buttons: HashMap<String, bool>,
     pub prev_mouse_buttons: HashMap<String, bool>,
+    /// Resolved actions; `None` until engine.get_input resolves them from the
+    /// current bindings. Replays fill these from the recording.
+    pub actions: Option<ActionState>,
+    pub prev_actions: Option<ActionState>,
+    pub pads: [PadState; MAX_PADS],
+    pub prev_pads: [PadState; MAX_PADS],
+    /// Wheel lines this update
+    pub wheel: [f32; 2],
+    /// Mouse on the virtual canvas (y-up), `None` over the letterbox bars
+    pub mouse_virtual: Option<[f32; 2]>,
+    /// Mouse in UI units (y-up), recorded so replays hit-test the same widgets at any
+    /// window size. `None` maps `mouse_x/y` through the live UI viewport instead.
+    pub mouse_ui: Option<[f32; 2]>,
+    /// Camera and layers for mouse_world
+    pub view: Option<CanvasView>,
+    /// Characters committed this update; `None` when the source has no text stream
+    /// (hand-built snapshots), so UI text fields fall back to keys
+    pub text: Option<String>,
+    /// IME composition and its caret as a byte range
+    pub preedit: String,
+    pub preedit_cursor: Option<(usize, usize)>,
 }
 
 impl InputSnapshot {
@@ -110,6 +207,17 @@ impl InputSnapshot {
             mouse_y: 0.0,
             mouse_buttons: HashMap::new(),
             prev_mouse_buttons: HashMap::new(),
+            actions: None,
+            prev_actions: None,
+            pads: [PadState::default(); MAX_PADS],
+            prev_pads: [PadState::default(); MAX_PADS],
+            wheel: [0.0, 0.0],
+            mouse_virtual: None,
+            mouse_ui: None,
+            view: None,
+            text: None,
+            preedit: String::new(),
+            preedit_cursor: None,
         }
     }
 }
@@ -153,6 +261,28 @@ impl UserData for InputSnapshot {
             let was_down = this.prev_keys.get(&key).copied().unwrap_or(false);
             Ok(!is_down && was_down)
         });
+        // modifiers() -> { shift, ctrl, alt, super }, either side held
+        methods.add_method("modifiers", |lua, this, ()| {
+            let down = |k: u32| this.keys.get(&k).copied().unwrap_or(false);
+            let t = lua.create_table()?;
+            t.set(
+                "shift",
+                down(stable_keys::SHIFT_LEFT) || down(stable_keys::SHIFT_RIGHT),
+            )?;
+            t.set(
+                "ctrl",
+                down(stable_keys::CONTROL_LEFT) || down(stable_keys::CONTROL_RIGHT),
+            )?;
+            t.set(
+                "alt",
+                down(stable_keys::ALT_LEFT) || down(stable_keys::ALT_RIGHT),
+            )?;
+            t.set(
+                "super",
+                down(stable_keys::SUPER_LEFT) || down(stable_keys::SUPER_RIGHT),
+            )?;
+            Ok(t)
+        });
 
         // --- Mouse ---
         methods.add_method("get_mouse_button", |_, this, button: String| {
@@ -180,6 +310,102 @@ impl UserData for InputSnapshot {
         });
 
         methods.add_method("mouse_pos", |_, this, ()| Ok((this.mouse_x, this.mouse_y)));
+        methods.add_method("wheel", |_, this, ()| Ok((this.wheel[0], this.wheel[1])));
+        // mouse_virtual() -> x, y on the virtual canvas (y-up), or nil over the letterbox bars
+        methods.add_method("mouse_virtual", |_, this, ()| {
+            Ok((
+                this.mouse_virtual.map(|p| p[0]),
+                this.mouse_virtual.map(|p| p[1]),
+            ))
+        });
+        // mouse_world(layer?) -> x, y under the cursor as drawn on `layer`, or nil
+        methods.add_method("mouse_world", |_, this, layer: Option<String>| {
+            let view = this.view.clone().unwrap_or_default();
+            let w = this
+                .mouse_virtual
+                .and_then(|v| view.virtual_to_world(v, layer.as_deref()));
+            Ok((w.map(|p| p[0]), w.map(|p| p[1])))
+        });
+
+        // --- Text ---
+        methods.add_method("text", |_, this, ()| {
+            Ok(this.text.clone().unwrap_or_default())
+        });
+        // preedit() -> composition, caret start, caret end (bytes before the caret)
+        methods.add_method("preedit", |_, this, ()| {
+            let (start, end) = this.preedit_cursor.unzip();
+            Ok((this.preedit.clone(), start, end))
+        });
+
+        // --- Gamepads (1-based indices) ---
+        fn pad(pads: &[PadState; MAX_PADS], index: usize) -> PadState {
+            index
+                .checked_sub(1)
+                .and_then(|i| pads.get(i))
+                .copied()
+                .unwrap_or_default()
+        }
+        fn pad_button(name: &str) -> mlua::Result<PadButton> {
+            PadButton::parse(name)
+                .ok_or_else(|| mlua::Error::RuntimeError(format!("pad: unknown button '{}'", name)))
+        }
+        methods.add_method("pad_connected", |_, this, index: usize| {
+            Ok(pad(&this.pads, index).connected)
+        });
+        methods.add_method("pad_down", |_, this, (index, name): (usize, String)| {
+            Ok(pad(&this.pads, index).down(pad_button(&name)?))
+        });
+        methods.add_method("pad_pressed", |_, this, (index, name): (usize, String)| {
+            let b = pad_button(&name)?;
+            Ok(pad(&this.pads, index).down(b) && !pad(&this.prev_pads, index).down(b))
+        });
+        methods.add_method("pad_released", |_, this, (index, name): (usize, String)| {
+            let b = pad_button(&name)?;
+            Ok(!pad(&this.pads, index).down(b) && pad(&this.prev_pads, index).down(b))
+        });
+        methods.add_method("pad_axis", |_, this, (index, name): (usize, String)| {
+            let axis = PadAxis::parse(&name).ok_or_else(|| {
+                mlua::Error::RuntimeError(format!("pad_axis: unknown axis '{}'", name))
+            })?;
+            Ok(pad(&this.pads, index).axis(axis))
+        });
+        // pad_events() -> { {pad = i, event = "connected"|"disconnected"}, ... } since last frame
+        methods.add_method("pad_events", |lua, this, ()| {
+            let events = lua.create_table()?;
+            for (i, (now, before)) in this.pads.iter().zip(this.prev_pads.iter()).enumerate() {
+                if now.connected != before.connected {
+                    let e = lua.create_table()?;
+                    e.set("pad", i + 1)?;
+                    e.set(
+                        "event",
+                        if now.connected {
+                            "connected"
+                        } else {
+                            "disconnected"
+                        },
+                    )?;
+                    events.push(e)?;
+                }
+            }
+            Ok(events)
+        });
+
+        // --- Actions (engine.input_map) ---
+        fn action(s: &Option<ActionState>, name: &str) -> bool {
+            s.as_ref().is_some_and(|a| a.down(name))
+        }
+        methods.add_method("action_down", |_, this, name: String| {
+            Ok(action(&this.actions, &name))
+        });
+        methods.add_method("action_pressed", |_, this, name: String| {
+            Ok(action(&this.actions, &name) && !action(&this.prev_actions, &name))
+        });
+        methods.add_method("action_released", |_, this, name: String| {
+            Ok(!action(&this.actions, &name) && action(&this.prev_actions, &name))
+        });
+        methods.add_method("axis", |_, this, name: String| {
+            Ok(this.actions.as_ref().map_or(0.0, |a| a.value(&name)))
+        });
     }
 }
 
@@ -228,127 +454,9 @@ impl UserData for EngineCapabilities {
 
 fn create_keys_table(lua: &Lua) -> mlua::Result<mlua::Table> {
     let keys = lua.create_table()?;
-
-    // Letters
-    keys.set("KeyA", stable_keys::KEY_A)?;
-    keys.set("KeyB", stable_keys::KEY_B)?;
-    keys.set("KeyC", stable_keys::KEY_C)?;
-    keys.set("KeyD", stable_keys::KEY_D)?;
-    keys.set("KeyE", stable_keys::KEY_E)?;
-    keys.set("KeyF", stable_keys::KEY_F)?;
-    keys.set("KeyG", stable_keys::KEY_G)?;
-    keys.set("KeyH", stable_keys::KEY_H)?;
-    keys.set("KeyI", stable_keys::KEY_I)?;
-    keys.set("KeyJ", stable_keys::KEY_J)?;
-    keys.set("KeyK", stable_keys::KEY_K)?;
-    keys.set("KeyL", stable_keys::KEY_L)?;
-    keys.set("KeyM", stable_keys::KEY_M)?;
-    keys.set("KeyN", stable_keys::KEY_N)?;
-    keys.set("KeyO", stable_keys::KEY_O)?;
-    keys.set("KeyP", stable_keys::KEY_P)?;
-    keys.set("KeyQ", stable_keys::KEY_Q)?;
-    keys.set("KeyR", stable_keys::KEY_R)?;
-    keys.set("KeyS", stable_keys::KEY_S)?;
-    keys.set("KeyT", stable_keys::KEY_T)?;
-    keys.set("KeyU", stable_keys::KEY_U)?;
-    keys.set("KeyV", stable_keys::KEY_V)?;
-    keys.set("KeyW", stable_keys::KEY_W)?;
-    keys.set("KeyX", stable_keys::KEY_X)?;
-    keys.set("KeyY", stable_keys::KEY_Y)?;
-    keys.set("KeyZ", stable_keys::KEY_Z)?;
-
-    // Digits
-    keys.set("Digit0", stable_keys::DIGIT_0)?;
-    keys.set("Digit1", stable_keys::DIGIT_1)?;
-    keys.set("Digit2", stable_keys::DIGIT_2)?;
-    keys.set("Digit3", stable_keys::DIGIT_3)?;
-    keys.set("Digit4", stable_keys::DIGIT_4)?;
-    keys.set("Digit5", stable_keys::DIGIT_5)?;
-    keys.set("Digit6", stable_keys::DIGIT_6)?;
-    keys.set("Digit7", stable_keys::DIGIT_7)?;
-    keys.set("Digit8", stable_keys::DIGIT_8)?;
-    keys.set("Digit9", stable_keys::DIGIT_9)?;
-
-    // Symbols
-    keys.set("Backquote", stable_keys::BACKQUOTE)?;
-    keys.set("Backslash", stable_keys::BACKSLASH)?;
-    keys.set("BracketLeft", stable_keys::BRACKET_LEFT)?;
-    keys.set("BracketRight", stable_keys::BRACKET_RIGHT)?;
-    keys.set("Comma", stable_keys::COMMA)?;
-    keys.set("Equal", stable_keys::EQUAL)?;
-    keys.set("Minus", stable_keys::MINUS)?;
-    keys.set("Period", stable_keys::PERIOD)?;
-    keys.set("Quote", stable_keys::QUOTE)?;
-    keys.set("Semicolon", stable_keys::SEMICOLON)?;
-    keys.set("Slash", stable_keys::SLASH)?;
-
-    // Modifiers
-    keys.set("AltLeft", stable_keys::ALT_LEFT)?;
-    keys.set("AltRight", stable_keys::ALT_RIGHT)?;
-    keys.set("ControlLeft", stable_keys::CONTROL_LEFT)?;
-    keys.set("ControlRight", stable_keys::CONTROL_RIGHT)?;
-    keys.set("ShiftLeft", stable_keys::SHIFT_LEFT)?;
-    keys.set("ShiftRight", stable_keys::SHIFT_RIGHT)?;
-    keys.set("SuperLeft", stable_keys::SUPER_LEFT)?;
-    keys.set("SuperRight", stable_keys::SUPER_RIGHT)?;
-
-    // Special keys
-    keys.set("Backspace", stable_keys::BACKSPACE)?;
-    keys.set("CapsLock", stable_keys::CAPS_LOCK)?;
-    keys.set("ContextMenu", stable_keys::CONTEXT_MENU)?;
-    keys.set("Enter", stable_keys::ENTER)?;
-    keys.set("Space", stable_keys::SPACE)?;
-    keys.set("Tab", stable_keys::TAB)?;
-
-    // Arrow keys
-    keys.set("ArrowDown", stable_keys::ARROW_DOWN)?;
-    keys.set("ArrowLeft", stable_keys::ARROW_LEFT)?;
-    keys.set("ArrowRight", stable_keys::ARROW_RIGHT)?;
-    keys.set("ArrowUp", stable_keys::ARROW_UP)?;
-
-    // Navigation
-    keys.set("End", stable_keys::END)?;
-    keys.set("Home", stable_keys::HOME)?;
-    keys.set("PageDown", stable_keys::PAGE_DOWN)?;
-    keys.set("PageUp", stable_keys::PAGE_UP)?;
-
-    // Function keys
-    keys.set("F1", stable_keys::F1)?;
-    keys.set("F2", stable_keys::F2)?;
-    keys.set("F3", stable_keys::F3)?;
-    keys.set("F4", stable_keys::F4)?;
-    keys.set("F5", stable_keys::F5)?;
-    keys.set("F6", stable_keys::F6)?;
-    keys.set("F7", stable_keys::F7)?;
-    keys.set("F8", stable_keys::F8)?;
-    keys.set("F9", stable_keys::F9)?;
-    keys.set("F10", stable_keys::F10)?;
-    keys.set("F11", stable_keys::F11)?;
-    keys.set("F12", stable_keys::F12)?;
-
-    // Numpad
-    keys.set("Numpad0", stable_keys::NUMPAD_0)?;
-    keys.set("Numpad1", stable_keys::NUMPAD_1)?;
-    keys.set("Numpad2", stable_keys::NUMPAD_2)?;
-    keys.set("Numpad3", stable_keys::NUMPAD_3)?;
-    keys.set("Numpad4", stable_keys::NUMPAD_4)?;
-    keys.set("Numpad5", stable_keys::NUMPAD_5)?;
-    keys.set("Numpad6", stable_keys::NUMPAD_6)?;
-    keys.set("Numpad7", stable_keys::NUMPAD_7)?;
-    keys.set("Numpad8", stable_keys::NUMPAD_8)?;
-    keys.set("Numpad9", stable_keys::NUMPAD_9)?;
-    keys.set("NumpadAdd", stable_keys::NUMPAD_ADD)?;
-    keys.set("NumpadDecimal", stable_keys::NUMPAD_DECIMAL)?;
-    keys.set("NumpadDivide", stable_keys::NUMPAD_DIVIDE)?;
-    keys.set("NumpadMultiply", stable_keys::NUMPAD_MULTIPLY)?;
-    keys.set("NumpadSubtract", stable_keys::NUMPAD_SUBTRACT)?;
-    keys.set("NumpadEnter", stable_keys::NUMPAD_ENTER)?;
-
-    // International
-    keys.set("IntlBackslash", stable_keys::INTL_BACKSLASH)?;
-    keys.set("IntlRo", stable_keys::INTL_RO)?;
-    keys.set("IntlYen", stable_keys::INTL_YEN)?;
-
+    for (name, code) in stable_keys::KEY_NAMES {
+        keys.set(*name, *code)?;
+    }
     Ok(keys)
 }
 
@@ -362,7 +470,7 @@ fn parse_hex_color(hex: u32) -> (f32, f32, f32, f32) {
 }
 
 /// Parse hex color from string ("#RRGGBB", "#RRGGBBAA", "RRGGBB", "RRGGBBAA")
-fn parse_hex_string(hex_str: &str) -> Result<(f32, f32, f32, f32), String> {
+pub(crate) fn parse_hex_string(hex_str: &str) -> Result<(f32, f32, f32, f32), String> {
     let hex_str = hex_str.trim_start_matches('#');
 
     let hex_val = match hex_str.len() {
@@ -381,6 +489,99 @@ fn parse_hex_string(hex_str: &str) -> Result<(f32, f32, f32, f32), String> {
     Ok(parse_hex_color(hex_val))
 }
 
+// engine.cursor{ visible?, confine?, pos = {x, y}? }
+fn cursor_arg(opts: &mlua::Table) -> mlua::Result<CursorRequest> {
+    let position = match opts.get::<Option<mlua::Table>>("pos")? {
+        Some(p) => Some([
+            p.raw_get::<Option<f32>>(1)?
+                .or(p.get::<Option<f32>>("x")?)
+                .unwrap_or(0.0),
+            p.raw_get::<Option<f32>>(2)?
+                .or(p.get::<Option<f32>>("y")?)
+                .unwrap_or(0.0),
+        ]),
+        None => None,
+    };
+    Ok(CursorRequest {
+        visible: opts.get("visible")?,
+        confine: opts.get("confine")?,
+        position,
+    })
+}
+
+// engine.text_input_start({ x, y, w, h }?) with the field in virtual canvas pixels
+fn text_input_arg(rect: Option<mlua::Table>) -> mlua::Result<TextInputRequest> {
+    let rect = match rect {
+        Some(r) => Some([r.get("x")?, r.get("y")?, r.get("w")?, r.get("h")?]),
+        None => None,
+    };
+    Ok(TextInputRequest::Start(rect))
+}
+
+fn ui_mode_arg(mode: &str) -> mlua::Result<UiSpace> {
+    match mode {
+        "virtual" => Ok(UiSpace::Virtual),
+        "native" => Ok(UiSpace::Native),
+        _ => Err(mlua::Error::RuntimeError(format!(
+            "set_ui_mode: unknown mode '{}' (use \"virtual\" or \"native\")",
+            mode
+        ))),
+    }
+}
+
+/// Merge engine.hud_config options into `config`; omitted fields keep their value
+fn apply_hud_config(config: &mut HudConfig, def: &mlua::Table) -> mlua::Result<()> {
+    if let Some(corner) = def.get::<Option<String>>("corner")? {
+        config.corner = HudCorner::parse(&corner).ok_or_else(|| {
+            mlua::Error::RuntimeError(format!(
+                "hud_config: unknown corner '{}' (use top_left, top_right, bottom_left or bottom_right)",
+                corner
+            ))
+        })?;
+    }
+    if let Some(lines) = def.get::<Option<usize>>("lines")? {
+        config.lines = lines.min(engine_core::hud::HISTORY_LINES);
+    }
+    if let Some(columns) = def.get::<Option<usize>>("columns")? {
+        config.columns = columns.clamp(1, 256);
+    }
+    let to_u8 = |c: [f32; 4]| c.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+    if !matches!(def.get::<Value>("color")?, Value::Nil) {
+        config.text_color = to_u8(color_arg(def.get("color")?)?);
+    }
+    if !matches!(def.get::<Value>("background")?, Value::Nil) {
+        config.background = to_u8(color_arg(def.get("background")?)?);
+    }
+    if let Some(alpha) = def.get::<Option<f32>>("background_alpha")? {
+        config.background[3] = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    if let Some(metrics) = def.get::<Option<bool>>("metrics")? {
+        config.show_metrics = metrics;
+    }
+    Ok(())
+}
+
+/// Color argument: {r,g,b,a} table, 0xRRGGBBAA integer or hex string; nil is white
+pub(crate) fn color_arg(value: Value) -> mlua::Result<[f32; 4]> {
+    let (r, g, b, a) = match value {
+        Value::Nil => (1.0, 1.0, 1.0, 1.0),
+        Value::Table(t) => (
+            t.get("r")?,
+            t.get("g")?,
+            t.get("b")?,
+            t.get::<Option<f32>>("a")?.unwrap_or(1.0),
+        ),
+        Value::Integer(hex) => parse_hex_color(hex as u32),
+        Value::String(s) => parse_hex_string(&s.to_str()?).map_err(mlua::Error::RuntimeError)?,
+        _ => {
+            return Err(mlua::Error::RuntimeError(
+                "color must be color table, hex integer or hex string".into(),
+            ))
+        }
+    };
+    Ok([r, g, b, a])
+}
+
 /// Convert HSV to RGB. H in [0,360], S,V in [0,1]
 fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
     let h = h / 60.0;
@@ -402,16 +603,28 @@ fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
 
 /// Main engine API struct
 pub struct EngineApi {
-    next_entity_id: u32,
+    entities: Rc<RefCell<EntityAllocator>>,
+    entity_destroyed: Rc<RefCell<Option<DestroyEntityCb>>>,
+    tasks: Rc<RefCell<crate::tasks::TaskScheduler>>, // engine.spawn coroutines
+    tweens: Rc<RefCell<crate::tween::TweenSet>>,     // engine.tween animations
+    collisions: Rc<RefCell<CollisionWorld>>,         // colliders synced from set_transforms
+    tilemaps: Rc<RefCell<crate::tilemap::TilemapRegistry>>, // engine.tilemap_load maps
+    fonts: Rc<RefCell<crate::font::FontRegistry>>,   // engine.font_load fonts
+    audio: Rc<RefCell<crate::audio::AudioRegistry>>, // engine.sound_load sounds
+    quads: Rc<RefCell<Vec<engine_core::state::QuadData>>>, // engine.text glyphs and engine.ui widgets this update
+    ui: Rc<RefCell<crate::ui::UiState>>,                   // engine.ui focus and style
     next_texture_id: u32,
     fixed_time: Rc<RefCell<f64>>, // shared with time() closure
     persistence_store: Rc<RefCell<HashMap<String, Value>>>,
-    rng_state: Rc<RefCell<u64>>,       // deterministic RNG
-    input: Rc<RefCell<InputSnapshot>>, // Shared input state
+    save_store: Rc<RefCell<crate::persist::SaveStore>>, // on-disk save slots
+    rng_state: Rc<RefCell<u64>>,                        // deterministic RNG
+    input: Rc<RefCell<InputSnapshot>>,                  // Shared input state
+    input_map: Rc<RefCell<InputMapState>>,              // engine.input_map bindings
 
     // Simple rate limiters (window start, count)
     log_rl: Rc<RefCell<(f64, u32)>>,
     hud_rl: Rc<RefCell<(f64, u32)>>,
+    hud_config: Rc<RefCell<HudConfig>>, // merged engine.hud_config options
     capabilities: EngineCapabilities,
 
     // Internal sugar API buffers (growable)
@@ -424,14 +637,26 @@ pub struct EngineApi {
 impl EngineApi {
     pub fn new() -> Self {
         Self {
-            next_entity_id: 1,
+            entities: Rc::new(RefCell::new(EntityAllocator::new())),
+            entity_destroyed: Rc::new(RefCell::new(None)),
+            tasks: Rc::new(RefCell::new(crate::tasks::TaskScheduler::default())),
+            tweens: Rc::new(RefCell::new(crate::tween::TweenSet::default())),
+            collisions: Rc::new(RefCell::new(CollisionWorld::default())),
+            tilemaps: Rc::new(RefCell::new(crate::tilemap::TilemapRegistry::default())),
+            fonts: Rc::new(RefCell::new(crate::font::FontRegistry::default())),
+            audio: Rc::new(RefCell::new(crate::audio::AudioRegistry::default())),
+            quads: Rc::new(RefCell::new(Vec::new())),
+            ui: Rc::new(RefCell::new(crate::ui::UiState::default())),
             next_texture_id: 1,
             fixed_time: Rc::new(RefCell::new(0.0)),
             persistence_store: Rc::new(RefCell::new(HashMap::new())),
+            save_store: Rc::new(RefCell::new(crate::persist::SaveStore::new("saves"))),
             rng_state: Rc::new(RefCell::new(0x9E3779B97F4A7C15)),
             input: Rc::new(RefCell::new(InputSnapshot::new())),
+            input_map: Rc::new(RefCell::new(InputMapState::default())),
             log_rl: Rc::new(RefCell::new((0.0, 0))),
             hud_rl: Rc::new(RefCell::new((0.0, 0))),
+            hud_config: Rc::new(RefCell::new(HudConfig::default())),
             capabilities: EngineCapabilities::default(),
             sugar_transforms: Rc::new(RefCell::new(TransformBuffer::new(128))), // Start with reasonable capacity
             sugar_sprites: Rc::new(RefCell::new(SpriteBuffer::new(128))),
@@ -439,10 +664,87 @@ impl EngineApi {
         }
     }
 
+    /// Directory used by `engine.save_slot`/`engine.load_slot` (default "saves").
+    pub fn set_save_dir(&self, dir: impl Into<std::path::PathBuf>) {
+        *self.save_store.borrow_mut() = crate::persist::SaveStore::new(dir);
+    }
+
+    /// Directory used by `engine.input_map_save`/`engine.input_map_load` (default "config").
+    pub fn set_config_dir(&self, dir: impl Into<std::path::PathBuf>) {
+        self.input_map.borrow_mut().dir = dir.into();
+    }
+
+    /// Where sound and music commands go; set by the callbacks variant of setup.
+    pub fn set_audio_sink(&self, sink: AudioCb) {
+        self.audio.borrow_mut().host = Some(sink);
+    }
+
+    /// Input returned by engine.get_input when no provider is installed
+    pub fn set_input_snapshot(&self, snap: InputSnapshot) {
+        *self.input.borrow_mut() = snap;
+    }
+
+    pub fn input_map(&self) -> InputMap {
+        self.input_map.borrow().map.clone()
+    }
+
+    /// Actions scripts saw on their latest engine.get_input call
+    pub fn last_actions(&self) -> ActionState {
+        self.input_map.borrow().last.clone()
+    }
+
+    /// engine.random state; recordings store it so replays draw the same numbers
+    pub fn rng_state(&self) -> u64 {
+        *self.rng_state.borrow()
+    }
+
+    pub fn set_rng_state(&self, state: u64) {
+        *self.rng_state.borrow_mut() = state;
+    }
+
+    /// Shared bindings state, for hosts that record actions after `self` is moved
+    pub fn input_map_state(&self) -> Rc<RefCell<InputMapState>> {
+        self.input_map.clone()
+    }
+
     pub fn update_time(&mut self, dt: f64) {
         *self.fixed_time.borrow_mut() += dt;
     }
 
+    /// Resume `engine.spawn` tasks that are due at the current fixed time. Call once
+    /// per fixed step after `on_update`; returns failure reports with tracebacks.
+    pub fn step_tasks(&self, over_budget: &dyn Fn() -> bool) -> Vec<String> {
+        crate::tasks::step(&self.tasks, *self.fixed_time.borrow(), over_budget)
+    }
+
+    /// Number of tasks still waiting to resume.
+    pub fn task_count(&self) -> usize {
+        self.tasks.borrow().len()
+    }
+
+    /// Advance `engine.tween` animations to the current fixed time. Call once per
+    /// fixed step; returns failure reports.
+    pub fn step_tweens(&self) -> Vec<String> {
+        crate::tween::step(&self.tweens, *self.fixed_time.borrow())
+    }
+
+    /// Number of tweens still pending or running.
+    pub fn tween_count(&self) -> usize {
+        self.tweens.borrow().len()
+    }
+
+    /// Recompute collider contacts from the latest transforms. Call once per fixed
+    /// step after `on_update`; scripts read the result via `engine.collisions()`.
+    pub fn step_collisions(&self) {
+        self.collisions.borrow_mut().step();
+    }
+
+    /// Take the quads emitted by `engine.text` and `engine.ui` since the last call. Call once per
+    /// fixed step after `on_update` and hand them to `EngineState::set_quads`.
+    pub fn take_quads(&self) -> Vec<engine_core::state::QuadData> {
+        std::mem::take(&mut *self.quads.borrow_mut())
+    }
+
     pub fn setup_engine_namespace(&self, lua: &Lua) -> Result<()> {
         let globals = lua.globals();
 
@@ -489,20 +791,50 @@ impl EngineApi {
             .set("get_capabilities", caps_func)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
-        // Entity management
-        let next_entity_id = std::cell::RefCell::new(self.next_entity_id);
+        // Entity management (generational ids with a free list)
+        lua.set_app_data(SharedEntities(self.entities.clone()));
+        let entities = self.entities.clone();
         let entity_func = lua
             .create_function(move |_, ()| {
-                let mut id = next_entity_id.borrow_mut();
-                let entity = EntityId(*id);
-                *id += 1;
-                Ok(entity)
+                let id = entities
+                    .borrow_mut()
+                    .create()
+                    .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+                Ok(EntityId(id))
             })
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
         engine_table
             .set("create_entity", entity_func)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
+        let entities = self.entities.clone();
+        let destroyed_cb = self.entity_destroyed.clone();
+        let colliders = self.collisions.clone();
+        let destroy_func = lua
+            .create_function(move |_, ud: AnyUserData| {
+                let id = ud
+                    .borrow::<EntityId>()
+                    .map_err(|_| {
+                        mlua::Error::RuntimeError(
+                            "ARG_ERROR: destroy_entity expects EntityId".into(),
+                        )
+                    })?
+                    .0;
+                // Returns false for an already-destroyed (or stale) handle
+                if !entities.borrow_mut().destroy(id) {
+                    return Ok(false);
+                }
+                colliders.borrow_mut().remove(id);
+                if let Some(cb) = destroyed_cb.borrow().as_ref() {
+                    cb(id);
+                }
+                Ok(true)
+            })
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("destroy_entity", destroy_func)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
         // Texture loading
         let next_texture_id = std::cell::RefCell::new(self.next_texture_id);
         let texture_func = lua
@@ -519,8 +851,9 @@ impl EngineApi {
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
         // Transform batching (typed buffers only)
+        let colliders = self.collisions.clone();
         let transform_func = lua
-            .create_function(|_, v: Value| match v {
+            .create_function(move |_, v: Value| match v {
                 Value::UserData(ud) => {
                     if let Ok(tb) = ud.borrow::<TransformBuffer>() {
                         let rows = *tb.len.borrow();
@@ -530,6 +863,7 @@ impl EngineApi {
                                 "TransformBuffer length exceeds capacity".into(),
                             ));
                         }
+                        colliders.borrow_mut().sync_transforms(&buf[..rows * 6]);
                         tracing::debug!("Setting {} transforms (typed)", rows);
                         Ok(())
                     } else {
@@ -601,10 +935,10 @@ impl EngineApi {
         let sugar_sprites_sprite = self.sugar_sprites.clone();
         let layer_resolve_cell = self.layer_resolve.clone();
         let sprite_sugar_func = lua
-            .create_function(move |_lua, sprite_def: mlua::Table| {
+            .create_function(move |lua, sprite_def: mlua::Table| {
                 // Extract required fields
                 let entity: AnyUserData = sprite_def.get("entity")?;
-                let entity_id = entity.borrow::<EntityId>()?.0;
+                let entity_id = live_sprite_entity(lua, &entity)?;
 
                 // Extract position
                 let pos: mlua::Table = sprite_def.get("pos")?;
@@ -794,8 +1128,13 @@ impl EngineApi {
 
         // Input system
         let input_snapshot = self.input.clone();
+        let input_map = self.input_map.clone();
         let input_func = lua
-            .create_function(move |_, ()| Ok(input_snapshot.borrow().clone()))
+            .create_function(move |_, ()| {
+                let mut snap = input_snapshot.borrow().clone();
+                input_map.borrow_mut().apply(&mut snap);
+                Ok(snap)
+            })
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
         engine_table
             .set("get_input", input_func)
@@ -979,6 +1318,41 @@ impl EngineApi {
             .set("restore", restore_func)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
+        // Disk-backed save slots
+        let saves = self.save_store.clone();
+        let save_slot_func = lua
+            .create_function(move |_, (slot, value): (String, Value)| {
+                if !value.is_table() {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "save_slot '{}': expected a table, got {}",
+                        slot,
+                        value.type_name()
+                    )));
+                }
+                saves
+                    .borrow()
+                    .save(&slot, &value)
+                    .map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+            })
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("save_slot", save_slot_func)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        let saves = self.save_store.clone();
+        let load_slot_func = lua
+            .create_function(
+                move |lua, slot: String| match saves.borrow().load(lua, &slot) {
+                    Ok(Some(v)) => Ok(v),
+                    Ok(None) => Ok(Value::Nil),
+                    Err(e) => Err(mlua::Error::RuntimeError(e.to_string())),
+                },
+            )
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("load_slot", load_slot_func)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
         // Logging system (rate-limited 30 msgs/sec)
         let fixed_time_for_log = self.fixed_time.clone();
         let log_rl = self.log_rl.clone();
@@ -1016,6 +1390,8 @@ impl EngineApi {
                 metrics_table.set("cpu_frame_ms", 0.0)?;
                 metrics_table.set("ffi_calls", 0)?;
                 metrics_table.set("sprites_submitted", 0)?;
+                metrics_table.set("lua_gc_ms", 0.0)?;
+                metrics_table.set("lua_mem_mb", lua.used_memory() as f64 / 1024.0 / 1024.0)?;
                 Ok(metrics_table)
             })
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
@@ -1039,6 +1415,17 @@ impl EngineApi {
             .set("hud_printf", hud_fn)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
+        // HUD config: validated and merged; the host is told in the callbacks variant
+        let hud_config = self.hud_config.clone();
+        let hud_config_fn = lua
+            .create_function(move |_, def: mlua::Table| {
+                apply_hud_config(&mut hud_config.borrow_mut(), &def)
+            })
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("hud_config", hud_config_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
         // Clear color
         let set_clear_color_fn = lua
             .create_function(|_, _: mlua::Variadic<mlua::Value>| Ok(()))
@@ -1055,6 +1442,38 @@ impl EngineApi {
             .set("set_render_mode", set_render_fn)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
+        // UI layers
+        let set_ui_mode_fn = lua
+            .create_function(|_, mode: String| ui_mode_arg(&mode).map(|_| ()))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("set_ui_mode", set_ui_mode_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let cursor_fn = lua
+            .create_function(|_, opts: mlua::Table| cursor_arg(&opts).map(|_| ()))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("cursor", cursor_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let text_input_start_fn = lua
+            .create_function(|_, rect: Option<mlua::Table>| text_input_arg(rect).map(|_| ()))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("text_input_start", text_input_start_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let text_input_stop_fn = lua
+            .create_function(|_, ()| Ok(()))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("text_input_stop", text_input_stop_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let ui_size_fn = lua
+            .create_function(|_, ()| Ok((0, 0)))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        engine_table
+            .set("ui_size", ui_size_fn)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
         // Atlas load
         let atlas_func = lua
             .create_function(|_, _: (String, String)| Ok(Value::Nil))
@@ -1063,6 +1482,66 @@ impl EngineApi {
             .set("atlas_load", atlas_func)
             .map_err(|e| anyhow::anyhow!(e.to_string()))?;
 
+        // Coroutine tasks: spawn / wait / wait_frames / wait_until
+        crate::tasks::install(
+            lua,
+            &engine_table,
+            self.tasks.clone(),
+            self.fixed_time.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Tweens: tween / cancel_tweens / ease
+        crate::tween::install(
+            lua,
+            &engine_table,
+            self.tweens.clone(),
+            self.fixed_time.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Collision world: colliders, queries, raycasts and contact events
+        crate::collision::install(lua, &engine_table, self.collisions.clone())
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Tilemaps: tilemap_load (host sinks are attached by the callbacks variant)
+        crate::tilemap::install(lua, &engine_table, self.tilemaps.clone())
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Bitmap fonts: font_load / text / text_measure
+        crate::font::install(
+            lua,
+            &engine_table,
+            self.fonts.clone(),
+            self.quads.clone(),
+            self.layer_resolve.clone(),
+            self.fixed_time.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Action bindings: input_map / input_bindings / input_map_save / input_map_load
+        crate::input_map::install(lua, &engine_table, self.input_map.clone())
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Audio: sound_load / sound_play / music_play / set_bus_volume
+        crate::audio::install(
+            lua,
+            &engine_table,
+            self.audio.clone(),
+            self.layer_resolve.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        // Immediate-mode widgets: engine.ui.*
+        crate::ui::install(
+            lua,
+            &engine_table,
+            self.ui.clone(),
+            self.quads.clone(),
+            self.layer_resolve.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
         // Lock the engine table metatable
         let metatable = lua
             .create_table()
@@ -1141,7 +1620,7 @@ impl UserData for SpriteBuffer {
     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
         methods.add_method_mut(
             "set",
-            |_,
+            |lua,
              this,
              (i, id_ud, tex_ud, u0, v0, u1, v1, r, g, b, a, z): (
                 usize,
@@ -1164,7 +1643,7 @@ impl UserData for SpriteBuffer {
                 if idx >= cap {
                     return Err(mlua::Error::RuntimeError("index exceeds capacity".into()));
                 }
-                let entity_id = id_ud.borrow::<EntityId>()?.0;
+                let entity_id = live_sprite_entity(lua, &id_ud)?;
                 let texture_id = tex_ud.borrow::<TextureHandle>()?.0;
                 let mut rows = this.rows.borrow_mut();
                 rows[idx] = SpriteData {
@@ -1184,7 +1663,7 @@ impl UserData for SpriteBuffer {
         );
         methods.add_method_mut(
             "set_tex",
-            |_, this, (i, id_ud, tex_ud): (usize, AnyUserData, AnyUserData)| {
+            |lua, this, (i, id_ud, tex_ud): (usize, AnyUserData, AnyUserData)| {
                 let idx = i
                     .checked_sub(1)
                     .ok_or_else(|| mlua::Error::RuntimeError("index must be >= 1".into()))?;
@@ -1192,7 +1671,7 @@ impl UserData for SpriteBuffer {
                 if idx >= cap {
                     return Err(mlua::Error::RuntimeError("index exceeds capacity".into()));
                 }
-                let entity_id = id_ud.borrow::<EntityId>()?.0;
+                let entity_id = live_sprite_entity(lua, &id_ud)?;
                 let texture_id = tex_ud.borrow::<TextureHandle>()?.0;
                 let mut rows = this.rows.borrow_mut();
                 let row = &mut rows[idx];
@@ -1302,8 +1781,10 @@ impl UserData for SpriteBuffer {
 // Atlas object exposed to Lua
 #[derive(Clone)]
 pub struct Atlas {
-    texture: TextureHandle,
-    uv_map: HashMap<String, [f32; 4]>,
+    pub(crate) texture: TextureHandle,
+    pub(crate) uv_map: HashMap<String, [f32; 4]>,
+    /// Sheet size in pixels (1x1 when the json gives uv coordinates)
+    pub(crate) sheet: [f32; 2],
 }
 
 impl UserData for Atlas {
@@ -1410,12 +1891,18 @@ impl EngineApi {
         // Override set_transforms (accepts table or TransformBuffer)
         let st_cb = set_transforms_cb.clone();
         let transforms_scratch = std::cell::RefCell::new(Vec::<f64>::with_capacity(1024));
+        let colliders = self.collisions.clone();
         let transform_func = match lua.create_function(move |lua, arg: Value| {
             match arg {
                 Value::UserData(ud) => {
                     if let Ok(tb) = ud.borrow::<TransformBuffer>() {
                         let rows = *tb.len.borrow();
                         let cap = *tb.cap.borrow();
+                        {
+                            let buf = tb.buf.borrow();
+                            let take = (rows * 6).min(buf.len());
+                            colliders.borrow_mut().sync_transforms(&buf[..take]);
+                        }
                         if let Some(cb) = &set_transforms_f32_cb {
                             (cb)(tb.buf.clone(), rows, cap);
                         } else {
@@ -1438,6 +1925,10 @@ impl EngineApi {
                     out.clear();
                     parse_transforms_table_to_out(lua, arr, &mut out)?;
                     tracing::debug!("Setting {} transforms", out.len() / 6);
+                    if !colliders.borrow().is_empty() {
+                        let rows: Vec<f32> = out.iter().map(|v| *v as f32).collect();
+                        colliders.borrow_mut().sync_transforms(&rows);
+                    }
                     (st_cb)(&out);
                     Ok(())
                 }
@@ -1559,11 +2050,13 @@ impl EngineApi {
         let provider = callbacks.metrics_provider.clone();
         let metrics_func = lua
             .create_function(move |lua, ()| {
-                let (cpu_ms, sprites, ffi) = provider();
+                let m = provider();
                 let tbl = lua.create_table()?;
-                tbl.set("cpu_frame_ms", cpu_ms)?;
-                tbl.set("sprites_submitted", sprites)?;
-                tbl.set("ffi_calls", ffi)?;
+                tbl.set("cpu_frame_ms", m.cpu_frame_ms)?;
+                tbl.set("sprites_submitted", m.sprites_submitted)?;
+                tbl.set("ffi_calls", m.ffi_calls)?;
+                tbl.set("lua_gc_ms", m.lua_gc_ms)?;
+                tbl.set("lua_mem_mb", m.lua_mem_mb)?;
                 Ok(tbl)
             })
             .map_err(|e| anyhow::Error::msg(format!("Failed to override get_metrics: {}", e)))?;
@@ -1574,8 +2067,13 @@ impl EngineApi {
 
         // Override get_input using provider
         let input_p = callbacks.input_provider.clone();
+        let input_map = self.input_map.clone();
         let input_func = lua
-            .create_function(move |_, ()| Ok(input_p()))
+            .create_function(move |_, ()| {
+                let mut snap = input_p();
+                input_map.borrow_mut().apply(&mut snap);
+                Ok(snap)
+            })
             .map_err(|e| anyhow::Error::msg(format!("Failed to override get_input: {}", e)))?;
         engine_table
             .set("get_input", input_func)
@@ -1616,6 +2114,21 @@ impl EngineApi {
             .set("hud_printf", hud_fn)
             .map_err(|e| anyhow::Error::msg(format!("Failed to set hud_printf: {}", e)))?;
 
+        // engine.hud_config{corner?, lines?, columns?, color?, background?, background_alpha?, metrics?}
+        let hud_config_cb = callbacks.hud_config_cb.clone();
+        let hud_config = self.hud_config.clone();
+        let hud_config_fn = lua
+            .create_function(move |_, def: mlua::Table| {
+                let mut config = hud_config.borrow_mut();
+                apply_hud_config(&mut config, &def)?;
+                hud_config_cb(config.clone());
+                Ok(())
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create hud_config: {}", e)))?;
+        engine_table
+            .set("hud_config", hud_config_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set hud_config: {}", e)))?;
+
         // Add set_clear_color(r, g, b, a?) -> sets background; alpha optional (default 1)
         let scc = callbacks.set_clear_color_cb.clone();
         let set_clear_color_fn = lua
@@ -1663,6 +2176,62 @@ impl EngineApi {
             .set("set_render_mode", set_render_fn)
             .map_err(|e| anyhow::Error::msg(format!("Failed to set set_render_mode: {}", e)))?;
 
+        // set_ui_mode("virtual"|"native") and ui_size() -> w, h in UI units
+        let sum = callbacks.set_ui_mode_cb.clone();
+        let set_ui_mode_fn = lua
+            .create_function(move |_, mode: String| {
+                sum(ui_mode_arg(&mode)?);
+                Ok(())
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create set_ui_mode: {}", e)))?;
+        engine_table
+            .set("set_ui_mode", set_ui_mode_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set set_ui_mode: {}", e)))?;
+        let ui_viewport_p = callbacks.ui_viewport_provider.clone();
+        let ui_size_fn = lua
+            .create_function(move |_, ()| {
+                let [w, h] = ui_viewport_p().units;
+                Ok((w, h))
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create ui_size: {}", e)))?;
+        engine_table
+            .set("ui_size", ui_size_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set ui_size: {}", e)))?;
+
+        // cursor{ visible?, confine?, pos? } with pos in virtual canvas pixels
+        let cursor_cb = callbacks.cursor_cb.clone();
+        let cursor_fn = lua
+            .create_function(move |_, opts: mlua::Table| {
+                cursor_cb(cursor_arg(&opts)?);
+                Ok(())
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create cursor: {}", e)))?;
+        engine_table
+            .set("cursor", cursor_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set cursor: {}", e)))?;
+
+        // text_input_start(rect?) / text_input_stop() toggle IME composition
+        let text_input_cb = callbacks.text_input_cb.clone();
+        let text_input_start_fn = lua
+            .create_function(move |_, rect: Option<mlua::Table>| {
+                text_input_cb(text_input_arg(rect)?);
+                Ok(())
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create text_input_start: {}", e)))?;
+        engine_table
+            .set("text_input_start", text_input_start_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set text_input_start: {}", e)))?;
+        let text_input_cb = callbacks.text_input_cb.clone();
+        let text_input_stop_fn = lua
+            .create_function(move |_, ()| {
+                text_input_cb(TextInputRequest::Stop);
+                Ok(())
+            })
+            .map_err(|e| anyhow::Error::msg(format!("Failed to create text_input_stop: {}", e)))?;
+        engine_table
+            .set("text_input_stop", text_input_stop_fn)
+            .map_err(|e| anyhow::Error::msg(format!("Failed to set text_input_stop: {}", e)))?;
+
         // Camera minimal API
         {
             let cset = callbacks.camera_set_cb.clone();
@@ -1722,10 +2291,42 @@ impl EngineApi {
                         }
                         _ => None,
                     };
-                    let screen_space: Option<bool> = match opts.get::<mlua::Value>("screen_space") { Ok(mlua::Value::Boolean(b)) => Some(b), _ => None };
-                    let visible: Option<bool> = match opts.get::<mlua::Value>("visible") { Ok(mlua::Value::Boolean(b)) => Some(b), _ => None };
-                    let shake: Option<f32> = match opts.get::<mlua::Value>("shake") { Ok(mlua::Value::Number(n)) => Some(n as f32), _ => None };
-                    lset(name, order, parallax, screen_space, visible, shake);
+                    let screen_space: Option<bool> = match opts.get::<mlua::Value>("screen_space") {
+                        Ok(mlua::Value::Boolean(b)) => Some(b),
+                        _ => None,
+                    };
+                    let visible: Option<bool> = match opts.get::<mlua::Value>("visible") {
+                        Ok(mlua::Value::Boolean(b)) => Some(b),
+                        _ => None,
+                    };
+                    let shake: Option<f32> = match opts.get::<mlua::Value>("shake") {
+                        Ok(mlua::Value::Number(n)) => Some(n as f32),
+                        _ => None,
+                    };
+                    let ui: Option<bool> = match opts.get::<mlua::Value>("ui") {
+                        Ok(mlua::Value::Boolean(b)) => Some(b),
+                        _ => None,
+                    };
+                    let volume: Option<f32> = match opts.get::<mlua::Value>("volume") {
+                        Ok(mlua::Value::Number(n)) => Some((n as f32).max(0.0)),
+                        Ok(mlua::Value::Integer(i)) => Some((i as f32).max(0.0)),
+                        _ => None,
+                    };
+                    let muted: Option<bool> = match opts.get::<mlua::Value>("muted") {
+                        Ok(mlua::Value::Boolean(b)) => Some(b),
+                        _ => None,
+                    };
+                    lset(
+                        name,
+                        order,
+                        parallax,
+                        screen_space,
+                        visible,
+                        shake,
+                        ui,
+                        volume,
+                        muted,
+                    );
                     Ok(())
                 })
                 .map_err(|e| anyhow::Error::msg(format!("Failed to create layer_set: {}", e)))?;
@@ -1750,6 +2351,21 @@ impl EngineApi {
 
         // Provide resolver to sugar path
         *self.layer_resolve.borrow_mut() = Some(callbacks.layer_resolve_cb.clone());
+        // Let the host drop render state for destroyed entities
+        *self.entity_destroyed.borrow_mut() = Some(callbacks.destroy_entity_cb.clone());
+        // Tileset images get their own id range, like atlas textures
+        self.tilemaps.borrow_mut().host = Some(crate::tilemap::TilemapHost {
+            load_texture: callbacks.load_texture_cb.clone(),
+            commands: callbacks.tilemap_cb.clone(),
+            next_texture_id: self.next_texture_id + 20_000,
+        });
+        self.set_audio_sink(callbacks.audio_cb.clone());
+        // Map the mouse into UI units for engine.ui
+        self.ui.borrow_mut().viewport = Some(callbacks.ui_viewport_provider.clone());
+        self.fonts.borrow_mut().host = Some(crate::font::FontHost {
+            load_texture: callbacks.load_texture_cb.clone(),
+            next_texture_id: self.next_texture_id + 30_000,
+        });
 
         // Override load_texture to notify host and return a handle immediately
         let next_texture_id = std::cell::RefCell::new(self.next_texture_id);
@@ -1810,6 +2426,7 @@ impl EngineApi {
                 let atlas = Atlas {
                     texture: TextureHandle(id),
                     uv_map,
+                    sheet: [sheet_w, sheet_h],
                 };
                 Ok(Value::UserData(lua.create_userdata(atlas)?))
             })
@@ -1829,7 +2446,7 @@ fn parse_transforms_table_to_out(
     out: &mut Vec<f64>,
 ) -> mlua::Result<()> {
     let len = arr.raw_len();
-    if len % 6 != 0 {
+    if !len.is_multiple_of(6) {
         return Err(mlua::Error::RuntimeError(format!(
             "ARG_ERROR: set_transforms stride mismatch (got={}, want=6)",
             len % 6
@@ -1879,12 +2496,12 @@ fn parse_transforms_table_to_out(
 }
 
 fn parse_sprites_table_to_out(
-    _lua: &Lua,
+    lua: &Lua,
     sprites: mlua::Table,
     out: &mut Vec<SpriteV2>,
 ) -> mlua::Result<()> {
     let len = sprites.raw_len();
-    if len % 11 != 0 {
+    if !len.is_multiple_of(11) {
         return Err(mlua::Error::RuntimeError(format!(
             "ARG_ERROR: submit_sprites stride mismatch (got={}, want=11)",
             len % 11
@@ -1896,8 +2513,8 @@ fn parse_sprites_table_to_out(
         // entity (UserData EntityId)
         let ent_ud: mlua::AnyUserData = sprites.raw_get(i)?;
         i += 1;
-        let entity_id = if let Ok(ent) = ent_ud.borrow::<EntityId>() {
-            ent.0
+        let entity_id = if ent_ud.is::<EntityId>() {
+            live_sprite_entity(lua, &ent_ud)?
         } else {
             return Err(mlua::Error::RuntimeError(
                 "ARG_ERROR: sprite id must be EntityId".into(),
@@ -1988,7 +2605,7 @@ impl UserData for FrameBuilder {
             if idx >= cap {
                 return Err(mlua::Error::RuntimeError("index exceeds capacity".into()));
             }
-            let entity_id = id_ud.borrow::<EntityId>()?.0;
+            let entity_id = live_sprite_entity(lua, &id_ud)?;
             let texture_id = tex_ud.borrow::<TextureHandle>()?.0;
             let mut rows = sb.rows.borrow_mut();
             rows[idx] = SpriteData {
@@ -2017,7 +2634,7 @@ impl UserData for FrameBuilder {
                 if idx >= cap {
                     return Err(mlua::Error::RuntimeError("index exceeds capacity".into()));
                 }
-                let entity_id = id_ud.borrow::<EntityId>()?.0;
+                let entity_id = live_sprite_entity(lua, &id_ud)?;
                 let mut rows = sb.rows.borrow_mut();
                 let row = &mut rows[idx];
                 row.entity_id = entity_id;
@@ -2072,7 +2689,7 @@ impl UserData for FrameBuilder {
                 if idx >= cap {
                     return Err(mlua::Error::RuntimeError("index exceeds capacity".into()));
                 }
-                let entity_id = id_ud.borrow::<EntityId>()?.0;
+                let entity_id = live_sprite_entity(lua, &id_ud)?;
                 let atlas = atlas_ud.borrow::<Atlas>()?;
                 let uv = atlas.uv_map.get(&name).ok_or_else(|| {
                     mlua::Error::RuntimeError(format!("unknown atlas name: {}", name))
@@ -2108,4 +2725,261 @@ impl UserData for FrameBuilder {
     }
 }
 
-// (no tests in this module)
+/// Lua state with a fresh engine namespace installed, for tests across the crate
+#[cfg(test)]
+pub(crate) fn test_lua() -> (Lua, EngineApi) {
+    let lua = Lua::new();
+    let api = EngineApi::new();
+    api.setup_engine_namespace(&lua).unwrap();
+    (lua, api)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destroy_entity_invalidates_handle() {
+        let (lua, _api) = test_lua();
+        let (alive, destroyed_again, reused_alive, old_alive): (bool, bool, bool, bool) = lua
+            .load(
+                r#"
+                local e = engine.create_entity()
+                local ok = engine.destroy_entity(e)
+                local again = engine.destroy_entity(e)
+                local f = engine.create_entity()
+                return ok, again, f:is_alive(), e:is_alive()
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert!(alive && !destroyed_again && reused_alive && !old_alive);
+
+        let err = lua
+            .load(
+                r#"
+                local e = engine.create_entity()
+                engine.destroy_entity(e)
+                engine.begin_frame()
+                engine.sprite{ entity = e, texture = engine.load_texture("x.png"), pos = {0, 0}, size = 8 }
+                "#,
+            )
+            .exec()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dead Entity("), "{}", err);
+    }
+
+    #[test]
+    fn test_hud_config_merges_options() {
+        let (lua, api) = test_lua();
+        lua.load(
+            r#"
+            engine.hud_config{ corner = "bottom_right", lines = 20, background = "10203040" }
+            engine.hud_config{ background_alpha = 0.5, metrics = false, color = 0xFF0000FF }
+            "#,
+        )
+        .exec()
+        .unwrap();
+        let config = api.hud_config.borrow().clone();
+        assert_eq!(config.corner, HudCorner::BottomRight);
+        assert_eq!((config.lines, config.columns), (20, 64));
+        assert_eq!(config.background, [0x10, 0x20, 0x30, 128]);
+        assert_eq!(config.text_color, [255, 0, 0, 255]);
+        assert!(!config.show_metrics);
+        assert!(lua
+            .load("engine.hud_config{ corner = 'middle' }")
+            .exec()
+            .is_err());
+    }
+
+    #[test]
+    fn test_pad_queries_and_events() {
+        use engine_core::gamepad::VirtualGamepad;
+        use engine_core::input::InputState;
+        let (lua, api) = test_lua();
+        let mut before = InputState::new();
+        VirtualGamepad::connect(&mut before, 2).press(PadButton::East);
+        let mut now = InputState::new();
+        VirtualGamepad::connect(&mut now, 0)
+            .press(PadButton::South)
+            .axis(PadAxis::LeftX, 0.1)
+            .axis(PadAxis::RightX, 1.0);
+        let mut snap = InputSnapshot::new();
+        (snap.pads, snap.prev_pads) = (now.pads, before.pads);
+        api.set_input_snapshot(snap);
+        let (down, pressed, lx, rx, events): (bool, bool, f32, f32, String) = lua
+            .load(
+                r#"
+                local i = engine.get_input()
+                local ev = {}
+                for _, e in ipairs(i:pad_events()) do ev[#ev + 1] = e.pad .. e.event end
+                return i:pad_down(1, "South"), i:pad_pressed(1, "South"),
+                    i:pad_axis(1, "LeftX"), i:pad_axis(1, "RightX"), table.concat(ev, ",")
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert!(down && pressed);
+        // Small deflections fall inside the deadzone
+        assert_eq!((lx, rx), (0.0, 1.0));
+        assert_eq!(events, "1connected,3disconnected");
+        let released: bool = lua
+            .load("return engine.get_input():pad_released(3, 'East')")
+            .eval()
+            .unwrap();
+        assert!(released);
+        assert!(lua
+            .load("engine.get_input():pad_down(1, 'A')")
+            .exec()
+            .is_err());
+    }
+
+    // 320x180 canvas pillarboxed 100px into a 840x360 window, camera at (40, 10)
+    // and a half-parallax "bg" layer
+    fn pillarboxed_view() -> CanvasView {
+        use engine_core::state::Layers;
+        let mut layers = Layers::with_defaults();
+        layers.define_or_update("bg".to_string(), -1);
+        layers.by_name_mut("bg").unwrap().parallax_x = 0.5;
+        CanvasView {
+            canvas: UiViewport {
+                x: 100.0,
+                y: 0.0,
+                width: 640.0,
+                height: 360.0,
+                units: [320.0, 180.0],
+            },
+            camera: (40.0, 10.0),
+            layers,
+        }
+    }
+
+    #[test]
+    fn test_canvas_view_maps_window_to_virtual_and_back() {
+        let view = pillarboxed_view();
+        // Pillarbox bars are outside the canvas
+        assert_eq!(view.window_to_virtual(50.0, 100.0), None);
+        let v = view.window_to_virtual(420.0, 90.0).unwrap();
+        assert_eq!(v, [160.0, 135.0]);
+        assert_eq!(view.virtual_to_window(v), [420.0, 90.0]);
+    }
+
+    #[test]
+    fn test_mouse_virtual_world_and_wheel() {
+        let (lua, api) = test_lua();
+        let view = pillarboxed_view();
+        let mut snap = InputSnapshot::new();
+        snap.wheel = [0.0, -2.0];
+        snap.mouse_virtual = view.window_to_virtual(420.0, 90.0);
+        snap.view = Some(view);
+        api.set_input_snapshot(snap);
+        let (vx, vy, wx, bx, ux, missing, wheel): (f32, f32, f32, f32, f32, bool, f32) = lua
+            .load(
+                r#"
+                local i = engine.get_input()
+                local vx, vy = i:mouse_virtual()
+                local wx = i:mouse_world()
+                local bx = i:mouse_world("bg")
+                local ux = i:mouse_world("@ui")
+                local _, wy = i:wheel()
+                return vx, vy, wx, bx, ux, i:mouse_world("nope") == nil, wy
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((vx, vy), (160.0, 135.0));
+        assert_eq!((wx, bx, ux), (200.0, 180.0, 160.0));
+        assert!(missing);
+        assert_eq!(wheel, -2.0);
+    }
+
+    #[test]
+    fn test_cursor_options_are_validated() {
+        let (lua, _api) = test_lua();
+        lua.load("engine.cursor{ visible = false, pos = { 10, 20 } }")
+            .exec()
+            .unwrap();
+        assert!(lua.load("engine.cursor{ pos = 5 }").exec().is_err());
+    }
+
+    #[test]
+    fn test_keys_table_has_distinct_window_codes() {
+        let (lua, _api) = test_lua();
+        // Every engine.keys entry is a distinct code the window can produce
+        let codes: Vec<u32> = lua
+            .load("local t = {} for _, c in pairs(engine.keys) do t[#t + 1] = c end return t")
+            .eval()
+            .unwrap();
+        assert_eq!(codes.len(), 126);
+        assert_eq!(
+            codes.iter().collect::<std::collections::HashSet<_>>().len(),
+            126
+        );
+        assert!(codes
+            .iter()
+            .all(|&c| stable_keys::stable_to_winit(c).is_some()));
+    }
+
+    #[test]
+    fn test_modifiers_cover_both_sides() {
+        let (lua, api) = test_lua();
+        let mut snap = InputSnapshot::new();
+        snap.keys.insert(stable_keys::SHIFT_RIGHT, true);
+        snap.keys.insert(stable_keys::CONTROL_LEFT, true);
+        snap.keys.insert(stable_keys::ESCAPE, true);
+        api.set_input_snapshot(snap);
+        let mods: String = lua
+            .load(
+                r#"
+                local i = engine.get_input()
+                local m = i:modifiers()
+                assert(i:pressed(engine.keys.Escape))
+                return tostring(m.shift) .. tostring(m.ctrl) .. tostring(m.alt) .. tostring(m.super)
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!(mods, "truetruefalsefalse");
+    }
+
+    #[test]
+    fn test_text_stream_and_preedit() {
+        use engine_core::input::InputState;
+        let (lua, api) = test_lua();
+        let mut input = InputState::new();
+        input.push_text("A\u{8}!");
+        input.push_text("\r\u{e9}");
+        input.set_preedit("\u{304b}\u{306a}", Some((3, 3)));
+        let mut snap = InputSnapshot::new();
+        snap.text = Some(input.text.clone());
+        (snap.preedit, snap.preedit_cursor) = (input.preedit.clone(), input.preedit_cursor);
+        api.set_input_snapshot(snap);
+        let (text, preedit, caret): (String, String, usize) = lua
+            .load(
+                r#"
+                engine.text_input_start({ x = 10, y = 20, w = 100, h = 16 })
+                engine.text_input_start()
+                engine.text_input_stop()
+                local i = engine.get_input()
+                local p, a = i:preedit()
+                return i:text(), p, a
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!(text, "A!\u{e9}");
+        assert_eq!((preedit.as_str(), caret), ("\u{304b}\u{306a}", 3));
+        input.clear();
+        assert!(input.text.is_empty());
+    }
+
+    #[test]
+    fn test_text_input_rect_is_validated() {
+        let (lua, _api) = test_lua();
+        assert!(lua
+            .load("engine.text_input_start({ x = 'left' })")
+            .exec()
+            .is_err());
+    }
+}
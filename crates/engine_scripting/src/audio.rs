@@ -0,0 +1,314 @@
+// Lua bindings for sounds, music and buses (engine.sound_load / sound_play / music_play)
+use crate::api::{EntityId, LayerResolveCb};
+use engine_core::audio::{AudioCommand, Bus, Emitter, MusicStream, PlayParams, SoundData};
+use mlua::{AnyUserData, Lua, Table, UserData, UserDataMethods};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Sounds decoded by scripts. Ids and voice handles are handed out here so scripts
+/// get them immediately; the host mixer receives matching `AudioCommand`s.
+#[derive(Default)]
+pub struct AudioRegistry {
+    by_path: HashMap<String, SoundHandle>,
+    next_sound: u32,
+    next_voice: u64,
+    pub host: Option<Rc<dyn Fn(AudioCommand)>>,
+}
+
+impl AudioRegistry {
+    fn send(&self, cmd: AudioCommand) {
+        if let Some(host) = &self.host {
+            host(cmd);
+        }
+    }
+}
+
+/// Script-visible handle returned by `engine.sound_load`
+#[derive(Debug, Clone, Copy)]
+pub struct SoundHandle {
+    pub id: u32,
+    duration: f64,
+}
+
+impl UserData for SoundHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!("Sound({})", this.id))
+        });
+        methods.add_method("duration", |_, this, ()| Ok(this.duration));
+    }
+}
+
+fn bus_arg(name: &str, who: &str) -> mlua::Result<Bus> {
+    Bus::parse(name).ok_or_else(|| {
+        mlua::Error::RuntimeError(format!(
+            "{}: unknown bus '{}' (use \"sfx\", \"music\" or \"ui\")",
+            who, name
+        ))
+    })
+}
+
+// `pos = {x, y}` or `entity = e` from a sound_play / sound_set options table
+fn emitter_arg(o: &Table, who: &str) -> mlua::Result<Option<Emitter>> {
+    if let Some(e) = o.get::<Option<AnyUserData>>("entity")? {
+        let id = e.borrow::<EntityId>().map_err(|_| {
+            mlua::Error::RuntimeError(format!("{}: entity must be an EntityId", who))
+        })?;
+        return Ok(Some(Emitter::Entity(id.0)));
+    }
+    match o.get::<Option<Table>>("pos")? {
+        Some(p) => {
+            let x = p.raw_get::<Option<f32>>(1)?.or(p.get::<Option<f32>>("x")?);
+            let y = p.raw_get::<Option<f32>>(2)?.or(p.get::<Option<f32>>("y")?);
+            Ok(Some(Emitter::Point([x.unwrap_or(0.0), y.unwrap_or(0.0)])))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Install sound_load, sound_play, sound_set, sound_stop, music_play, music_stop
+/// and set_bus_volume on the engine table.
+pub fn install(
+    lua: &Lua,
+    engine: &Table,
+    registry: Rc<RefCell<AudioRegistry>>,
+    layer_resolve: Rc<RefCell<Option<LayerResolveCb>>>,
+) -> mlua::Result<()> {
+    // sound_load(path) -> Sound; decoded once per path
+    let reg = registry.clone();
+    let load = lua.create_function(move |_, path: String| {
+        let mut reg = reg.borrow_mut();
+        if let Some(handle) = reg.by_path.get(&path) {
+            return Ok(*handle);
+        }
+        let data = SoundData::load(&path)
+            .map_err(|e| mlua::Error::RuntimeError(format!("sound_load: {}", e)))?;
+        reg.next_sound += 1;
+        let handle = SoundHandle {
+            id: reg.next_sound,
+            duration: data.duration(),
+        };
+        reg.send(AudioCommand::LoadSound {
+            id: handle.id,
+            data: Arc::new(data),
+        });
+        reg.by_path.insert(path, handle);
+        Ok(handle)
+    })?;
+    engine.set("sound_load", load)?;
+
+    // sound_play(sound, {volume?, pitch?, pan?, loop?, bus?, pos?, entity?, layer?,
+    // min_distance?, max_distance?}) -> voice. `pos` or `entity` makes the sound
+    // positional: attenuated and panned relative to the camera each update.
+    let reg = registry.clone();
+    let play = lua.create_function(move |_, (sound, opts): (AnyUserData, Option<Table>)| {
+        let sound = *sound.borrow::<SoundHandle>()?;
+        let mut params = PlayParams::default();
+        if let Some(o) = opts {
+            params.volume = o.get::<Option<f32>>("volume")?.unwrap_or(1.0).max(0.0);
+            params.pitch = o.get::<Option<f32>>("pitch")?.unwrap_or(1.0);
+            params.pan = o.get::<Option<f32>>("pan")?.unwrap_or(0.0).clamp(-1.0, 1.0);
+            params.looping = o.get::<Option<bool>>("loop")?.unwrap_or(false);
+            if let Some(bus) = o.get::<Option<String>>("bus")? {
+                params.bus = bus_arg(&bus, "sound_play")?;
+            }
+            params.emitter = emitter_arg(&o, "sound_play")?;
+            params.attenuation.min_distance = o.get::<Option<f32>>("min_distance")?;
+            params.attenuation.max_distance = o.get::<Option<f32>>("max_distance")?;
+            if let Some(cb) = &*layer_resolve.borrow() {
+                if let Some(name) = o.get::<Option<String>>("layer")? {
+                    params.layer = Some(cb(name));
+                }
+            }
+        }
+        if params.pitch <= 0.0 {
+            return Err(mlua::Error::RuntimeError(
+                "sound_play: pitch must be positive".into(),
+            ));
+        }
+        let mut reg = reg.borrow_mut();
+        reg.next_voice += 1;
+        let voice = reg.next_voice;
+        reg.send(AudioCommand::Play {
+            voice,
+            sound: sound.id,
+            params,
+        });
+        Ok(voice)
+    })?;
+    engine.set("sound_play", play)?;
+
+    // sound_set(voice, {volume?, pitch?, pan?, pos?, entity?}) changes a playing sound
+    let reg = registry.clone();
+    let set = lua.create_function(move |_, (voice, o): (u64, Table)| {
+        let pitch = o.get::<Option<f32>>("pitch")?;
+        if pitch.is_some_and(|p| p <= 0.0) {
+            return Err(mlua::Error::RuntimeError(
+                "sound_set: pitch must be positive".into(),
+            ));
+        }
+        reg.borrow().send(AudioCommand::Update {
+            voice,
+            volume: o.get::<Option<f32>>("volume")?.map(|v| v.max(0.0)),
+            pitch,
+            pan: o.get::<Option<f32>>("pan")?.map(|p| p.clamp(-1.0, 1.0)),
+            emitter: emitter_arg(&o, "sound_set")?,
+        });
+        Ok(())
+    })?;
+    engine.set("sound_set", set)?;
+
+    // sound_stop(voice)
+    let reg = registry.clone();
+    let stop = lua.create_function(move |_, voice: u64| {
+        reg.borrow().send(AudioCommand::Stop { voice });
+        Ok(())
+    })?;
+    engine.set("sound_stop", stop)?;
+
+    // music_play(path, {volume?, fade?, loop?}) streams a track, crossfading over
+    // `fade` seconds from the current one; loops by default
+    let reg = registry.clone();
+    let music_play = lua.create_function(move |_, (path, opts): (String, Option<Table>)| {
+        let (mut volume, mut fade, mut looping) = (1.0, 0.0, true);
+        if let Some(o) = opts {
+            volume = o.get::<Option<f32>>("volume")?.unwrap_or(volume).max(0.0);
+            fade = o.get::<Option<f32>>("fade")?.unwrap_or(fade).max(0.0);
+            looping = o.get::<Option<bool>>("loop")?.unwrap_or(looping);
+        }
+        let stream = MusicStream::open(&path, looping)
+            .map_err(|e| mlua::Error::RuntimeError(format!("music_play: {}", e)))?;
+        reg.borrow().send(AudioCommand::PlayMusic {
+            stream,
+            volume,
+            fade,
+        });
+        Ok(())
+    })?;
+    engine.set("music_play", music_play)?;
+
+    // music_stop(fade?)
+    let reg = registry.clone();
+    let music_stop = lua.create_function(move |_, fade: Option<f32>| {
+        reg.borrow().send(AudioCommand::StopMusic {
+            fade: fade.unwrap_or(0.0).max(0.0),
+        });
+        Ok(())
+    })?;
+    engine.set("music_stop", music_stop)?;
+
+    // set_bus_volume("sfx"|"music"|"ui", volume)
+    let set_bus = lua.create_function(move |_, (bus, volume): (String, f32)| {
+        let bus = bus_arg(&bus, "set_bus_volume")?;
+        registry.borrow().send(AudioCommand::SetBusVolume {
+            bus,
+            volume: volume.max(0.0),
+        });
+        Ok(())
+    })?;
+    engine.set("set_bus_volume", set_bus)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_lua;
+    use engine_core::audio::Mixer;
+
+    // Half a second of half-scale mono at 100 Hz, and an engine whose audio
+    // commands go straight to a 100 Hz mixer
+    fn setup(test: &str) -> (std::path::PathBuf, Lua, Rc<RefCell<Mixer>>) {
+        let dir =
+            std::env::temp_dir().join(format!("luarite_audio_{}_{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("blip.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut w = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..50 {
+            w.write_sample(16384i16).unwrap();
+        }
+        w.finalize().unwrap();
+
+        let (lua, api) = test_lua();
+        let mixer = Rc::new(RefCell::new(Mixer::new(100)));
+        let sink = mixer.clone();
+        api.set_audio_sink(Rc::new(move |cmd| sink.borrow_mut().apply(cmd)));
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        (dir, lua, mixer)
+    }
+
+    #[test]
+    fn test_sound_commands_reach_mixer() {
+        let (dir, lua, mixer) = setup("play");
+        let (duration, voice): (f64, u64) = lua
+            .load(
+                r#"
+                local s = engine.sound_load(PATH)
+                assert(engine.sound_load(PATH) ~= nil and tostring(s) == "Sound(1)")
+                engine.set_bus_volume("ui", 0.5)
+                local v = engine.sound_play(s, { bus = "ui", pan = -1 })
+                engine.sound_play(s, { loop = true })
+                return s:duration(), v
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((duration, voice), (0.5, 1));
+
+        let mut out = vec![0.0; 4];
+        mixer.borrow_mut().render(&mut out);
+        assert_eq!(out, vec![0.75, 0.5, 0.75, 0.5]);
+        lua.load("engine.sound_stop(1)").exec().unwrap();
+        mixer.borrow_mut().advance(2.0);
+        assert_eq!(mixer.borrow().voice_count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_positional_options() {
+        let (dir, lua, mixer) = setup("positional");
+        // Point emitters can be moved, entities must be EntityIds
+        lua.load(
+            r#"
+            local s = engine.sound_load(PATH)
+            local v = engine.sound_play(s, { pos = { 0, 0 }, loop = true })
+            engine.sound_set(v, { pos = { 10, 0 }, volume = 0.5 })
+            local e = engine.create_entity()
+            assert(engine.sound_play(s, { entity = e, min_distance = 4, layer = "fx" }) == 2)
+            "#,
+        )
+        .exec()
+        .unwrap();
+        mixer
+            .borrow_mut()
+            .spatialize(&engine_core::state::EngineState::new());
+        assert_eq!(mixer.borrow().voice_count(), 2);
+        assert!(lua
+            .load("engine.sound_play(engine.sound_load(PATH), { entity = 5 })")
+            .exec()
+            .is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_bad_bus_and_format_are_errors() {
+        let (dir, lua, _mixer) = setup("errors");
+        assert!(lua
+            .load("engine.sound_play(engine.sound_load(PATH), { bus = 'voice' })")
+            .exec()
+            .is_err());
+        assert!(lua
+            .load("engine.sound_load(PATH .. '.mp3')")
+            .exec()
+            .is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,210 @@
+// Lua bindings for the engine_core collision world
+use crate::api::EntityId;
+use engine_core::collision::{Collider, CollisionWorld, Shape};
+use glam::Vec2;
+use mlua::{AnyUserData, Lua, Table, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn entity_arg(ud: &AnyUserData, func: &str) -> mlua::Result<u32> {
+    ud.borrow::<EntityId>()
+        .map(|e| e.0)
+        .map_err(|_| mlua::Error::RuntimeError(format!("ARG_ERROR: {} expects EntityId", func)))
+}
+
+fn mask_arg(mask: Option<i64>) -> u32 {
+    mask.map(|m| m as u32).unwrap_or(u32::MAX)
+}
+
+fn entity_list(lua: &Lua, ids: Vec<u32>) -> mlua::Result<Table> {
+    lua.create_sequence_from(ids.into_iter().map(EntityId))
+}
+
+fn parse_collider(opts: &Table) -> mlua::Result<Collider> {
+    let shape: Option<String> = opts.get("shape")?;
+    let mut collider =
+        match shape.as_deref().unwrap_or("aabb") {
+            "aabb" => match (opts.get::<Option<f32>>("w")?, opts.get::<Option<f32>>("h")?) {
+                (Some(w), Some(h)) => Collider::aabb(w, h),
+                // Size follows the transform's w/h
+                (None, None) => Collider {
+                    shape: Shape::Aabb {
+                        half: Vec2::ZERO,
+                        auto_size: true,
+                    },
+                    ..Collider::aabb(0.0, 0.0)
+                },
+                _ => return Err(mlua::Error::RuntimeError(
+                    "collider_set: aabb needs both w and h (or neither to use the transform size)"
+                        .into(),
+                )),
+            },
+            "circle" => {
+                let r: Option<f32> = opts.get("r")?;
+                Collider::circle(r.ok_or_else(|| {
+                    mlua::Error::RuntimeError("collider_set: circle needs r".into())
+                })?)
+            }
+            other => {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "collider_set: unknown shape '{}' (use \"aabb\" or \"circle\")",
+                    other
+                )))
+            }
+        };
+    if let Some(layer) = opts.get::<Option<i64>>("layer")? {
+        collider.layer = layer as u32;
+    }
+    collider.mask = mask_arg(opts.get("mask")?);
+    Ok(collider)
+}
+
+/// Install collider and query functions on the engine table.
+pub fn install(lua: &Lua, engine: &Table, world: Rc<RefCell<CollisionWorld>>) -> mlua::Result<()> {
+    let w = world.clone();
+    let set = lua.create_function(move |lua, (ud, opts): (AnyUserData, Table)| {
+        let id = entity_arg(&ud, "collider_set")?;
+        if let Some(ents) = lua.app_data_ref::<crate::api::SharedEntities>() {
+            if !ents.0.borrow().is_alive(id) {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "collider_set: Entity({}) is dead",
+                    id
+                )));
+            }
+        }
+        let collider = parse_collider(&opts)?;
+        let mut world = w.borrow_mut();
+        world.set_collider(id, collider);
+        if let (Some(x), Some(y)) = (opts.get::<Option<f32>>("x")?, opts.get::<Option<f32>>("y")?) {
+            world.set_position(id, Vec2::new(x, y));
+        }
+        Ok(())
+    })?;
+    engine.set("collider_set", set)?;
+
+    let w = world.clone();
+    let remove = lua.create_function(move |_, ud: AnyUserData| {
+        let id = entity_arg(&ud, "collider_remove")?;
+        Ok(w.borrow_mut().remove(id))
+    })?;
+    engine.set("collider_remove", remove)?;
+
+    // Rect is given by its bottom-left (minimum) corner and size; world space is y-up
+    let w = world.clone();
+    let query_rect = lua.create_function(
+        move |lua, (x, y, rw, rh, mask): (f32, f32, f32, f32, Option<i64>)| {
+            let min = Vec2::new(x, y);
+            let ids = w
+                .borrow_mut()
+                .query_rect(min, min + Vec2::new(rw, rh), mask_arg(mask));
+            entity_list(lua, ids)
+        },
+    )?;
+    engine.set("query_rect", query_rect)?;
+
+    let w = world.clone();
+    let query_point = lua.create_function(move |lua, (x, y, mask): (f32, f32, Option<i64>)| {
+        let ids = w.borrow_mut().query_point(Vec2::new(x, y), mask_arg(mask));
+        entity_list(lua, ids)
+    })?;
+    engine.set("query_point", query_point)?;
+
+    let w = world.clone();
+    let raycast =
+        lua.create_function(
+            move |lua,
+                  (x, y, dx, dy, max_dist, mask): (
+                f32,
+                f32,
+                f32,
+                f32,
+                Option<f32>,
+                Option<i64>,
+            )| {
+                let hit = w.borrow_mut().raycast(
+                    Vec2::new(x, y),
+                    Vec2::new(dx, dy),
+                    max_dist.unwrap_or(f32::INFINITY),
+                    mask_arg(mask),
+                );
+                let Some(hit) = hit else {
+                    return Ok(Value::Nil);
+                };
+                let t = lua.create_table()?;
+                t.set("entity", EntityId(hit.id))?;
+                t.set("x", hit.point.x)?;
+                t.set("y", hit.point.y)?;
+                t.set("nx", hit.normal.x)?;
+                t.set("ny", hit.normal.y)?;
+                t.set("distance", hit.distance)?;
+                Ok(Value::Table(t))
+            },
+        )?;
+    engine.set("raycast", raycast)?;
+
+    let collisions = lua.create_function(move |lua, ()| {
+        let world = world.borrow();
+        let out = lua.create_table_with_capacity(world.events().len(), 0)?;
+        for (i, e) in world.events().iter().enumerate() {
+            let t = lua.create_table_with_capacity(0, 3)?;
+            t.set("a", EntityId(e.a))?;
+            t.set("b", EntityId(e.b))?;
+            t.set("phase", e.phase.as_str())?;
+            out.raw_set(i + 1, t)?;
+        }
+        Ok(out)
+    })?;
+    engine.set("collisions", collisions)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::test_lua;
+
+    #[test]
+    fn test_colliders_follow_transform_buffer() {
+        let (lua, api) = test_lua();
+        lua.load(
+            r#"
+            a = engine.create_entity()
+            b = engine.create_entity()
+            engine.collider_set(a, { shape = "aabb", w = 10, h = 10 })
+            engine.collider_set(b, { shape = "circle", r = 4 })
+            T = engine.create_transform_buffer(2)
+            T:set(1, a, 0, 0, 0, 10, 10)
+            T:set(2, b, 6, 0, 0, 8, 8)
+            engine.set_transforms(T)
+            "#,
+        )
+        .exec()
+        .unwrap();
+        api.step_collisions();
+        let (phase, hits, ray): (String, usize, f64) = lua
+            .load(
+                r#"
+                local ev = engine.collisions()
+                local hit = engine.raycast(-50, 0, 1, 0)
+                return ev[1].phase, #engine.query_point(7, 0), hit.distance
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((phase.as_str(), hits, ray), ("begin", 1, 45.0));
+
+        // (x, y) is the bottom-left corner: the rect extends up from y, not down
+        let (above, below): (usize, usize) = lua
+            .load("return #engine.query_rect(0, 5.5, 1, 2), #engine.query_rect(0, -7, 1, 3)")
+            .eval()
+            .unwrap();
+        assert_eq!((above, below), (0, 1));
+
+        lua.load("engine.destroy_entity(b)").exec().unwrap();
+        api.step_collisions();
+        let phase: String = lua
+            .load("return engine.collisions()[1].phase")
+            .eval()
+            .unwrap();
+        assert_eq!(phase, "end");
+    }
+}
@@ -0,0 +1,407 @@
+// Lua bindings for bitmap fonts (engine.font_load / engine.text / engine.text_measure)
+use crate::api::{color_arg, parse_hex_string, LayerResolveCb};
+use engine_core::font::{parse_markup, Align, BitmapFont, GlyphQuad, LayoutOptions};
+use engine_core::state::QuadData;
+use mlua::{Lua, Table, UserData, UserDataMethods, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Host sink for font page textures
+pub struct FontHost {
+    pub load_texture: Rc<dyn Fn(String, u32)>,
+    /// Next texture id handed to page images
+    pub next_texture_id: u32,
+}
+
+/// Fonts loaded by scripts, cached by path so reloads reuse their page textures
+#[derive(Default)]
+pub struct FontRegistry {
+    by_path: HashMap<String, Rc<BitmapFont>>,
+    pub host: Option<FontHost>,
+}
+
+/// Script-visible handle returned by `engine.font_load`
+#[derive(Clone)]
+pub struct FontHandle(pub Rc<BitmapFont>);
+
+impl UserData for FontHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!("Font({})", this.0.face))
+        });
+        methods.add_method("line_height", |_, this, ()| Ok(this.0.line_height));
+        // measure(text, max_width?) -> w, h in font pixels
+        methods.add_method(
+            "measure",
+            |_, this, (text, max_width): (mlua::String, Option<f32>)| {
+                let opts = LayoutOptions {
+                    max_width,
+                    ..Default::default()
+                };
+                let layout = this.0.layout(&text.to_str()?, &opts);
+                Ok((layout.width, layout.height))
+            },
+        );
+    }
+}
+
+/// Inline tags understood by `engine.text` when `markup` is on
+const MARKUP_TAGS: &[&str] = &["color", "wave"];
+/// `[wave]` defaults, in font pixels and radians
+const WAVE_AMPLITUDE: f32 = 2.0;
+const WAVE_SPEED: f32 = 6.0;
+const WAVE_PHASE: f32 = 0.6;
+
+// Options shared by text and text_measure, with markup already stripped
+struct TextArgs {
+    font: Rc<BitmapFont>,
+    plain: String,
+    colors: Vec<(Range<usize>, [f32; 4])>,
+    /// Amplitude in font pixels
+    waves: Vec<(Range<usize>, f32)>,
+    opts: LayoutOptions,
+    scale: f32,
+}
+
+fn text_args(def: &Table, who: &str) -> mlua::Result<TextArgs> {
+    let font = match def.get::<Value>("font")? {
+        Value::UserData(ud) => ud.borrow::<FontHandle>()?.0.clone(),
+        _ => {
+            return Err(mlua::Error::RuntimeError(format!(
+                "ARG_ERROR: {} expects font = Font (from engine.font_load)",
+                who
+            )))
+        }
+    };
+    let text: mlua::String = def.get("text")?;
+    let scale: f32 = def.get::<Option<f32>>("scale")?.unwrap_or(1.0);
+    let align = match def.get::<Option<String>>("align")? {
+        None => Align::Left,
+        Some(a) => Align::parse(&a).ok_or_else(|| {
+            mlua::Error::RuntimeError(format!(
+                "{}: unknown align '{}' (use \"left\", \"center\", \"right\" or \"justify\")",
+                who, a
+            ))
+        })?,
+    };
+    let opts = LayoutOptions {
+        align,
+        max_width: def.get::<Option<f32>>("max_width")?.map(|w| w / scale),
+        line_spacing: def.get::<Option<f32>>("line_spacing")?.unwrap_or(0.0) / scale,
+    };
+    let (plain, spans) = if def.get::<Option<bool>>("markup")?.unwrap_or(true) {
+        parse_markup(&text.to_str()?, MARKUP_TAGS)
+            .map_err(|e| mlua::Error::RuntimeError(format!("{}: {}", who, e)))?
+    } else {
+        (text.to_str()?.to_string(), Vec::new())
+    };
+    let (mut colors, mut waves) = (Vec::new(), Vec::new());
+    for span in spans {
+        let value = span.value.as_deref();
+        match span.tag.as_str() {
+            "color" => {
+                let hex = value.unwrap_or("");
+                let (r, g, b, a) = parse_hex_string(hex).map_err(|e| {
+                    mlua::Error::RuntimeError(format!("{}: [color={}]: {}", who, hex, e))
+                })?;
+                colors.push((span.start..span.end, [r, g, b, a]));
+            }
+            _ => {
+                let amp = match value {
+                    None => WAVE_AMPLITUDE,
+                    Some(v) => v.parse::<f32>().map_err(|_| {
+                        mlua::Error::RuntimeError(format!("{}: [wave={}] expects a number", who, v))
+                    })?,
+                };
+                waves.push((span.start..span.end, amp));
+            }
+        }
+    }
+    Ok(TextArgs {
+        font,
+        plain,
+        colors,
+        waves,
+        opts,
+        scale,
+    })
+}
+
+// Innermost span covering char `index`; spans are ordered by start
+fn span_at<T: Copy>(spans: &[(Range<usize>, T)], index: usize) -> Option<T> {
+    spans
+        .iter()
+        .rev()
+        .find(|(r, _)| r.contains(&index))
+        .map(|(_, v)| *v)
+}
+
+// Quad for one laid-out glyph; `anchor` is the text's top-left in engine space
+fn glyph_quad(
+    font: &BitmapFont,
+    g: &GlyphQuad,
+    anchor: [f32; 2],
+    scale: f32,
+    color: [f32; 4],
+    z: f32,
+    layer_id: u32,
+) -> QuadData {
+    let size = g.size * scale;
+    // Layout is y-down from the anchor; engine space is y-up
+    let left = anchor[0] + g.top_left.x * scale;
+    let top = anchor[1] - g.top_left.y * scale;
+    let [u0, v0, u1, v1] = g.uv;
+    QuadData {
+        texture_id: font
+            .page_textures
+            .get(g.page as usize)
+            .copied()
+            .unwrap_or(0),
+        center: [left + size.x * 0.5, top - size.y * 0.5],
+        size: [size.x, size.y],
+        // The sprite pipeline samples v0 at the low-y edge
+        uv: [u0, v1, u1, v0],
+        color,
+        z,
+        layer_id,
+    }
+}
+
+/// Scale, tint and placement for `push_text`
+#[derive(Clone, Copy)]
+pub(crate) struct TextPaint {
+    pub scale: f32,
+    pub color: [f32; 4],
+    pub z: f32,
+    pub layer_id: u32,
+}
+
+/// Append plain single-colour text at `anchor` (top-left); returns its size
+pub(crate) fn push_text(
+    out: &mut Vec<QuadData>,
+    font: &BitmapFont,
+    text: &str,
+    anchor: [f32; 2],
+    paint: TextPaint,
+) -> (f32, f32) {
+    let layout = font.layout(text, &LayoutOptions::default());
+    let TextPaint {
+        scale,
+        color,
+        z,
+        layer_id,
+    } = paint;
+    for g in &layout.quads {
+        out.push(glyph_quad(font, g, anchor, scale, color, z, layer_id));
+    }
+    (layout.width * scale, layout.height * scale)
+}
+
+/// Install font_load, text and text_measure on the engine table. Glyphs are appended
+/// to `quads`, which the host hands to the renderer after each update.
+pub fn install(
+    lua: &Lua,
+    engine: &Table,
+    registry: Rc<RefCell<FontRegistry>>,
+    quads: Rc<RefCell<Vec<QuadData>>>,
+    layer_resolve: Rc<RefCell<Option<LayerResolveCb>>>,
+    fixed_time: Rc<RefCell<f64>>,
+) -> mlua::Result<()> {
+    let load = lua.create_function(move |_, path: String| {
+        let mut reg = registry.borrow_mut();
+        if let Some(font) = reg.by_path.get(&path) {
+            return Ok(FontHandle(font.clone()));
+        }
+        let mut font = BitmapFont::load(&path)
+            .map_err(|e| mlua::Error::RuntimeError(format!("font_load: {}", e)))?;
+        if let Some(host) = reg.host.as_mut() {
+            for (page, tex) in font.pages.iter().zip(font.page_textures.iter_mut()) {
+                *tex = host.next_texture_id;
+                host.next_texture_id += 1;
+                (host.load_texture)(page.to_string_lossy().into_owned(), *tex);
+            }
+        }
+        let font = Rc::new(font);
+        reg.by_path.insert(path, font.clone());
+        Ok(FontHandle(font))
+    })?;
+    engine.set("font_load", load)?;
+
+    // text{font, text, pos, color?, layer?, z?, align?, max_width?, line_spacing?, scale?,
+    //      markup?, reveal?} -> w, h, chars
+    // `reveal` shows only the first n chars of the text (markup excluded) for typewriter
+    // effects; `chars` is the total so scripts know when the reveal is done.
+    let text = lua.create_function(move |_, def: Table| {
+        let args = text_args(&def, "text")?;
+        let pos: Table = def.get("pos")?;
+        let (x, y): (f32, f32) = (pos.raw_get(1)?, pos.raw_get(2)?);
+        let color = color_arg(def.get("color")?)?;
+        let z: f32 = def.get::<Option<f32>>("z")?.unwrap_or(0.0);
+        let reveal = def.get::<Option<usize>>("reveal")?.unwrap_or(usize::MAX);
+        let mut layer_id = 0;
+        if let Some(cb) = &*layer_resolve.borrow() {
+            if let Some(name) = def.get::<Option<String>>("layer")? {
+                layer_id = cb(name);
+            }
+        }
+
+        let (font, scale) = (&args.font, args.scale);
+        let layout = font.layout(&args.plain, &args.opts);
+        let time = *fixed_time.borrow() as f32;
+        let mut out = quads.borrow_mut();
+        for g in layout.quads.iter().filter(|g| g.index < reveal) {
+            // Tag colours keep the base alpha so fades apply to tinted glyphs too
+            let color = match span_at(&args.colors, g.index) {
+                Some([r, gr, b, a]) => [r, gr, b, a * color[3]],
+                None => color,
+            };
+            let wave = span_at(&args.waves, g.index)
+                .map(|amp| (time * WAVE_SPEED + g.index as f32 * WAVE_PHASE).sin() * amp)
+                .unwrap_or(0.0);
+            let anchor = [x, y + wave * scale];
+            out.push(glyph_quad(font, g, anchor, scale, color, z, layer_id));
+        }
+        Ok((
+            layout.width * scale,
+            layout.height * scale,
+            args.plain.chars().count(),
+        ))
+    })?;
+    engine.set("text", text)?;
+
+    // text_measure{font, text, align?, max_width?, line_spacing?, scale?, markup?}
+    //   -> {width, height, chars, lines = {{first, count, width, y}, ...}}
+    // `first` is the 1-based char index of the line; `y` is its top, down from the anchor.
+    let measure = lua.create_function(|lua, def: Table| {
+        let args = text_args(&def, "text_measure")?;
+        let layout = args.font.layout(&args.plain, &args.opts);
+        let scale = args.scale;
+        let lines = lua.create_table()?;
+        for line in &layout.lines {
+            let t = lua.create_table()?;
+            t.set("first", line.first + 1)?;
+            t.set("count", line.count)?;
+            t.set("width", line.width * scale)?;
+            t.set("y", line.y * scale)?;
+            lines.push(t)?;
+        }
+        let out = lua.create_table()?;
+        out.set("width", layout.width * scale)?;
+        out.set("height", layout.height * scale)?;
+        out.set("chars", args.plain.chars().count())?;
+        out.set("lines", lines)?;
+        Ok(out)
+    })?;
+    engine.set("text_measure", measure)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::test_lua;
+
+    // Two-glyph JSON BMFont ('H' and 'i') written under a dir for `test`
+    fn mini_font(test: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("luarite_font_{}_{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mini.json");
+        std::fs::write(
+            &path,
+            r#"{ "pages": ["mini.png"], "info": { "face": "Mini" },
+                 "common": { "lineHeight": 8, "base": 6, "scaleW": 32, "scaleH": 32 },
+                 "chars": [
+                   { "id": 72, "x": 0, "y": 0, "width": 4, "height": 6, "xoffset": 0, "yoffset": 1, "xadvance": 5, "page": 0 },
+                   { "id": 105, "x": 4, "y": 0, "width": 1, "height": 6, "xoffset": 0, "yoffset": 1, "xadvance": 2, "page": 0 } ] }"#,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_text_emits_glyph_quads() {
+        let path = mini_font("quads");
+        let (lua, api) = test_lua();
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        let (w, h): (f32, f32) = lua
+            .load(
+                r#"
+                local f = engine.font_load(PATH)
+                assert(engine.font_load(PATH) ~= nil and tostring(f) == "Font(Mini)")
+                return engine.text{ font = f, text = "Hi", pos = {100, 50}, scale = 2, color = "FF0000" }
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((w, h), (14.0, 16.0));
+
+        let quads = api.take_quads();
+        assert_eq!(quads.len(), 2);
+        // 'H' is 8x12 at (100, 48) top-left in y-up space
+        assert_eq!(
+            (quads[0].center, quads[0].size),
+            ([104.0, 42.0], [8.0, 12.0])
+        );
+        assert_eq!(quads[0].color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(quads[1].center[0], 111.0);
+        assert!(api.take_quads().is_empty());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_markup_tints_and_reveal_hides_glyphs() {
+        let path = mini_font("markup");
+        let (lua, api) = test_lua();
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        // Tags are stripped and tint the glyphs they cover;
+        // reveal hides chars past the typewriter position
+        let chars: usize = lua
+            .load(
+                r#"
+                local f = engine.font_load(PATH)
+                local _, _, n = engine.text{ font = f, text = "H[color=#00FF0080]ii[/color]H", pos = {0, 0},
+                                             color = {r = 1, g = 1, b = 1, a = 0.5}, reveal = 3 }
+                return n
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!(chars, 4);
+        let quads = api.take_quads();
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[0].color, [1.0, 1.0, 1.0, 0.5]);
+        assert_eq!(quads[1].color[..2], [0.0, 1.0]);
+        assert!((quads[1].color[3] - 0.25).abs() < 0.01);
+        assert!(lua
+            .load(
+                "engine.text{ font = engine.font_load(PATH), text = '[color=red]x', pos = {0, 0} }"
+            )
+            .exec()
+            .is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_text_measure_reports_wrapped_lines() {
+        let path = mini_font("measure");
+        let (lua, api) = test_lua();
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        let (lines, second): (usize, usize) = lua
+            .load(
+                r#"
+                local f = engine.font_load(PATH)
+                local m = engine.text_measure{ font = f, text = "Hi [wave]Hi[/wave]", max_width = 8, line_spacing = 2 }
+                assert(m.width == 7 and m.height == 18 and m.lines[2].y == 10)
+                return #m.lines, m.lines[2].first
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((lines, second), (2, 4));
+        // Measuring draws nothing
+        assert!(api.take_quads().is_empty());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,432 @@
+// Action mapping: named buttons and axes resolved from raw keys and mouse buttons
+// (engine.input_map, input:action_down, input:axis). Bindings can be changed at
+// runtime and saved as JSON; replays carry the resolved values, not the bindings.
+use crate::api::InputSnapshot;
+use crate::persist::{valid_file_name, write_atomic};
+use anyhow::Result;
+use mlua::{Lua, Table, Value};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const MOUSE_BUTTONS: [&str; 5] = [
+    "MouseLeft",
+    "MouseRight",
+    "MouseMiddle",
+    "MouseBack",
+    "MouseForward",
+];
+
+/// One physical input: a stable key code or a mouse button name ("MouseLeft")
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Binding {
+    Key(u32),
+    Mouse(String),
+}
+
+/// A named action: a button held by any of its bindings, or an axis in -1..1
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Button(Vec<Binding>),
+    Axis {
+        negative: Vec<Binding>,
+        positive: Vec<Binding>,
+    },
+}
+
+/// Current bindings by action name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputMap {
+    pub actions: BTreeMap<String, Action>,
+}
+
+impl InputMap {
+    /// Resolve every action; `down` reports whether a binding is held
+    pub fn resolve(&self, down: impl Fn(&Binding) -> bool) -> ActionState {
+        let any = |bindings: &[Binding]| bindings.iter().any(&down);
+        let values = self
+            .actions
+            .iter()
+            .map(|(name, action)| {
+                let v = match action {
+                    Action::Button(b) => any(b) as i32 as f32,
+                    Action::Axis { negative, positive } => {
+                        any(positive) as i32 as f32 - any(negative) as i32 as f32
+                    }
+                };
+                (name.clone(), v)
+            })
+            .collect();
+        ActionState { values }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| anyhow::Error::msg(e.to_string()))
+    }
+
+    pub fn from_json(src: &str) -> Result<Self> {
+        serde_json::from_str(src).map_err(|e| anyhow::Error::msg(e.to_string()))
+    }
+
+    /// Written to a temp file and renamed over the old one
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        write_atomic(path, &[self.to_json()?.as_bytes()])
+            .map_err(|e| anyhow::Error::msg(format!("input map '{}': {}", path.display(), e)))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::Error::msg(format!("input map '{}': {}", path.display(), e)))?;
+        Self::from_json(&src)
+            .map_err(|e| anyhow::Error::msg(format!("input map '{}': {}", path.display(), e)))
+    }
+}
+
+/// Resolved action values for one frame: 0/1 for buttons, -1..1 for axes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionState {
+    pub values: BTreeMap<String, f32>,
+}
+
+impl ActionState {
+    pub fn value(&self, name: &str) -> f32 {
+        self.values.get(name).copied().unwrap_or(0.0)
+    }
+
+    pub fn down(&self, name: &str) -> bool {
+        self.value(name) != 0.0
+    }
+
+    /// Only the held actions; released ones read as 0 anyway
+    pub fn active(&self) -> Self {
+        let values = self
+            .values
+            .iter()
+            .filter(|(_, v)| **v != 0.0)
+            .map(|(k, v)| (k.clone(), *v))
+            .collect();
+        Self { values }
+    }
+}
+
+/// Bindings plus the actions most recently handed to scripts (for recording)
+#[derive(Debug)]
+pub struct InputMapState {
+    pub map: InputMap,
+    pub last: ActionState,
+    /// Where input_map_save/load keep `<name>.json` (default "config")
+    pub dir: PathBuf,
+}
+
+impl Default for InputMapState {
+    fn default() -> Self {
+        Self {
+            map: InputMap::default(),
+            last: ActionState::default(),
+            dir: PathBuf::from("config"),
+        }
+    }
+}
+
+impl InputMapState {
+    /// `<dir>/<name>.json`; names use the save slot rules so scripts stay in `dir`
+    pub fn file_path(&self, name: &str) -> Result<PathBuf> {
+        if !valid_file_name(name) {
+            return Err(anyhow::Error::msg(format!(
+                "invalid input map name '{}' (use 1-64 of A-Z a-z 0-9 _ -)",
+                name
+            )));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Fill in actions the snapshot does not already carry (replays bring their own)
+    pub fn apply(&mut self, snap: &mut InputSnapshot) {
+        if snap.actions.is_none() {
+            let keys = &snap.keys;
+            let buttons = &snap.mouse_buttons;
+            snap.actions = Some(self.map.resolve(|b| binding_down(b, keys, buttons)));
+        }
+        if snap.prev_actions.is_none() {
+            let keys = &snap.prev_keys;
+            let buttons = &snap.prev_mouse_buttons;
+            snap.prev_actions = Some(self.map.resolve(|b| binding_down(b, keys, buttons)));
+        }
+        self.last = snap.actions.clone().unwrap_or_default();
+    }
+}
+
+fn binding_down(
+    b: &Binding,
+    keys: &std::collections::HashMap<u32, bool>,
+    buttons: &std::collections::HashMap<String, bool>,
+) -> bool {
+    match b {
+        Binding::Key(k) => keys.get(k).copied().unwrap_or(false),
+        Binding::Mouse(m) => buttons.get(m).copied().unwrap_or(false),
+    }
+}
+
+fn binding_arg(v: Value, action: &str) -> mlua::Result<Binding> {
+    match v {
+        Value::Integer(k) if k >= 0 => Ok(Binding::Key(k as u32)),
+        Value::String(s) => {
+            let name = s.to_str()?.to_string();
+            if MOUSE_BUTTONS.contains(&name.as_str()) {
+                Ok(Binding::Mouse(name))
+            } else {
+                Err(mlua::Error::RuntimeError(format!(
+                    "input_map: action '{}' has unknown mouse button '{}'",
+                    action, name
+                )))
+            }
+        }
+        other => Err(mlua::Error::RuntimeError(format!(
+            "input_map: action '{}' expects key codes or mouse button names, got {}",
+            action,
+            other.type_name()
+        ))),
+    }
+}
+
+// A single binding or a list of them
+fn bindings_arg(v: Value, action: &str) -> mlua::Result<Vec<Binding>> {
+    match v {
+        Value::Table(t) => t
+            .sequence_values::<Value>()
+            .map(|b| binding_arg(b?, action))
+            .collect(),
+        other => Ok(vec![binding_arg(other, action)?]),
+    }
+}
+
+fn action_arg(t: Table, name: &str) -> mlua::Result<Action> {
+    match t.get::<Option<Table>>("axis")? {
+        Some(axis) => Ok(Action::Axis {
+            negative: bindings_arg(axis.raw_get(1)?, name)?,
+            positive: bindings_arg(axis.raw_get(2)?, name)?,
+        }),
+        None => Ok(Action::Button(bindings_arg(Value::Table(t), name)?)),
+    }
+}
+
+fn binding_value(lua: &Lua, b: &Binding) -> mlua::Result<Value> {
+    match b {
+        Binding::Key(k) => Ok(Value::Integer(*k as i64)),
+        Binding::Mouse(m) => Ok(Value::String(lua.create_string(m)?)),
+    }
+}
+
+fn bindings_table(lua: &Lua, bindings: &[Binding]) -> mlua::Result<Table> {
+    let t = lua.create_table()?;
+    for b in bindings {
+        t.push(binding_value(lua, b)?)?;
+    }
+    Ok(t)
+}
+
+/// Install input_map, input_bindings, input_map_save and input_map_load.
+pub fn install(lua: &Lua, engine: &Table, state: Rc<RefCell<InputMapState>>) -> mlua::Result<()> {
+    // input_map{ name = {bindings...} | {axis = {neg, pos}} | false } rebinds the
+    // listed actions and keeps the rest; false removes an action
+    let st = state.clone();
+    let map = lua.create_function(move |_, defs: Table| {
+        let mut changes = Vec::new();
+        for pair in defs.pairs::<String, Value>() {
+            let (name, v) = pair?;
+            let action = match v {
+                Value::Boolean(false) => None,
+                Value::Table(t) => Some(action_arg(t, &name)?),
+                other => Some(Action::Button(bindings_arg(other, &name)?)),
+            };
+            changes.push((name, action));
+        }
+        let actions = &mut st.borrow_mut().map.actions;
+        for (name, action) in changes {
+            match action {
+                Some(a) => actions.insert(name, a),
+                None => actions.remove(&name),
+            };
+        }
+        Ok(())
+    })?;
+    engine.set("input_map", map)?;
+
+    // input_bindings() -> table in the same shape input_map accepts
+    let st = state.clone();
+    let bindings = lua.create_function(move |lua, ()| {
+        let out = lua.create_table()?;
+        for (name, action) in st.borrow().map.actions.iter() {
+            let t = match action {
+                Action::Button(b) => bindings_table(lua, b)?,
+                Action::Axis { negative, positive } => {
+                    let axis = lua.create_table()?;
+                    axis.push(bindings_table(lua, negative)?)?;
+                    axis.push(bindings_table(lua, positive)?)?;
+                    let t = lua.create_table()?;
+                    t.set("axis", axis)?;
+                    t
+                }
+            };
+            out.set(name.as_str(), t)?;
+        }
+        Ok(out)
+    })?;
+    engine.set("input_bindings", bindings)?;
+
+    // input_map_save(name) writes <config dir>/<name>.json
+    let st = state.clone();
+    let save = lua.create_function(move |_, name: String| {
+        let st = st.borrow();
+        let save = || -> Result<()> {
+            let path = st.file_path(&name)?;
+            std::fs::create_dir_all(&st.dir).map_err(|e| {
+                anyhow::Error::msg(format!("cannot create {}: {}", st.dir.display(), e))
+            })?;
+            st.map.save(path)
+        };
+        save().map_err(|e| mlua::Error::RuntimeError(format!("input_map_save: {}", e)))
+    })?;
+    engine.set("input_map_save", save)?;
+
+    // input_map_load(name) replaces all bindings
+    let load = lua.create_function(move |_, name: String| {
+        let map = state
+            .borrow()
+            .file_path(&name)
+            .and_then(InputMap::load)
+            .map_err(|e| mlua::Error::RuntimeError(format!("input_map_load: {}", e)))?;
+        state.borrow_mut().map = map;
+        Ok(())
+    })?;
+    engine.set("input_map_load", load)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_lua, EngineApi};
+    use engine_core::stable_keys::{KEY_A, KEY_D, KEY_W, SPACE};
+
+    // Engine with `jump` on Space/W/left click and `move_x` on A/D
+    fn mapped() -> (Lua, EngineApi) {
+        let (lua, api) = test_lua();
+        lua.load(
+            r#"
+            local K = engine.keys
+            engine.input_map{ jump = {K.Space, K.KeyW, "MouseLeft"}, move_x = {axis = {K.KeyA, K.KeyD}} }
+            "#,
+        )
+        .exec()
+        .unwrap();
+        (lua, api)
+    }
+
+    fn snap(keys: &[u32], prev: &[u32]) -> InputSnapshot {
+        let mut s = InputSnapshot::new();
+        s.keys = keys.iter().map(|k| (*k, true)).collect();
+        s.prev_keys = prev.iter().map(|k| (*k, true)).collect();
+        s
+    }
+
+    #[test]
+    fn test_actions_resolve_from_bindings() {
+        let (lua, api) = mapped();
+        api.set_input_snapshot(snap(&[KEY_W, KEY_D], &[KEY_D]));
+        let (down, pressed, axis): (bool, bool, f32) = lua
+            .load(
+                r#"
+                local i = engine.get_input()
+                return i:action_down("jump"), i:action_pressed("jump"), i:axis("move_x")
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((down, pressed, axis), (true, true, 1.0));
+        assert_eq!(
+            api.last_actions().values,
+            BTreeMap::from([("jump".to_string(), 1.0), ("move_x".to_string(), 1.0)])
+        );
+    }
+
+    #[test]
+    fn test_rebinding_changes_live_resolution() {
+        let (lua, api) = mapped();
+        lua.load("engine.input_map{ jump = engine.keys.KeyA, move_x = false }")
+            .exec()
+            .unwrap();
+        api.set_input_snapshot(snap(&[KEY_A, SPACE], &[]));
+        let (jump, axis): (bool, f32) = lua
+            .load("local i = engine.get_input() return i:action_down('jump'), i:axis('move_x')")
+            .eval()
+            .unwrap();
+        assert_eq!((jump, axis), (true, 0.0));
+        assert!(lua
+            .load("engine.input_map{ jump = 'Space' }")
+            .exec()
+            .is_err());
+    }
+
+    #[test]
+    fn test_recorded_actions_win_over_bindings() {
+        let (lua, api) = mapped();
+        let mut replayed = snap(&[SPACE], &[]);
+        replayed.actions = Some(ActionState {
+            values: BTreeMap::from([("move_x".to_string(), -1.0)]),
+        });
+        api.set_input_snapshot(replayed);
+        let (jump, axis): (bool, f32) = lua
+            .load("local i = engine.get_input() return i:action_down('jump'), i:axis('move_x')")
+            .eval()
+            .unwrap();
+        assert_eq!((jump, axis), (false, -1.0));
+    }
+
+    #[test]
+    fn test_bindings_round_trip_through_json_and_lua() {
+        let (lua, api) = mapped();
+        let map = InputMap::from_json(&api.input_map().to_json().unwrap()).unwrap();
+        assert_eq!(map, api.input_map());
+        let n: i64 = lua
+            .load("local b = engine.input_bindings() return b.jump[1]")
+            .eval()
+            .unwrap();
+        assert_eq!(n, SPACE as i64);
+    }
+
+    #[test]
+    fn test_saved_by_name_under_the_config_dir() {
+        let (lua, api) = mapped();
+        let map = api.input_map();
+        let dir = std::env::temp_dir().join(format!("luarite_input_map_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        api.set_config_dir(&dir);
+        lua.load("engine.input_map_save('controls') engine.input_map{ jump = false } engine.input_map_load('controls')")
+            .exec()
+            .unwrap();
+        assert!(dir.join("controls.json").exists());
+        assert!(!dir.join("controls.json.tmp").exists());
+        assert_eq!(api.input_map(), map);
+        // Paths are rejected
+        for bad in ["'../controls'", "'/tmp/controls'", "''"] {
+            assert!(lua
+                .load(format!("engine.input_map_save({})", bad))
+                .exec()
+                .is_err());
+            assert!(lua
+                .load(format!("engine.input_map_load({})", bad))
+                .exec()
+                .is_err());
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,15 @@
 #![deny(warnings)]
 
 pub mod api;
+pub mod audio;
+pub mod collision;
+pub mod font;
+pub mod input_map;
 pub mod persist;
 pub mod reload;
+pub mod replay;
 pub mod sandbox;
+pub mod tasks;
+pub mod tilemap;
+pub mod tween;
+pub mod ui;
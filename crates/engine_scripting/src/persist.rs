@@ -1 +1,274 @@
-// State persistence for hot reload
+// State persistence for hot reload and on-disk save slots
+use anyhow::Result;
+use mlua::{Lua, LuaSerdeExt, Table, Value};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Current on-disk save format version
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+// First token of the header line: "LUARITE-SAVE <version> <checksum>"
+const SAVE_MAGIC: &str = "LUARITE-SAVE";
+
+/// Save slots stored as `<dir>/<slot>.sav`.
+///
+/// File layout is one header line followed by the JSON payload:
+/// `LUARITE-SAVE <version> <fnv1a64 of payload, hex>\n<payload>`.
+#[derive(Debug, Clone)]
+pub struct SaveStore {
+    dir: PathBuf,
+}
+
+impl SaveStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Serialize `value` into `slot`. The file is written to a temp file and renamed
+    /// over the old one, so a crash mid-write never leaves a truncated save.
+    pub fn save(&self, slot: &str, value: &Value) -> Result<()> {
+        let path = self.slot_path(slot)?;
+        check_serializable(value, "value", &mut HashSet::new())?;
+        let payload = serde_json::to_string(value)
+            .map_err(|e| anyhow::Error::msg(format!("save_slot '{}': {}", slot, e)))?;
+
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            anyhow::Error::msg(format!(
+                "save_slot '{}': cannot create {}: {}",
+                slot,
+                self.dir.display(),
+                e
+            ))
+        })?;
+        let header = format!(
+            "{} {} {:016x}\n",
+            SAVE_MAGIC,
+            SAVE_FORMAT_VERSION,
+            fnv1a64(payload.as_bytes())
+        );
+        write_atomic(&path, &[header.as_bytes(), payload.as_bytes()])
+            .map_err(|e| anyhow::Error::msg(format!("save_slot '{}': write failed: {}", slot, e)))
+    }
+
+    /// Load `slot`, returning `None` if it was never saved.
+    pub fn load(&self, lua: &Lua, slot: &str) -> Result<Option<Value>> {
+        let path = self.slot_path(slot)?;
+        let data = match std::fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(anyhow::Error::msg(format!(
+                    "load_slot '{}': read failed: {}",
+                    slot, e
+                )))
+            }
+        };
+        let corrupt = |why: &str| anyhow::Error::msg(format!("load_slot '{}': {}", slot, why));
+
+        let (header, payload) = data
+            .split_once('\n')
+            .ok_or_else(|| corrupt("missing header"))?;
+        let mut parts = header.split(' ');
+        if parts.next() != Some(SAVE_MAGIC) {
+            return Err(corrupt("not a save file"));
+        }
+        let version = parts
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .ok_or_else(|| corrupt("bad version"))?;
+        if version == 0 || version > SAVE_FORMAT_VERSION {
+            return Err(corrupt(&format!("unsupported save version {}", version)));
+        }
+        let checksum = parts
+            .next()
+            .and_then(|c| u64::from_str_radix(c, 16).ok())
+            .ok_or_else(|| corrupt("bad checksum field"))?;
+        if checksum != fnv1a64(payload.as_bytes()) {
+            return Err(corrupt("checksum mismatch (file is corrupt)"));
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_str(payload).map_err(|e| corrupt(&e.to_string()))?;
+        let options = mlua::SerializeOptions::new()
+            .serialize_none_to_null(false)
+            .set_array_metatable(false);
+        let value = lua
+            .to_value_with(&json, options)
+            .map_err(|e| corrupt(&e.to_string()))?;
+        Ok(Some(value))
+    }
+
+    fn slot_path(&self, slot: &str) -> Result<PathBuf> {
+        if !valid_file_name(slot) {
+            return Err(anyhow::Error::msg(format!(
+                "invalid save slot name '{}' (use 1-64 of A-Z a-z 0-9 _ -)",
+                slot
+            )));
+        }
+        Ok(self.dir.join(format!("{}.sav", slot)))
+    }
+}
+
+/// Names scripts may use for files they write: 1-64 of A-Z a-z 0-9 _ -,
+/// so they can never leave their directory.
+pub(crate) fn valid_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Write `parts` to `<path>.tmp` and rename it over `path`, so a crash mid-write
+/// never leaves a truncated file.
+pub(crate) fn write_atomic(path: &Path, parts: &[&[u8]]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let write = || -> std::io::Result<()> {
+        let mut f = std::fs::File::create(&tmp)?;
+        for part in parts {
+            f.write_all(part)?;
+        }
+        f.sync_all()?;
+        std::fs::rename(&tmp, path)
+    };
+    write().inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// Reject values JSON cannot round-trip, naming the offending field.
+fn check_serializable(value: &Value, path: &str, visiting: &mut HashSet<usize>) -> Result<()> {
+    let reject = |what: &str| {
+        Err(anyhow::Error::msg(format!(
+            "cannot save {} at {}",
+            what, path
+        )))
+    };
+    match value {
+        Value::Nil | Value::Boolean(_) | Value::Integer(_) | Value::String(_) => Ok(()),
+        Value::Number(n) if n.is_finite() => Ok(()),
+        Value::Number(_) => reject("non-finite number"),
+        Value::Table(t) => check_table(t, path, visiting),
+        Value::Function(_) => reject("function"),
+        Value::UserData(_) | Value::LightUserData(_) => reject("userdata"),
+        Value::Thread(_) => reject("coroutine"),
+        _ => reject("unsupported value"),
+    }
+}
+
+fn check_table(t: &Table, path: &str, visiting: &mut HashSet<usize>) -> Result<()> {
+    let ptr = t.to_pointer() as usize;
+    if !visiting.insert(ptr) {
+        return Err(anyhow::Error::msg(format!(
+            "cannot save recursive table at {}",
+            path
+        )));
+    }
+    let len = t.raw_len();
+    let mut count = 0usize;
+    for pair in t.clone().pairs::<Value, Value>() {
+        let (k, v) = pair.map_err(|e| anyhow::Error::msg(e.to_string()))?;
+        count += 1;
+        let child = match &k {
+            Value::String(s) => format!("{}.{}", path, s.to_string_lossy()),
+            Value::Integer(i) if *i >= 1 && (*i as usize) <= len => format!("{}[{}]", path, i),
+            other => {
+                return Err(anyhow::Error::msg(format!(
+                    "cannot save table key {:?} at {} (keys must be strings or 1..n array indices)",
+                    other, path
+                )))
+            }
+        };
+        check_serializable(&v, &child, visiting)?;
+    }
+    if len > 0 && count != len {
+        return Err(anyhow::Error::msg(format!(
+            "cannot save table mixing array items and named fields at {}",
+            path
+        )));
+    }
+    visiting.remove(&ptr);
+    Ok(())
+}
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Empty save dir unique to one test
+    fn store(test: &str) -> (std::path::PathBuf, SaveStore) {
+        let dir =
+            std::env::temp_dir().join(format!("luarite_saves_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = SaveStore::new(&dir);
+        (dir, store)
+    }
+
+    #[test]
+    fn test_save_slot_roundtrip() {
+        let (dir, store) = store("roundtrip");
+        let lua = Lua::new();
+        let value: Value = lua
+            .load("return { name = 'hero', hp = 7, pos = { 1.5, 2 }, flags = { door = true } }")
+            .eval()
+            .unwrap();
+        store.save("slot1", &value).unwrap();
+        assert!(!dir.join("slot1.sav.tmp").exists());
+        let loaded = store.load(&lua, "slot1").unwrap().unwrap();
+        lua.globals().set("s", loaded).unwrap();
+        let ok: bool = lua
+            .load("return s.name == 'hero' and s.hp == 7 and s.pos[1] == 1.5 and s.pos[2] == 2 and s.flags.door")
+            .eval()
+            .unwrap();
+        assert!(ok);
+        assert!(store.load(&lua, "missing").unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_tampered_save_fails_checksum() {
+        let (dir, store) = store("tamper");
+        let lua = Lua::new();
+        let value: Value = lua.load("return { name = 'hero' }").eval().unwrap();
+        store.save("slot1", &value).unwrap();
+        let file = dir.join("slot1.sav");
+        let data = std::fs::read_to_string(&file)
+            .unwrap()
+            .replace("hero", "HERO");
+        std::fs::write(&file, data).unwrap();
+        let err = store.load(&lua, "slot1").unwrap_err().to_string();
+        assert!(err.contains("checksum mismatch"), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_functions_and_bad_slot_names_are_rejected() {
+        let (dir, store) = store("reject");
+        let lua = Lua::new();
+        let bad: Value = lua
+            .load("return { player = { on_hit = function() end } }")
+            .eval()
+            .unwrap();
+        let err = store.save("slot2", &bad).unwrap_err().to_string();
+        assert_eq!(err, "cannot save function at value.player.on_hit");
+        assert!(store.save("../escape", &Value::Boolean(true)).is_err());
+        assert!(!dir.join("slot2.sav").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1 +1,269 @@
 // Hot reload and env swapping
+use crate::sandbox::LuaSandbox;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Outcome of one reload pass
+#[derive(Debug, Default, Clone)]
+pub struct ReloadReport {
+    /// Files that were re-executed, in reload order
+    pub reloaded: Vec<PathBuf>,
+    /// Files whose reload failed, with the error message
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl ReloadReport {
+    pub fn is_empty(&self) -> bool {
+        self.reloaded.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Watches the main script and every module the sandbox has loaded.
+///
+/// Files are only stat'ed every `poll_interval`; a change is acted on once no
+/// further change has been seen for `debounce`, so editors that write in several
+/// steps trigger a single reload. Changed modules are patched in place, followed
+/// by every module (and finally the main script) that depends on them.
+pub struct HotReloader {
+    script_path: PathBuf,
+    script_name: String,
+    poll_interval: Duration,
+    debounce: Duration,
+    last_poll: Option<Instant>,
+    mtimes: HashMap<PathBuf, Option<SystemTime>>,
+    pending: BTreeSet<PathBuf>,
+    last_change: Option<Instant>,
+}
+
+impl HotReloader {
+    pub fn new(script_path: impl Into<PathBuf>, script_name: &str) -> Self {
+        let script_path = script_path.into();
+        let mut mtimes = HashMap::new();
+        mtimes.insert(script_path.clone(), mtime(&script_path));
+        Self {
+            script_path,
+            script_name: script_name.to_string(),
+            poll_interval: Duration::from_millis(250),
+            debounce: Duration::from_millis(150),
+            last_poll: None,
+            mtimes,
+            pending: BTreeSet::new(),
+            last_change: None,
+        }
+    }
+
+    pub fn with_timing(mut self, poll_interval: Duration, debounce: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.debounce = debounce;
+        self
+    }
+
+    /// Check for changes (at most once per poll interval) and reload what is due.
+    /// Returns `None` when nothing was reloaded.
+    pub fn poll(&mut self, sandbox: &LuaSandbox, now: Instant) -> Option<ReloadReport> {
+        let due = match self.last_poll {
+            Some(t) => now.duration_since(t) >= self.poll_interval,
+            None => true,
+        };
+        if !due {
+            return None;
+        }
+        self.last_poll = Some(now);
+
+        // Pick up modules required since the last poll without treating them as changed
+        for (_, path) in sandbox.loaded_modules() {
+            self.mtimes.entry(path).or_insert_with_key(|p| mtime(p));
+        }
+
+        for (path, seen) in self.mtimes.iter_mut() {
+            let current = mtime(path);
+            if current != *seen {
+                *seen = current;
+                self.pending.insert(path.clone());
+                self.last_change = Some(now);
+            }
+        }
+
+        let settled = match self.last_change {
+            Some(t) => now.duration_since(t) >= self.debounce,
+            None => false,
+        };
+        if self.pending.is_empty() || !settled {
+            return None;
+        }
+        let changed = std::mem::take(&mut self.pending);
+        self.last_change = None;
+        Some(self.reload(sandbox, &changed))
+    }
+
+    /// Reload everything immediately (manual reload), regardless of timestamps.
+    pub fn reload_all(&mut self, sandbox: &LuaSandbox) -> ReloadReport {
+        let mut all: BTreeSet<PathBuf> = sandbox
+            .loaded_modules()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect();
+        all.insert(self.script_path.clone());
+        for path in all.iter() {
+            self.mtimes.insert(path.clone(), mtime(path));
+        }
+        self.pending.clear();
+        self.last_change = None;
+        self.reload(sandbox, &all)
+    }
+
+    fn reload(&self, sandbox: &LuaSandbox, changed: &BTreeSet<PathBuf>) -> ReloadReport {
+        let modules = sandbox.loaded_modules();
+        let path_of: HashMap<String, PathBuf> = modules.iter().cloned().collect();
+        let seeds: Vec<String> = modules
+            .iter()
+            .filter(|(_, path)| changed.contains(path))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let mut report = ReloadReport::default();
+        let mut reload_script = changed.contains(&self.script_path);
+        for name in reload_order(sandbox, &seeds) {
+            if name == self.script_name {
+                reload_script = true;
+                continue;
+            }
+            let Some(path) = path_of.get(&name) else {
+                continue;
+            };
+            match sandbox.reload_module(&name) {
+                Ok(()) => report.reloaded.push(path.clone()),
+                Err(e) => report.failed.push((path.clone(), e.to_string())),
+            }
+        }
+
+        if reload_script {
+            let result = std::fs::read_to_string(&self.script_path)
+                .map_err(|e| anyhow::Error::msg(format!("Failed to read script: {}", e)))
+                .and_then(|src| sandbox.reload_script(&src, &self.script_name));
+            match result {
+                Ok(()) => report.reloaded.push(self.script_path.clone()),
+                Err(e) => report
+                    .failed
+                    .push((self.script_path.clone(), e.to_string())),
+            }
+        }
+        report
+    }
+}
+
+/// `seeds` plus everything that transitively requires them, ordered so that each
+/// module comes after all of its reloaded dependencies.
+fn reload_order(sandbox: &LuaSandbox, seeds: &[String]) -> Vec<String> {
+    let mut affected: BTreeSet<String> = BTreeSet::new();
+    let mut stack: Vec<String> = seeds.to_vec();
+    while let Some(name) = stack.pop() {
+        if affected.insert(name.clone()) {
+            stack.extend(sandbox.module_dependents(&name));
+        }
+    }
+
+    let edges: HashMap<String, Vec<String>> = affected
+        .iter()
+        .map(|name| (name.clone(), sandbox.module_dependents(name)))
+        .collect();
+    let mut indegree: HashMap<&str, usize> = affected.iter().map(|n| (n.as_str(), 0)).collect();
+    for dependents in edges.values() {
+        for d in dependents {
+            if let Some(n) = indegree.get_mut(d.as_str()) {
+                *n += 1;
+            }
+        }
+    }
+
+    // Kahn's algorithm; BTreeSet keeps the order deterministic
+    let mut ready: BTreeSet<&str> = indegree
+        .iter()
+        .filter(|(_, n)| **n == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    let mut order = Vec::with_capacity(affected.len());
+    while let Some(name) = ready.pop_first() {
+        order.push(name.to_string());
+        for d in edges.get(name).into_iter().flatten() {
+            if let Some(n) = indegree.get_mut(d.as_str()) {
+                *n -= 1;
+                if *n == 0 {
+                    ready.insert(d.as_str());
+                }
+            }
+        }
+    }
+    order
+}
+
+fn mtime(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &std::path::Path, src: &str, secs: u64) {
+        std::fs::write(path, src).unwrap();
+        let f = std::fs::File::options().write(true).open(path).unwrap();
+        f.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_poll_debounces_and_reloads_dependents_in_order() {
+        let base = std::env::temp_dir().join(format!("luarite_reload_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let dir = base.join("scripts");
+        std::fs::create_dir_all(&dir).unwrap();
+        touch(&dir.join("a.lua"), "return { v = 1 }", 1_000);
+        touch(
+            &dir.join("b.lua"),
+            "local a = require('a')\nreturn { v = a.v }",
+            1_000,
+        );
+        touch(&dir.join("c.lua"), "return {}", 1_000);
+        let main = dir.join("main.lua");
+        let main_src = "b = require('b')\nc = require('c')";
+        touch(&main, main_src, 1_000);
+
+        let sandbox = LuaSandbox::new().unwrap();
+        crate::api::EngineApi::new()
+            .setup_engine_namespace(sandbox.lua())
+            .unwrap();
+        sandbox.set_script_root(&dir).unwrap();
+        sandbox.load_script(main_src, "main.lua").unwrap();
+
+        let mut reloader = HotReloader::new(&main, "main.lua")
+            .with_timing(Duration::from_millis(100), Duration::from_millis(300));
+        let t0 = Instant::now();
+        assert!(reloader.poll(&sandbox, t0).is_none());
+
+        touch(&dir.join("a.lua"), "return { v = 2 }", 2_000);
+        // Inside the poll interval: nothing is stat'ed yet
+        assert!(reloader
+            .poll(&sandbox, t0 + Duration::from_millis(50))
+            .is_none());
+        // Change seen, but still inside the debounce window
+        assert!(reloader
+            .poll(&sandbox, t0 + Duration::from_millis(100))
+            .is_none());
+        let report = reloader
+            .poll(&sandbox, t0 + Duration::from_millis(400))
+            .expect("reload after debounce");
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        let names: Vec<String> = report
+            .reloaded
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.lua", "b.lua", "main.lua"]);
+        assert!(reloader
+            .poll(&sandbox, t0 + Duration::from_millis(800))
+            .is_none());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
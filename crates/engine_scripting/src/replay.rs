@@ -0,0 +1,780 @@
+// Versioned replay container written by --record and read by --replay.
+//
+// Layout: MAGIC, format version (u32 LE), header length (u32 LE) and the JSON
+// header, then chunks of `keyframe_interval` frames. Each chunk starts with its
+// first frame index (u64 LE), frame count (u32 LE) and payload length (u32 LE);
+// the payload is one JSON record per line, deflated when the header says so.
+// Every chunk opens on a keyframe that also carries the previous frame's input,
+// so a reader can seek by skipping whole chunks and decode from there.
+use crate::api::InputSnapshot;
+use crate::input_map::ActionState;
+use crate::sandbox::LuaSandbox;
+use anyhow::Result;
+use engine_core::gamepad::{PadState, MAX_PADS};
+use engine_core::state::StateHasher;
+use mlua::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+pub const MAGIC: &[u8; 8] = b"LRREPLAY";
+pub const FORMAT_VERSION: u32 = 1;
+/// Oldest format version this build still reads
+pub const MIN_FORMAT_VERSION: u32 = 1;
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 120;
+// Limits on what on_hash may return; shared subtables count once per reference
+const MAX_HASH_DEPTH: usize = 32;
+const MAX_HASH_ENTRIES: usize = 100_000;
+// Limits on lengths read from a file, so a corrupt one cannot exhaust memory
+const MAX_HEADER_BYTES: u32 = 64 * 1024;
+const MAX_CHUNK_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What a recording needs to be replayed faithfully
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub api_version: u32,
+    /// engine.random state when recording started
+    pub seed: u64,
+    pub timestep: f64,
+    /// Hash of the main script source
+    pub script_hash: u64,
+    pub compressed: bool,
+    pub keyframe_interval: u32,
+}
+
+/// Input scripts saw during one fixed update
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameInput {
+    pub keys: Vec<u32>,
+    pub buttons: Vec<String>,
+    pub mouse: [f64; 2],
+    pub wheel: [f32; 2],
+    pub mouse_virtual: Option<[f32; 2]>,
+    /// Mouse in UI units; missing in recordings made before it was stored
+    #[serde(default)]
+    pub mouse_ui: Option<[f32; 2]>,
+    /// Held actions by name
+    pub actions: Option<ActionState>,
+    /// Connected pads
+    pub pads: Vec<RecordedPad>,
+    pub text: Option<String>,
+    pub preedit: String,
+    pub preedit_cursor: Option<(usize, usize)>,
+}
+
+impl FrameInput {
+    pub fn from_snapshot(snap: &InputSnapshot) -> Self {
+        let mut keys: Vec<u32> = snap
+            .keys
+            .iter()
+            .filter(|(_, d)| **d)
+            .map(|(k, _)| *k)
+            .collect();
+        keys.sort_unstable();
+        let mut buttons: Vec<String> = snap
+            .mouse_buttons
+            .iter()
+            .filter(|(_, d)| **d)
+            .map(|(b, _)| b.clone())
+            .collect();
+        buttons.sort();
+        Self {
+            keys,
+            buttons,
+            mouse: [snap.mouse_x, snap.mouse_y],
+            wheel: snap.wheel,
+            mouse_virtual: snap.mouse_virtual,
+            mouse_ui: snap.mouse_ui,
+            actions: snap.actions.as_ref().map(ActionState::active),
+            pads: RecordedPad::from_pads(&snap.pads),
+            text: snap.text.clone(),
+            preedit: snap.preedit.clone(),
+            preedit_cursor: snap.preedit_cursor,
+        }
+    }
+
+    /// Snapshot for scripts; `prev` supplies the press/release edges
+    pub fn to_snapshot(&self, prev: Option<&FrameInput>) -> InputSnapshot {
+        let mut snap = InputSnapshot::new();
+        snap.keys = self.keys.iter().map(|k| (*k, true)).collect();
+        snap.mouse_buttons = self.buttons.iter().map(|b| (b.clone(), true)).collect();
+        [snap.mouse_x, snap.mouse_y] = self.mouse;
+        snap.wheel = self.wheel;
+        snap.mouse_virtual = self.mouse_virtual;
+        snap.mouse_ui = self.mouse_ui;
+        snap.actions = self.actions.clone();
+        snap.pads = RecordedPad::to_pads(&self.pads);
+        snap.text = self.text.clone();
+        snap.preedit = self.preedit.clone();
+        snap.preedit_cursor = self.preedit_cursor;
+        if let Some(p) = prev {
+            snap.prev_keys = p.keys.iter().map(|k| (*k, true)).collect();
+            snap.prev_mouse_buttons = p.buttons.iter().map(|b| (b.clone(), true)).collect();
+            snap.prev_actions = p.actions.clone();
+            snap.prev_pads = RecordedPad::to_pads(&p.pads);
+        }
+        snap
+    }
+}
+
+/// One connected pad in a recording
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPad {
+    pub index: usize,
+    pub buttons: u32,
+    pub axes: [f32; 6],
+}
+
+impl RecordedPad {
+    fn from_pads(pads: &[PadState; MAX_PADS]) -> Vec<Self> {
+        pads.iter()
+            .enumerate()
+            .filter(|(_, p)| p.connected)
+            .map(|(index, p)| Self {
+                index,
+                buttons: p.buttons,
+                axes: p.axes,
+            })
+            .collect()
+    }
+
+    // Indices past MAX_PADS are dropped
+    fn to_pads(recorded: &[Self]) -> [PadState; MAX_PADS] {
+        let mut pads = [PadState::default(); MAX_PADS];
+        for r in recorded {
+            if let Some(pad) = pads.get_mut(r.index) {
+                *pad = PadState {
+                    connected: true,
+                    buttons: r.buttons,
+                    axes: r.axes,
+                };
+            }
+        }
+        pads
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameRecord {
+    /// Fixed update index, from 0
+    pub frame: u64,
+    pub dt: f64,
+    pub input: FrameInput,
+    /// Previous frame's input, on keyframes only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<FrameInput>,
+    /// Per-subsystem state hashes after the update
+    pub hashes: Vec<(String, u64)>,
+}
+
+/// First frame whose hashes disagree with the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub frame: u64,
+    /// Subsystem, recorded hash, replayed hash
+    pub subsystems: Vec<(String, Option<u64>, Option<u64>)>,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex = |h: &Option<u64>| h.map_or("missing".to_string(), |h| format!("{:016x}", h));
+        write!(f, "replay diverged at frame {}:", self.frame)?;
+        for (name, expected, got) in &self.subsystems {
+            write!(
+                f,
+                " {} (recorded {}, replayed {})",
+                name,
+                hex(expected),
+                hex(got)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare recorded and replayed hashes for one frame. Only subsystems the
+/// recording has are checked, so recordings made before a subsystem joined the
+/// digest still replay.
+pub fn compare(
+    frame: u64,
+    recorded: &[(String, u64)],
+    replayed: &[(String, u64)],
+) -> Option<Divergence> {
+    let find =
+        |list: &[(String, u64)], name: &str| list.iter().find(|(n, _)| n == name).map(|(_, h)| *h);
+    let subsystems: Vec<_> = recorded
+        .iter()
+        .map(|(n, h)| (n.clone(), Some(*h), find(replayed, n)))
+        .filter(|(_, a, b)| a != b)
+        .collect();
+    (!subsystems.is_empty()).then_some(Divergence { frame, subsystems })
+}
+
+/// Hash of the table the script's `on_hash()` returns, for game state that lives
+/// only in Lua. None when the script defines no on_hash.
+pub fn script_state_hash(sandbox: &LuaSandbox) -> Result<Option<u64>> {
+    if !sandbox.has_function("on_hash") {
+        return Ok(None);
+    }
+    let value: Value = sandbox.call_function("on_hash", ())?;
+    let mut bytes = Vec::new();
+    let mut visiting = HashSet::new();
+    let mut entries = 0;
+    encode_value(&value, &mut bytes, &mut visiting, &mut entries)
+        .map_err(|e| anyhow::Error::msg(format!("on_hash: {}", e)))?;
+    let mut h = StateHasher::new();
+    h.write(&bytes);
+    Ok(Some(h.finish()))
+}
+
+// Tagged encoding; table entries are sorted by encoded key so iteration order
+// (which differs between otherwise equal tables) never changes the hash.
+// `visiting` holds the tables on the current path; `entries` counts every
+// table entry encoded so far.
+fn encode_value(
+    value: &Value,
+    out: &mut Vec<u8>,
+    visiting: &mut HashSet<usize>,
+    entries: &mut usize,
+) -> mlua::Result<()> {
+    match value {
+        Value::Nil => out.push(0),
+        Value::Boolean(b) => out.extend([1, *b as u8]),
+        Value::Integer(i) => {
+            out.push(2);
+            out.extend(i.to_le_bytes());
+        }
+        Value::Number(n) => {
+            out.push(3);
+            out.extend(n.to_bits().to_le_bytes());
+        }
+        Value::String(s) => {
+            let s = s.as_bytes();
+            out.push(4);
+            out.extend((s.len() as u32).to_le_bytes());
+            out.extend(&s[..]);
+        }
+        Value::Table(t) => {
+            let ptr = t.to_pointer() as usize;
+            if !visiting.insert(ptr) {
+                return Err(mlua::Error::RuntimeError("cyclic table".into()));
+            }
+            if visiting.len() > MAX_HASH_DEPTH {
+                return Err(mlua::Error::RuntimeError(format!(
+                    "tables nested more than {} deep",
+                    MAX_HASH_DEPTH
+                )));
+            }
+            let mut pairs = Vec::new();
+            for pair in t.pairs::<Value, Value>() {
+                let (k, v) = pair?;
+                *entries += 1;
+                if *entries > MAX_HASH_ENTRIES {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "more than {} table entries",
+                        MAX_HASH_ENTRIES
+                    )));
+                }
+                let mut key = Vec::new();
+                encode_value(&k, &mut key, visiting, entries)?;
+                let mut val = Vec::new();
+                encode_value(&v, &mut val, visiting, entries)?;
+                pairs.push((key, val));
+            }
+            visiting.remove(&ptr);
+            pairs.sort();
+            out.push(5);
+            out.extend((pairs.len() as u32).to_le_bytes());
+            for (k, v) in pairs {
+                out.extend(k);
+                out.extend(v);
+            }
+        }
+        other => {
+            return Err(mlua::Error::RuntimeError(format!(
+                "cannot hash a {}",
+                other.type_name()
+            )))
+        }
+    }
+    Ok(())
+}
+
+pub struct ReplayWriter<W: Write> {
+    out: W,
+    header: ReplayHeader,
+    chunk: Vec<FrameRecord>,
+    last_input: Option<FrameInput>,
+}
+
+impl<W: Write> ReplayWriter<W> {
+    pub fn new(mut out: W, header: ReplayHeader) -> Result<Self> {
+        let json = serde_json::to_vec(&header)?;
+        out.write_all(MAGIC)?;
+        out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        out.write_all(&(json.len() as u32).to_le_bytes())?;
+        out.write_all(&json)?;
+        Ok(Self {
+            out,
+            header,
+            chunk: Vec::new(),
+            last_input: None,
+        })
+    }
+
+    pub fn push(&mut self, mut record: FrameRecord) -> Result<()> {
+        if self.chunk.is_empty() {
+            record.prev = self.last_input.clone();
+        }
+        self.last_input = Some(record.input.clone());
+        self.chunk.push(record);
+        if self.chunk.len() >= self.header.keyframe_interval.max(1) as usize {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write out the pending partial chunk
+    pub fn flush(&mut self) -> Result<()> {
+        let Some(first) = self.chunk.first().map(|r| r.frame) else {
+            return Ok(());
+        };
+        let mut payload = Vec::new();
+        for record in &self.chunk {
+            serde_json::to_writer(&mut payload, record)?;
+            payload.push(b'\n');
+        }
+        if self.header.compressed {
+            let mut enc =
+                flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(&payload)?;
+            payload = enc.finish()?;
+        }
+        if payload.len() as u64 > MAX_CHUNK_BYTES {
+            return Err(anyhow::Error::msg(format!(
+                "replay chunk at frame {} is larger than {} bytes",
+                first, MAX_CHUNK_BYTES
+            )));
+        }
+        self.out.write_all(&first.to_le_bytes())?;
+        self.out
+            .write_all(&(self.chunk.len() as u32).to_le_bytes())?;
+        self.out.write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.out.write_all(&payload)?;
+        self.out.flush()?;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for ReplayWriter<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::error!("Failed to finish replay: {}", e);
+        }
+    }
+}
+
+pub struct ReplayReader<R: Read + Seek> {
+    input: R,
+    header: ReplayHeader,
+    body_start: u64,
+    pending: VecDeque<FrameRecord>,
+}
+
+impl<R: Read + Seek> ReplayReader<R> {
+    pub fn new(mut input: R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        if input.read_exact(&mut magic).is_err() || &magic != MAGIC {
+            return Err(anyhow::Error::msg(
+                "not a replay file (text recordings from older builds are not supported)",
+            ));
+        }
+        let version = read_u32(&mut input)?;
+        if !(MIN_FORMAT_VERSION..=FORMAT_VERSION).contains(&version) {
+            return Err(anyhow::Error::msg(format!(
+                "unsupported replay format v{} (this build reads v{} to v{})",
+                version, MIN_FORMAT_VERSION, FORMAT_VERSION
+            )));
+        }
+        let len = read_u32(&mut input)?;
+        if len > MAX_HEADER_BYTES {
+            return Err(anyhow::Error::msg(format!(
+                "replay header is {} bytes (at most {})",
+                len, MAX_HEADER_BYTES
+            )));
+        }
+        let mut json = vec![0u8; len as usize];
+        input
+            .read_exact(&mut json)
+            .map_err(|_| anyhow::Error::msg("replay ends inside its header"))?;
+        let header: ReplayHeader = serde_json::from_slice(&json)?;
+        let body_start = input.stream_position()?;
+        Ok(Self {
+            input,
+            header,
+            body_start,
+            pending: VecDeque::new(),
+        })
+    }
+
+    pub fn header(&self) -> &ReplayHeader {
+        &self.header
+    }
+
+    /// Next recorded frame, or `None` at the end of the recording
+    pub fn next_frame(&mut self) -> Result<Option<FrameRecord>> {
+        if self.pending.is_empty() {
+            let Some((first, count, len)) = self.chunk_header()? else {
+                return Ok(None);
+            };
+            self.pending = self.read_chunk(first, count, len)?.into();
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    /// Position on `frame` by skipping whole chunks; the frame keeps its
+    /// recorded press/release edges
+    pub fn seek(&mut self, frame: u64) -> Result<()> {
+        self.input.seek(SeekFrom::Start(self.body_start))?;
+        self.pending.clear();
+        while let Some((first, count, len)) = self.chunk_header()? {
+            if frame < first + count as u64 {
+                let mut records = self.read_chunk(first, count, len)?;
+                let keep = records
+                    .iter()
+                    .position(|r| r.frame >= frame)
+                    .unwrap_or(records.len());
+                let prev = match keep {
+                    0 => records[0].prev.clone(),
+                    n => Some(records[n - 1].input.clone()),
+                };
+                self.pending = records.split_off(keep).into();
+                if let Some(r) = self.pending.front_mut() {
+                    r.prev = prev;
+                }
+                return Ok(());
+            }
+            self.input.seek(SeekFrom::Current(len as i64))?;
+        }
+        Ok(())
+    }
+
+    fn chunk_header(&mut self) -> Result<Option<(u64, u32, u32)>> {
+        let mut buf = [0u8; 16];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.input.read(&mut buf[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(anyhow::Error::msg("replay ends inside a chunk header")),
+                n => filled += n,
+            }
+        }
+        let first = u64::from_le_bytes(buf[0..8].try_into()?);
+        let count = u32::from_le_bytes(buf[8..12].try_into()?);
+        let len = u32::from_le_bytes(buf[12..16].try_into()?);
+        if count == 0 {
+            return Err(anyhow::Error::msg(format!(
+                "replay chunk at frame {} is empty",
+                first
+            )));
+        }
+        if first.checked_add(count as u64).is_none() {
+            return Err(anyhow::Error::msg(format!(
+                "replay chunk at frame {} runs past the last frame number",
+                first
+            )));
+        }
+        if len as u64 > MAX_CHUNK_BYTES {
+            return Err(anyhow::Error::msg(format!(
+                "replay chunk at frame {} is {} bytes (at most {})",
+                first, len, MAX_CHUNK_BYTES
+            )));
+        }
+        Ok(Some((first, count, len)))
+    }
+
+    // Records of the chunk whose header was just read; they must be exactly
+    // frames `first..first + count`
+    fn read_chunk(&mut self, first: u64, count: u32, len: u32) -> Result<Vec<FrameRecord>> {
+        let bad =
+            |what: &str| anyhow::Error::msg(format!("replay chunk at frame {} {}", first, what));
+        let mut payload = Vec::new();
+        (&mut self.input)
+            .take(len as u64)
+            .read_to_end(&mut payload)?;
+        if payload.len() != len as usize {
+            return Err(bad("is cut short"));
+        }
+        if self.header.compressed {
+            let mut text = Vec::new();
+            flate2::read::DeflateDecoder::new(&payload[..])
+                .take(MAX_CHUNK_BYTES + 1)
+                .read_to_end(&mut text)
+                .map_err(|e| bad(&format!("does not inflate: {}", e)))?;
+            if text.len() as u64 > MAX_CHUNK_BYTES {
+                return Err(bad("inflates past the size limit"));
+            }
+            payload = text;
+        }
+        let mut records: Vec<FrameRecord> = Vec::new();
+        for line in payload.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+            let record = serde_json::from_slice(line).map_err(|e| bad(&e.to_string()))?;
+            records.push(record);
+        }
+        let frames = records.iter().map(|r| r.frame);
+        if records.len() != count as usize || !frames.eq(first..first + count as u64) {
+            return Err(bad("does not hold the frames its header lists"));
+        }
+        Ok(records)
+    }
+}
+
+fn read_u32(r: &mut impl Read) -> Result<u32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn record(frame: u64, key: u32) -> FrameRecord {
+        let mut input = FrameInput::default();
+        if key != 0 {
+            input.keys.push(key);
+        }
+        input.text = Some(format!("\t{}\n", frame));
+        input.mouse_ui = Some([frame as f32, 2.0]);
+        FrameRecord {
+            frame,
+            dt: 1.0 / 60.0,
+            input,
+            prev: None,
+            hashes: vec![("transforms".into(), frame), ("camera".into(), 7)],
+        }
+    }
+
+    fn header(compressed: bool) -> ReplayHeader {
+        ReplayHeader {
+            api_version: crate::api::API_VERSION,
+            seed: 42,
+            timestep: 1.0 / 60.0,
+            script_hash: 9,
+            compressed,
+            keyframe_interval: 4,
+        }
+    }
+
+    // Ten frames in chunks of four; key 5 is held on even frames
+    fn recording(compressed: bool) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut w = ReplayWriter::new(&mut buf, header(compressed)).unwrap();
+        for f in 0..10 {
+            w.push(record(f, if f.is_multiple_of(2) { 5 } else { 0 }))
+                .unwrap();
+        }
+        drop(w);
+        buf
+    }
+
+    fn read_all(buf: &[u8]) -> Result<Vec<FrameRecord>> {
+        let mut r = ReplayReader::new(Cursor::new(buf))?;
+        let mut frames = Vec::new();
+        while let Some(rec) = r.next_frame()? {
+            frames.push(rec);
+        }
+        Ok(frames)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for compressed in [false, true] {
+            let buf = recording(compressed);
+            let r = ReplayReader::new(Cursor::new(&buf)).unwrap();
+            assert_eq!(r.header(), &header(compressed));
+            let frames = read_all(&buf).unwrap();
+            assert_eq!(frames.len(), 10);
+            assert_eq!(frames[9].input.text.as_deref(), Some("\t9\n"));
+            // Chunks open on keyframes carrying the previous input
+            assert!(frames[4].prev.as_ref().unwrap().keys.is_empty());
+            assert!(frames[5].prev.is_none());
+        }
+    }
+
+    #[test]
+    fn test_seek_keeps_recorded_edges() {
+        for compressed in [false, true] {
+            let buf = recording(compressed);
+            let mut r = ReplayReader::new(Cursor::new(&buf)).unwrap();
+            r.seek(6).unwrap();
+            let rec = r.next_frame().unwrap().unwrap();
+            assert_eq!(rec.frame, 6);
+            let snap = rec.input.to_snapshot(rec.prev.as_ref());
+            assert!(snap.keys[&5] && snap.prev_keys.is_empty());
+            assert_eq!(snap.mouse_ui, Some([6.0, 2.0]));
+            r.seek(9).unwrap();
+            assert_eq!(r.next_frame().unwrap().unwrap().frame, 9);
+            assert!(r.next_frame().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_compare_reports_changed_subsystems() {
+        let recorded = record(3, 0).hashes;
+        assert!(compare(3, &recorded, &recorded).is_none());
+        let replayed = vec![("transforms".to_string(), 3), ("camera".to_string(), 8)];
+        let d = compare(3, &recorded, &replayed).unwrap();
+        assert_eq!(d.subsystems, vec![("camera".to_string(), Some(7), Some(8))]);
+        assert!(d
+            .to_string()
+            .starts_with("replay diverged at frame 3: camera"));
+        // Subsystems added since the recording was made are not checked
+        let mut newer = recorded.clone();
+        newer.push(("script".to_string(), 1));
+        assert!(compare(3, &recorded, &newer).is_none());
+        assert!(compare(3, &newer, &recorded).is_some());
+    }
+
+    // Offset of the first chunk header
+    fn body_start(buf: &[u8]) -> usize {
+        16 + u32::from_le_bytes(buf[12..16].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn test_truncated_recording_is_an_error() {
+        for compressed in [false, true] {
+            let buf = recording(compressed);
+            let body = body_start(&buf);
+            // Inside the header, a chunk header and a chunk payload
+            for cut in [10, body - 1, body + 8, buf.len() - 1] {
+                assert!(read_all(&buf[..cut]).is_err(), "cut at {}", cut);
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupt_recording_is_an_error() {
+        for compressed in [false, true] {
+            let mut buf = recording(compressed);
+            let body = body_start(&buf);
+            // Scramble the first chunk's payload
+            for b in &mut buf[body + 16..body + 40] {
+                *b ^= 0x5a;
+            }
+            assert!(read_all(&buf).is_err());
+        }
+        assert!(ReplayReader::new(Cursor::new(b"H 1\tK \n".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_version_is_an_error() {
+        for version in [0, FORMAT_VERSION + 1] {
+            let mut buf = recording(false);
+            buf[8..12].copy_from_slice(&u32::to_le_bytes(version));
+            let err = ReplayReader::new(Cursor::new(&buf)).err().unwrap();
+            assert!(
+                err.to_string().contains("unsupported replay format"),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_lengths_and_frame_ranges_are_errors() {
+        let buf = recording(false);
+        let body = body_start(&buf);
+        let patched = |at: usize, bytes: &[u8]| {
+            let mut b = buf.clone();
+            b[at..at + bytes.len()].copy_from_slice(bytes);
+            read_all(&b)
+        };
+        // Header and chunk lengths past the limits
+        assert!(patched(12, &u32::MAX.to_le_bytes()).is_err());
+        assert!(patched(body + 12, &u32::MAX.to_le_bytes()).is_err());
+        // Empty chunk, a chunk running past u64::MAX, and a count that disagrees
+        assert!(patched(body + 8, &0u32.to_le_bytes()).is_err());
+        assert!(patched(body, &(u64::MAX - 1).to_le_bytes()).is_err());
+        assert!(patched(body + 8, &3u32.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_actions_and_pads_survive_any_names() {
+        let mut snap = InputSnapshot::new();
+        snap.actions = Some(ActionState {
+            values: [("a|b=1".to_string(), 1.0), ("idle".to_string(), 0.0)].into(),
+        });
+        snap.pads[2] = PadState {
+            connected: true,
+            buttons: 5,
+            axes: [-0.5, 0.0, 0.0, 0.0, 0.0, 1.0],
+        };
+        let input = FrameInput::from_snapshot(&snap);
+        let json = serde_json::to_string(&input).unwrap();
+        let back: FrameInput = serde_json::from_str(&json).unwrap();
+        let replayed = back.to_snapshot(None);
+        // Released actions are not stored
+        assert_eq!(
+            replayed.actions.unwrap().values,
+            [("a|b=1".to_string(), 1.0)].into()
+        );
+        assert_eq!(replayed.pads, snap.pads);
+    }
+
+    fn sandbox() -> LuaSandbox {
+        let sandbox = LuaSandbox::new().unwrap();
+        crate::api::EngineApi::new()
+            .setup_engine_namespace(sandbox.lua())
+            .unwrap();
+        sandbox
+    }
+
+    // Hash of the table `on_hash` returns after running `build` on an empty `t`
+    fn hash_of(sandbox: &LuaSandbox, build: &str) -> Result<Option<u64>> {
+        sandbox
+            .load_script(
+                &format!("function on_hash() local t = {{}} {} return t end", build),
+                "game.lua",
+            )
+            .unwrap();
+        script_state_hash(sandbox)
+    }
+
+    #[test]
+    fn test_script_state_hash_is_optional() {
+        assert!(script_state_hash(&sandbox()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_script_state_hash_ignores_key_order() {
+        let sandbox = sandbox();
+        let a = hash_of(&sandbox, "t.hp = 3 t.pos = {1, 2.5} t[1] = 'x'").unwrap();
+        let b = hash_of(&sandbox, "t[1] = 'x' t.pos = {1, 2.5} t.hp = 3").unwrap();
+        let c = hash_of(&sandbox, "t[1] = 'x' t.pos = {1, 2.5} t.hp = 4").unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_script_state_hash_rejects_functions_and_cycles() {
+        let sandbox = sandbox();
+        let e = hash_of(&sandbox, "t.f = function() end").unwrap_err();
+        assert!(e.to_string().contains("cannot hash a function"), "{}", e);
+        let e = hash_of(&sandbox, "t.t = t").unwrap_err();
+        assert!(e.to_string().contains("cyclic table"), "{}", e);
+    }
+
+    #[test]
+    fn test_script_state_hash_caps_shared_subtables() {
+        let sandbox = sandbox();
+        // A subtable shared at every level is fine once, but 2^30 paths are not walked
+        let a = hash_of(&sandbox, "local s = {1} t.a = s t.b = s").unwrap();
+        let b = hash_of(&sandbox, "t.a = {1} t.b = {1}").unwrap();
+        assert_eq!(a, b);
+        let e = hash_of(&sandbox, "for _ = 1, 30 do t = { a = t, b = t } end").unwrap_err();
+        assert!(e.to_string().contains("table entries"), "{}", e);
+    }
+}
@@ -1,18 +1,307 @@
 use anyhow::Result;
-use mlua::{Function, Lua, Table, Value};
+use mlua::{
+    Debug, FromLuaMulti, Function, HookTriggers, IntoLuaMulti, Lua, RegistryKey, Table, Thread,
+    Value, VmState,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Rebinds math.random to the deterministic engine RNG inside a script env.
+const MATH_RANDOM_SHIM: &str = r#"
+  do
+    local r = engine.random
+    function math.random(a,b)
+      if a == nil then return r()
+      elseif b == nil then return math.floor(r()*a) + 1
+      else return math.floor(r()*(b-a+1)) + a end
+    end
+  end
+"#;
+
+// How often (in VM instructions) the budget hook runs
+const BUDGET_HOOK_INTERVAL: u32 = 1000;
+
+/// Per-callback execution limits, enforced through a Lua count hook.
+/// `None` disables that limit; the default budget is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScriptBudget {
+    pub max_instructions: Option<u64>,
+    pub max_wall_time: Option<Duration>,
+}
+
+/// A script call aborted because it exceeded its [`ScriptBudget`].
+/// Returned (wrapped in `anyhow::Error`) from `call_function` and the load/reload paths.
+#[derive(Debug, Clone)]
+pub struct ScriptTimeout {
+    /// Callback or chunk the engine was running (e.g. "on_update")
+    pub callback: String,
+    /// Innermost Lua function executing when the budget ran out
+    pub function: String,
+    /// "chunk:line" of the offending instruction
+    pub location: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ScriptTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Script timeout in {}: {} at {} (in {})",
+            self.callback, self.reason, self.location, self.function
+        )
+    }
+}
+
+impl std::error::Error for ScriptTimeout {}
+
+/// Lua collector mode; parameters follow `collectgarbage("incremental"/"generational")`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GcMode {
+    Incremental {
+        pause: i32,
+        step_multiplier: i32,
+        step_size: i32,
+    },
+    Generational {
+        minor_multiplier: i32,
+        major_multiplier: i32,
+    },
+}
+
+/// Collector tuning plus the explicit step the host runs once per frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GcConfig {
+    pub mode: GcMode,
+    /// Kilobytes of work for the per-frame `gc_frame_step`; 0 only samples the heap
+    pub step_kb: i32,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        // Lua 5.4 incremental defaults
+        Self {
+            mode: GcMode::Incremental {
+                pause: 200,
+                step_multiplier: 100,
+                step_size: 13,
+            },
+            step_kb: 16,
+        }
+    }
+}
+
+/// Result of one `gc_frame_step`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcSample {
+    pub step_time: Duration,
+    pub used_bytes: usize,
+}
+
+/// Budget bookkeeping shared with the count hook
+#[derive(Default)]
+struct BudgetState {
+    budget: ScriptBudget,
+    // Callback label and start time while a guarded call is running
+    armed: Option<(String, Instant)>,
+    instructions: u64,
+    tripped: Option<ScriptTimeout>,
+}
+
+// Lua app data so coroutine resumes can find the budget
+struct SharedBudget(Rc<RefCell<BudgetState>>);
+
+fn budget_triggers() -> HookTriggers {
+    HookTriggers::new().every_nth_instruction(BUDGET_HOOK_INTERVAL)
+}
+
+// Count hook that trips the budget of the armed guarded call
+fn budget_hook(
+    state: Rc<RefCell<BudgetState>>,
+) -> impl Fn(&Lua, Debug) -> mlua::Result<VmState> + 'static {
+    move |_lua, debug| {
+        let mut st = state.borrow_mut();
+        let elapsed = match &st.armed {
+            Some((_, start)) => start.elapsed(),
+            None => return Ok(VmState::Continue),
+        };
+        st.instructions += BUDGET_HOOK_INTERVAL as u64;
+        if st.tripped.is_none() {
+            let reason = match (st.budget.max_instructions, st.budget.max_wall_time) {
+                (Some(max), _) if st.instructions > max => {
+                    Some(format!("exceeded {} instructions", max))
+                }
+                (_, Some(max)) if elapsed > max => Some(format!("exceeded {:?} wall time", max)),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                let src = debug.source();
+                let chunk = src.short_src.as_deref().unwrap_or("?").to_string();
+                let function = match debug.names().name {
+                    Some(name) => format!("function '{}'", name),
+                    None if src.what == "main" => "main chunk".to_string(),
+                    None => {
+                        format!("function <{}:{}>", chunk, src.line_defined.unwrap_or(0))
+                    }
+                };
+                let callback = st
+                    .armed
+                    .as_ref()
+                    .map(|(l, _)| l.clone())
+                    .unwrap_or_default();
+                st.tripped = Some(ScriptTimeout {
+                    callback,
+                    function,
+                    location: format!("{}:{}", chunk, debug.curr_line()),
+                    reason,
+                });
+            }
+        }
+        match &st.tripped {
+            Some(t) => Err(mlua::Error::RuntimeError(t.to_string())),
+            None => Ok(VmState::Continue),
+        }
+    }
+}
+
+/// Resume `thread` under the script budget. mlua runs the count hook on one Lua
+/// thread at a time and coroutines do not inherit it, so the hook moves onto
+/// `thread` for the resume and back to the calling thread afterwards.
+pub fn resume_budgeted<R: FromLuaMulti>(
+    lua: &Lua,
+    thread: &Thread,
+    args: impl IntoLuaMulti,
+) -> mlua::Result<R> {
+    let state = lua.app_data_ref::<SharedBudget>().map(|b| b.0.clone());
+    let Some(state) = state.filter(|s| s.borrow().budget != ScriptBudget::default()) else {
+        return thread.resume(args);
+    };
+    thread.set_hook(budget_triggers(), budget_hook(state.clone()));
+    let result = thread.resume(args);
+    lua.set_hook(budget_triggers(), budget_hook(state));
+    result
+}
+
+/// A module loaded through the sandboxed `require`
+struct LoadedModule {
+    path: PathBuf,
+    value: RegistryKey,
+    source_hash: u64,
+}
+
+/// Module cache and loader state shared with the `require` closure
+#[derive(Default)]
+struct ModuleRegistry {
+    root: Option<PathBuf>,
+    loaded: HashMap<String, LoadedModule>,
+    // Stack of modules currently executing, used for cycle detection
+    loading: Vec<String>,
+    // Name of the top-level script being executed (load_script/reload_script)
+    current_script: Option<String>,
+    // module -> names of modules/scripts that required it
+    dependents: HashMap<String, BTreeSet<String>>,
+}
+
+impl ModuleRegistry {
+    /// Drop every edge recorded for `requirer` before it re-executes.
+    fn forget_requirer(&mut self, requirer: &str) {
+        for set in self.dependents.values_mut() {
+            set.remove(requirer);
+        }
+    }
+}
 
 pub struct LuaSandbox {
     lua: Lua,
+    modules: Rc<RefCell<ModuleRegistry>>,
+    budget: Rc<RefCell<BudgetState>>,
+    gc: RefCell<GcConfig>,
 }
 
 impl LuaSandbox {
     pub fn new() -> Result<Self> {
         let lua = Lua::new();
-        let sandbox = Self { lua };
+        let budget = Rc::new(RefCell::new(BudgetState::default()));
+        lua.set_app_data(SharedBudget(budget.clone()));
+        let sandbox = Self {
+            lua,
+            modules: Rc::new(RefCell::new(ModuleRegistry::default())),
+            budget,
+            gc: RefCell::new(GcConfig::default()),
+        };
         sandbox.setup_safe_environment()?;
         Ok(sandbox)
     }
 
+    /// Set the directory `require` resolves modules against (e.g. "scripts").
+    /// `require("enemies.slime")` then maps to `<root>/enemies/slime.lua`.
+    pub fn set_script_root(&self, root: impl AsRef<Path>) -> Result<()> {
+        let root = root.as_ref().canonicalize().map_err(|e| {
+            anyhow::Error::msg(format!(
+                "Invalid script root {}: {}",
+                root.as_ref().display(),
+                e
+            ))
+        })?;
+        self.modules.borrow_mut().root = Some(root);
+        Ok(())
+    }
+
+    /// Limit how long a single engine-invoked script call may run. A call that goes
+    /// over is aborted with a [`ScriptTimeout`]; the hook keeps failing until the
+    /// call has fully unwound.
+    pub fn set_budget(&self, budget: ScriptBudget) {
+        self.budget.borrow_mut().budget = budget;
+        if budget == ScriptBudget::default() {
+            self.lua.remove_hook();
+            return;
+        }
+        self.lua
+            .set_hook(budget_triggers(), budget_hook(self.budget.clone()));
+    }
+
+    pub fn budget(&self) -> ScriptBudget {
+        self.budget.borrow().budget
+    }
+
+    /// True once the running guarded call has blown its budget. Every Lua call made
+    /// before it unwinds fails too, so callers looping over scripts should stop.
+    pub fn over_budget(&self) -> bool {
+        self.budget.borrow().tripped.is_some()
+    }
+
+    /// Run `f` under the script budget. The outer error is a [`ScriptTimeout`];
+    /// the inner result is whatever the call itself returned. Nested calls share
+    /// the outermost budget window.
+    pub fn guarded<R>(
+        &self,
+        label: &str,
+        f: impl FnOnce() -> mlua::Result<R>,
+    ) -> Result<mlua::Result<R>> {
+        {
+            let mut st = self.budget.borrow_mut();
+            if st.armed.is_some() {
+                drop(st);
+                return Ok(f());
+            }
+            st.armed = Some((label.to_string(), Instant::now()));
+            st.instructions = 0;
+            st.tripped = None;
+        }
+        let result = f();
+        let tripped = {
+            let mut st = self.budget.borrow_mut();
+            st.armed = None;
+            st.tripped.take()
+        };
+        match tripped {
+            Some(t) => Err(anyhow::Error::new(t)),
+            None => Ok(result),
+        }
+    }
+
     fn setup_safe_environment(&self) -> Result<()> {
         let globals = self.lua.globals();
 
@@ -53,6 +342,16 @@ impl LuaSandbox {
         // Lock package system on globals (affects any accidental access)
         self.lock_package_system(&globals)?;
 
+        // Scripts reach the sandboxed require through safe_base
+        if let Ok(require) = globals.get::<Function>("require") {
+            if let Err(e) = safe.set("require", require) {
+                return Err(anyhow::Error::msg(format!(
+                    "safe.set require failed: {}",
+                    e
+                )));
+            }
+        }
+
         // Store safe_base for use when loading scripts
         if let Err(e) = self.lua.set_named_registry_value("safe_base", safe) {
             return Err(anyhow::Error::msg(format!(
@@ -96,24 +395,20 @@ impl LuaSandbox {
             )));
         }
 
-        // Replace require with our controlled version
-        let controlled_require =
-            match self
-                .lua
-                .create_function(|_lua, module_name: String| -> mlua::Result<()> {
-                    Err(mlua::Error::RuntimeError(format!(
-                        "Module loading disabled in sandbox: {}",
-                        module_name
-                    )))
-                }) {
-                Ok(func) => func,
-                Err(e) => {
-                    return Err(anyhow::Error::msg(format!(
-                        "Failed to create require function: {}",
-                        e
-                    )))
-                }
-            };
+        // Replace require with our controlled version: resolves only under the
+        // script root, loads into a fresh safe env, caches and detects cycles.
+        let modules = self.modules.clone();
+        let controlled_require = match self.lua.create_function(move |lua, module_name: String| {
+            require_module(lua, &modules, &module_name)
+        }) {
+            Ok(func) => func,
+            Err(e) => {
+                return Err(anyhow::Error::msg(format!(
+                    "Failed to create require function: {}",
+                    e
+                )))
+            }
+        };
 
         if let Err(e) = globals.set("require", controlled_require) {
             return Err(anyhow::Error::msg(format!(
@@ -126,52 +421,14 @@ impl LuaSandbox {
     }
 
     pub fn load_script(&self, script_content: &str, script_name: &str) -> Result<()> {
-        let safe_base: Table = self
-            .lua
-            .named_registry_value("safe_base")
-            .map_err(|e| anyhow::Error::msg(format!("get safe_base failed: {}", e)))?;
-
         // Create a fresh environment whose __index points to safe_base
-        let env = self
-            .lua
-            .create_table()
-            .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
-        let mt = self
-            .lua
-            .create_table()
-            .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
-        mt.set("__index", safe_base)
-            .map_err(|e| anyhow::Error::msg(format!("set __index failed: {}", e)))?;
-        env.set_metatable(Some(mt));
-
-        // Inject engine into env if present
-        if let Ok(engine_tbl) = self.lua.globals().get::<Table>("engine") {
-            env.set("engine", engine_tbl)
-                .map_err(|e| anyhow::Error::msg(format!("env.set engine failed: {}", e)))?;
-        }
-
-        // Inject math.random shim to use the deterministic engine RNG.
-        let math_random_shim = r#"
-  do
-    local r = engine.random
-    function math.random(a,b)
-      if a == nil then return r()
-      elseif b == nil then return math.floor(r()*a) + 1
-      else return math.floor(r()*(b-a+1)) + a end
-    end
-  end
-"#;
-        self.lua
-            .load(math_random_shim)
-            .set_name("math.random shim")
-            .set_environment(env.clone())
-            .exec()
-            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let env = build_env(&self.lua)?;
+        self.begin_script(script_name);
 
         // Load and run chunk in this environment
         let chunk = self.lua.load(script_content).set_name(script_name);
         let chunk = chunk.set_environment(env.clone());
-        chunk.exec().map_err(|e| {
+        self.guarded(script_name, || chunk.exec())?.map_err(|e| {
             anyhow::Error::msg(format!("Failed to load script {}: {}", script_name, e))
         })?;
 
@@ -189,59 +446,35 @@ impl LuaSandbox {
             .named_registry_value("current_env")
             .map_err(|e| anyhow::Error::msg(format!("get current_env failed: {}", e)))?;
 
+        // Patch edited modules in place first so the script's require calls see new code
+        for name in self.changed_modules() {
+            if let Err(e) = self.reload_module(&name) {
+                tracing::error!("Module reload failed: {}", e);
+            }
+        }
+
         // Build new env from safe_base
-        let safe_base: Table = self
-            .lua
-            .named_registry_value("safe_base")
-            .map_err(|e| anyhow::Error::msg(format!("get safe_base failed: {}", e)))?;
-        let env = self
-            .lua
-            .create_table()
-            .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
-        let mt = self
-            .lua
-            .create_table()
-            .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
-        mt.set("__index", safe_base)
-            .map_err(|e| anyhow::Error::msg(format!("set __index failed: {}", e)))?;
-        env.set_metatable(Some(mt));
-        if let Ok(engine_tbl) = self.lua.globals().get::<Table>("engine") {
-            env.set("engine", engine_tbl)
-                .map_err(|e| anyhow::Error::msg(format!("env.set engine failed: {}", e)))?;
-        }
-
-        // Inject math.random shim to use the deterministic engine RNG.
-        let math_random_shim = r#"
-  do
-    local r = engine.random
-    function math.random(a,b)
-      if a == nil then return r()
-      elseif b == nil then return math.floor(r()*a) + 1
-      else return math.floor(r()*(b-a+1)) + a end
-    end
-  end
-"#;
-        self.lua
-            .load(math_random_shim)
-            .set_name("math.random shim")
-            .set_environment(env.clone())
-            .exec()
-            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        let env = build_env(&self.lua)?;
+        self.begin_script(script_name);
 
         // Load script into new env
         let chunk = self.lua.load(script_content).set_name(script_name);
         let chunk = chunk.set_environment(env.clone());
-        chunk.exec().map_err(|e| {
+        self.guarded(script_name, || chunk.exec())?.map_err(|e| {
             anyhow::Error::msg(format!("Failed to load script {}: {}", script_name, e))
         })?;
 
         // Call new env's on_start() first to (re)initialize arrays/tables
         if let Ok(on_start) = env.get::<mlua::Function>("on_start") {
-            let _ = on_start.call::<()>(());
+            if let Err(e) = self.guarded("on_start", || on_start.call::<()>(()))? {
+                tracing::warn!("on_start failed after reloading {}: {}", script_name, e);
+            }
         }
         // Then allow state migration via on_reload(old_env)
         if let Ok(on_reload) = env.get::<mlua::Function>("on_reload") {
-            let _ = on_reload.call::<()>(old_env);
+            if let Err(e) = self.guarded("on_reload", || on_reload.call::<()>(old_env))? {
+                tracing::warn!("on_reload failed for {}: {}", script_name, e);
+            }
         }
 
         // Swap current env
@@ -252,6 +485,114 @@ impl LuaSandbox {
         Ok(())
     }
 
+    // Top-level requires made while the script runs are attributed to it
+    fn begin_script(&self, script_name: &str) {
+        let mut reg = self.modules.borrow_mut();
+        reg.forget_requirer(script_name);
+        reg.current_script = Some(script_name.to_string());
+    }
+
+    /// Names of cached modules whose file contents differ from what was loaded.
+    pub fn changed_modules(&self) -> Vec<String> {
+        let reg = self.modules.borrow();
+        let mut names: Vec<String> = reg
+            .loaded
+            .iter()
+            .filter(|(_, m)| match std::fs::read_to_string(&m.path) {
+                Ok(src) => hash_source(&src) != m.source_hash,
+                Err(_) => false,
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Re-execute a cached module from disk and patch its exports in place.
+    ///
+    /// If both the old and new exports are tables, the old table keeps its identity
+    /// (so modules that already required it see the new code) and receives the new
+    /// fields; the new version's `on_reload(old)` is then called with a shallow copy
+    /// of the previous contents for state migration.
+    pub fn reload_module(&self, module_name: &str) -> Result<()> {
+        let (path, old_value) = {
+            let reg = self.modules.borrow();
+            let m = reg.loaded.get(module_name).ok_or_else(|| {
+                anyhow::Error::msg(format!("Module '{}' is not loaded", module_name))
+            })?;
+            let old: Value = self
+                .lua
+                .registry_value(&m.value)
+                .map_err(|e| anyhow::Error::msg(format!("get module value failed: {}", e)))?;
+            (m.path.clone(), old)
+        };
+        let src = std::fs::read_to_string(&path).map_err(|e| {
+            anyhow::Error::msg(format!("Failed to read module {}: {}", path.display(), e))
+        })?;
+        let new_value = self
+            .guarded(module_name, || {
+                execute_module(&self.lua, &self.modules, module_name, &path, &src)
+            })?
+            .map_err(|e| {
+                anyhow::Error::msg(format!("Failed to reload module {}: {}", module_name, e))
+            })?;
+
+        let value = match (&old_value, &new_value) {
+            (Value::Table(old), Value::Table(new)) => {
+                let snapshot = patch_table_in_place(&self.lua, old, new)
+                    .map_err(|e| anyhow::Error::msg(format!("patch module failed: {}", e)))?;
+                if let Ok(on_reload) = old.raw_get::<Function>("on_reload") {
+                    if let Err(e) = self.guarded(module_name, || on_reload.call::<()>(snapshot))? {
+                        tracing::warn!("on_reload failed for module {}: {}", module_name, e);
+                    }
+                }
+                old_value.clone()
+            }
+            _ => new_value,
+        };
+
+        let key = self
+            .lua
+            .create_registry_value(value)
+            .map_err(|e| anyhow::Error::msg(format!("create_registry_value failed: {}", e)))?;
+        let mut reg = self.modules.borrow_mut();
+        if let Some(m) = reg.loaded.get_mut(module_name) {
+            m.value = key;
+            m.source_hash = hash_source(&src);
+        }
+        tracing::info!("Module reloaded: {}", module_name);
+        Ok(())
+    }
+
+    /// Modules (or the top-level script name) that directly `require`d `module_name`.
+    pub fn module_dependents(&self, module_name: &str) -> Vec<String> {
+        let reg = self.modules.borrow();
+        match reg.dependents.get(module_name) {
+            Some(set) => set.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Cached module names and their resolved file paths.
+    pub fn loaded_modules(&self) -> Vec<(String, PathBuf)> {
+        let reg = self.modules.borrow();
+        let mut out: Vec<(String, PathBuf)> = reg
+            .loaded
+            .iter()
+            .map(|(name, m)| (name.clone(), m.path.clone()))
+            .collect();
+        out.sort();
+        out
+    }
+
+    /// Whether the current script defines `func_name` (e.g. an optional on_start)
+    pub fn has_function(&self, func_name: &str) -> bool {
+        self.lua
+            .named_registry_value::<Table>("current_env")
+            .and_then(|env| env.get::<Option<Function>>(func_name))
+            .is_ok_and(|f| f.is_some())
+    }
+
     pub fn call_function<A, R>(&self, func_name: &str, args: A) -> Result<R>
     where
         A: mlua::IntoLuaMulti,
@@ -266,7 +607,7 @@ impl LuaSandbox {
             .get(func_name)
             .map_err(|e| anyhow::anyhow!("Function '{}' not found: {}", func_name, e))?;
 
-        match func.call(args) {
+        match self.guarded(func_name, || func.call(args))? {
             Ok(result) => Ok(result),
             Err(e) => Err(anyhow::Error::msg(format!(
                 "Error calling function '{}': {}",
@@ -311,4 +652,430 @@ impl LuaSandbox {
         // Get Lua memory usage in MB
         self.lua.used_memory() as f64 / 1024.0 / 1024.0
     }
+
+    /// Cap the Lua heap at `bytes` (0 = unlimited). Allocations past the cap fail
+    /// with a Lua memory error in the offending script call instead of aborting.
+    pub fn set_memory_limit(&self, bytes: usize) -> Result<()> {
+        self.lua
+            .set_memory_limit(bytes)
+            .map_err(|e| anyhow::Error::msg(format!("set_memory_limit failed: {}", e)))?;
+        Ok(())
+    }
+
+    pub fn set_gc_config(&self, config: GcConfig) {
+        match config.mode {
+            GcMode::Incremental {
+                pause,
+                step_multiplier,
+                step_size,
+            } => {
+                self.lua.gc_inc(pause, step_multiplier, step_size);
+            }
+            GcMode::Generational {
+                minor_multiplier,
+                major_multiplier,
+            } => {
+                self.lua.gc_gen(minor_multiplier, major_multiplier);
+            }
+        }
+        *self.gc.borrow_mut() = config;
+    }
+
+    pub fn gc_config(&self) -> GcConfig {
+        *self.gc.borrow()
+    }
+
+    /// Run the configured per-frame collector step and sample the heap size.
+    pub fn gc_frame_step(&self) -> Result<GcSample> {
+        let step_kb = self.gc.borrow().step_kb;
+        let start = Instant::now();
+        if step_kb > 0 {
+            self.lua
+                .gc_step_kbytes(step_kb)
+                .map_err(|e| anyhow::Error::msg(format!("gc step failed: {}", e)))?;
+        }
+        Ok(GcSample {
+            step_time: start.elapsed(),
+            used_bytes: self.lua.used_memory(),
+        })
+    }
+}
+
+/// Build a fresh script environment: `__index` → safe_base, plus `engine` and the
+/// deterministic math.random shim.
+fn build_env(lua: &Lua) -> Result<Table> {
+    let safe_base: Table = lua
+        .named_registry_value("safe_base")
+        .map_err(|e| anyhow::Error::msg(format!("get safe_base failed: {}", e)))?;
+    let env = lua
+        .create_table()
+        .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
+    let mt = lua
+        .create_table()
+        .map_err(|e| anyhow::Error::msg(format!("create_table failed: {}", e)))?;
+    mt.set("__index", safe_base)
+        .map_err(|e| anyhow::Error::msg(format!("set __index failed: {}", e)))?;
+    env.set_metatable(Some(mt));
+
+    // Inject engine into env if present
+    if let Ok(engine_tbl) = lua.globals().get::<Table>("engine") {
+        env.set("engine", engine_tbl)
+            .map_err(|e| anyhow::Error::msg(format!("env.set engine failed: {}", e)))?;
+    }
+
+    // Inject math.random shim to use the deterministic engine RNG.
+    lua.load(MATH_RANDOM_SHIM)
+        .set_name("math.random shim")
+        .set_environment(env.clone())
+        .exec()
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    Ok(env)
+}
+
+/// Map a dotted module name to a file under `root`, rejecting anything that could
+/// escape it. Tries `a/b.lua` first, then `a/b/init.lua`.
+fn resolve_module_path(root: &Path, module_name: &str) -> std::result::Result<PathBuf, String> {
+    let valid_segment = |seg: &str| {
+        !seg.is_empty()
+            && seg
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if !module_name.split('.').all(valid_segment) {
+        return Err(format!("invalid module name '{}'", module_name));
+    }
+    let mut base = root.to_path_buf();
+    for seg in module_name.split('.') {
+        base.push(seg);
+    }
+    let candidates = [base.with_extension("lua"), base.join("init.lua")];
+    for candidate in candidates.iter() {
+        if let Ok(canon) = candidate.canonicalize() {
+            // Symlinks may still point outside the root
+            if !canon.starts_with(root) {
+                return Err(format!(
+                    "module '{}' resolves outside the script root",
+                    module_name
+                ));
+            }
+            if canon.is_file() {
+                return Ok(canon);
+            }
+        }
+    }
+    Err(format!(
+        "module '{}' not found under {}",
+        module_name,
+        root.display()
+    ))
+}
+
+fn hash_source(src: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    src.hash(&mut h);
+    h.finish()
+}
+
+/// Run a module chunk in its own safe env and return its exports (`true` if it returns nothing).
+fn execute_module(
+    lua: &Lua,
+    modules: &Rc<RefCell<ModuleRegistry>>,
+    module_name: &str,
+    path: &Path,
+    src: &str,
+) -> mlua::Result<Value> {
+    let env = build_env(lua).map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+    let chunk_name = {
+        let reg = modules.borrow();
+        match reg.root.as_deref().and_then(|r| path.strip_prefix(r).ok()) {
+            Some(rel) => rel.display().to_string(),
+            None => path.display().to_string(),
+        }
+    };
+
+    {
+        let mut reg = modules.borrow_mut();
+        reg.forget_requirer(module_name);
+        reg.loading.push(module_name.to_string());
+    }
+    let result = lua
+        .load(src)
+        .set_name(chunk_name)
+        .set_environment(env)
+        .call::<Value>(());
+    modules.borrow_mut().loading.pop();
+
+    match result? {
+        Value::Nil => Ok(Value::Boolean(true)),
+        v => Ok(v),
+    }
+}
+
+fn require_module(
+    lua: &Lua,
+    modules: &Rc<RefCell<ModuleRegistry>>,
+    module_name: &str,
+) -> mlua::Result<Value> {
+    let path = {
+        let mut reg = modules.borrow_mut();
+        let requirer = reg
+            .loading
+            .last()
+            .cloned()
+            .or_else(|| reg.current_script.clone());
+        if let Some(requirer) = requirer {
+            reg.dependents
+                .entry(module_name.to_string())
+                .or_default()
+                .insert(requirer);
+        }
+        if let Some(m) = reg.loaded.get(module_name) {
+            return lua.registry_value(&m.value);
+        }
+        if reg.loading.iter().any(|n| n == module_name) {
+            let mut chain = reg.loading.clone();
+            chain.push(module_name.to_string());
+            return Err(mlua::Error::RuntimeError(format!(
+                "circular require: {}",
+                chain.join(" -> ")
+            )));
+        }
+        let root = reg.root.as_deref().ok_or_else(|| {
+            mlua::Error::RuntimeError(format!(
+                "Module loading disabled in sandbox: {}",
+                module_name
+            ))
+        })?;
+        resolve_module_path(root, module_name).map_err(mlua::Error::RuntimeError)?
+    };
+
+    let src = std::fs::read_to_string(&path)
+        .map_err(|e| mlua::Error::RuntimeError(format!("require '{}': {}", module_name, e)))?;
+    let value = execute_module(lua, modules, module_name, &path, &src)?;
+    let key = lua.create_registry_value(value.clone())?;
+    modules.borrow_mut().loaded.insert(
+        module_name.to_string(),
+        LoadedModule {
+            path,
+            value: key,
+            source_hash: hash_source(&src),
+        },
+    );
+    Ok(value)
+}
+
+/// Copy every field of `new` into `old` and drop keys `new` no longer defines.
+/// Returns a shallow copy of `old` taken before patching.
+fn patch_table_in_place(lua: &Lua, old: &Table, new: &Table) -> mlua::Result<Table> {
+    let snapshot = lua.create_table()?;
+    let mut stale = Vec::new();
+    for pair in old.pairs::<Value, Value>() {
+        let (k, v) = pair?;
+        if new.raw_get::<Value>(k.clone())?.is_nil() {
+            stale.push(k.clone());
+        }
+        snapshot.raw_set(k, v)?;
+    }
+    for k in stale {
+        old.raw_set(k, Value::Nil)?;
+    }
+    for pair in new.pairs::<Value, Value>() {
+        let (k, v) = pair?;
+        old.raw_set(k, v)?;
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script_dir(tag: &str, files: &[(&str, &str)]) -> PathBuf {
+        let base =
+            std::env::temp_dir().join(format!("luarite_sandbox_{}_{}", tag, std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        let dir = base.join("scripts");
+        for (rel, src) in files {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        }
+        dir
+    }
+
+    fn sandbox_with_engine() -> LuaSandbox {
+        let sandbox = LuaSandbox::new().unwrap();
+        crate::api::EngineApi::new()
+            .setup_engine_namespace(sandbox.lua())
+            .unwrap();
+        sandbox
+    }
+
+    #[test]
+    fn test_require_resolves_and_caches() {
+        let dir = script_dir(
+            "cache",
+            &[
+                (
+                    "enemies/slime.lua",
+                    "loads = (loads or 0) + 1\nreturn { hp = 3 }",
+                ),
+                (
+                    "util/init.lua",
+                    "return { twice = function(x) return x * 2 end }",
+                ),
+            ],
+        );
+        let sandbox = sandbox_with_engine();
+        sandbox.set_script_root(&dir).unwrap();
+        sandbox
+            .load_script(
+                r#"
+                local a = require("enemies.slime")
+                local b = require("enemies.slime")
+                same = a == b
+                hp = a.hp
+                doubled = require("util").twice(21)
+                "#,
+                "main.lua",
+            )
+            .unwrap();
+        let env: Table = sandbox.lua().named_registry_value("current_env").unwrap();
+        assert!(env.get::<bool>("same").unwrap());
+        assert_eq!(env.get::<i64>("hp").unwrap(), 3);
+        assert_eq!(env.get::<i64>("doubled").unwrap(), 42);
+        assert_eq!(sandbox.loaded_modules().len(), 2);
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_require_rejects_traversal_and_cycles() {
+        let dir = script_dir(
+            "reject",
+            &[
+                ("a.lua", "return require('b')"),
+                ("b.lua", "return require('a')"),
+            ],
+        );
+        std::fs::write(dir.parent().unwrap().join("secret.lua"), "return 1").unwrap();
+        let sandbox = sandbox_with_engine();
+        sandbox.set_script_root(&dir).unwrap();
+
+        for bad in ["..secret", "../secret", "a/b", ""] {
+            let src = format!("require({:?})", bad);
+            let err = sandbox
+                .load_script(&src, "main.lua")
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("invalid module name"), "{}: {}", bad, err);
+        }
+        let err = sandbox
+            .load_script("require('a')", "main.lua")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("circular require: a -> b -> a"), "{}", err);
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_budget_aborts_runaway_callback() {
+        let sandbox = sandbox_with_engine();
+        sandbox.set_budget(ScriptBudget {
+            max_instructions: Some(100_000),
+            max_wall_time: None,
+        });
+        sandbox
+            .load_script(
+                "local function spin()\n  while true do end\nend\nfunction on_update()\n  spin()\nend\nfunction ok() return 7 end",
+                "main.lua",
+            )
+            .unwrap();
+        let err = sandbox
+            .call_function::<(), ()>("on_update", ())
+            .unwrap_err();
+        let timeout = err.downcast_ref::<ScriptTimeout>().expect("ScriptTimeout");
+        assert_eq!(timeout.callback, "on_update");
+        assert_eq!(timeout.function, "function 'spin'");
+        assert_eq!(timeout.location, "[string \"main.lua\"]:2");
+        // The sandbox stays usable after the aborted call
+        assert_eq!(sandbox.call_function::<(), i64>("ok", ()).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_memory_limit_fails_script_not_host() {
+        let sandbox = sandbox_with_engine();
+        sandbox
+            .load_script(
+                "function grow()\n  local t = {}\n  for i = 1, 1e7 do t[i] = string.rep('x', 64) .. i end\nend",
+                "main.lua",
+            )
+            .unwrap();
+        sandbox
+            .set_memory_limit(sandbox.lua().used_memory() + 4 * 1024 * 1024)
+            .unwrap();
+        let err = sandbox
+            .call_function::<(), ()>("grow", ())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("memory"), "{}", err);
+        let sample = sandbox.gc_frame_step().unwrap();
+        assert!(sample.used_bytes > 0);
+    }
+
+    #[test]
+    fn test_require_disabled_without_root() {
+        let sandbox = sandbox_with_engine();
+        let err = sandbox
+            .load_script("require('x')", "main.lua")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("Module loading disabled in sandbox"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_reload_script_patches_modules_in_place() {
+        let dir = script_dir(
+            "reload",
+            &[(
+                "m.lua",
+                "local M = {}\nfunction M.v() return 1 end\nreturn M",
+            )],
+        );
+        let sandbox = sandbox_with_engine();
+        sandbox.set_script_root(&dir).unwrap();
+        sandbox
+            .load_script(
+                "held = require('m')\nfunction on_update() return held.v() end",
+                "main.lua",
+            )
+            .unwrap();
+        assert_eq!(
+            sandbox.call_function::<(), i64>("on_update", ()).unwrap(),
+            1
+        );
+
+        std::fs::write(
+            dir.join("m.lua"),
+            "local M = {}\nfunction M.v() return 2 end\nreturn M",
+        )
+        .unwrap();
+        assert_eq!(sandbox.changed_modules(), vec!["m".to_string()]);
+        sandbox
+            .reload_script(
+                "held = require('m')\nfunction on_update() return held.v() end",
+                "main.lua",
+            )
+            .unwrap();
+        assert_eq!(
+            sandbox.call_function::<(), i64>("on_update", ()).unwrap(),
+            2
+        );
+        assert!(sandbox.changed_modules().is_empty());
+        let _ = std::fs::remove_dir_all(dir.parent().unwrap());
+    }
 }
@@ -0,0 +1,449 @@
+// Coroutine scheduler behind engine.spawn / engine.wait*
+use crate::sandbox::resume_budgeted;
+use mlua::{
+    Function, Lua, MultiValue, Thread, ThreadStatus, UserData, UserDataMethods, Value, WeakLua,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// Lua half of the wait API. Runs with the real globals so it can capture
+// coroutine.yield/running, which scripts themselves cannot reach.
+const WAIT_PRELUDE: &str = r#"
+  local engine, yield, running = ...
+  local function in_task(name)
+    local _, main = running()
+    if main then error(name .. " can only be called from a task started with engine.spawn", 3) end
+  end
+  function engine.wait(seconds)
+    in_task("engine.wait")
+    yield("time", tonumber(seconds) or 0)
+  end
+  function engine.wait_frames(n)
+    in_task("engine.wait_frames")
+    yield("frames", math.max(1, math.floor(tonumber(n) or 1)))
+  end
+  function engine.wait_until(pred)
+    in_task("engine.wait_until")
+    if type(pred) ~= "function" then error("engine.wait_until expects a function", 2) end
+    yield("until", pred)
+  end
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Waiting,
+    Running,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+impl TaskStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Waiting => "waiting",
+            TaskStatus::Running => "running",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+            TaskStatus::Failed => "failed",
+        }
+    }
+
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Done | TaskStatus::Cancelled | TaskStatus::Failed
+        )
+    }
+}
+
+/// Script-visible handle returned by `engine.spawn`
+#[derive(Clone)]
+pub struct TaskHandle {
+    id: u32,
+    status: Rc<Cell<TaskStatus>>,
+}
+
+impl UserData for TaskHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| Ok(format!("Task({})", this.id)));
+        methods.add_method("cancel", |_, this, ()| {
+            if !this.status.get().is_finished() {
+                this.status.set(TaskStatus::Cancelled);
+            }
+            Ok(())
+        });
+        methods.add_method("status", |_, this, ()| Ok(this.status.get().as_str()));
+        methods.add_method("is_done", |_, this, ()| Ok(this.status.get().is_finished()));
+    }
+}
+
+enum Wake {
+    Time(f64),
+    Frames(u32),
+    Until(Function),
+}
+
+struct Task {
+    handle: TaskHandle,
+    thread: Thread,
+    wake: Wake,
+}
+
+/// Tasks resume in spawn order once per fixed step, against fixed-step time,
+/// so schedules replay identically.
+#[derive(Default)]
+pub struct TaskScheduler {
+    tasks: Vec<Task>,
+    next_id: u32,
+    traceback: Option<Function>,
+    // Resuming needs the Lua state, which the host does not pass to step
+    lua: Option<WeakLua>,
+    // Failures from tasks that errored before their first wait (inside engine.spawn)
+    pending_errors: Vec<String>,
+}
+
+impl TaskScheduler {
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+/// Install `engine.spawn`, `engine.wait`, `engine.wait_frames` and `engine.wait_until`.
+pub fn install(
+    lua: &Lua,
+    engine: &mlua::Table,
+    scheduler: Rc<RefCell<TaskScheduler>>,
+    fixed_time: Rc<RefCell<f64>>,
+) -> mlua::Result<()> {
+    let globals = lua.globals();
+    let coroutine: mlua::Table = globals.get("coroutine")?;
+    scheduler.borrow_mut().lua = Some(lua.weak());
+    scheduler.borrow_mut().traceback = globals
+        .get::<mlua::Table>("debug")
+        .and_then(|d| d.get::<Function>("traceback"))
+        .ok();
+    lua.load(WAIT_PRELUDE)
+        .set_name("engine.wait prelude")
+        .call::<()>((
+            engine.clone(),
+            coroutine.get::<Function>("yield")?,
+            coroutine.get::<Function>("running")?,
+        ))?;
+
+    let spawn = lua.create_function(move |lua, (func, args): (Function, MultiValue)| {
+        let handle = {
+            let mut s = scheduler.borrow_mut();
+            s.next_id += 1;
+            TaskHandle {
+                id: s.next_id,
+                status: Rc::new(Cell::new(TaskStatus::Running)),
+            }
+        };
+        let thread = lua.create_thread(func)?;
+        // Run synchronously up to the first wait, like a direct call
+        let now = *fixed_time.borrow();
+        let outcome = resume(lua, &thread, args, now);
+        let mut s = scheduler.borrow_mut();
+        let traceback = s.traceback.clone();
+        if let Some(task) = settle(
+            handle.clone(),
+            thread,
+            outcome,
+            traceback.as_ref(),
+            &mut s.pending_errors,
+        ) {
+            s.tasks.push(task);
+        }
+        Ok(handle)
+    })?;
+    engine.set("spawn", spawn)?;
+    Ok(())
+}
+
+/// Advance all tasks by one fixed step. Returns error reports (with tracebacks)
+/// for tasks that failed during this step. Once `over_budget` reports the script
+/// budget spent, the remaining tasks are kept untouched for the next step.
+pub fn step(
+    scheduler: &Rc<RefCell<TaskScheduler>>,
+    now: f64,
+    over_budget: &dyn Fn() -> bool,
+) -> Vec<String> {
+    // Take the list so tasks can spawn new tasks while we resume them
+    let (current, traceback, lua, mut errors) = {
+        let mut s = scheduler.borrow_mut();
+        let Some(lua) = s.lua.as_ref().and_then(WeakLua::try_upgrade) else {
+            return Vec::new();
+        };
+        (
+            std::mem::take(&mut s.tasks),
+            s.traceback.clone(),
+            lua,
+            std::mem::take(&mut s.pending_errors),
+        )
+    };
+    let mut kept = Vec::with_capacity(current.len());
+    let mut current = current.into_iter();
+    while let Some(mut task) = current.next() {
+        if over_budget() {
+            kept.push(task);
+            kept.extend(current);
+            break;
+        }
+        if task.handle.status.get() == TaskStatus::Cancelled {
+            continue;
+        }
+        let ready = match &mut task.wake {
+            Wake::Time(t) => now >= *t,
+            Wake::Frames(n) => {
+                *n -= 1;
+                *n == 0
+            }
+            Wake::Until(pred) => match pred.call::<Value>(()) {
+                Ok(v) => !matches!(v, Value::Nil | Value::Boolean(false)),
+                Err(e) => {
+                    task.handle.status.set(TaskStatus::Failed);
+                    errors.push(report(&task.handle, &task.thread, &e, traceback.as_ref()));
+                    continue;
+                }
+            },
+        };
+        if !ready {
+            kept.push(task);
+            continue;
+        }
+        task.handle.status.set(TaskStatus::Running);
+        let outcome = resume(&lua, &task.thread, MultiValue::new(), now);
+        if let Some(t) = settle(
+            task.handle,
+            task.thread,
+            outcome,
+            traceback.as_ref(),
+            &mut errors,
+        ) {
+            kept.push(t);
+        }
+    }
+    for e in errors.iter() {
+        tracing::error!("{}", e);
+    }
+    let mut s = scheduler.borrow_mut();
+    kept.append(&mut s.tasks);
+    s.tasks = kept;
+    errors
+}
+
+fn resume(lua: &Lua, thread: &Thread, args: MultiValue, now: f64) -> mlua::Result<Option<Wake>> {
+    let yielded: MultiValue = resume_budgeted(lua, thread, args)?;
+    if thread.status() != ThreadStatus::Resumable {
+        return Ok(None);
+    }
+    let mut it = yielded.into_iter();
+    let kind = match it.next() {
+        Some(Value::String(s)) => s.to_string_lossy(),
+        _ => String::new(),
+    };
+    let arg = it.next().unwrap_or(Value::Nil);
+    let wake = match (kind.as_str(), arg) {
+        ("time", Value::Number(s)) => Wake::Time(now + s),
+        ("time", Value::Integer(s)) => Wake::Time(now + s as f64),
+        ("frames", Value::Integer(n)) => Wake::Frames(n.max(1) as u32),
+        ("frames", Value::Number(n)) => Wake::Frames((n as u32).max(1)),
+        ("until", Value::Function(f)) => Wake::Until(f),
+        _ => Wake::Frames(1),
+    };
+    Ok(Some(wake))
+}
+
+// Turn a resume outcome into a task to keep (if still waiting) and update its status
+fn settle(
+    handle: TaskHandle,
+    thread: Thread,
+    outcome: mlua::Result<Option<Wake>>,
+    traceback: Option<&Function>,
+    errors: &mut Vec<String>,
+) -> Option<Task> {
+    match outcome {
+        Ok(Some(wake)) => {
+            if handle.status.get() != TaskStatus::Cancelled {
+                handle.status.set(TaskStatus::Waiting);
+            }
+            Some(Task {
+                handle,
+                thread,
+                wake,
+            })
+        }
+        Ok(None) => {
+            if handle.status.get() != TaskStatus::Cancelled {
+                handle.status.set(TaskStatus::Done);
+            }
+            None
+        }
+        Err(e) => {
+            handle.status.set(TaskStatus::Failed);
+            errors.push(report(&handle, &thread, &e, traceback));
+            None
+        }
+    }
+}
+
+fn report(
+    handle: &TaskHandle,
+    thread: &Thread,
+    err: &mlua::Error,
+    traceback: Option<&Function>,
+) -> String {
+    let msg = err.to_string();
+    let trace = traceback
+        .and_then(|tb| tb.call::<String>((thread.clone(), msg.clone())).ok())
+        .unwrap_or(msg);
+    format!("Task({}) failed: {}", handle.id, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::EngineApi;
+    use crate::sandbox::{LuaSandbox, ScriptBudget, ScriptTimeout};
+
+    const TIMER_SCRIPT: &str = r#"
+        log = {}
+        function start_timer()
+          timer = engine.spawn(function(tag)
+            log[#log + 1] = tag .. ":start"
+            engine.wait(0.5)
+            log[#log + 1] = tag .. ":waited"
+            engine.wait_frames(2)
+            log[#log + 1] = tag .. ":frames"
+            engine.wait(10)
+            log[#log + 1] = tag .. ":never"
+          end, "a")
+        end
+        function on_reload(old)
+          log, timer = old.log, old.timer
+        end
+        function log_text() return table.concat(log, ",") end
+        function cancel_timer() timer:cancel() end
+        function timer_status() return timer:status() end
+    "#;
+
+    fn sandbox_with(script: &str) -> (LuaSandbox, EngineApi) {
+        let sandbox = LuaSandbox::new().unwrap();
+        let api = EngineApi::new();
+        api.setup_engine_namespace(sandbox.lua()).unwrap();
+        sandbox.load_script(script, "main.lua").unwrap();
+        (sandbox, api)
+    }
+
+    // Errors from `frames` fixed steps of 0.25s
+    fn step(api: &mut EngineApi, frames: usize) -> Vec<String> {
+        let mut errors = Vec::new();
+        for _ in 0..frames {
+            api.update_time(0.25);
+            errors.extend(api.step_tasks(&|| false));
+        }
+        errors
+    }
+
+    #[test]
+    fn test_waits_resume_on_fixed_steps() {
+        let (sandbox, mut api) = sandbox_with(TIMER_SCRIPT);
+        sandbox.call_function::<(), ()>("start_timer", ()).unwrap();
+        assert!(step(&mut api, 4).is_empty());
+        let log: String = sandbox.call_function("log_text", ()).unwrap();
+        assert_eq!(log, "a:start,a:waited,a:frames");
+        assert_eq!(api.task_count(), 1);
+    }
+
+    #[test]
+    fn test_failing_task_reports_traceback() {
+        let (sandbox, mut api) = sandbox_with(
+            r#"
+            function start_failing()
+              engine.spawn(function()
+                engine.wait_frames(1)
+                local t = nil
+                return t.field
+              end)
+            end
+            "#,
+        );
+        sandbox
+            .call_function::<(), ()>("start_failing", ())
+            .unwrap();
+        let errors = step(&mut api, 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("stack traceback"), "{}", errors[0]);
+        assert_eq!(api.task_count(), 0);
+    }
+
+    #[test]
+    fn test_task_state_is_carried_through_reload() {
+        let (sandbox, mut api) = sandbox_with(TIMER_SCRIPT);
+        sandbox.call_function::<(), ()>("start_timer", ()).unwrap();
+        assert!(step(&mut api, 2).is_empty());
+        sandbox.reload_script(TIMER_SCRIPT, "main.lua").unwrap();
+        assert!(step(&mut api, 2).is_empty());
+        let log: String = sandbox.call_function("log_text", ()).unwrap();
+        assert_eq!(log, "a:start,a:waited,a:frames");
+    }
+
+    #[test]
+    fn test_cancelled_task_never_resumes() {
+        let (sandbox, mut api) = sandbox_with(TIMER_SCRIPT);
+        sandbox.call_function::<(), ()>("start_timer", ()).unwrap();
+        assert!(step(&mut api, 1).is_empty());
+        sandbox.call_function::<(), ()>("cancel_timer", ()).unwrap();
+        api.update_time(20.0);
+        assert!(api.step_tasks(&|| false).is_empty());
+        assert_eq!(api.task_count(), 0);
+        let status: String = sandbox.call_function("timer_status", ()).unwrap();
+        assert_eq!(status, "cancelled");
+        let log: String = sandbox.call_function("log_text", ()).unwrap();
+        assert_eq!(log, "a:start");
+    }
+
+    #[test]
+    fn test_spinning_task_does_not_take_others_down() {
+        let sandbox = LuaSandbox::new().unwrap();
+        sandbox.set_budget(ScriptBudget {
+            max_instructions: Some(100_000),
+            max_wall_time: None,
+        });
+        let api = EngineApi::new();
+        api.setup_engine_namespace(sandbox.lua()).unwrap();
+        let script = r#"
+            done = false
+            function start_tasks()
+              engine.spawn(function() engine.wait_frames(1) while true do end end)
+              engine.spawn(function() engine.wait_frames(1) done = true end)
+            end
+            function is_done() return done end
+        "#;
+        sandbox.load_script(script, "main.lua").unwrap();
+        sandbox.call_function::<(), ()>("start_tasks", ()).unwrap();
+
+        let mut errors = Vec::new();
+        let outcome = sandbox.guarded("tasks", || {
+            errors = api.step_tasks(&|| sandbox.over_budget());
+            Ok(())
+        });
+        assert!(outcome
+            .unwrap_err()
+            .downcast_ref::<ScriptTimeout>()
+            .is_some());
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].starts_with("Task(1) failed"), "{}", errors[0]);
+        assert_eq!(api.task_count(), 1);
+
+        // The second task was left waiting and runs in the next budget window
+        let outcome = sandbox.guarded("tasks", || Ok(api.step_tasks(&|| sandbox.over_budget())));
+        assert!(outcome.unwrap().unwrap().is_empty());
+        assert!(sandbox.call_function::<(), bool>("is_done", ()).unwrap());
+        assert_eq!(api.task_count(), 0);
+    }
+}
@@ -0,0 +1,306 @@
+// Lua bindings for Tiled tilemaps (engine.tilemap_load)
+use engine_core::tilemap::{Tilemap, TilemapCommand, GID_MASK};
+use mlua::{Lua, LuaSerdeExt, Table, UserData, UserDataMethods, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Host sinks for tileset textures and tilemap changes
+pub struct TilemapHost {
+    pub load_texture: Rc<dyn Fn(String, u32)>,
+    pub commands: Rc<dyn Fn(TilemapCommand)>,
+    /// Next texture id handed to tileset images
+    pub next_texture_id: u32,
+}
+
+/// Maps loaded by scripts. Loading a path again replaces the previous map under
+/// the same id, so hot reloads pick up map edits instead of stacking copies.
+#[derive(Default)]
+pub struct TilemapRegistry {
+    maps: HashMap<u32, Tilemap>,
+    by_path: HashMap<String, u32>,
+    next_id: u32,
+    pub host: Option<TilemapHost>,
+}
+
+impl TilemapRegistry {
+    pub fn len(&self) -> usize {
+        self.maps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+
+    fn send(&self, cmd: TilemapCommand) {
+        if let Some(host) = &self.host {
+            (host.commands)(cmd);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SharedTilemaps(Rc<RefCell<TilemapRegistry>>);
+
+/// Script-visible handle returned by `engine.tilemap_load`
+#[derive(Debug, Clone, Copy)]
+pub struct TilemapHandle(pub u32);
+
+fn with_map<R>(
+    lua: &Lua,
+    id: u32,
+    f: impl FnOnce(&mut TilemapRegistry, u32) -> mlua::Result<R>,
+) -> mlua::Result<R> {
+    let shared = lua
+        .app_data_ref::<SharedTilemaps>()
+        .map(|s| s.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("tilemaps are not available".into()))?;
+    let mut reg = shared.borrow_mut();
+    if !reg.maps.contains_key(&id) {
+        return Err(mlua::Error::RuntimeError(format!(
+            "Tilemap({}) has been unloaded",
+            id
+        )));
+    }
+    f(&mut reg, id)
+}
+
+// Tile layer by name or 1-based index
+fn layer_arg(map: &Tilemap, layer: &Value) -> mlua::Result<usize> {
+    let found = match layer {
+        Value::String(s) => map.layer_index(&s.to_str()?),
+        Value::Integer(i) => usize::try_from(*i - 1)
+            .ok()
+            .filter(|i| *i < map.layers.len()),
+        _ => None,
+    };
+    found.ok_or_else(|| {
+        mlua::Error::RuntimeError(format!(
+            "Tilemap: no tile layer {:?}",
+            layer.to_string().unwrap_or_default()
+        ))
+    })
+}
+
+fn names<'a>(lua: &Lua, names: impl Iterator<Item = &'a str>) -> mlua::Result<Table> {
+    lua.create_sequence_from(names)
+}
+
+impl UserData for TilemapHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!("Tilemap({})", this.0))
+        });
+        methods.add_method("size", |lua, this, ()| {
+            with_map(lua, this.0, |reg, id| {
+                let m = &reg.maps[&id];
+                Ok((m.width, m.height))
+            })
+        });
+        methods.add_method("tile_size", |lua, this, ()| {
+            with_map(lua, this.0, |reg, id| {
+                let m = &reg.maps[&id];
+                Ok((m.tile_w, m.tile_h))
+            })
+        });
+        methods.add_method("layers", |lua, this, ()| {
+            with_map(lua, this.0, |reg, id| {
+                names(lua, reg.maps[&id].layers.iter().map(|l| l.name.as_str()))
+            })
+        });
+        methods.add_method("object_layers", |lua, this, ()| {
+            with_map(lua, this.0, |reg, id| {
+                names(
+                    lua,
+                    reg.maps[&id].object_layers.iter().map(|l| l.name.as_str()),
+                )
+            })
+        });
+        // objects(layer) -> array of Tiled objects as plain tables
+        methods.add_method("objects", |lua, this, name: String| {
+            with_map(lua, this.0, |reg, id| {
+                let layer = reg.maps[&id].object_layer(&name).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!("Tilemap: no object layer '{}'", name))
+                })?;
+                let options = mlua::SerializeOptions::new()
+                    .serialize_none_to_null(false)
+                    .set_array_metatable(false);
+                lua.to_value_with(&layer.objects, options)
+            })
+        });
+        // get_tile(layer, x, y) -> gid without flip flags (0 = empty), nil outside the map
+        methods.add_method("get_tile", |lua, this, (layer, x, y): (Value, i64, i64)| {
+            with_map(lua, this.0, |reg, id| {
+                let m = &reg.maps[&id];
+                let li = layer_arg(m, &layer)?;
+                if x < 0 || y < 0 {
+                    return Ok(None);
+                }
+                Ok(m.tile(li, x as u32, y as u32).map(|gid| gid & GID_MASK))
+            })
+        });
+        // set_tile(layer, x, y, gid); only the chunk holding the tile is re-meshed
+        methods.add_method(
+            "set_tile",
+            |lua, this, (layer, x, y, gid): (Value, i64, i64, u32)| {
+                with_map(lua, this.0, |reg, id| {
+                    let m = reg.maps.get_mut(&id).unwrap();
+                    let li = layer_arg(m, &layer)?;
+                    if x < 0 || y < 0 {
+                        return Err(mlua::Error::RuntimeError(format!(
+                            "Tilemap:set_tile: ({}, {}) is outside the map",
+                            x, y
+                        )));
+                    }
+                    let (x, y) = (x as u32, y as u32);
+                    let changed = m.set_tile(li, x, y, gid).map_err(|e| {
+                        mlua::Error::RuntimeError(format!("Tilemap:set_tile: {}", e))
+                    })?;
+                    if changed {
+                        reg.send(TilemapCommand::SetTile {
+                            map: id,
+                            layer: li,
+                            x,
+                            y,
+                            gid,
+                        });
+                    }
+                    Ok(())
+                })
+            },
+        );
+        // Map-space position (y up) -> tile containing it; layer offsets not applied
+        methods.add_method("world_to_tile", |lua, this, (x, y): (f32, f32)| {
+            with_map(lua, this.0, |reg, id| {
+                let m = &reg.maps[&id];
+                let (tw, th) = (m.tile_w as f32, m.tile_h as f32);
+                Ok((
+                    (x / tw).floor() as i64,
+                    m.height as i64 - 1 - (y / th).floor() as i64,
+                ))
+            })
+        });
+        // Tile -> map-space center of that tile
+        methods.add_method("tile_to_world", |lua, this, (tx, ty): (i64, i64)| {
+            with_map(lua, this.0, |reg, id| {
+                let m = &reg.maps[&id];
+                let (tw, th) = (m.tile_w as f32, m.tile_h as f32);
+                Ok((
+                    (tx as f32 + 0.5) * tw,
+                    (m.height as i64 - 1 - ty) as f32 * th + th * 0.5,
+                ))
+            })
+        });
+        methods.add_method("unload", |lua, this, ()| {
+            with_map(lua, this.0, |reg, id| {
+                reg.maps.remove(&id);
+                reg.by_path.retain(|_, v| *v != id);
+                reg.send(TilemapCommand::Unload(id));
+                Ok(())
+            })
+        });
+    }
+}
+
+/// Install `engine.tilemap_load` on the engine table.
+pub fn install(
+    lua: &Lua,
+    engine: &Table,
+    registry: Rc<RefCell<TilemapRegistry>>,
+) -> mlua::Result<()> {
+    lua.set_app_data(SharedTilemaps(registry.clone()));
+    let load = lua.create_function(move |_, path: String| {
+        let mut map = Tilemap::load(&path)
+            .map_err(|e| mlua::Error::RuntimeError(format!("tilemap_load: {}", e)))?;
+        let mut reg = registry.borrow_mut();
+        if let Some(host) = reg.host.as_mut() {
+            for ts in &mut map.tilesets {
+                ts.texture_id = host.next_texture_id;
+                host.next_texture_id += 1;
+                (host.load_texture)(ts.image.to_string_lossy().into_owned(), ts.texture_id);
+            }
+        }
+        let id = match reg.by_path.get(&path) {
+            Some(&id) => id,
+            None => {
+                reg.next_id += 1;
+                let id = reg.next_id;
+                reg.by_path.insert(path, id);
+                id
+            }
+        };
+        reg.send(TilemapCommand::Load(id, Box::new(map.clone())));
+        reg.maps.insert(id, map);
+        Ok(TilemapHandle(id))
+    })?;
+    engine.set("tilemap_load", load)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::test_lua;
+
+    // 3x2 map with a flipped tile and one spawn object, written under a dir for `test`
+    fn level(test: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("luarite_tilemap_{}_{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("level.tmj");
+        std::fs::write(
+            &path,
+            r#"{ "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+                 "tilesets": [{ "firstgid": 1, "name": "t", "image": "t.png", "tilewidth": 16,
+                                "tileheight": 16, "columns": 2, "tilecount": 4,
+                                "imagewidth": 32, "imageheight": 32 }],
+                 "layers": [
+                   { "type": "tilelayer", "name": "ground", "width": 3, "height": 2,
+                     "data": [1, 2, 0, 0, 0, 1073741828] },
+                   { "type": "objectgroup", "name": "spawns", "objects": [
+                     { "id": 7, "name": "player", "type": "spawn", "x": 24, "y": 8 } ] }
+                 ] }"#,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tilemap_edits_and_queries() {
+        let path = level("edits");
+        let (lua, _api) = test_lua();
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        let (flipped, edited, spawn_y, tile): (u32, u32, f64, i64) = lua
+            .load(
+                r#"
+                M = engine.tilemap_load(PATH)
+                M:set_tile("ground", 2, 0, 3)
+                local tx, ty = M:world_to_tile(M:tile_to_world(2, 0))
+                return M:get_tile(1, 2, 1), M:get_tile("ground", 2, 0), M:objects("spawns")[1].y, tx * 10 + ty
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((flipped, edited, spawn_y, tile), (4, 3, 24.0, 20));
+        assert!(lua.load("M:set_tile('ground', 3, 0, 1)").exec().is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_reloading_a_path_resets_edits_and_unload_invalidates() {
+        let path = level("reload");
+        let (lua, _api) = test_lua();
+        lua.globals().set("PATH", path.to_str().unwrap()).unwrap();
+        lua.load("M = engine.tilemap_load(PATH) M:set_tile(1, 2, 0, 3)")
+            .exec()
+            .unwrap();
+        // Same path reuses the id and resets edits; the old handle follows it
+        let (same, tile): (bool, u32) = lua
+            .load("local N = engine.tilemap_load(PATH); return tostring(N) == tostring(M), M:get_tile(1, 2, 0)")
+            .eval()
+            .unwrap();
+        assert_eq!((same, tile), (true, 0));
+        lua.load("M:unload()").exec().unwrap();
+        assert!(lua.load("return M:size()").exec().is_err());
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
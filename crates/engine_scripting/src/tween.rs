@@ -0,0 +1,642 @@
+// Property tweens behind engine.tween, advanced on fixed-step time
+use mlua::{Function, Lua, Table, UserData, UserDataMethods, Value};
+use std::cell::{Cell, RefCell};
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EaseFamily {
+    Linear,
+    Quad,
+    Cubic,
+    Quart,
+    Quint,
+    Sine,
+    Expo,
+    Circ,
+    Back,
+    Elastic,
+    Bounce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EaseMode {
+    In,
+    Out,
+    InOut,
+}
+
+/// Easing curve named like `"outCubic"`, `"inOutBack"` or `"linear"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Easing {
+    pub family: EaseFamily,
+    pub mode: EaseMode,
+}
+
+impl Easing {
+    pub const LINEAR: Easing = Easing {
+        family: EaseFamily::Linear,
+        mode: EaseMode::In,
+    };
+
+    pub fn parse(name: &str) -> Option<Easing> {
+        if name == "linear" {
+            return Some(Easing::LINEAR);
+        }
+        let (mode, rest) = if let Some(r) = name.strip_prefix("inOut") {
+            (EaseMode::InOut, r)
+        } else if let Some(r) = name.strip_prefix("in") {
+            (EaseMode::In, r)
+        } else if let Some(r) = name.strip_prefix("out") {
+            (EaseMode::Out, r)
+        } else {
+            return None;
+        };
+        let family = match rest {
+            "Quad" => EaseFamily::Quad,
+            "Cubic" => EaseFamily::Cubic,
+            "Quart" => EaseFamily::Quart,
+            "Quint" => EaseFamily::Quint,
+            "Sine" => EaseFamily::Sine,
+            "Expo" => EaseFamily::Expo,
+            "Circ" => EaseFamily::Circ,
+            "Back" => EaseFamily::Back,
+            "Elastic" => EaseFamily::Elastic,
+            "Bounce" => EaseFamily::Bounce,
+            _ => return None,
+        };
+        Some(Easing { family, mode })
+    }
+
+    /// Map linear progress `t` in [0, 1] onto the curve.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        let f = self.family;
+        match self.mode {
+            EaseMode::In => ease_in(f, t),
+            EaseMode::Out => 1.0 - ease_in(f, 1.0 - t),
+            EaseMode::InOut if t < 0.5 => ease_in(f, 2.0 * t) / 2.0,
+            EaseMode::InOut => 1.0 - ease_in(f, 2.0 - 2.0 * t) / 2.0,
+        }
+    }
+}
+
+fn ease_in(family: EaseFamily, t: f64) -> f64 {
+    match family {
+        EaseFamily::Linear => t,
+        EaseFamily::Quad => t * t,
+        EaseFamily::Cubic => t * t * t,
+        EaseFamily::Quart => t.powi(4),
+        EaseFamily::Quint => t.powi(5),
+        EaseFamily::Sine => 1.0 - (t * PI / 2.0).cos(),
+        EaseFamily::Expo if t == 0.0 => 0.0,
+        EaseFamily::Expo => 2f64.powf(10.0 * t - 10.0),
+        EaseFamily::Circ => 1.0 - (1.0 - t * t).max(0.0).sqrt(),
+        EaseFamily::Back => {
+            let c1 = 1.70158;
+            (c1 + 1.0) * t * t * t - c1 * t * t
+        }
+        EaseFamily::Elastic if t == 0.0 || t == 1.0 => t,
+        EaseFamily::Elastic => {
+            -(2f64.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * (2.0 * PI / 3.0)).sin()
+        }
+        EaseFamily::Bounce => 1.0 - bounce_out(1.0 - t),
+    }
+}
+
+fn bounce_out(t: f64) -> f64 {
+    let (n1, d1) = (7.5625, 2.75);
+    if t < 1.0 / d1 {
+        n1 * t * t
+    } else if t < 2.0 / d1 {
+        let t = t - 1.5 / d1;
+        n1 * t * t + 0.75
+    } else if t < 2.5 / d1 {
+        let t = t - 2.25 / d1;
+        n1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d1;
+        n1 * t * t + 0.984375
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenStatus {
+    Pending,
+    Running,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+impl TweenStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            TweenStatus::Pending => "pending",
+            TweenStatus::Running => "running",
+            TweenStatus::Done => "done",
+            TweenStatus::Cancelled => "cancelled",
+            TweenStatus::Failed => "failed",
+        }
+    }
+
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            TweenStatus::Done | TweenStatus::Cancelled | TweenStatus::Failed
+        )
+    }
+}
+
+/// Script-visible handle returned by `engine.tween` and `Tween:next`
+#[derive(Clone)]
+pub struct TweenHandle {
+    id: u32,
+    status: Rc<Cell<TweenStatus>>,
+    // Fixed time at which the tween finished; chained tweens start from here
+    end_time: Rc<Cell<f64>>,
+}
+
+impl UserData for TweenHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method("__tostring", |_, this, ()| {
+            Ok(format!("Tween({})", this.id))
+        });
+        methods.add_method("cancel", |_, this, ()| {
+            if !this.status.get().is_finished() {
+                this.status.set(TweenStatus::Cancelled);
+            }
+            Ok(())
+        });
+        methods.add_method("status", |_, this, ()| Ok(this.status.get().as_str()));
+        methods.add_method("is_done", |_, this, ()| Ok(this.status.get().is_finished()));
+        // Queue a tween that starts when this one completes
+        methods.add_method(
+            "next",
+            |lua, this, (target, duration, props, opts): (Table, f64, Table, Value)| {
+                create(lua, target, duration, props, opts, Some(this.clone()))
+            },
+        );
+    }
+}
+
+#[derive(Clone)]
+enum PropValue {
+    Number(f64),
+    // Numeric fields of a nested table, e.g. a color {r, g, b, a}
+    Fields(Vec<(String, f64)>),
+}
+
+struct Tween {
+    handle: TweenHandle,
+    target: Table,
+    keys: Vec<String>,
+    to: Vec<PropValue>,
+    from: Option<Vec<PropValue>>,
+    duration: f64,
+    delay: f64,
+    ease: Easing,
+    yoyo: bool,
+    // Extra plays after the first; None loops forever
+    repeats: Option<u32>,
+    // Absolute fixed time playback starts; None while waiting on `after`
+    start: Option<f64>,
+    after: Option<TweenHandle>,
+    on_complete: Option<Function>,
+}
+
+/// Active tweens, advanced in creation order once per fixed step
+#[derive(Default)]
+pub struct TweenSet {
+    tweens: Vec<Tween>,
+    next_id: u32,
+}
+
+impl TweenSet {
+    pub fn len(&self) -> usize {
+        self.tweens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tweens.is_empty()
+    }
+}
+
+// Lets handle methods reach the set and the clock without owning them
+struct SharedTweens {
+    set: Rc<RefCell<TweenSet>>,
+    fixed_time: Rc<RefCell<f64>>,
+}
+
+/// Install `engine.tween`, `engine.cancel_tweens` and `engine.ease`.
+pub fn install(
+    lua: &Lua,
+    engine: &Table,
+    set: Rc<RefCell<TweenSet>>,
+    fixed_time: Rc<RefCell<f64>>,
+) -> mlua::Result<()> {
+    lua.set_app_data(SharedTweens {
+        set: set.clone(),
+        fixed_time,
+    });
+
+    let tween = lua.create_function(
+        |lua, (target, duration, props, opts): (Table, f64, Table, Value)| {
+            create(lua, target, duration, props, opts, None)
+        },
+    )?;
+    engine.set("tween", tween)?;
+
+    let cancel = lua.create_function(move |_, target: Table| {
+        let mut count = 0;
+        for t in set.borrow().tweens.iter() {
+            if t.target.to_pointer() == target.to_pointer() && !t.handle.status.get().is_finished()
+            {
+                t.handle.status.set(TweenStatus::Cancelled);
+                count += 1;
+            }
+        }
+        Ok(count)
+    })?;
+    engine.set("cancel_tweens", cancel)?;
+
+    let ease =
+        lua.create_function(|_, (name, t): (String, f64)| Ok(parse_ease(&name)?.apply(t)))?;
+    engine.set("ease", ease)?;
+    Ok(())
+}
+
+fn parse_ease(name: &str) -> mlua::Result<Easing> {
+    Easing::parse(name).ok_or_else(|| {
+        mlua::Error::RuntimeError(format!(
+            "unknown easing '{}' (use linear, or in/out/inOut + Quad, Cubic, Quart, Quint, Sine, Expo, Circ, Back, Elastic, Bounce)",
+            name
+        ))
+    })
+}
+
+fn create(
+    lua: &Lua,
+    target: Table,
+    duration: f64,
+    props: Table,
+    opts: Value,
+    after: Option<TweenHandle>,
+) -> mlua::Result<TweenHandle> {
+    if !duration.is_finite() || duration < 0.0 {
+        return Err(mlua::Error::RuntimeError(format!(
+            "engine.tween: duration must be a non-negative number, got {}",
+            duration
+        )));
+    }
+
+    let mut keys = Vec::new();
+    let mut to = Vec::new();
+    for pair in props.pairs::<String, Value>() {
+        let (key, value) = pair?;
+        let v = match value {
+            Value::Table(t) => PropValue::Fields(numeric_fields(&t, &key)?),
+            other => PropValue::Number(number(&other).ok_or_else(|| {
+                mlua::Error::RuntimeError(format!(
+                    "engine.tween: '{}' must be a number or a table of numbers",
+                    key
+                ))
+            })?),
+        };
+        keys.push(key);
+        to.push(v);
+    }
+    // Apply properties in a stable order regardless of table iteration order
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+    let keys: Vec<String> = order.iter().map(|i| keys[*i].clone()).collect();
+    let to: Vec<PropValue> = order.iter().map(|i| to[*i].clone()).collect();
+
+    let mut ease = Easing::LINEAR;
+    let (mut delay, mut yoyo, mut repeats, mut on_complete) = (0.0, false, Some(0), None);
+    match opts {
+        Value::Nil => {}
+        Value::String(s) => ease = parse_ease(&s.to_str()?)?,
+        Value::Table(o) => {
+            if let Some(name) = o.get::<Option<String>>("ease")? {
+                ease = parse_ease(&name)?;
+            }
+            delay = o.get::<Option<f64>>("delay")?.unwrap_or(0.0).max(0.0);
+            yoyo = o.get::<Option<bool>>("yoyo")?.unwrap_or(false);
+            repeats = match o.get::<Value>("loop")? {
+                Value::Nil => {
+                    if yoyo {
+                        Some(1) // there and back
+                    } else {
+                        Some(0)
+                    }
+                }
+                Value::Boolean(true) => None,
+                Value::Boolean(false) => Some(0),
+                v => match number(&v) {
+                    Some(n) if n >= 0.0 => Some(n as u32),
+                    _ => {
+                        return Err(mlua::Error::RuntimeError(
+                            "engine.tween: loop must be true or a repeat count".into(),
+                        ))
+                    }
+                },
+            };
+            on_complete = o.get::<Option<Function>>("on_complete")?;
+        }
+        _ => {
+            return Err(mlua::Error::RuntimeError(
+                "engine.tween: 4th argument must be an easing name or an options table".into(),
+            ))
+        }
+    }
+
+    // Catch bad targets at the call site rather than a frame later
+    if after.is_none() {
+        read_from(&target, &keys, &to)?;
+    }
+
+    let shared = lua
+        .app_data_ref::<SharedTweens>()
+        .ok_or_else(|| mlua::Error::RuntimeError("tweens not installed".into()))?;
+    let now = *shared.fixed_time.borrow();
+    let mut set = shared.set.borrow_mut();
+    set.next_id += 1;
+    let handle = TweenHandle {
+        id: set.next_id,
+        status: Rc::new(Cell::new(TweenStatus::Pending)),
+        end_time: Rc::new(Cell::new(0.0)),
+    };
+    set.tweens.push(Tween {
+        handle: handle.clone(),
+        target,
+        keys,
+        to,
+        from: None,
+        duration,
+        delay,
+        ease,
+        yoyo,
+        repeats,
+        start: after.is_none().then_some(now + delay),
+        after,
+        on_complete,
+    });
+    Ok(handle)
+}
+
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Number(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn numeric_fields(t: &Table, key: &str) -> mlua::Result<Vec<(String, f64)>> {
+    let mut fields = Vec::new();
+    for pair in t.pairs::<String, Value>() {
+        let (k, v) = pair?;
+        let n = number(&v).ok_or_else(|| {
+            mlua::Error::RuntimeError(format!("engine.tween: '{}.{}' must be a number", key, k))
+        })?;
+        fields.push((k, n));
+    }
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(fields)
+}
+
+// Current values of the tweened properties, shaped like `to`
+fn read_from(target: &Table, keys: &[String], to: &[PropValue]) -> mlua::Result<Vec<PropValue>> {
+    let mut from = Vec::with_capacity(to.len());
+    for (key, end) in keys.iter().zip(to) {
+        let current: Value = target.raw_get(key.as_str())?;
+        let value = match (end, &current) {
+            (PropValue::Number(_), v) => number(v).map(PropValue::Number),
+            (PropValue::Fields(fields), Value::Table(t)) => fields
+                .iter()
+                .map(|(k, _)| number(&t.raw_get(k.as_str()).ok()?).map(|n| (k.clone(), n)))
+                .collect::<Option<Vec<_>>>()
+                .map(PropValue::Fields),
+            _ => None,
+        };
+        from.push(value.ok_or_else(|| {
+            mlua::Error::RuntimeError(format!(
+                "engine.tween: target.{} is {} but the tween expects {}",
+                key,
+                current.type_name(),
+                match end {
+                    PropValue::Number(_) => "a number",
+                    PropValue::Fields(_) => "a table with the same numeric fields",
+                }
+            ))
+        })?);
+    }
+    Ok(from)
+}
+
+fn write(
+    target: &Table,
+    keys: &[String],
+    from: &[PropValue],
+    to: &[PropValue],
+    k: f64,
+) -> mlua::Result<()> {
+    let lerp = |a: f64, b: f64| a + (b - a) * k;
+    for ((key, a), b) in keys.iter().zip(from).zip(to) {
+        match (a, b) {
+            (PropValue::Number(a), PropValue::Number(b)) => {
+                target.raw_set(key.as_str(), lerp(*a, *b))?
+            }
+            (PropValue::Fields(a), PropValue::Fields(b)) => {
+                // Update the nested table in place so shared references see the change
+                let t: Table = target.raw_get(key.as_str())?;
+                for ((name, a), (_, b)) in a.iter().zip(b) {
+                    t.raw_set(name.as_str(), lerp(*a, *b))?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Advance all tweens to fixed time `now`. Returns error reports for tweens that
+/// failed and for `on_complete` callbacks that raised.
+pub fn step(set: &Rc<RefCell<TweenSet>>, now: f64) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut completed = Vec::new();
+    {
+        // Only raw table access happens under the borrow, so no Lua code can re-enter
+        let mut s = set.borrow_mut();
+        s.tweens.retain_mut(|tw| {
+            if tw.handle.status.get() == TweenStatus::Cancelled {
+                return false;
+            }
+            if tw.start.is_none() {
+                let parent = tw
+                    .after
+                    .as_ref()
+                    .map(|h| (h.status.get(), h.end_time.get()));
+                match parent {
+                    Some((TweenStatus::Done, end)) => tw.start = Some(end + tw.delay),
+                    Some((TweenStatus::Cancelled | TweenStatus::Failed, _)) => {
+                        tw.handle.status.set(TweenStatus::Cancelled);
+                        return false;
+                    }
+                    _ => return true,
+                }
+            }
+            let start = tw.start.unwrap_or(now);
+            if now < start {
+                return true;
+            }
+            if tw.from.is_none() {
+                match read_from(&tw.target, &tw.keys, &tw.to) {
+                    Ok(from) => tw.from = Some(from),
+                    Err(e) => {
+                        tw.handle.status.set(TweenStatus::Failed);
+                        errors.push(format!("Tween({}) failed: {}", tw.handle.id, e));
+                        return false;
+                    }
+                }
+            }
+
+            let plays = tw.repeats.map(|r| r as f64 + 1.0);
+            let elapsed = now - start;
+            let (t, finished) = if tw.duration <= 0.0 {
+                (1.0, true)
+            } else {
+                let play = (elapsed / tw.duration).floor();
+                match plays {
+                    Some(n) if play >= n => {
+                        let backwards = tw.yoyo && (n as u64).is_multiple_of(2);
+                        (if backwards { 0.0 } else { 1.0 }, true)
+                    }
+                    _ => {
+                        let frac = elapsed / tw.duration - play;
+                        let backwards = tw.yoyo && !(play as u64).is_multiple_of(2);
+                        (if backwards { 1.0 - frac } else { frac }, false)
+                    }
+                }
+            };
+            let from = tw.from.as_deref().unwrap_or_default();
+            if let Err(e) = write(&tw.target, &tw.keys, from, &tw.to, tw.ease.apply(t)) {
+                tw.handle.status.set(TweenStatus::Failed);
+                errors.push(format!("Tween({}) failed: {}", tw.handle.id, e));
+                return false;
+            }
+            if !finished {
+                tw.handle.status.set(TweenStatus::Running);
+                return true;
+            }
+            tw.handle.status.set(TweenStatus::Done);
+            tw.handle
+                .end_time
+                .set(start + tw.duration * plays.unwrap_or(1.0));
+            if let Some(cb) = tw.on_complete.take() {
+                completed.push((tw.handle.id, cb));
+            }
+            false
+        });
+    }
+    for (id, cb) in completed {
+        if let Err(e) = cb.call::<()>(()) {
+            errors.push(format!("Tween({}) on_complete failed: {}", id, e));
+        }
+    }
+    for e in errors.iter() {
+        tracing::error!("{}", e);
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_lua, EngineApi};
+
+    #[test]
+    fn test_easings_start_at_0_and_end_at_1() {
+        for name in [
+            "linear",
+            "inQuad",
+            "outCubic",
+            "inOutBack",
+            "outElastic",
+            "inOutBounce",
+        ] {
+            let e = Easing::parse(name).unwrap();
+            assert!(
+                e.apply(0.0).abs() < 1e-9 && (e.apply(1.0) - 1.0).abs() < 1e-9,
+                "{}",
+                name
+            );
+        }
+        assert!(Easing::parse("outWobble").is_none());
+    }
+
+    fn step(api: &mut EngineApi, dt: f64) {
+        api.update_time(dt);
+        assert!(api.step_tweens().is_empty());
+    }
+
+    fn get(lua: &Lua, expr: &str) -> f64 {
+        lua.load(expr).eval().unwrap()
+    }
+
+    #[test]
+    fn test_tween_interpolates_numbers_and_colors() {
+        let (lua, mut api) = test_lua();
+        lua.load(
+            r#"
+            box = { x = 0, tint = engine.rgba(0, 0, 0, 255) }
+            engine.tween(box, 1.0, { x = 100, tint = engine.rgba(255, 0, 0, 255) })
+            "#,
+        )
+        .exec()
+        .unwrap();
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "box.x"), 50.0);
+        assert!((get(&lua, "box.tint.r") - 0.5).abs() < 1e-6);
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "box.x"), 100.0);
+        assert_eq!(api.tween_count(), 0);
+    }
+
+    #[test]
+    fn test_yoyo_returns_to_the_start() {
+        let (lua, mut api) = test_lua();
+        lua.load("pulse = { s = 1 } engine.tween(pulse, 0.5, { s = 2 }, { yoyo = true })")
+            .exec()
+            .unwrap();
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "pulse.s"), 2.0);
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "pulse.s"), 1.0);
+    }
+
+    #[test]
+    fn test_next_waits_for_the_previous_tween_and_its_delay() {
+        let (lua, mut api) = test_lua();
+        lua.load(
+            r#"
+            box = { x = 0 }
+            first = engine.tween(box, 1.0, { x = 100 })
+            second = first:next(box, 0.5, { x = 50 }, { delay = 0.5, ease = "outCubic" })
+            "#,
+        )
+        .exec()
+        .unwrap();
+        step(&mut api, 1.0);
+        assert_eq!(get(&lua, "box.x"), 100.0);
+        // Second tween waits out its delay, then eases from 100 to 50
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "box.x"), 100.0);
+        step(&mut api, 0.5);
+        assert_eq!(get(&lua, "box.x"), 50.0);
+        assert_eq!(api.tween_count(), 0);
+        let status: String = lua.load("return second:status()").eval().unwrap();
+        assert_eq!(status, "done");
+    }
+}
@@ -0,0 +1,618 @@
+// Lua bindings for the immediate-mode UI (engine.ui.*)
+use crate::api::{color_arg, Atlas, InputSnapshot, LayerResolveCb, UiViewportCb};
+use crate::font::{push_text, FontHandle, TextPaint};
+use engine_core::font::BitmapFont;
+use engine_core::stable_keys as keys;
+use engine_core::state::QuadData;
+use engine_core::ui::{nine_slice, widget_id, NavInput, Rect, Response, UiContext, UiInput};
+use mlua::{AnyUserData, Function, Lua, Table, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Inner padding around labels, in UI units
+const PAD: f32 = 4.0;
+/// Widget height and default width when no font is set
+const DEFAULT_H: f32 = 20.0;
+const DEFAULT_W: f32 = 120.0;
+/// Slider keyboard step as a fraction of its range
+const SLIDER_STEPS: f32 = 20.0;
+
+// Nine-slice frames picked from an atlas by part name ("button", "button_hover", ...)
+struct Skin {
+    texture: u32,
+    frames: HashMap<String, [f32; 4]>,
+    slice: f32,
+    slice_uv: [f32; 2],
+}
+
+/// Look of every widget; merged by `engine.ui.style`
+pub struct Style {
+    font: Option<Rc<BitmapFont>>,
+    scale: f32,
+    text_color: [f32; 4],
+    fill: [f32; 4],
+    hover: [f32; 4],
+    down: [f32; 4],
+    accent: [f32; 4],
+    focus: [f32; 4],
+    skin: Option<Skin>,
+    layer: String,
+    z: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            font: None,
+            scale: 1.0,
+            text_color: [1.0, 1.0, 1.0, 1.0],
+            fill: [0.18, 0.18, 0.22, 0.92],
+            hover: [0.26, 0.26, 0.32, 0.95],
+            down: [0.12, 0.12, 0.15, 1.0],
+            accent: [0.95, 0.75, 0.3, 1.0],
+            focus: [1.0, 0.85, 0.35, 1.0],
+            skin: None,
+            layer: engine_core::state::UI_LAYER.to_string(),
+            z: 0.0,
+        }
+    }
+}
+
+impl Style {
+    fn line_h(&self) -> f32 {
+        self.font
+            .as_ref()
+            .map_or(DEFAULT_H, |f| f.line_height * self.scale + 2.0 * PAD)
+    }
+
+    fn text_w(&self, text: &str) -> f32 {
+        self.font.as_ref().map_or(0.0, |f| {
+            f.layout(text, &Default::default()).width * self.scale
+        })
+    }
+}
+
+/// Focus/pointer state, style and host hooks shared by the engine.ui functions
+#[derive(Default)]
+pub struct UiState {
+    ctx: UiContext,
+    style: Style,
+    layer_id: u32,
+    /// Maps window-pixel mouse positions to UI units; raw positions are used without it
+    pub viewport: Option<UiViewportCb>,
+}
+
+impl UiState {
+    fn text_paint(&self, color: [f32; 4], z: f32) -> TextPaint {
+        TextPaint {
+            scale: self.style.scale,
+            color,
+            z: self.style.z + z,
+            layer_id: self.layer_id,
+        }
+    }
+
+    fn solid(&self, out: &mut Vec<QuadData>, rect: Rect, color: [f32; 4], z: f32) {
+        out.push(QuadData {
+            texture_id: 0,
+            center: rect.center(),
+            size: [rect.width, rect.height],
+            uv: [0.0, 0.0, 1.0, 1.0],
+            color,
+            z: self.style.z + z,
+            layer_id: self.layer_id,
+        });
+    }
+
+    // Skin frame for `part` in the widget's state, falling back to the plain part
+    fn frame(&self, part: &str, state: &str) -> Option<[f32; 4]> {
+        let skin = self.style.skin.as_ref()?;
+        if !state.is_empty() {
+            if let Some(uv) = skin.frames.get(&format!("{}_{}", part, state)) {
+                return Some(*uv);
+            }
+        }
+        skin.frames.get(part).copied()
+    }
+
+    /// Draw a widget background: a nine-slice skin frame, or flat colours with a
+    /// focus outline when the atlas has no frame for `part`.
+    fn panel(&self, out: &mut Vec<QuadData>, part: &str, rect: Rect, resp: Response) {
+        let state = if resp.held {
+            "down"
+        } else if resp.hovered {
+            "hover"
+        } else if resp.focused {
+            "focus"
+        } else {
+            ""
+        };
+        if let (Some(skin), Some(uv)) = (&self.style.skin, self.frame(part, state)) {
+            for (piece, [u0, v0, u1, v1]) in nine_slice(rect, uv, skin.slice, skin.slice_uv) {
+                out.push(QuadData {
+                    texture_id: skin.texture,
+                    center: piece.center(),
+                    size: [piece.width, piece.height],
+                    // The sprite pipeline samples v0 at the low-y edge
+                    uv: [u0, v1, u1, v0],
+                    color: [1.0, 1.0, 1.0, 1.0],
+                    z: self.style.z,
+                    layer_id: self.layer_id,
+                });
+            }
+            return;
+        }
+        let color = match state {
+            "down" => self.style.down,
+            "hover" => self.style.hover,
+            _ => self.style.fill,
+        };
+        self.solid(out, rect, color, 0.0);
+        if resp.focused {
+            let (l, t, w, h) = (rect.left, rect.top, rect.width, rect.height);
+            for edge in [
+                Rect::new(l, t, w, 1.0),
+                Rect::new(l, t - h + 1.0, w, 1.0),
+                Rect::new(l, t, 1.0, h),
+                Rect::new(l + w - 1.0, t, 1.0, h),
+            ] {
+                self.solid(out, edge, self.style.focus, 0.3);
+            }
+        }
+    }
+
+    /// Accent piece (check mark, slider knob, caret): skin frame or accent colour
+    fn accent(&self, out: &mut Vec<QuadData>, part: &str, rect: Rect, tint: f32) {
+        if self.frame(part, "").is_some() {
+            self.panel(out, part, rect, Response::default());
+        } else {
+            let [r, g, b, a] = self.style.accent;
+            self.solid(out, rect, [r, g, b, a * tint], 0.1);
+        }
+    }
+
+    /// Label vertically centred in a row of height `h` whose top is `top`
+    fn label(&self, out: &mut Vec<QuadData>, text: &str, left: f32, top: f32, h: f32) {
+        if let Some(font) = &self.style.font {
+            let th = font.line_height * self.style.scale;
+            let paint = self.text_paint(self.style.text_color, 0.2);
+            push_text(out, font, text, [left, top - (h - th) * 0.5], paint);
+        }
+    }
+}
+
+// Optional trailing table accepted by every widget
+#[derive(Default)]
+struct WidgetOpts {
+    w: Option<f32>,
+    h: Option<f32>,
+    id: Option<String>,
+    step: Option<f32>,
+    max_len: Option<usize>,
+}
+
+fn widget_opts(opts: Option<Table>) -> mlua::Result<WidgetOpts> {
+    let Some(t) = opts else {
+        return Ok(WidgetOpts::default());
+    };
+    Ok(WidgetOpts {
+        w: t.get("w")?,
+        h: t.get("h")?,
+        id: t.get("id")?,
+        step: t.get("step")?,
+        max_len: t.get("max_len")?,
+    })
+}
+
+// Keyboard navigation and typed text from one input snapshot. Typed text comes from
+// the text stream; without one, keys are visited in code order so typed text never
+// depends on hash map iteration.
+fn ui_input(snap: &InputSnapshot, viewport: Option<&UiViewportCb>) -> UiInput {
+    let down = |k: u32| snap.keys.get(&k).copied().unwrap_or(false);
+    let pressed = |k: u32| down(k) && !snap.prev_keys.get(&k).copied().unwrap_or(false);
+    let shift = down(keys::SHIFT_LEFT) || down(keys::SHIFT_RIGHT);
+    let tab = pressed(keys::TAB);
+    let (mx, my) = (snap.mouse_x as f32, snap.mouse_y as f32);
+    let pointer = match (snap.mouse_ui, viewport) {
+        (Some(p), _) => p,
+        (None, Some(vp)) => vp().window_to_ui(mx, my),
+        (None, None) => [mx, my],
+    };
+    let mouse = |m: &HashMap<String, bool>| m.get("MouseLeft").copied().unwrap_or(false);
+    let (now, before) = (mouse(&snap.mouse_buttons), mouse(&snap.prev_mouse_buttons));
+    let mut typed_keys: Vec<u32> = snap.keys.keys().copied().filter(|&k| pressed(k)).collect();
+    typed_keys.sort_unstable();
+    UiInput {
+        pointer: Some(pointer),
+        pointer_down: now,
+        pointer_pressed: now && !before,
+        pointer_released: !now && before,
+        nav: NavInput {
+            up: pressed(keys::ARROW_UP) || (tab && shift),
+            down: pressed(keys::ARROW_DOWN) || (tab && !shift),
+            left: pressed(keys::ARROW_LEFT),
+            right: pressed(keys::ARROW_RIGHT),
+            activate: pressed(keys::ENTER) || pressed(keys::NUMPAD_ENTER) || pressed(keys::SPACE),
+        },
+        typed: match &snap.text {
+            Some(text) => text.chars().collect(),
+            None => typed_keys
+                .into_iter()
+                .filter_map(|k| keys::to_char(k, shift))
+                .collect(),
+        },
+        backspace: pressed(keys::BACKSPACE),
+    }
+}
+
+fn style_arg(style: &mut Style, def: &Table) -> mlua::Result<()> {
+    match def.get::<Value>("font")? {
+        Value::Nil => {}
+        Value::Boolean(false) => style.font = None,
+        Value::UserData(ud) => style.font = Some(ud.borrow::<FontHandle>()?.0.clone()),
+        _ => {
+            return Err(mlua::Error::RuntimeError(
+                "ui.style: font must be a Font (from engine.font_load) or false".into(),
+            ))
+        }
+    }
+    if let Some(scale) = def.get::<Option<f32>>("scale")? {
+        style.scale = scale;
+    }
+    for (key, slot) in [
+        ("text_color", &mut style.text_color),
+        ("fill", &mut style.fill),
+        ("hover", &mut style.hover),
+        ("down", &mut style.down),
+        ("accent", &mut style.accent),
+        ("focus", &mut style.focus),
+    ] {
+        let value: Value = def.get(key)?;
+        if !value.is_nil() {
+            *slot = color_arg(value)?;
+        }
+    }
+    if let Some(layer) = def.get::<Option<String>>("layer")? {
+        style.layer = layer;
+    }
+    if let Some(z) = def.get::<Option<f32>>("z")? {
+        style.z = z;
+    }
+    match def.get::<Value>("atlas")? {
+        Value::Nil => {}
+        Value::Boolean(false) => style.skin = None,
+        Value::UserData(ud) => {
+            let atlas = ud.borrow::<Atlas>()?;
+            let skin: Table = def.get("skin")?;
+            let mut frames = HashMap::new();
+            for pair in skin.pairs::<String, String>() {
+                let (part, name) = pair?;
+                let uv = atlas.uv_map.get(&name).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!(
+                        "ui.style: skin.{} names unknown atlas frame '{}'",
+                        part, name
+                    ))
+                })?;
+                frames.insert(part, *uv);
+            }
+            let slice = def.get::<Option<f32>>("slice")?.unwrap_or(0.0);
+            style.skin = Some(Skin {
+                texture: atlas.texture.0,
+                frames,
+                slice,
+                slice_uv: [slice / atlas.sheet[0], slice / atlas.sheet[1]],
+            });
+        }
+        _ => {
+            return Err(mlua::Error::RuntimeError(
+                "ui.style: atlas must be an Atlas (from engine.atlas_load) or false".into(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Install engine.ui: begin, style, button, checkbox, slider, list and text_field.
+/// Widgets append quads to `quads`, like engine.text.
+pub fn install(
+    lua: &Lua,
+    engine: &Table,
+    state: Rc<RefCell<UiState>>,
+    quads: Rc<RefCell<Vec<QuadData>>>,
+    layer_resolve: Rc<RefCell<Option<LayerResolveCb>>>,
+) -> mlua::Result<()> {
+    let ui = lua.create_table()?;
+
+    // begin(input?, nav?) starts a UI frame. `input` defaults to engine.get_input();
+    // `nav` = {up, down, left, right, activate} adds gamepad or scripted navigation.
+    let st = state.clone();
+    let begin = lua.create_function(
+        move |lua, (input, nav): (Option<AnyUserData>, Option<Table>)| {
+            let input = match input {
+                Some(ud) => ud,
+                None => {
+                    let engine: Table = lua.globals().get("engine")?;
+                    engine
+                        .get::<Function>("get_input")?
+                        .call::<AnyUserData>(())?
+                }
+            };
+            let mut st = st.borrow_mut();
+            let mut frame = ui_input(&*input.borrow::<InputSnapshot>()?, st.viewport.as_ref());
+            if let Some(nav) = nav {
+                let n = &mut frame.nav;
+                n.up |= nav.get::<Option<bool>>("up")?.unwrap_or(false);
+                n.down |= nav.get::<Option<bool>>("down")?.unwrap_or(false);
+                n.left |= nav.get::<Option<bool>>("left")?.unwrap_or(false);
+                n.right |= nav.get::<Option<bool>>("right")?.unwrap_or(false);
+                n.activate |= nav.get::<Option<bool>>("activate")?.unwrap_or(false);
+            }
+            st.layer_id = match &*layer_resolve.borrow() {
+                Some(cb) => cb(st.style.layer.clone()),
+                None => 0,
+            };
+            st.ctx.begin(frame);
+            Ok(())
+        },
+    )?;
+    ui.set("begin", begin)?;
+
+    // style{font?, scale?, text_color?, fill?, hover?, down?, accent?, focus?, layer?, z?,
+    //       atlas?, skin?, slice?} merges into the current style
+    let st = state.clone();
+    let style =
+        lua.create_function(move |_, def: Table| style_arg(&mut st.borrow_mut().style, &def))?;
+    ui.set("style", style)?;
+
+    // button(label, x, y, opts?) -> clicked
+    let (st, q) = (state.clone(), quads.clone());
+    let button = lua.create_function(
+        move |_, (label, x, y, opts): (String, f32, f32, Option<Table>)| {
+            let o = widget_opts(opts)?;
+            let mut st = st.borrow_mut();
+            let h = o.h.unwrap_or_else(|| st.style.line_h());
+            let tw = st.style.text_w(&label);
+            let w = o.w.unwrap_or((tw + 4.0 * PAD).max(h));
+            let rect = Rect::new(x, y, w, h);
+            let resp = st
+                .ctx
+                .interact(widget_id("button", o.id.as_deref().unwrap_or(&label)), rect);
+            let mut out = q.borrow_mut();
+            st.panel(&mut out, "button", rect, resp);
+            st.label(&mut out, &label, x + (w - tw) * 0.5, y, h);
+            Ok(resp.clicked)
+        },
+    )?;
+    ui.set("button", button)?;
+
+    // checkbox(label, checked, x, y, opts?) -> checked, changed
+    let (st, q) = (state.clone(), quads.clone());
+    let checkbox = lua.create_function(
+        move |_, (label, checked, x, y, opts): (String, bool, f32, f32, Option<Table>)| {
+            let o = widget_opts(opts)?;
+            let mut st = st.borrow_mut();
+            let h = o.h.unwrap_or_else(|| st.style.line_h());
+            let w = o.w.unwrap_or(h + PAD + st.style.text_w(&label));
+            let id = widget_id("checkbox", o.id.as_deref().unwrap_or(&label));
+            let resp = st.ctx.interact(id, Rect::new(x, y, w, h));
+            let checked = checked != resp.clicked;
+            let mut out = q.borrow_mut();
+            st.panel(&mut out, "checkbox", Rect::new(x, y, h, h), resp);
+            if checked {
+                let inset = (h * 0.25).floor();
+                let mark = Rect::new(x + inset, y - inset, h - 2.0 * inset, h - 2.0 * inset);
+                st.accent(&mut out, "checkmark", mark, 1.0);
+            }
+            st.label(&mut out, &label, x + h + PAD, y, h);
+            Ok((checked, resp.clicked))
+        },
+    )?;
+    ui.set("checkbox", checkbox)?;
+
+    // slider(label, value, min, max, x, y, opts?) -> value, changed
+    // Drag with the pointer or step with left/right while focused; opts.step also
+    // snaps dragged values.
+    let (st, q) = (state.clone(), quads.clone());
+    let slider = lua.create_function(
+        move |_,
+              (label, value, min, max, x, y, opts): (
+            String,
+            f32,
+            f32,
+            f32,
+            f32,
+            f32,
+            Option<Table>,
+        )| {
+            let o = widget_opts(opts)?;
+            let mut st = st.borrow_mut();
+            let h = o.h.unwrap_or_else(|| st.style.line_h());
+            let w = o.w.unwrap_or(DEFAULT_W);
+            let rect = Rect::new(x, y, w, h);
+            let resp = st
+                .ctx
+                .interact(widget_id("slider", o.id.as_deref().unwrap_or(&label)), rect);
+            let knob_w = (h * 0.5).max(4.0).min(w);
+            let span = max - min;
+            let mut v = value;
+            let input = st.ctx.input();
+            if let (true, Some([px, _])) = (resp.held, input.pointer) {
+                let t = (px - x - knob_w * 0.5) / (w - knob_w).max(1.0);
+                v = min + t.clamp(0.0, 1.0) * span;
+                if let Some(step) = o.step.filter(|s| *s > 0.0) {
+                    v = min + ((v - min) / step).round() * step;
+                }
+            } else if resp.focused && input.nav.left != input.nav.right {
+                let step = o.step.unwrap_or(span / SLIDER_STEPS);
+                v += if input.nav.right { step } else { -step };
+            }
+            let v = v.clamp(min.min(max), max.max(min));
+            let t = if span != 0.0 { (v - min) / span } else { 0.0 };
+            let mut out = q.borrow_mut();
+            st.panel(&mut out, "slider", rect, resp);
+            let knob = Rect::new(x + t * (w - knob_w), y, knob_w, h);
+            st.accent(&mut out, "knob", knob, 1.0);
+            st.label(&mut out, &label, x + w + PAD, y, h);
+            Ok((v, v != value))
+        },
+    )?;
+    ui.set("slider", slider)?;
+
+    // list(items, selected, x, y, opts?) -> selected, changed
+    // One focusable row per item; `selected` is a 1-based index (0 for none).
+    let (st, q) = (state.clone(), quads.clone());
+    let list = lua.create_function(
+        move |_, (items, selected, x, y, opts): (Vec<String>, usize, f32, f32, Option<Table>)| {
+            let o = widget_opts(opts)?;
+            let mut st = st.borrow_mut();
+            let h = o.h.unwrap_or_else(|| st.style.line_h());
+            let widest = items.iter().map(|s| st.style.text_w(s)).fold(0.0, f32::max);
+            let w = o.w.unwrap_or((widest + 2.0 * PAD).max(DEFAULT_W));
+            let scope = format!("list/{}", o.id.as_deref().unwrap_or(""));
+            let mut pick = selected;
+            let mut out = q.borrow_mut();
+            for (i, item) in items.iter().enumerate() {
+                let row = Rect::new(x, y - i as f32 * h, w, h);
+                let resp = st.ctx.interact(widget_id(&scope, &i.to_string()), row);
+                if resp.clicked {
+                    pick = i + 1;
+                }
+                if pick == i + 1 && st.frame("row_selected", "").is_some() {
+                    st.panel(&mut out, "row_selected", row, resp);
+                } else {
+                    st.panel(&mut out, "row", row, resp);
+                    if pick == i + 1 {
+                        st.accent(&mut out, "row_selected", row, 0.5);
+                    }
+                }
+                st.label(&mut out, item, x + PAD, row.top, h);
+            }
+            Ok((pick, pick != selected))
+        },
+    )?;
+    ui.set("list", list)?;
+
+    // text_field(label, text, x, y, opts?) -> text, changed
+    // Types while focused (click or navigate to it); opts.max_len caps the length.
+    let (st, q) = (state.clone(), quads.clone());
+    let text_field = lua.create_function(
+        move |_, (label, text, x, y, opts): (String, String, f32, f32, Option<Table>)| {
+            let o = widget_opts(opts)?;
+            let mut st = st.borrow_mut();
+            let h = o.h.unwrap_or_else(|| st.style.line_h());
+            let w = o.w.unwrap_or(DEFAULT_W);
+            let rect = Rect::new(x, y, w, h);
+            let resp = st.ctx.interact(
+                widget_id("text_field", o.id.as_deref().unwrap_or(&label)),
+                rect,
+            );
+            let mut value = text.clone();
+            if resp.focused {
+                let input = st.ctx.input();
+                if input.backspace {
+                    value.pop();
+                }
+                let room = o.max_len.unwrap_or(usize::MAX);
+                for c in &input.typed {
+                    if value.chars().count() < room {
+                        value.push(*c);
+                    }
+                }
+            }
+            let mut out = q.borrow_mut();
+            st.panel(&mut out, "field", rect, resp);
+            st.label(&mut out, &value, x + PAD, y, h);
+            if resp.focused {
+                let caret_h = h - 2.0 * PAD;
+                let caret = Rect::new(x + PAD + st.style.text_w(&value), y - PAD, 1.0, caret_h);
+                st.accent(&mut out, "caret", caret, 1.0);
+            }
+            st.label(&mut out, &label, x + w + PAD, y, h);
+            let changed = value != text;
+            Ok((value, changed))
+        },
+    )?;
+    ui.set("text_field", text_field)?;
+
+    engine.set("ui", ui)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_lua;
+    use engine_core::state::UiViewport;
+
+    #[test]
+    fn test_keyboard_navigation_drives_widgets() {
+        let (lua, api) = test_lua();
+        let (clicks, volume, mode): (u32, f32, usize) = lua
+            .load(
+                r#"
+                local clicks, volume, mode, on = 0, 0.5, 1, false
+                local function frame(nav)
+                    engine.ui.begin(nil, nav)
+                    if engine.ui.button("Play", 10, 100) then clicks = clicks + 1 end
+                    on = engine.ui.checkbox("Sound", on, 10, 76)
+                    volume = engine.ui.slider("Volume", volume, 0, 1, 10, 52, { step = 0.25 })
+                    mode = engine.ui.list({ "Easy", "Hard" }, mode, 10, 28, { id = "mode" })
+                end
+                frame()                                  -- registers focus order
+                frame({ down = true, activate = true })  -- focus Play and click it
+                frame({ down = true, activate = true })  -- Sound
+                frame({ down = true, right = true })     -- Volume
+                frame({ down = true })                   -- Easy
+                frame({ down = true, activate = true })  -- Hard
+                assert(on == true)
+                return clicks, volume, mode
+                "#,
+            )
+            .eval()
+            .unwrap();
+        assert_eq!((clicks, volume, mode), (1, 0.75, 2));
+
+        // No font or atlas: flat quads only, five widget fills per frame at least
+        let out = api.take_quads();
+        assert!(out.iter().all(|q| q.texture_id == 0));
+        assert!(out.len() > 6 * 5);
+        assert!(lua.load("engine.ui.style{ font = 42 }").exec().is_err());
+    }
+
+    #[test]
+    fn test_snapshot_maps_to_ui_input() {
+        let mut snap = InputSnapshot::new();
+        for k in [
+            keys::KEY_B,
+            keys::KEY_A,
+            keys::SHIFT_LEFT,
+            keys::TAB,
+            keys::DIGIT_1,
+        ] {
+            snap.keys.insert(k, true);
+        }
+        snap.prev_keys.insert(keys::DIGIT_1, true);
+        snap.mouse_x = 120.0;
+        snap.mouse_y = 30.0;
+        snap.mouse_buttons.insert("MouseLeft".into(), true);
+        // 640x360 letterbox at (100, 20) showing 320x180 UI units
+        let vp: UiViewportCb = Rc::new(|| UiViewport {
+            x: 100.0,
+            y: 20.0,
+            width: 640.0,
+            height: 360.0,
+            units: [320.0, 180.0],
+        });
+        let input = ui_input(&snap, Some(&vp));
+        assert_eq!(input.typed, vec!['A', 'B']);
+        assert!(input.nav.up && !input.nav.down);
+        assert_eq!(input.pointer, Some([10.0, 175.0]));
+        assert!(input.pointer_pressed && input.pointer_down);
+
+        // A recorded UI pointer wins over the live viewport, so replays at another
+        // window size hit the same widgets
+        snap.mouse_ui = Some([40.0, 90.0]);
+        assert_eq!(ui_input(&snap, Some(&vp)).pointer, Some([40.0, 90.0]));
+    }
+}
@@ -1,12 +1,16 @@
 #![deny(warnings)]
 
 use anyhow::Result;
+use engine_core::input_script::InputScript;
 use engine_core::state::SpriteData;
 use engine_core::window::EngineWindow;
-use engine_scripting::api::{EngineApi, InputSnapshot, SpriteV2};
-use engine_scripting::sandbox::LuaSandbox;
-use std::cell::RefCell;
-use std::io::BufRead;
+use engine_scripting::api::{EngineApi, InputSnapshot, MetricsSnapshot, SpriteV2};
+use engine_scripting::reload::{HotReloader, ReloadReport};
+use engine_scripting::replay::{
+    self, FrameInput, FrameRecord, ReplayHeader, ReplayReader, ReplayWriter,
+};
+use engine_scripting::sandbox::{GcConfig, GcMode, LuaSandbox, ScriptBudget, ScriptTimeout};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use tracing::{info, Level};
@@ -25,23 +29,78 @@ fn main() -> Result<()> {
     // Parse simple CLI flags for record/replay
     let mut record_path: Option<String> = None;
     let mut replay_path: Option<String> = None;
+    let mut record_compress = false;
+    // `luarite sim`: run the scripts headless for a number of frames
+    let mut sim = false;
+    let mut sim_frames: Option<u64> = None;
+    let mut sim_input: Option<String> = None;
+    // Per-callback script budget; a runaway on_update is aborted instead of hanging the host
+    let mut budget = ScriptBudget {
+        max_instructions: None,
+        max_wall_time: Some(std::time::Duration::from_millis(100)),
+    };
+    // Lua heap cap (0 = unlimited) and collector tuning
+    let mut lua_mem_mb: usize = 256;
+    let mut gc_config = GcConfig::default();
     {
         let mut args = std::env::args().skip(1);
         while let Some(arg) = args.next() {
             match arg.as_str() {
+                "sim" => sim = true,
+                "--frames" => sim_frames = args.next().and_then(|v| v.parse::<u64>().ok()),
+                "--input" => sim_input = args.next(),
                 "--record" => record_path = args.next(),
                 "--replay" => replay_path = args.next(),
+                "--record-compress" => record_compress = true,
+                "--script-budget-ms" => {
+                    budget.max_wall_time = args
+                        .next()
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .filter(|ms| *ms > 0)
+                        .map(std::time::Duration::from_millis)
+                }
+                "--script-budget-instr" => {
+                    budget.max_instructions = args
+                        .next()
+                        .and_then(|v| v.parse::<u64>().ok())
+                        .filter(|n| *n > 0)
+                }
+                "--lua-mem-mb" => {
+                    if let Some(mb) = args.next().and_then(|v| v.parse::<usize>().ok()) {
+                        lua_mem_mb = mb;
+                    }
+                }
+                "--gc" => match args.next().as_deref() {
+                    Some("gen") => {
+                        gc_config.mode = GcMode::Generational {
+                            minor_multiplier: 20,
+                            major_multiplier: 100,
+                        }
+                    }
+                    Some("inc") => gc_config.mode = GcConfig::default().mode,
+                    _ => {}
+                },
+                "--gc-step-kb" => {
+                    if let Some(kb) = args.next().and_then(|v| v.parse::<i32>().ok()) {
+                        gc_config.step_kb = kb.max(0);
+                    }
+                }
                 _ => {}
             }
         }
     }
-    // Reduce terminal output now that we have an on-screen HUD. Default to WARN.
-    tracing_subscriber::fmt().with_max_level(Level::WARN).init();
+    // Reduce terminal output now that we have an on-screen HUD. Default to WARN;
+    // sim has no HUD and reports its run summary at INFO.
+    let level = if sim { Level::INFO } else { Level::WARN };
+    tracing_subscriber::fmt().with_max_level(level).init();
 
     info!("Luarite Engine starting...");
 
     // Initialize Lua sandbox and engine API
     let sandbox = Rc::new(LuaSandbox::new()?);
+    sandbox.set_budget(budget);
+    sandbox.set_memory_limit(lua_mem_mb * 1024 * 1024)?;
+    sandbox.set_gc_config(gc_config);
     let api = EngineApi::new();
 
     // Shared exchange between Lua callbacks and engine update
@@ -59,11 +118,20 @@ fn main() -> Result<()> {
         drained_sprites_this_frame: bool,
         clear_color: Option<[f32; 4]>,
         render_mode: Option<engine_core::state::VirtualResolution>,
+        ui_space: Option<engine_core::state::UiSpace>,
         // Camera (v0)
         camera_x: f32,
         camera_y: f32,
         // Layers registry mirror (applied to engine_state each frame)
         layers: engine_core::state::Layers,
+        // Entities destroyed by scripts since the last drain
+        destroyed_entities: Vec<u32>,
+        // Tilemap loads/edits/unloads, applied after texture loads
+        tilemap_commands: Vec<engine_core::tilemap::TilemapCommand>,
+        // engine.cursor requests, merged into engine state in call order
+        cursor_requests: Vec<engine_core::state::CursorRequest>,
+        // Last engine.text_input_start/stop of the update
+        text_input: Option<engine_core::state::TextInputRequest>,
     }
     impl Default for ScriptExchange {
         fn default() -> Self {
@@ -80,30 +148,63 @@ fn main() -> Result<()> {
                 drained_sprites_this_frame: false,
                 clear_color: None,
                 render_mode: None,
+                ui_space: None,
                 camera_x: 0.0,
                 camera_y: 0.0,
                 layers: engine_core::state::Layers::with_defaults(),
+                destroyed_entities: Vec::new(),
+                tilemap_commands: Vec::new(),
+                cursor_requests: Vec::new(),
+                text_input: None,
             }
         }
     }
     let exchange = Rc::new(RefCell::new(ScriptExchange::default()));
 
     // HUD metrics shared with Lua get_metrics()
-    #[derive(Clone, Copy, Default)]
-    struct HudMetrics {
-        cpu_frame_ms: f64,
-        sprites_submitted: u32,
-        ffi_calls: u32,
-    }
-    let hud_metrics = Arc::new(Mutex::new(HudMetrics::default()));
+    let hud_metrics = Arc::new(Mutex::new(MetricsSnapshot::default()));
     let hud_lines: Arc<Mutex<std::collections::VecDeque<String>>> =
         Arc::new(Mutex::new(std::collections::VecDeque::with_capacity(16)));
+    // HUD layout/colors set by engine.hud_config
+    let hud_config = Arc::new(Mutex::new(engine_core::hud::HudConfig::default()));
     // Window size shared with Lua window_size()
     let window_size = Arc::new(Mutex::new((1024u32, 768u32)));
+    // UI pass placement shared with Lua ui_size() and engine.ui
+    let ui_viewport = Arc::new(Mutex::new(engine_core::state::UiViewport::default()));
+    // Letterbox, camera and layers the mouse is mapped through (last presented frame)
+    let canvas_view = Arc::new(Mutex::new(engine_core::state::CanvasView::default()));
+    // Audio mixer: pulled by the output device when there is one, otherwise stepped
+    // with the fixed update so voices still start and finish on time
+    let mixer = Arc::new(Mutex::new(engine_core::audio::Mixer::new(
+        engine_core::audio::OUTPUT_RATE,
+    )));
+    #[cfg(feature = "audio-output")]
+    let _audio_output = if sim {
+        None
+    } else {
+        engine_core::audio::output::start(mixer.clone())
+            .map_err(|e| tracing::warn!("Audio output unavailable: {}", e))
+            .ok()
+    };
+    #[cfg(feature = "audio-output")]
+    let audio_device = _audio_output.is_some();
+    #[cfg(not(feature = "audio-output"))]
+    let audio_device = false;
+    #[cfg(not(feature = "audio-output"))]
+    if !sim {
+        tracing::warn!(
+            "Built without the audio-output feature: sounds are mixed but not played \
+             (rebuild with --features audio-output)"
+        );
+    }
 
     // Create window early to access input handle for providers
     let mut window = EngineWindow::new();
 
+    // Script errors, timeouts and replay divergences; `sim` fails if any occurred
+    let failures = Rc::new(Cell::new(0u64));
+    let replay_done = Rc::new(Cell::new(false));
+
     // Replay snapshot store (shared across providers and frame callbacks)
     let replay_snapshot_global: Arc<Mutex<InputSnapshot>> =
         Arc::new(Mutex::new(InputSnapshot::default()));
@@ -113,6 +214,8 @@ fn main() -> Result<()> {
         Arc::new(Mutex::new(InputSnapshot::default()));
     let prev_input_snapshot_global: Arc<Mutex<InputSnapshot>> =
         Arc::new(Mutex::new(InputSnapshot::default()));
+    // Actions resolved by engine.input_map, recorded next to the raw keys
+    let input_actions = api.input_map_state();
 
     // Install engine namespace with sinks that fill the exchange
     {
@@ -165,9 +268,9 @@ fn main() -> Result<()> {
             let hm = hud_metrics.clone();
             Rc::new(move || {
                 if let Ok(m) = hm.lock() {
-                    (m.cpu_frame_ms, m.sprites_submitted, m.ffi_calls)
+                    *m
                 } else {
-                    (0.0, 0, 0)
+                    MetricsSnapshot::default()
                 }
             })
         };
@@ -175,6 +278,8 @@ fn main() -> Result<()> {
         let input_handle = window.input_handle();
         let last_used_for_live = last_used_input_global.clone();
         let prev_input_for_live = prev_input_snapshot_global.clone();
+        let view_for_live = canvas_view.clone();
+        let ui_viewport_for_live = ui_viewport.clone();
         let live_input_provider: Rc<dyn Fn() -> InputSnapshot> = Rc::new(move || {
             let mut snap = InputSnapshot::default();
             if let Ok(mut prev) = prev_input_for_live.lock() {
@@ -183,20 +288,37 @@ fn main() -> Result<()> {
                 }
             }
 
+            if let Ok(view) = view_for_live.lock() {
+                snap.view = Some(view.clone());
+            }
             if let Ok(inp) = input_handle.lock() {
                 snap.mouse_x = inp.mouse_x;
                 snap.mouse_y = inp.mouse_y;
+                snap.wheel = inp.wheel;
+                snap.mouse_virtual = snap
+                    .view
+                    .as_ref()
+                    .and_then(|v| v.window_to_virtual(inp.mouse_x as f32, inp.mouse_y as f32));
+                snap.mouse_ui = ui_viewport_for_live
+                    .lock()
+                    .ok()
+                    .map(|vp| vp.window_to_ui(inp.mouse_x as f32, inp.mouse_y as f32));
                 for k in inp.keys.iter() {
                     snap.keys.insert(*k, true);
                 }
                 for b in inp.mouse_buttons.iter() {
                     snap.mouse_buttons.insert(b.clone(), true);
                 }
+                snap.pads = inp.pads;
+                snap.text = Some(inp.text.clone());
+                snap.preedit = inp.preedit.clone();
+                snap.preedit_cursor = inp.preedit_cursor;
             }
 
             if let Ok(prev) = prev_input_for_live.lock() {
                 snap.prev_keys = prev.keys.clone();
                 snap.prev_mouse_buttons = prev.mouse_buttons.clone();
+                snap.prev_pads = prev.pads;
             }
 
             if let Ok(mut dst) = last_used_for_live.lock() {
@@ -207,14 +329,19 @@ fn main() -> Result<()> {
         // Replay snapshot to be filled each frame (end of frame) if replaying
         let replay_snapshot = replay_snapshot_global.clone();
         let last_used_for_replay = last_used_input_global.clone();
+        let view_for_replay = canvas_view.clone();
         let replay_input_provider: Rc<dyn Fn() -> InputSnapshot> = {
             let rs = replay_snapshot.clone();
             Rc::new(move || {
                 if let Ok(snap) = rs.lock() {
+                    // Mouse positions come from the recording; camera and layers are
+                    // reproduced by the replayed scripts
+                    let mut snap = snap.clone();
+                    snap.view = view_for_replay.lock().ok().map(|v| v.clone());
                     if let Ok(mut dst) = last_used_for_replay.lock() {
                         *dst = snap.clone();
                     }
-                    return snap.clone();
+                    return snap;
                 }
                 let d = InputSnapshot::default();
                 if let Ok(mut dst) = last_used_for_replay.lock() {
@@ -252,10 +379,15 @@ fn main() -> Result<()> {
                     let h = hud_lines.clone();
                     Rc::new(move |msg: String| {
                         if let Ok(mut q) = h.lock() {
-                            if q.len() >= 12 {
-                                q.pop_front();
-                            }
-                            q.push_back(msg);
+                            engine_core::hud::push_line(&mut q, msg);
+                        }
+                    })
+                },
+                hud_config_cb: {
+                    let hc = hud_config.clone();
+                    Rc::new(move |config| {
+                        if let Ok(mut c) = hc.lock() {
+                            *c = config;
                         }
                     })
                 },
@@ -278,6 +410,28 @@ fn main() -> Result<()> {
                         ex.render_mode = Some(resolution);
                     })
                 },
+                set_ui_mode_cb: {
+                    let ex_ui = exchange.clone();
+                    Rc::new(move |space: engine_core::state::UiSpace| {
+                        ex_ui.borrow_mut().ui_space = Some(space);
+                    })
+                },
+                ui_viewport_provider: {
+                    let uv = ui_viewport.clone();
+                    Rc::new(move || uv.lock().map(|v| *v).unwrap_or_default())
+                },
+                cursor_cb: {
+                    let ex_cursor = exchange.clone();
+                    Rc::new(move |req| {
+                        ex_cursor.borrow_mut().cursor_requests.push(req);
+                    })
+                },
+                text_input_cb: {
+                    let ex_text = exchange.clone();
+                    Rc::new(move |req| {
+                        ex_text.borrow_mut().text_input = Some(req);
+                    })
+                },
                 // New camera/layers callbacks
                 camera_set_cb: {
                     let ex_cam = exchange.clone();
@@ -310,15 +464,65 @@ fn main() -> Result<()> {
                 },
                 layer_set_cb: {
                     let ex_layers = exchange.clone();
-                    Rc::new(move |name: String, order: Option<i32>, parallax: Option<(f32,f32)>, screen_space: Option<bool>, visible: Option<bool>, shake: Option<f32>| {
-                        let mut ex = ex_layers.borrow_mut();
-                        ex.layers.resolve_or_create(&name);
-                        if let Some(l) = ex.layers.by_name_mut(&name) {
-                            if let Some(o) = order { l.order = o; }
-                            if let Some((px,py)) = parallax { l.parallax_x = px; l.parallax_y = py; }
-                            if let Some(ss) = screen_space { l.screen_space = ss; }
-                            if let Some(v) = visible { l.visible = v; }
-                            if let Some(s) = shake { l.shake_factor = s; }
+                    Rc::new(
+                        move |name: String,
+                              order: Option<i32>,
+                              parallax: Option<(f32, f32)>,
+                              screen_space: Option<bool>,
+                              visible: Option<bool>,
+                              shake: Option<f32>,
+                              ui: Option<bool>,
+                              volume: Option<f32>,
+                              muted: Option<bool>| {
+                            let mut ex = ex_layers.borrow_mut();
+                            ex.layers.resolve_or_create(&name);
+                            if let Some(l) = ex.layers.by_name_mut(&name) {
+                                if let Some(o) = order {
+                                    l.order = o;
+                                }
+                                if let Some((px, py)) = parallax {
+                                    l.parallax_x = px;
+                                    l.parallax_y = py;
+                                }
+                                if let Some(ss) = screen_space {
+                                    l.screen_space = ss;
+                                }
+                                if let Some(v) = visible {
+                                    l.visible = v;
+                                }
+                                if let Some(s) = shake {
+                                    l.shake_factor = s;
+                                }
+                                if let Some(u) = ui {
+                                    l.ui = u;
+                                }
+                                if let Some(v) = volume {
+                                    l.volume = v;
+                                }
+                                if let Some(m) = muted {
+                                    l.muted = m;
+                                }
+                            }
+                        },
+                    )
+                },
+                destroy_entity_cb: {
+                    let ex_destroy = exchange.clone();
+                    Rc::new(move |id: u32| {
+                        ex_destroy.borrow_mut().destroyed_entities.push(id);
+                    })
+                },
+                tilemap_cb: {
+                    let ex_tiles = exchange.clone();
+                    Rc::new(move |cmd| {
+                        ex_tiles.borrow_mut().tilemap_commands.push(cmd);
+                    })
+                },
+                audio_cb: {
+                    let mixer_cmds = mixer.clone();
+                    Rc::new(move |cmd| {
+                        if let Ok(mut m) = mixer_cmds.lock() {
+                            m.apply(cmd);
                         }
                     })
                 },
@@ -339,80 +543,251 @@ fn main() -> Result<()> {
 
     // Load the main game script from disk (enables reload)
     const SCRIPT_PATH: &str = "scripts/game.lua";
+    // require("a.b") resolves to scripts/a/b.lua
+    sandbox.set_script_root("scripts")?;
     let script_src = std::fs::read_to_string(SCRIPT_PATH)?;
     sandbox.load_script(&script_src, "game.lua")?;
-    let mut last_mtime = std::fs::metadata(SCRIPT_PATH)
-        .and_then(|m| m.modified())
-        .ok();
+    // Watches game.lua plus every module it requires (polled, debounced)
+    let mut reloader = HotReloader::new(SCRIPT_PATH, "game.lua");
+
+    // Replays restore the recorded RNG state; recordings start from the current one
+    let script_hash = {
+        let mut h = engine_core::state::StateHasher::new();
+        h.write(script_src.as_bytes());
+        h.finish()
+    };
+    let mut replay_reader = match &replay_path {
+        Some(p) => {
+            let f = std::fs::File::open(p)
+                .map_err(|e| anyhow::Error::msg(format!("open replay '{}': {}", p, e)))?;
+            let reader = ReplayReader::new(std::io::BufReader::new(f))
+                .map_err(|e| anyhow::Error::msg(format!("replay '{}': {}", p, e)))?;
+            let header = reader.header();
+            if header.script_hash != script_hash {
+                tracing::warn!("Replay: {} changed since recording", SCRIPT_PATH);
+            }
+            if header.api_version != engine_scripting::api::API_VERSION {
+                tracing::warn!("Replay: recorded with API v{}", header.api_version);
+            }
+            if header.timestep != engine_core::time::FIXED_TIMESTEP {
+                tracing::warn!("Replay: recorded with timestep {}", header.timestep);
+            }
+            api.set_rng_state(header.seed);
+            Some(reader)
+        }
+        None => None,
+    };
+    let mut replay_next = match replay_reader.as_mut() {
+        Some(reader) => reader.next_frame()?,
+        None => None,
+    };
+    if replay_reader.is_some() && replay_next.is_none() {
+        tracing::warn!("Replay: no frames recorded");
+        replay_reader = None;
+        replay_done.set(true);
+    }
+    let mut recorder = match &record_path {
+        Some(p) => {
+            let f = std::fs::File::create(p)
+                .map_err(|e| anyhow::Error::msg(format!("create record file '{}': {}", p, e)))?;
+            let header = ReplayHeader {
+                api_version: engine_scripting::api::API_VERSION,
+                seed: api.rng_state(),
+                timestep: engine_core::time::FIXED_TIMESTEP,
+                script_hash,
+                compressed: record_compress,
+                keyframe_interval: replay::DEFAULT_KEYFRAME_INTERVAL,
+            };
+            Some(ReplayWriter::new(std::io::BufWriter::new(f), header)?)
+        }
+        None => None,
+    };
 
     // Wire script lifecycle into engine window
     {
         let sandbox_for_start = sandbox.clone();
+        let failures_for_start = failures.clone();
         window.set_script_on_start(move |_state| {
             // Call Lua on_start if present
+            if !sandbox_for_start.has_function("on_start") {
+                return;
+            }
             if let Err(e) = sandbox_for_start.call_function::<(), ()>("on_start", ()) {
-                tracing::warn!("on_start not called: {}", e);
+                tracing::error!("on_start error: {}", e);
+                failures_for_start.set(failures_for_start.get() + 1);
             }
         });
 
         let sandbox_for_update = sandbox.clone();
         let mut api_for_update = api; // move into closure to keep time updated
         let exchange_for_update = exchange.clone();
+        let mixer_for_update = mixer.clone();
         let sandbox_for_reload = sandbox.clone();
         let mut quiesce_frames: u8 = 0;
+        // Set when on_update blows its budget; cleared by the next successful reload
+        let mut script_faulted = false;
         let mut reload_key_down = false;
         let mut sprites_scratch: Vec<SpriteData> = Vec::with_capacity(1024);
+        let hud_for_reload = hud_lines.clone();
+        let hud_for_tasks = hud_lines.clone();
+        let hud_for_timeout = hud_lines.clone();
+        let hud_for_replay = hud_lines.clone();
+        let failures_for_update = failures.clone();
+        let failures_for_timeout = failures.clone();
+        let replay_done_set = replay_done.clone();
+        let replay_snapshot_set = replay_snapshot_global.clone();
+        let last_used_for_record = last_used_input_global.clone();
+        let actions_for_record = input_actions.clone();
+        // Fixed updates run so far; recordings index frames by it
+        let mut tick: u64 = 0;
+        let mut replay_prev: Option<FrameInput> = None;
+        let mut replay_diverged: u64 = 0;
+        let report_reload =
+            move |report: ReloadReport, state: &mut engine_core::state::EngineState| {
+                for (path, e) in report.failed.iter() {
+                    tracing::error!("Reload failed for {}: {}", path.display(), e);
+                }
+                if report.reloaded.is_empty() {
+                    return false;
+                }
+                let names: Vec<String> = report
+                    .reloaded
+                    .iter()
+                    .map(|p| {
+                        p.file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned()
+                    })
+                    .collect();
+                tracing::info!("Reloaded: {}", names.join(", "));
+                if let Ok(mut q) = hud_for_reload.lock() {
+                    engine_core::hud::push_line(&mut q, format!("reloaded: {}", names.join(", ")));
+                }
+                state.note_reload();
+                true
+            };
+        let report_timeout =
+            move |t: &ScriptTimeout, state: &mut engine_core::state::EngineState| {
+                tracing::error!("{}", t);
+                if let Ok(mut q) = hud_for_timeout.lock() {
+                    engine_core::hud::push_line(
+                        &mut q,
+                        format!("TIMEOUT {} at {}", t.function, t.location),
+                    );
+                    engine_core::hud::push_line(
+                        &mut q,
+                        "on_update paused: save or press R to reload".to_string(),
+                    );
+                }
+                state.note_script_timeout();
+                failures_for_timeout.set(failures_for_timeout.get() + 1);
+            };
         // Capture only what we need (avoid capturing `window` by value)
         let window_input_for_reload = window.input_handle();
         window.set_script_on_update(move |dt, state| {
             // Advance engine time for Lua view
             api_for_update.update_time(dt);
 
-            // Manual reload on 'R'
-            if let Ok(inp) = window_input_for_reload.lock() {
-                let is_down = inp.keys.contains(&engine_core::stable_keys::KEY_R);
-                if is_down && !reload_key_down {
-                    if let Ok(src) = std::fs::read_to_string(SCRIPT_PATH) {
-                        if let Err(e) = sandbox_for_reload.reload_script(&src, "game.lua") {
-                            tracing::error!("Manual reload failed: {}", e);
-                        } else {
-                            tracing::info!("Manual script reload triggered");
+            // Each fixed update drains script buffers once
+            {
+                let mut ex = exchange_for_update.borrow_mut();
+                ex.drained_tf32_this_frame = false;
+                ex.drained_sprites_this_frame = false;
+            }
+
+            // Replay: input for this update and the hashes it should produce
+            let mut expected: Option<(u64, Vec<(String, u64)>)> = None;
+            if let Some(rec) = replay_next.take() {
+                let snap = rec
+                    .input
+                    .to_snapshot(rec.prev.as_ref().or(replay_prev.as_ref()));
+                if let Ok(mut cur) = replay_snapshot_set.lock() {
+                    *cur = snap;
+                }
+                replay_prev = Some(rec.input);
+                expected = Some((rec.frame, rec.hashes));
+            }
+
+            // Live reload is off in sim, which runs the scripts exactly as loaded
+            if !sim {
+                // Manual reload on 'R'
+                if let Ok(inp) = window_input_for_reload.lock() {
+                    let is_down = inp.keys.contains(&engine_core::stable_keys::KEY_R);
+                    if is_down && !reload_key_down {
+                        tracing::info!("Manual script reload triggered");
+                        let report = reloader.reload_all(&sandbox_for_reload);
+                        if report_reload(report, state) {
                             quiesce_frames = 1;
+                            script_faulted = false;
                         }
                     }
+                    reload_key_down = is_down;
                 }
-                reload_key_down = is_down;
-            }
 
-            // File watcher: reload if modified
-            if let Ok(meta) = std::fs::metadata(SCRIPT_PATH) {
-                if let Ok(modified) = meta.modified() {
-                    if Some(modified) != last_mtime {
-                        match std::fs::read_to_string(SCRIPT_PATH) {
-                            Ok(src) => {
-                                if let Err(e) = sandbox_for_reload.reload_script(&src, "game.lua") {
-                                    tracing::error!("Reload failed: {}", e);
-                                } else {
-                                    tracing::info!("Script reloaded: {}", SCRIPT_PATH);
-                                    last_mtime = Some(modified);
-                                    quiesce_frames = 1; // skip next on_update
-                                }
-                            }
-                            Err(e) => tracing::warn!("Failed to read script: {}", e),
-                        }
+                // File watcher: polls on an interval, reloads changed modules and dependents
+                let now = std::time::Instant::now();
+                if let Some(report) = reloader.poll(&sandbox_for_reload, now) {
+                    if report_reload(report, state) {
+                        quiesce_frames = 1; // skip next on_update
+                        script_faulted = false;
                     }
                 }
             }
 
-            // Call Lua on_update(dt), unless quiescing this frame
-            if quiesce_frames == 0 {
+            // Call Lua on_update(dt), unless quiescing this frame or paused after a timeout
+            if script_faulted {
+                // Keep the window responsive; wait for a reload to fix the script
+            } else if quiesce_frames == 0 {
                 if let Err(e) = sandbox_for_update.call_function::<(f64,), ()>("on_update", (dt,)) {
-                    tracing::error!("on_update error: {}", e);
+                    if let Some(t) = e.downcast_ref::<ScriptTimeout>() {
+                        report_timeout(t, state);
+                        script_faulted = true;
+                    } else {
+                        tracing::error!("on_update error: {}", e);
+                        failures_for_update.set(failures_for_update.get() + 1);
+                    }
                 }
+                // Update contacts, resume engine.spawn tasks and advance tweens for this fixed step
+                if !script_faulted {
+                    // Failures are reported even when the budget trips partway through
+                    let mut errors = Vec::new();
+                    let step = || {
+                        let over_budget = || sandbox_for_update.over_budget();
+                        api_for_update.step_collisions();
+                        errors = api_for_update.step_tasks(&over_budget);
+                        if !over_budget() {
+                            errors.extend(api_for_update.step_tweens());
+                        }
+                        Ok(())
+                    };
+                    let outcome = sandbox_for_update.guarded("tasks", step);
+                    if let Ok(mut q) = hud_for_tasks.lock() {
+                        for e in errors {
+                            failures_for_update.set(failures_for_update.get() + 1);
+                            let first = e.lines().next().unwrap_or_default().to_string();
+                            engine_core::hud::push_line(&mut q, first);
+                        }
+                    }
+                    if let Err(e) = outcome {
+                        if let Some(t) = e.downcast_ref::<ScriptTimeout>() {
+                            report_timeout(t, state);
+                            script_faulted = true;
+                        }
+                    }
+                }
+                // Text glyphs emitted during this step replace the previous step's
+                state.set_quads(api_for_update.take_quads());
             } else {
                 quiesce_frames = quiesce_frames.saturating_sub(1);
             }
 
+            // Incremental GC step + heap sample for metrics/HUD
+            match sandbox_for_update.gc_frame_step() {
+                Ok(s) => state.note_lua_gc(s.step_time, s.used_bytes as f64 / 1024.0 / 1024.0),
+                Err(e) => tracing::warn!("Lua GC step failed: {}", e),
+            }
+
             // Drain exchange into engine state
             {
                 let mut ex = exchange_for_update.borrow_mut();
@@ -422,6 +797,15 @@ fn main() -> Result<()> {
                 if let Some(m) = ex.render_mode.take() {
                     state.set_virtual_resolution(m);
                 }
+                if let Some(space) = ex.ui_space.take() {
+                    state.set_ui_space(space);
+                }
+                for req in ex.cursor_requests.drain(..) {
+                    state.request_cursor(req);
+                }
+                if let Some(req) = ex.text_input.take() {
+                    state.request_text_input(req);
+                }
                 // Handle queued texture loads
                 if !ex.textures.is_empty() {
                     for (id, path) in ex.textures.drain(..) {
@@ -435,6 +819,9 @@ fn main() -> Result<()> {
                         }
                     }
                 }
+                for cmd in ex.tilemap_commands.drain(..) {
+                    state.apply_tilemap_command(cmd);
+                }
                 // Prefer zero-copy typed buffer swap if present
                 if let Some((rcbuf, rows, cap)) = ex.typed_buf.take() {
                     if !ex.drained_tf32_this_frame {
@@ -487,6 +874,106 @@ fn main() -> Result<()> {
                         tracing::error!("Failed to submit sprites: {}", e);
                     }
                 }
+                // Destroyed entities: drop their transforms/sprites engine-side
+                for id in ex.destroyed_entities.drain(..) {
+                    state.release_entity(id);
+                }
+            }
+
+            // Positional sounds follow this step's camera, transforms and layers
+            if let Ok(mut m) = mixer_for_update.lock() {
+                m.spatialize(state);
+                if !audio_device {
+                    m.advance(dt);
+                }
+            }
+
+            // Determinism: hash this update's state for recording or comparison
+            if recorder.is_some() || expected.is_some() {
+                let mut hashes: Vec<(String, u64)> = state
+                    .subsystem_hashes()
+                    .into_iter()
+                    .map(|(name, h)| (name.to_string(), h))
+                    .collect();
+                hashes.push(("rng".to_string(), api_for_update.rng_state()));
+                // Lua-only game state, when the script defines on_hash()
+                if !script_faulted {
+                    match replay::script_state_hash(&sandbox_for_update) {
+                        Ok(Some(h)) => hashes.push(("script".to_string(), h)),
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::error!("{}", e);
+                            failures_for_update.set(failures_for_update.get() + 1);
+                        }
+                    }
+                }
+                if let Some((frame, recorded)) = expected {
+                    if let Some(d) = replay::compare(frame, &recorded, &hashes) {
+                        replay_diverged += 1;
+                        failures_for_update.set(failures_for_update.get() + 1);
+                        if replay_diverged == 1 {
+                            tracing::error!("{}", d);
+                            let names: Vec<&str> =
+                                d.subsystems.iter().map(|(n, _, _)| n.as_str()).collect();
+                            if let Ok(mut q) = hud_for_replay.lock() {
+                                engine_core::hud::push_line(
+                                    &mut q,
+                                    format!(
+                                        "REPLAY DIVERGED at frame {}: {}",
+                                        d.frame,
+                                        names.join(", ")
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+                if let Some(w) = recorder.as_mut() {
+                    // Exactly what scripts saw via engine.get_input(), with resolved actions
+                    let mut snap = last_used_for_record
+                        .lock()
+                        .map(|s| s.clone())
+                        .unwrap_or_default();
+                    snap.actions = Some(actions_for_record.borrow().last.clone());
+                    let record = FrameRecord {
+                        frame: tick,
+                        dt,
+                        input: FrameInput::from_snapshot(&snap),
+                        prev: None,
+                        hashes,
+                    };
+                    if let Err(e) = w.push(record) {
+                        tracing::error!("Recording stopped: {}", e);
+                        recorder = None;
+                    }
+                }
+            }
+            tick += 1;
+
+            // Read one frame ahead so the end is known before the next update runs
+            if let Some(reader) = replay_reader.as_mut() {
+                match reader.next_frame() {
+                    Ok(Some(rec)) => replay_next = Some(rec),
+                    end => {
+                        if let Err(e) = end {
+                            tracing::error!("Replay stopped: {}", e);
+                            failures_for_update.set(failures_for_update.get() + 1);
+                        }
+                        let line = format!(
+                            "replay finished: {} frames, {} diverged",
+                            tick, replay_diverged
+                        );
+                        tracing::warn!("{}", line);
+                        if let Ok(mut q) = hud_for_replay.lock() {
+                            engine_core::hud::push_line(&mut q, line);
+                        }
+                        if let Ok(mut cur) = replay_snapshot_set.lock() {
+                            *cur = InputSnapshot::default();
+                        }
+                        replay_reader = None;
+                        replay_done_set.set(true);
+                    }
+                }
             }
         });
     }
@@ -495,125 +982,77 @@ fn main() -> Result<()> {
     {
         let hm = hud_metrics.clone();
         let ws_upd = window_size.clone();
-        let last_used_for_write = last_used_input_global.clone();
-        // Replay snapshot handle for this closure
-        let replay_snapshot_set = replay_snapshot_global.clone();
-        // Exchange for resetting per-frame drain flags
-        let exchange_reset = exchange.clone();
-        // Optional record/replay handles
-        let mut rec_file = record_path
-            .clone()
-            .map(|p| std::fs::File::create(p).expect("create record file"));
-        let mut rep_lines: Option<std::io::Lines<std::io::BufReader<std::fs::File>>> =
-            replay_path.clone().map(|p| {
-                let f = std::fs::File::open(p).expect("open replay file");
-                std::io::BufReader::new(f).lines()
-            });
+        let ui_upd = ui_viewport.clone();
+        let view_upd = canvas_view.clone();
 
         window.set_on_end_frame(move |state, metrics| {
             if let Ok(mut m) = hm.lock() {
                 m.cpu_frame_ms = metrics.current_metrics().cpu_frame_ms;
                 m.ffi_calls = state.get_ffi_calls_this_frame();
                 m.sprites_submitted = state.get_sprites().len() as u32;
+                m.lua_gc_ms = metrics.current_metrics().lua_gc_time_ms;
+                m.lua_mem_mb = metrics.current_metrics().lua_mem_mb;
             }
             // Update window size from engine_state
             let (w, h) = state.window_size();
             if let Ok(mut wh) = ws_upd.lock() {
                 *wh = (w, h);
             }
-
-            // Determinism: compute transform hash
-            let h64 = state.compute_transform_hash();
-
-            // Record: write simple input snapshot + hash per frame
-            if let Some(f) = rec_file.as_mut() {
-                use std::io::Write;
-                // Snapshot exactly what scripts saw via engine.get_input()
-                let mut keys: Vec<u32> = Vec::new();
-                let mut btns: Vec<String> = Vec::new();
-                let mut mx = 0.0f64;
-                let mut my = 0.0f64;
-                if let Ok(snap) = last_used_for_write.lock() {
-                    mx = snap.mouse_x;
-                    my = snap.mouse_y;
-                    for (k, v) in snap.keys.iter() {
-                        if *v {
-                            keys.push(*k);
-                        }
-                    }
-                    for (b, v) in snap.mouse_buttons.iter() {
-                        if *v {
-                            btns.push(b.clone());
-                        }
-                    }
-                }
-                keys.sort();
-                btns.sort();
-                let keys_s = keys
-                    .iter()
-                    .map(|k| k.to_string())
-                    .collect::<Vec<String>>()
-                    .join("|");
-                let btns_s = btns.join("|");
-                let _ = writeln!(
-                    f,
-                    "H {}\tK {}\tB {}\tMX {:.3}\tMY {:.3}",
-                    h64, keys_s, btns_s, mx, my
-                );
+            if let Ok(mut vp) = ui_upd.lock() {
+                *vp = state.ui_viewport();
             }
-            // Replay: read next line, parse snapshot + expected hash; set snapshot for next frame; compare hash
-            if let Some(lines) = rep_lines.as_mut() {
-                if let Some(Ok(line)) = lines.next() {
-                    let mut rs = InputSnapshot::default();
-                    let mut expected: Option<u64> = None;
-                    for tok in line.split('\t') {
-                        let tok = tok.trim();
-                        if let Some(rest) = tok.strip_prefix("H ") {
-                            expected = rest.parse::<u64>().ok();
-                        } else if let Some(rest) = tok.strip_prefix("K ") {
-                            for k_str in rest.split('|') {
-                                if !k_str.is_empty() {
-                                    if let Ok(k) = k_str.parse::<u32>() {
-                                        rs.keys.insert(k, true);
-                                    }
-                                }
-                            }
-                        } else if let Some(rest) = tok.strip_prefix("B ") {
-                            for b in rest.split('|') {
-                                if !b.is_empty() {
-                                    rs.mouse_buttons.insert(b.to_string(), true);
-                                }
-                            }
-                        } else if let Some(rest) = tok.strip_prefix("MX ") {
-                            rs.mouse_x = rest.parse::<f64>().unwrap_or(0.0);
-                        } else if let Some(rest) = tok.strip_prefix("MY ") {
-                            rs.mouse_y = rest.parse::<f64>().unwrap_or(0.0);
-                        }
-                    }
-                    if let Ok(mut cur) = replay_snapshot_set.lock() {
-                        *cur = rs;
-                    }
-                    if let Some(exp) = expected {
-                        if exp != h64 {
-                            tracing::error!("Determinism mismatch: expected={}, got={}", exp, h64);
-                        }
-                    }
-                }
-            }
-
-            // Reset per-frame drain latches so next frame will drain once again
-            {
-                let mut ex = exchange_reset.borrow_mut();
-                ex.drained_tf32_this_frame = false;
-                ex.drained_sprites_this_frame = false;
+            if let Ok(mut v) = view_upd.lock() {
+                *v = state.canvas_view();
             }
         });
     }
 
     // Provide HUD lines handle to engine window so it can render the overlay
     window.set_hud_lines_handle(hud_lines.clone());
+    window.set_hud_config_handle(hud_config.clone());
 
-    window.run()?;
+    if !sim {
+        window.run()?;
+        return Ok(());
+    }
 
+    // Headless: scripted (or replayed) input, one fixed update per frame, no rendering
+    let script = match &sim_input {
+        Some(p) => {
+            if replay_path.is_some() {
+                return Err(anyhow::Error::msg(
+                    "sim: --input and --replay are exclusive",
+                ));
+            }
+            let src = std::fs::read_to_string(p)
+                .map_err(|e| anyhow::Error::msg(format!("open input script '{}': {}", p, e)))?;
+            InputScript::parse(&src)
+                .map_err(|e| anyhow::Error::msg(format!("input script '{}': {}", p, e)))?
+        }
+        None => InputScript::default(),
+    };
+    // Default length: the whole replay, the whole input script, or ten seconds
+    let limit = sim_frames.unwrap_or(if replay_path.is_some() {
+        u64::MAX
+    } else if !script.is_empty() {
+        script.len()
+    } else {
+        600
+    });
+    let frames = window.run_headless(|frame, input| {
+        if frame >= limit || replay_done.get() {
+            return false;
+        }
+        script.apply(frame, input);
+        true
+    })?;
+    let failures = failures.get();
+    info!("sim: {} frames, {} failures", frames, failures);
+    if failures > 0 {
+        return Err(anyhow::Error::msg(format!(
+            "sim failed: {} script errors or replay divergences",
+            failures
+        )));
+    }
     Ok(())
 }